@@ -0,0 +1,15 @@
+-- Add up migration script here
+CREATE TABLE datakey_permission (
+            id INT AUTO_INCREMENT,
+            key_id INT NOT NULL,
+            user_id INT NOT NULL,
+            user_email varchar(60) NOT NULL,
+            token_id INT,
+            -- unique key treats NULL values as distinct, user level permission is indexed with token 0 instead
+            token_key INT AS (IFNULL(token_id, 0)) STORED,
+            create_at DATETIME,
+            PRIMARY KEY(id),
+            FOREIGN KEY (key_id) REFERENCES data_key(id),
+            FOREIGN KEY (user_id) REFERENCES user(id),
+            UNIQUE KEY `unique_key_and_user_and_token` (`key_id`,`user_id`, `token_key`)
+);
@@ -0,0 +1,19 @@
+-- Add up migration script here
+CREATE TABLE sign_audit (
+            id BIGINT AUTO_INCREMENT,
+            key_id INT,
+            key_name VARCHAR(100) NOT NULL,
+            key_type VARCHAR(20) NOT NULL,
+            user_id INT NOT NULL,
+            token_id INT NOT NULL,
+            client_address VARCHAR(100) NOT NULL,
+            sign_options VARCHAR(1000) NOT NULL,
+            content_digest VARCHAR(64) NOT NULL,
+            digest_source VARCHAR(10) NOT NULL,
+            result VARCHAR(10) NOT NULL,
+            error VARCHAR(1000),
+            create_at DATETIME,
+            PRIMARY KEY(id),
+            INDEX `index_key_name` (`key_name`),
+            INDEX `index_create_at` (`create_at`)
+);
@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS transparency_log_tree_head;
+DROP TABLE IF EXISTS transparency_log_node;
+DROP TABLE IF EXISTS transparency_log_leaf;
+DROP TABLE IF EXISTS transparency_log_head;
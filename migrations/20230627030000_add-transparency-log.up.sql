@@ -0,0 +1,34 @@
+-- Add up migration script here
+CREATE TABLE transparency_log_head (
+            id INT NOT NULL,
+            tree_size BIGINT NOT NULL,
+            update_at DATETIME,
+            PRIMARY KEY(id)
+);
+INSERT INTO transparency_log_head(id, tree_size, update_at) VALUES (1, 0, NOW());
+CREATE TABLE transparency_log_leaf (
+            leaf_index BIGINT NOT NULL,
+            key_id INT NOT NULL,
+            key_name VARCHAR(100) NOT NULL,
+            content_digest VARCHAR(64) NOT NULL,
+            signature_digest VARCHAR(64) NOT NULL,
+            leaf_hash VARCHAR(64) NOT NULL,
+            create_at DATETIME,
+            PRIMARY KEY(leaf_index),
+            INDEX `index_key_name` (`key_name`),
+            INDEX `index_signature_digest` (`signature_digest`)
+);
+CREATE TABLE transparency_log_node (
+            node_level INT NOT NULL,
+            node_index BIGINT NOT NULL,
+            hash VARCHAR(64) NOT NULL,
+            PRIMARY KEY(node_level, node_index)
+);
+CREATE TABLE transparency_log_tree_head (
+            tree_size BIGINT NOT NULL,
+            root_hash VARCHAR(64) NOT NULL,
+            signed_at BIGINT NOT NULL,
+            key_name VARCHAR(100) NOT NULL,
+            signature TEXT NOT NULL,
+            PRIMARY KEY(tree_size)
+);
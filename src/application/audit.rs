@@ -0,0 +1,56 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use crate::domain::audit::entity::{PagedSignAudit, SignAuditFilter};
+use crate::domain::audit::repository::Repository as AuditRepository;
+use crate::presentation::handler::control::model::user::dto::UserIdentity;
+use crate::util::error::Result;
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait AuditService: Send + Sync {
+    async fn get_sign_audits(&self, user: UserIdentity, filter: SignAuditFilter, page: u32, page_size: u32) -> Result<PagedSignAudit>;
+}
+
+pub struct DBAuditService<A>
+where
+    A: AuditRepository
+{
+    repository: A,
+}
+
+impl<A> DBAuditService<A>
+where
+    A: AuditRepository
+{
+    pub fn new(repository: A) -> Self {
+        Self {
+            repository,
+        }
+    }
+}
+
+#[async_trait]
+impl<A> AuditService for DBAuditService<A>
+where
+    A: AuditRepository
+{
+    //users can only read the records of their own sign requests and the requests signed with their own keys
+    async fn get_sign_audits(&self, user: UserIdentity, mut filter: SignAuditFilter, page: u32, page_size: u32) -> Result<PagedSignAudit> {
+        filter.visible_to = Some(user.id);
+        self.repository.get_by_filter(&filter, page, page_size).await
+    }
+}
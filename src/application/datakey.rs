@@ -15,6 +15,9 @@
  */
 
 use crate::domain::datakey::repository::Repository as DatakeyRepository;
+use crate::domain::permission::repository::Repository as PermissionRepository;
+use crate::domain::audit::repository::Repository as AuditRepository;
+use crate::domain::transparency::repository::Repository as TransparencyLogRepository;
 use crate::domain::sign_service::SignBackend;
 use crate::util::error::{Error, Result};
 use async_trait::async_trait;
@@ -28,8 +31,36 @@ use chrono::{Duration, Utc};
 use tokio::sync::RwLock;
 use tokio_util::sync::CancellationToken;
 use crate::domain::datakey::entity::KeyType::{OpenPGP, X509CA, X509EE, X509ICA};
+use crate::domain::permission::entity::KeyPermission;
+use crate::domain::audit::entity::SignAudit;
+use crate::domain::transparency::entity::{LogEntry, LogLeaf, SignedTreeHead};
+use crate::application::transparency::get_inclusion_proof;
+use crate::util::options;
+use crate::util::sign::SignType;
+use crate::util::digest::ContentDigest;
+use std::str::FromStr;
+use crate::domain::token::entity::Token;
+use crate::domain::user::entity::User;
 use crate::presentation::handler::control::model::user::dto::UserIdentity;
 
+//the sign types which are selected by file handlers are bound to the key types whose plugins build the payloads,
+//openpgp key ignores `cms` which is the default sign type of client.
+fn validate_sign_type(key_type: &KeyType, options: &HashMap<String, String>) -> Result<()> {
+    let sign_type = match options.get(options::SIGN_TYPE) {
+        None => return Ok(()),
+        Some(value) => SignType::from_str(value)?,
+    };
+    let valid_sign_type_by_key_type = HashMap::from([
+        (OpenPGP, vec![SignType::Cms, SignType::Jsf, SignType::XmlDsig, SignType::Cosign]),
+        (X509EE, vec![SignType::Cms, SignType::Authenticode, SignType::PKCS7, SignType::Raw, SignType::Jsf, SignType::XmlDsig,
+                      SignType::Cosign, SignType::Ima]),
+    ]);
+    match valid_sign_type_by_key_type.get(key_type) {
+        Some(sign_types) if sign_types.contains(&sign_type) => Ok(()),
+        _ => Err(Error::ActionsNotAllowedError(format!("sign type '{}' is not permitted for key type '{}'", sign_type, key_type))),
+    }
+}
+
 #[async_trait]
 pub trait KeyService: Send + Sync{
     async fn create(&self, data: &mut DataKey) -> Result<DataKey>;
@@ -47,8 +78,14 @@ pub trait KeyService: Send + Sync{
     async fn cancel_revoke(&self, user: UserIdentity, id_or_name: String) -> Result<()>;
     async fn enable(&self, user: Option<UserIdentity>, id_or_name: String) -> Result<()>;
     async fn disable(&self, user: Option<UserIdentity>, id_or_name: String) -> Result<()>;
+    //keys sign permission related operation
+    async fn get_permissions(&self, user: UserIdentity, id_or_name: String) -> Result<Vec<KeyPermission>>;
+    async fn grant_permission(&self, user: UserIdentity, id_or_name: String, grantee: User, token_id: Option<i32>) -> Result<KeyPermission>;
+    async fn revoke_permission(&self, user: UserIdentity, id_or_name: String, permission_id: i32) -> Result<()>;
     //used for data server
-    async fn sign(&self, key_type: String, key_name: String, options: &HashMap<String, String>, data: Vec<u8>) ->Result<Vec<u8>>;
+    //the fingerprint of key is returned along with the signature, the log entry is returned when transparency log is enabled
+    async fn sign(&self, token: &Token, client_address: String, key_type: String, key_name: String, options: &HashMap<String, String>, data: Vec<u8>) ->Result<(Vec<u8>, String, Option<LogEntry>)>;
+    async fn sign_prehashed(&self, token: &Token, client_address: String, key_type: String, key_name: String, options: &HashMap<String, String>, digest: ContentDigest) ->Result<(Vec<u8>, String, Option<LogEntry>)>;
 
     //method below used for maintenance
     fn start_cache_cleanup_loop(&self, cancel_token: CancellationToken) -> Result<()>;
@@ -60,29 +97,134 @@ pub trait KeyService: Send + Sync{
 
 
 
-pub struct DBKeyService<R, S>
+//content of sign request, either the whole content or the digest computed by client
+enum SignContent {
+    Raw(Vec<u8>),
+    Prehashed(ContentDigest),
+}
+
+pub struct DBKeyService<R, P, A, L, S>
 where
     R: DatakeyRepository + Clone + 'static,
+    P: PermissionRepository + 'static,
+    A: AuditRepository + 'static,
+    L: TransparencyLogRepository + 'static,
     S: SignBackend + ?Sized + 'static
 {
     repository: R,
+    permission_repository: P,
+    audit_repository: A,
+    log_repository: L,
+    //key type and name of the key used to sign the tree head, log is disabled when absent
+    log_key: Option<(String, String)>,
     sign_service: Arc<RwLock<Box<S>>>,
     container: DataKeyContainer<R>
 }
 
-impl<R, S> DBKeyService<R, S>
+impl<R, P, A, L, S> DBKeyService<R, P, A, L, S>
     where
         R: DatakeyRepository + Clone + 'static,
+        P: PermissionRepository + 'static,
+        A: AuditRepository + 'static,
+        L: TransparencyLogRepository + 'static,
         S: SignBackend + ?Sized + 'static
 {
-    pub fn new(repository: R, sign_service: Box<S>) -> Self {
+    pub fn new(repository: R, permission_repository: P, audit_repository: A, log_repository: L, sign_service: Box<S>) -> Self {
         Self {
             repository: repository.clone(),
+            permission_repository,
+            audit_repository,
+            log_repository,
+            log_key: None,
             sign_service: Arc::new(RwLock::new(sign_service)),
             container: DataKeyContainer::new(repository)
         }
     }
 
+    pub fn enable_transparency_log(&mut self, key_type: String, key_name: String) {
+        self.log_key = Some((key_type, key_name));
+    }
+
+    //append the signature into transparency log and sign the new tree head with the log key,
+    //the inclusion proof and the tree head are computed upon the tree which ends with this leaf.
+    async fn append_transparency_log(&self, key: &DataKey, content_digest: String, signature: &[u8]) -> Result<Option<LogEntry>> {
+        let (log_key_type, log_key_name) = match &self.log_key {
+            Some(log_key) => log_key,
+            None => return Ok(None)
+        };
+        let leaf = self.log_repository.append(LogLeaf::new(key.id, key.name.clone(), content_digest, signature)).await?;
+        let tree_size = leaf.leaf_index + 1;
+        let (inclusion_proof, root_hash) = get_inclusion_proof(&self.log_repository, leaf.leaf_index, tree_size).await?;
+        let timestamp = Utc::now().timestamp();
+        let log_key = self.container.get_data_key(log_key_type.clone(), log_key_name.clone()).await?;
+        //openpgp keys produce detached signatures, x509 keys produce detached cms
+        let mut sign_options = HashMap::from([
+            (options::DETACHED.to_string(), "true".to_string()),
+        ]);
+        if log_key.key_type != KeyType::OpenPGP {
+            sign_options.insert(options::SIGN_TYPE.to_string(), "cms".to_string());
+        }
+        let signature = self.sign_service.read().await.sign(
+            &log_key, SignedTreeHead::payload(tree_size, &root_hash, timestamp), sign_options).await?;
+        let tree_head = SignedTreeHead {
+            tree_size,
+            root_hash,
+            timestamp,
+            key_name: log_key.name,
+            signature,
+        };
+        self.log_repository.create_tree_head(&tree_head).await?;
+        Ok(Some(LogEntry {
+            leaf,
+            inclusion_proof,
+            tree_head,
+        }))
+    }
+
+    async fn record_audit<T>(&self, mut audit: SignAudit, result: &Result<T>) {
+        audit.set_result(result);
+        //failure in recording audit will not break the sign request
+        if let Err(e) = self.audit_repository.create(audit.clone()).await {
+            error!("failed to record sign audit {}: {}", audit, e);
+        }
+    }
+
+    async fn get_and_check_owner(&self, user: UserIdentity, id_or_name: String) -> Result<DataKey> {
+        let user_id = user.id;
+        let key = self.get_and_check_permission(Some(user), id_or_name, KeyAction::Read).await?;
+        if key.user != user_id {
+            return Err(Error::UnprivilegedError)
+        }
+        Ok(key)
+    }
+
+    //sign is denied by default, only the owner of the key and the granted users or tokens are allowed to sign.
+    async fn check_sign_permission(&self, token: &Token, key: &DataKey) -> Result<()> {
+        if key.user == token.user_id {
+            return Ok(())
+        }
+        let permissions = self.permission_repository.get_by_key_id(key.id).await?;
+        if KeyPermission::is_sign_allowed(&permissions, token.user_id, token.id) {
+            return Ok(())
+        }
+        Err(Error::ActionsNotAllowedError(format!("user '{}' is not permitted to sign with key '{}'", token.user_id, key.name)))
+    }
+
+    async fn sign_with_audit(&self, audit: &mut SignAudit, token: &Token, key_type: String, key_name: String, options: &HashMap<String, String>, content: SignContent) -> Result<(Vec<u8>, String, Option<LogEntry>)> {
+        let key = self.container.get_data_key(key_type, key_name).await?;
+        self.validate_type_and_state(&key, KeyAction::Sign)?;
+        validate_sign_type(&key.key_type, options)?;
+        self.check_sign_permission(token, &key).await?;
+        audit.key_id = Some(key.id);
+        let signature = match content {
+            SignContent::Raw(data) => self.sign_service.read().await.sign(&key, data, options.clone()).await?,
+            SignContent::Prehashed(digest) => self.sign_service.read().await.sign_prehashed(&key, digest, options.clone()).await?,
+        };
+        //signature which can't be logged must not be issued
+        let log_entry = self.append_transparency_log(&key, audit.content_digest.clone(), &signature).await?;
+        Ok((signature, key.fingerprint.clone(), log_entry))
+    }
+
     async fn get_and_check_permission(&self, _user: Option<UserIdentity>, id_or_name: String, action: KeyAction) -> Result<DataKey> {
         let id = id_or_name.parse::<i32>();
         let data_key: DataKey = match id {
@@ -163,9 +305,12 @@ impl<R, S> DBKeyService<R, S>
 }
 
 #[async_trait]
-impl<R, S> KeyService for DBKeyService<R, S>
+impl<R, P, A, L, S> KeyService for DBKeyService<R, P, A, L, S>
 where
     R: DatakeyRepository + Clone + 'static,
+    P: PermissionRepository + 'static,
+    A: AuditRepository + 'static,
+    L: TransparencyLogRepository + 'static,
     S: SignBackend + ?Sized + 'static
 {
     async fn create(&self, data: &mut DataKey) -> Result<DataKey> {
@@ -263,9 +408,37 @@ where
         self.repository.update_state(key.id, KeyState::Disabled).await
     }
 
-    async fn sign(&self, key_type: String, key_name: String, options: &HashMap<String, String>, data: Vec<u8>) -> Result<Vec<u8>> {
-        let key = self.container.get_data_key(key_type, key_name).await?;
-        self.sign_service.read().await.sign(&key, data, options.clone()).await
+    async fn get_permissions(&self, user: UserIdentity, id_or_name: String) -> Result<Vec<KeyPermission>> {
+        let key = self.get_and_check_owner(user, id_or_name).await?;
+        self.permission_repository.get_by_key_id(key.id).await
+    }
+
+    async fn grant_permission(&self, user: UserIdentity, id_or_name: String, grantee: User, token_id: Option<i32>) -> Result<KeyPermission> {
+        let key = self.get_and_check_owner(user, id_or_name).await?;
+        if key.key_type != OpenPGP && key.key_type != X509EE {
+            return Err(Error::ActionsNotAllowedError(format!("sign permission is not applicable for key type '{}'", key.key_type)));
+        }
+        self.permission_repository.create(KeyPermission::new(key.id, grantee.id, grantee.email, token_id)?).await
+    }
+
+    async fn revoke_permission(&self, user: UserIdentity, id_or_name: String, permission_id: i32) -> Result<()> {
+        let key = self.get_and_check_owner(user, id_or_name).await?;
+        self.permission_repository.delete_by_key_and_id(key.id, permission_id).await
+    }
+
+    async fn sign(&self, token: &Token, client_address: String, key_type: String, key_name: String, options: &HashMap<String, String>, data: Vec<u8>) -> Result<(Vec<u8>, String, Option<LogEntry>)> {
+        let mut audit = SignAudit::new(token, client_address, &key_type, &key_name, options, &data)?;
+        let result = self.sign_with_audit(&mut audit, token, key_type, key_name, options, SignContent::Raw(data)).await;
+        self.record_audit(audit, &result).await;
+        result
+    }
+
+    async fn sign_prehashed(&self, token: &Token, client_address: String, key_type: String, key_name: String, options: &HashMap<String, String>, digest: ContentDigest) -> Result<(Vec<u8>, String, Option<LogEntry>)> {
+        let mut audit = SignAudit::new(token, client_address, &key_type, &key_name, options, &[])?;
+        audit.set_client_digest(&digest.digest);
+        let result = self.sign_with_audit(&mut audit, token, key_type, key_name, options, SignContent::Prehashed(digest)).await;
+        self.record_audit(audit, &result).await;
+        result
     }
 
     fn start_cache_cleanup_loop(&self, cancel_token: CancellationToken) -> Result<()> {
@@ -358,3 +531,26 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_validate_sign_type() {
+        let options = |sign_type: SignType| HashMap::from([(options::SIGN_TYPE.to_string(), sign_type.to_string())]);
+        validate_sign_type(&OpenPGP, &HashMap::new()).expect("sign without sign type should work");
+        validate_sign_type(&OpenPGP, &options(SignType::Cms)).expect("default sign type of openpgp key should work");
+        validate_sign_type(&OpenPGP, &options(SignType::Jsf)).expect("embedded signature of openpgp key should work");
+        for sign_type in [SignType::Raw, SignType::Ima, SignType::Authenticode] {
+            validate_sign_type(&OpenPGP, &options(sign_type)).expect_err("sign type is not permitted for openpgp key");
+        }
+        validate_sign_type(&X509EE, &options(SignType::Raw)).expect("raw signature of x509ee key should work");
+        for key_type in [X509CA, X509ICA] {
+            validate_sign_type(&key_type, &options(SignType::Raw)).expect_err("raw signature of ca key should fail");
+            validate_sign_type(&key_type, &options(SignType::Cms)).expect_err("cms signature of ca key should fail");
+        }
+        validate_sign_type(&X509EE, &HashMap::from([(options::SIGN_TYPE.to_string(), "unknown".to_string())]))
+            .expect_err("unknown sign type should fail");
+    }
+}
@@ -1,2 +1,4 @@
 pub mod user;
-pub mod datakey;
\ No newline at end of file
+pub mod datakey;
+pub mod audit;
+pub mod transparency;
@@ -0,0 +1,108 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+use crate::domain::transparency::entity::{LogLeaf, SignedTreeHead};
+use crate::domain::transparency::merkle::{complete_subtrees, consistency_proof_ranges, inclusion_proof_ranges, range_hash};
+use crate::domain::transparency::repository::Repository as TransparencyLogRepository;
+use crate::util::error::{Error, Result};
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait TransparencyService: Send + Sync {
+    async fn get_tree_head(&self, tree_size: Option<u64>) -> Result<SignedTreeHead>;
+    //the proofs are computed upon the signed tree head, the latest one is used if tree size is absent
+    async fn get_inclusion_proof(&self, leaf_index: u64, tree_size: Option<u64>) -> Result<(LogLeaf, Vec<Vec<u8>>, SignedTreeHead)>;
+    async fn get_consistency_proof(&self, first: u64, second: Option<u64>) -> Result<(SignedTreeHead, Vec<Vec<u8>>, SignedTreeHead)>;
+}
+
+//compute the hashes of leaf ranges and the root hash of the tree with the persisted complete subtrees
+async fn get_range_hashes<L: TransparencyLogRepository>(repository: &L, ranges: &[(u64, u64)], tree_size: u64) -> Result<(Vec<Vec<u8>>, Vec<u8>)> {
+    let mut node_ids = complete_subtrees(0, tree_size);
+    for (start, end) in ranges.iter() {
+        node_ids.append(&mut complete_subtrees(*start, *end));
+    }
+    node_ids.sort();
+    node_ids.dedup();
+    let nodes = repository.get_nodes(&node_ids).await?;
+    let mut hashes = vec![];
+    for (start, end) in ranges.iter() {
+        hashes.push(range_hash(*start, *end, &nodes)?);
+    }
+    Ok((hashes, range_hash(0, tree_size, &nodes)?))
+}
+
+/// Inclusion proof of leaf and the root hash of tree with specified size.
+pub async fn get_inclusion_proof<L: TransparencyLogRepository>(repository: &L, leaf_index: u64, tree_size: u64) -> Result<(Vec<Vec<u8>>, Vec<u8>)> {
+    get_range_hashes(repository, &inclusion_proof_ranges(leaf_index, tree_size), tree_size).await
+}
+
+pub struct DBTransparencyService<L>
+where
+    L: TransparencyLogRepository
+{
+    repository: L,
+}
+
+impl<L> DBTransparencyService<L>
+where
+    L: TransparencyLogRepository
+{
+    pub fn new(repository: L) -> Self {
+        Self {
+            repository,
+        }
+    }
+
+    //the computed root must match the signed one, otherwise the persisted tree is corrupted
+    fn check_root(tree_head: &SignedTreeHead, root_hash: &[u8]) -> Result<()> {
+        if tree_head.root_hash != root_hash {
+            return Err(Error::DatabaseError(format!("root hash of tree with size {} mismatches the signed tree head", tree_head.tree_size)))
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<L> TransparencyService for DBTransparencyService<L>
+where
+    L: TransparencyLogRepository
+{
+    async fn get_tree_head(&self, tree_size: Option<u64>) -> Result<SignedTreeHead> {
+        self.repository.get_tree_head(tree_size).await
+    }
+
+    async fn get_inclusion_proof(&self, leaf_index: u64, tree_size: Option<u64>) -> Result<(LogLeaf, Vec<Vec<u8>>, SignedTreeHead)> {
+        let tree_head = self.repository.get_tree_head(tree_size).await?;
+        if leaf_index >= tree_head.tree_size {
+            return Err(Error::ParameterError(format!("leaf index {} is out of tree with size {}", leaf_index, tree_head.tree_size)))
+        }
+        let leaf = self.repository.get_leaf(leaf_index).await?;
+        let (proof, root_hash) = get_inclusion_proof(&self.repository, leaf_index, tree_head.tree_size).await?;
+        Self::check_root(&tree_head, &root_hash)?;
+        Ok((leaf, proof, tree_head))
+    }
+
+    async fn get_consistency_proof(&self, first: u64, second: Option<u64>) -> Result<(SignedTreeHead, Vec<Vec<u8>>, SignedTreeHead)> {
+        let second_head = self.repository.get_tree_head(second).await?;
+        if first == 0 || first > second_head.tree_size {
+            return Err(Error::ParameterError(format!("first tree size {} should be between 1 and {}", first, second_head.tree_size)))
+        }
+        let first_head = self.repository.get_tree_head(Some(first)).await?;
+        let (proof, root_hash) = get_range_hashes(
+            &self.repository, &consistency_proof_ranges(first, second_head.tree_size), second_head.tree_size).await?;
+        Self::check_root(&second_head, &root_hash)?;
+        Ok((first_head, proof, second_head))
+    }
+}
@@ -20,10 +20,12 @@ use config::{Config};
 use regex::Regex;
 use std::sync::{Arc, atomic::AtomicBool, RwLock};
 use super::traits::SignCommand;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::runtime;
 use crate::client::sign_identity;
-use std::collections::HashMap;
+use crate::client::manifest::{Manifest, OutputLayout};
+use crate::client::report::{Report, ReportEntry, ReportFormat};
+use std::collections::{HashMap, HashSet};
 
 use crate::util::error;
 use async_channel::{bounded};
@@ -37,14 +39,28 @@ use crate::client::worker::signer::RemoteSigner;
 use crate::client::worker::splitter::Splitter;
 use crate::client::worker::traits::SignHandler;
 use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::Instant;
+use tonic::metadata::AsciiMetadataValue;
+
+//environment variable which takes precedence over the `token` in client configuration
+pub const API_TOKEN_ENV: &str = "SIGNATRUST_API_TOKEN";
 
 lazy_static! {
     pub static ref FILE_EXTENSION: HashMap<FileType, Vec<&'static str>> = HashMap::from([
         (FileType::Rpm, vec!["rpm", "srpm"]),
         //Generic file can be used for any file
         (FileType::Generic, vec![".*"]),
-        (FileType::KernelModule, vec!["ko"]),
+        (FileType::KernelModule, vec!["ko", r"ko\.xz", r"ko\.zst", r"ko\.gz"]),
         (FileType::EfiImage, vec!["efi"]),
+        (FileType::Deb, vec!["deb", "udeb"]),
+        (FileType::Sbom, vec!["json", "xml", "spdx"]),
+        (FileType::EfiVariable, vec!["pem", "crt", "cer", "der", "sha256"]),
+        (FileType::Jar, vec!["jar", "war", "ear"]),
+    ]);
+    //file types which are recognized by the trailing components of file path instead of extension
+    pub static ref FILE_NAME: HashMap<FileType, Vec<&'static str>> = HashMap::from([
+        (FileType::AptRelease, vec!["Release"]),
+        (FileType::Repomd, vec!["repodata/repomd.xml"]),
     ]);
 }
 
@@ -70,6 +86,55 @@ pub struct CommandAdd {
     #[arg(value_enum, default_value_t=SignType::Cms)]
     #[arg(help = "specify the signature type, meaningful when key type is x509, EFI file supports `authenticode` only and KO file supports `cms` and `pkcs7`")]
     sign_type: SignType,
+    #[arg(long)]
+    #[arg(help = "save the transparency log inclusion proof next to the signed file with `.tlog.json` suffix")]
+    save_transparency_proof: bool,
+    #[arg(long)]
+    #[arg(help = "hash the content locally and only send the digest to server, it requires the data server supports digest signing")]
+    digest_only: bool,
+    #[arg(long)]
+    #[arg(help = "validate the checksums of metadata files referenced by repomd.xml before signing, meaningful when file type is repomd")]
+    validate_checksum: bool,
+    #[arg(long)]
+    #[arg(help = "only sign the header of rpm package as rpm 4.16+ does, the legacy header and payload signature and md5 digest are omitted, meaningful when file type is rpm")]
+    rpm_header_only: bool,
+    #[arg(long)]
+    #[arg(help = "specify the x509 key name for signing the files inside rpm package with IMA signature, meaningful when file type is rpm")]
+    ima_key_name: Option<String>,
+    #[arg(long)]
+    #[arg(help = "specify the UEFI secure boot variable to update, possible values are PK/KEK/db/dbx, meaningful when file type is efi-variable")]
+    efi_variable: Option<String>,
+    #[arg(long)]
+    #[arg(help = "specify the owner guid of signatures in EFI_SIGNATURE_LIST, meaningful when file type is efi-variable")]
+    efi_owner_guid: Option<String>,
+    #[arg(long)]
+    #[arg(help = "keep the existing signatures of EFI image and append the new signatures, otherwise the existing signatures are replaced, meaningful when file type is efi")]
+    efi_append: bool,
+    #[arg(long)]
+    #[arg(help = "specify additional x509 key name for signing EFI image, it can be used multiple times and the image is signed with all the keys, meaningful when file type is efi")]
+    efi_extra_key_name: Vec<String>,
+    #[arg(long)]
+    #[arg(help = "specify the digest algorithm of authenticode signature, possible values are sha2_256/sha2_384/sha2_512, meaningful when file type is efi")]
+    digest_algorithm: Option<String>,
+    #[arg(long)]
+    #[arg(help = "embed the signer certificate and its issuer certificate into cms signature, only valid when file type is generic and key type is x509ee")]
+    cms_certificate_chain: bool,
+    #[arg(long)]
+    #[arg(help = "specify the zstd compression level used when recompressing the signed .ko.zst files, it's not recorded in the file and defaults to 3 as zstd command does, meaningful when file type is kernel-module")]
+    zstd_level: Option<i32>,
+    #[arg(long)]
+    #[arg(help = "specify the format of signature file, possible values are der/pem for x509ee key and armored/binary/cleartext for pgp key, meaningful when file type is generic")]
+    signature_format: Option<String>,
+    #[arg(long)]
+    #[arg(help = "write the signed files into the directory with the same layout as input path and leave the inputs untouched, a manifest of signed files is generated as well")]
+    output_dir: Option<String>,
+    #[arg(long)]
+    #[arg(help = "write the report of signing result of each file into the specified file")]
+    report: Option<String>,
+    #[arg(long)]
+    #[arg(value_enum, default_value_t=ReportFormat::Json)]
+    #[arg(help = "specify the format of report, json and junit(JUnit XML) are supported")]
+    report_format: ReportFormat,
 }
 
 
@@ -87,62 +152,154 @@ pub struct CommandAddHandler {
     detached: bool,
     max_concurrency: usize,
     sign_type: SignType,
+    token: Option<AsciiMetadataValue>,
+    save_transparency_proof: bool,
+    digest_only: bool,
+    validate_checksum: bool,
+    rpm_header_only: bool,
+    ima_key_name: Option<String>,
+    efi_variable: Option<String>,
+    efi_owner_guid: Option<String>,
+    efi_append: bool,
+    efi_extra_key_name: Vec<String>,
+    digest_algorithm: Option<String>,
+    cms_certificate_chain: bool,
+    zstd_level: Option<i32>,
+    signature_format: Option<String>,
+    output_layout: Option<OutputLayout>,
+    report: Option<PathBuf>,
+    report_format: ReportFormat,
 }
 
 impl CommandAddHandler {
 
+    pub fn read_api_token(config: &Config) -> String {
+        match std::env::var(API_TOKEN_ENV) {
+            Ok(value) => value,
+            Err(_) => config.get_string("token").unwrap_or_default(),
+        }
+    }
+
+    pub fn get_api_token(config: &Config) -> Result<Option<AsciiMetadataValue>> {
+        let token = CommandAddHandler::read_api_token(config);
+        if token.is_empty() {
+            warn!("api token not configured, sign requests will be rejected by server with authentication enabled");
+            return Ok(None)
+        }
+        Ok(Some(AsciiMetadataValue::try_from(token.as_str()).map_err(
+            |_| error::Error::ConfigError("api token contains invalid characters".to_string()))?))
+    }
+
     fn get_sign_options(&self) -> HashMap<String, String> {
-        HashMap::from([
+        let mut sign_options = HashMap::from([
             (options::DETACHED.to_string(), self.detached.to_string()),
             (options::KEY_TYPE.to_string(), self.key_type.to_string()),
-            (options::SIGN_TYPE.to_string(), self.sign_type.to_string())])
+            (options::SIGN_TYPE.to_string(), self.sign_type.to_string()),
+            (options::VALIDATE_CHECKSUM.to_string(), self.validate_checksum.to_string()),
+            (options::RPM_HEADER_ONLY.to_string(), self.rpm_header_only.to_string()),
+            (options::EFI_APPEND.to_string(), self.efi_append.to_string())]);
+        if let Some(ima_key_name) = &self.ima_key_name {
+            sign_options.insert(options::IMA_KEY_NAME.to_string(), ima_key_name.clone());
+        }
+        if let Some(efi_variable) = &self.efi_variable {
+            sign_options.insert(options::EFI_VARIABLE.to_string(), efi_variable.clone());
+        }
+        if let Some(efi_owner_guid) = &self.efi_owner_guid {
+            sign_options.insert(options::EFI_OWNER_GUID.to_string(), efi_owner_guid.clone());
+        }
+        if let Some(digest_algorithm) = &self.digest_algorithm {
+            sign_options.insert(options::DIGEST_ALGORITHM.to_string(), digest_algorithm.clone());
+        }
+        if !self.efi_extra_key_name.is_empty() {
+            sign_options.insert(options::EFI_EXTRA_KEY_NAMES.to_string(), self.efi_extra_key_name.join(","));
+        }
+        if self.cms_certificate_chain {
+            sign_options.insert(options::CMS_CERTIFICATE_CHAIN.to_string(), "true".to_string());
+        }
+        if let Some(zstd_level) = &self.zstd_level {
+            sign_options.insert(options::ZSTD_LEVEL.to_string(), zstd_level.to_string());
+        }
+        if let Some(signature_format) = &self.signature_format {
+            sign_options.insert(options::SIGNATURE_FORMAT.to_string(), signature_format.clone());
+        }
+        sign_options
     }
     fn collect_file_candidates(&self) -> Result<Vec<sign_identity::SignIdentity>> {
-        if self.path.is_dir() {
-            let mut container = Vec::new();
-            for entry in walkdir::WalkDir::new(self.path.to_str().unwrap()) {
-                match entry {
-                    Ok(en)=> {
-                        if en.metadata()?.is_dir() {
-                            continue
-                        }
-                        if let Some(extension) = en.path().extension() {
-                            if self.file_candidates(extension.to_str().unwrap())? {
-                                container.push(
-                                    sign_identity::SignIdentity::new(
-                                        self.file_type.clone(),
-                                        en.path().to_path_buf(),
-                                        self.key_type.clone(),
-                                        self.key_name.clone(),
-                                        self.get_sign_options()));
-                            }
-                        }
-                    },
-                    Err(err)=> {
-                        error!("failed to scan file {}, will be skipped", err);
+        //oci image layout and ostree repository directories are signed as a whole
+        if self.file_type == FileType::OciImage || self.file_type == FileType::Ostree {
+            if !self.path.is_dir() {
+                return Err(error::Error::NoFileCandidateError);
+            }
+            return Ok(vec![sign_identity::SignIdentity::new(
+                self.file_type.clone(), self.path.clone(), self.key_type.clone(), self.key_name.clone(), self.get_sign_options())]);
+        }
+        let files = collect_files(&self.file_type, &self.path)?;
+        Ok(files.into_iter().map(|file| sign_identity::SignIdentity::new(
+            self.file_type.clone(), file, self.key_type.clone(), self.key_name.clone(), self.get_sign_options())).collect())
+    }
+}
+
+//collect the files of specified type, either the single file or all of the matched files in directory
+pub fn collect_files(file_type: &FileType, path: &Path) -> Result<Vec<PathBuf>> {
+    if path.is_dir() {
+        let mut container = Vec::new();
+        for entry in walkdir::WalkDir::new(path.to_str().unwrap()) {
+            match entry {
+                Ok(en)=> {
+                    if en.metadata()?.is_dir() {
+                        continue
                     }
+                    let matched = match file_name_candidates(file_type, en.path()) {
+                        Some(matched) => matched,
+                        None => file_candidates(file_type, en.path())?,
+                    };
+                    if matched {
+                        container.push(en.path().to_path_buf());
+                    }
+                },
+                Err(err)=> {
+                    error!("failed to scan file {}, will be skipped", err);
                 }
             }
-            return Ok(container);
-        } else if self.file_candidates(self.path.extension().unwrap().to_str().unwrap())? {
-                return Ok(vec![sign_identity::SignIdentity::new(
-                    self.file_type.clone(), self.path.clone(), self.key_type.clone(), self.key_name.clone(), self.get_sign_options())]);
         }
-        Err(error::Error::NoFileCandidateError)
+        return Ok(container);
+    }
+    let matched = match file_name_candidates(file_type, path) {
+        Some(matched) => matched,
+        None => file_candidates(file_type, path)?,
+    };
+    if matched {
+        return Ok(vec![path.to_path_buf()]);
     }
+    Err(error::Error::NoFileCandidateError)
+}
 
-    fn file_candidates(&self, extension: &str) -> Result<bool> {
-        let collections = FILE_EXTENSION.get(
-            &self.file_type).ok_or_else(||
-            error::Error::FileNotSupportError(extension.to_string(), self.file_type.to_string()))?;
-        for value in collections {
-            let re = Regex::new(format!(r"^{}$", value).as_str()).unwrap();
-            if re.is_match(extension) {
-                return Ok(true)
-            }
+//return None if the file type is not recognized by file name
+fn file_name_candidates(file_type: &FileType, path: &Path) -> Option<bool> {
+    let names = FILE_NAME.get(file_type)?;
+    Some(names.iter().any(|name| path.ends_with(name)))
+}
+
+//compound extension such as `ko.xz` is matched as well as the last extension
+fn file_candidates(file_type: &FileType, path: &Path) -> Result<bool> {
+    let extension = match path.extension() {
+        Some(extension) => extension.to_str().unwrap(),
+        None => return Ok(false),
+    };
+    let mut extensions = vec![extension.to_string()];
+    if let Some(inner) = path.file_stem().and_then(|stem| Path::new(stem).extension()) {
+        extensions.push(format!("{}.{}", inner.to_str().unwrap(), extension));
+    }
+    let collections = FILE_EXTENSION.get(
+        file_type).ok_or_else(||
+        error::Error::FileNotSupportError(extension.to_string(), file_type.to_string()))?;
+    for value in collections {
+        let re = Regex::new(format!(r"^{}$", value).as_str()).unwrap();
+        if extensions.iter().any(|extension| re.is_match(extension)) {
+            return Ok(true)
         }
-        Err(error::Error::FileNotSupportError(extension.to_string(), self.file_type.to_string()))
     }
+    Err(error::Error::FileNotSupportError(extension.to_string(), file_type.to_string()))
 }
 
 
@@ -167,10 +324,33 @@ impl SignCommand for CommandAddHandler {
             detached: command.detached,
             max_concurrency: config.read()?.get_string("max_concurrency")?.parse()?,
             sign_type: command.sign_type,
+            token: CommandAddHandler::get_api_token(&*config.read()?)?,
+            save_transparency_proof: command.save_transparency_proof,
+            digest_only: command.digest_only,
+            validate_checksum: command.validate_checksum,
+            rpm_header_only: command.rpm_header_only,
+            ima_key_name: command.ima_key_name,
+            efi_variable: command.efi_variable,
+            efi_owner_guid: command.efi_owner_guid,
+            efi_append: command.efi_append,
+            efi_extra_key_name: command.efi_extra_key_name,
+            digest_algorithm: command.digest_algorithm,
+            cms_certificate_chain: command.cms_certificate_chain,
+            zstd_level: command.zstd_level,
+            signature_format: command.signature_format,
+            output_layout: command.output_dir.map(|output_dir| OutputLayout::new(Path::new(&command.path), Path::new(&output_dir))),
+            report: command.report.map(PathBuf::from),
+            report_format: command.report_format,
         })
     }
 
     fn validate(&self) -> Result<()> {
+        if self.output_layout.as_ref().map(|layout| layout.is_in_place()).unwrap_or(false) {
+            return Err(error::Error::InvalidArgumentError("output directory should not be the input directory".to_string()))
+        }
+        if self.cms_certificate_chain && self.file_type != FileType::Generic {
+            return Err(error::Error::InvalidArgumentError("cms certificate chain is only supported for generic file".to_string()))
+        }
         FileHandlerFactory::get_handler(&self.file_type).validate_options(&self.get_sign_options())
     }
 
@@ -185,6 +365,7 @@ impl SignCommand for CommandAddHandler {
     //            vector                sign_chn                      assemble_chn             collect_chn
     //  fetcher-----------splitter * N----------remote signer * N---------------assembler * N--------------collector * N
     fn handle(&self) -> Result<bool> {
+        let start_time = Instant::now();
         let files = self.collect_file_candidates()?;
         let succeed_files = Arc::new(AtomicI32::new(0));
         let failed_files = Arc::new(AtomicI32::new(0));
@@ -199,10 +380,13 @@ impl SignCommand for CommandAddHandler {
         let (collect_s, collect_r) = bounded::<sign_identity::SignIdentity>(self.max_concurrency);
         info!("starting to sign {} files", files.len());
         let lb_config = self.config.read()?.get_table("server")?;
-        runtime.block_on(async {
+        if let Some(layout) = &self.output_layout {
+            std::fs::create_dir_all(layout.get_output_dir())?;
+        }
+        let (manifest, report_entries) = runtime.block_on(async {
             let channel = ChannelFactory::new(
                 &lb_config).await.unwrap().get_channel().unwrap();
-            let mut signer = RemoteSigner::new(channel, self.buffer_size);
+            let mut signer = RemoteSigner::new(channel, self.buffer_size, self.token.clone(), self.digest_only);
             //split file
             let send_handlers = files.into_iter().map(|file|{
                 let task_split_s = split_s.clone();
@@ -251,13 +435,15 @@ impl SignCommand for CommandAddHandler {
             });
             //assemble file
             let working_dir = self.working_dir.clone();
+            let save_transparency_proof = self.save_transparency_proof;
+            let output_layout = self.output_layout.clone();
             let task_collect_s = collect_s.clone();
             let assemble_handler = tokio::spawn(async move {
                 loop {
                     let sign_identity = assemble_r.recv().await;
                     match sign_identity {
                         Ok(identity) => {
-                            let mut assembler = Assembler::new( working_dir.clone());
+                            let mut assembler = Assembler::new( working_dir.clone(), save_transparency_proof, output_layout.clone());
                             assembler.handle(identity, task_collect_s.clone()).await;
                         },
                         Err(_) => {
@@ -271,10 +457,14 @@ impl SignCommand for CommandAddHandler {
             let succeed_files_c = succeed_files.clone();
             let failed_files_c = failed_files.clone();
             let collect_handler = tokio::spawn(async move {
+                let mut manifest = Manifest::default();
+                let mut report_entries = Vec::new();
                 loop {
                     let sign_identity = collect_r.recv().await;
                     match sign_identity {
                         Ok(identity) => {
+                            manifest.entries.append(&mut identity.manifest_entries.borrow_mut());
+                            report_entries.push(ReportEntry::new(&identity));
                             if identity.error.borrow().clone().is_err() {
                                 error!("failed to sign file {} due to error {:?}",
                                     identity.file_path.as_path().display(),
@@ -287,7 +477,7 @@ impl SignCommand for CommandAddHandler {
                         },
                         Err(_) => {
                             info!("collect channel closed");
-                            return
+                            return (manifest, report_entries)
                         }
                     }
                 }
@@ -303,11 +493,26 @@ impl SignCommand for CommandAddHandler {
             drop(assemble_s);
             assemble_handler.await.expect("assemble worker finished correctly");
             drop(collect_s);
-            collect_handler.await.expect("collect worker finished correctly");
+            let result = collect_handler.await.expect("collect worker finished correctly");
             info!("Successfully signed {} files failed {} files",
                 succeed_files.load(Ordering::Relaxed), failed_files.load(Ordering::Relaxed));
             info!("sign files process finished");
+            result
         });
+        if let Some(layout) = &self.output_layout {
+            //copy the rest of input so that output directory is complete, for instance, blobs of OCI image layout
+            let written = manifest.entries.iter().map(|entry| PathBuf::from(&entry.output)).collect::<HashSet<_>>();
+            let failed = report_entries.iter().filter(|entry| entry.is_failed()).map(|entry| PathBuf::from(&entry.file)).collect::<Vec<_>>();
+            let copied = layout.copy_unchanged(&self.path, &written, &failed)?;
+            info!("{} unchanged files copied into {}", copied, layout.get_output_dir().display());
+            let path = layout.get_manifest_path();
+            manifest.save(&path)?;
+            info!("manifest of signed files saved to {}", path.display());
+        }
+        if let Some(path) = &self.report {
+            Report::new(report_entries, start_time.elapsed()).save(path, &self.report_format)?;
+            info!("report of signing result saved to {}", path.display());
+        }
         if failed_files.load(Ordering::Relaxed) != 0 {
             return Ok(false)
         }
@@ -0,0 +1,548 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use crate::util::error::{Error, Result};
+use config::Config;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Cursor, Read, Write};
+use std::os::unix::io::FromRawFd;
+use std::path::Path;
+use std::sync::{Arc, atomic::AtomicBool, RwLock};
+use super::add::CommandAddHandler;
+use super::traits::SignCommand;
+use chrono::{DateTime, Utc};
+use pgp::composed::{Deserializable, SignedPublicKey, StandaloneSignature};
+use pgp::packet::Signature;
+use pgp::types::{KeyId, KeyTrait};
+use tokio::runtime;
+use tonic::metadata::AsciiMetadataValue;
+use crate::client::load_balancer::factory::ChannelFactory;
+use crate::client::worker::signer::RemoteSigner;
+use crate::util::options;
+use crate::util::payload::PGP_CLEARTEXT_HEADER;
+use crate::util::sign::KeyType;
+
+//path of armored public keys (single file or directory) which are used to verify signatures
+pub const PUBLIC_KEYS_CONFIG: &str = "gpg_public_keys";
+const STATUS_PREFIX: &str = "[GNUPG:]";
+const SIGNATURE_HEADER: &str = "-----BEGIN PGP SIGNATURE-----";
+//gpg options which are accepted for compatibility but have no effect
+const IGNORED_FLAGS: [&str; 10] = ["batch", "no-verbose", "verbose", "quiet", "no-secmem-warning", "yes", "no-tty",
+    "no-auto-check-trustdb", "no-permission-warning", "textmode"];
+const IGNORED_OPTIONS: [&str; 8] = ["pinentry-mode", "keyid-format", "passphrase-fd", "passphrase", "homedir",
+    "trust-model", "compress-algo", "weak-digest"];
+
+//subset of gpg arguments used by `git commit -S`, `rpmsign` and `debsign`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GpgArguments {
+    pub sign: bool,
+    pub detach: bool,
+    pub clearsign: bool,
+    pub verify: bool,
+    pub armor: bool,
+    pub local_user: Option<String>,
+    pub output: Option<String>,
+    pub status_fd: Option<i32>,
+    pub digest_algo: Option<String>,
+    pub files: Vec<String>,
+}
+
+impl GpgArguments {
+    pub fn parse(args: &[String]) -> Result<Self> {
+        let mut arguments = GpgArguments::default();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if arg == "--" {
+                arguments.files.extend(iter.by_ref().cloned());
+            } else if let Some(long) = arg.strip_prefix("--") {
+                let (name, inline_value) = match long.split_once('=') {
+                    Some((name, value)) => (name, Some(value.to_string())),
+                    None => (long, None),
+                };
+                if arguments.apply_flag(name) {
+                    continue
+                }
+                let value = match inline_value {
+                    Some(value) => value,
+                    None => iter.next().cloned().ok_or_else(
+                        || Error::InvalidArgumentError(format!("missing value for option --{}", name)))?,
+                };
+                arguments.apply_option(name, value)?;
+            } else if arg.len() > 1 && arg.starts_with('-') {
+                //short options can be combined, for instance `-bsau KEY`
+                let shorts = &arg[1..];
+                for (index, short) in shorts.char_indices() {
+                    let name = match short {
+                        'a' => "armor",
+                        'b' => "detach-sign",
+                        's' => "sign",
+                        't' => "textmode",
+                        'v' => "verbose",
+                        'q' => "quiet",
+                        'u' => "local-user",
+                        'o' => "output",
+                        _ => return Err(Error::InvalidArgumentError(format!("unsupported option -{}", short))),
+                    };
+                    if arguments.apply_flag(name) {
+                        continue
+                    }
+                    let remain = &shorts[index + short.len_utf8()..];
+                    let value = if remain.is_empty() {
+                        iter.next().cloned().ok_or_else(
+                            || Error::InvalidArgumentError(format!("missing value for option -{}", short)))?
+                    } else {
+                        remain.to_string()
+                    };
+                    arguments.apply_option(name, value)?;
+                    break
+                }
+            } else {
+                arguments.files.push(arg.clone());
+            }
+        }
+        Ok(arguments)
+    }
+
+    fn apply_flag(&mut self, name: &str) -> bool {
+        match name {
+            "sign" => self.sign = true,
+            "detach-sign" => self.detach = true,
+            "clearsign" | "clear-sign" => self.clearsign = true,
+            "verify" => self.verify = true,
+            "armor" => self.armor = true,
+            "no-armor" => self.armor = false,
+            _ => return IGNORED_FLAGS.contains(&name),
+        }
+        true
+    }
+
+    fn apply_option(&mut self, name: &str, value: String) -> Result<()> {
+        match name {
+            "local-user" | "default-key" => self.local_user = Some(value),
+            "output" => self.output = Some(value),
+            "status-fd" => self.status_fd = Some(value.parse().map_err(
+                |_| Error::InvalidArgumentError(format!("invalid status fd {}", value)))?),
+            "digest-algo" => self.digest_algo = Some(value),
+            _ if IGNORED_OPTIONS.contains(&name) => {}
+            _ => return Err(Error::InvalidArgumentError(format!("unsupported option --{}", name))),
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.verify {
+            if self.sign || self.detach || self.clearsign {
+                return Err(Error::InvalidArgumentError("--verify can't be combined with signing".to_string()))
+            }
+            if self.files.is_empty() || self.files.len() > 2 {
+                return Err(Error::InvalidArgumentError("--verify requires signature file and optional signed file".to_string()))
+            }
+            return Ok(())
+        }
+        //inline signed message (`-s` alone) is not supported
+        if self.sign && !self.detach && !self.clearsign {
+            return Err(Error::InvalidArgumentError(
+                "--sign must be combined with --detach-sign or --clearsign".to_string()))
+        }
+        if !self.detach && !self.clearsign {
+            return Err(Error::InvalidArgumentError(
+                "only detached signature (--detach-sign) and cleartext signature (--clearsign) are supported".to_string()))
+        }
+        if self.local_user.is_none() {
+            return Err(Error::InvalidArgumentError("key name must be specified with --local-user".to_string()))
+        }
+        if self.files.len() > 1 {
+            return Err(Error::InvalidArgumentError("only one file can be signed".to_string()))
+        }
+        Ok(())
+    }
+}
+
+//convert gpg digest algorithm names into the ones accepted by openpgp plugin
+fn get_digest_algorithm(digest_algo: &str) -> Result<&'static str> {
+    match digest_algo.to_lowercase().as_str() {
+        "sha1" => Ok("sha1"),
+        "sha224" => Ok("sha2_224"),
+        "sha256" => Ok("sha2_256"),
+        "sha384" => Ok("sha2_384"),
+        "sha512" => Ok("sha2_512"),
+        _ => Err(Error::InvalidArgumentError(format!("unsupported digest algorithm {}", digest_algo))),
+    }
+}
+
+//the signature can be armored, binary or embedded in a cleartext signed message
+pub fn parse_signature(content: &[u8]) -> Result<Signature> {
+    let text = String::from_utf8_lossy(content);
+    match text.find(SIGNATURE_HEADER) {
+        Some(index) => Ok(StandaloneSignature::from_string(&text[index..])?.0.signature),
+        None => Ok(StandaloneSignature::from_bytes(Cursor::new(content))?.signature),
+    }
+}
+
+//canonical text and signature of cleartext signed message, check https://www.rfc-editor.org/rfc/rfc4880#section-7
+pub fn parse_cleartext(message: &str) -> Result<(Vec<u8>, Signature)> {
+    let invalid = || Error::InvalidArgumentError("invalid cleartext signed message".to_string());
+    let body = message.trim_start().strip_prefix(PGP_CLEARTEXT_HEADER).ok_or_else(invalid)?;
+    let text_start = body.find("\n\n").or_else(|| body.find("\r\n\r\n")).ok_or_else(invalid)?;
+    let signature_start = body.find(SIGNATURE_HEADER).ok_or_else(invalid)?;
+    if signature_start < text_start {
+        return Err(invalid())
+    }
+    let text = body[text_start..signature_start].trim_start_matches(['\r', '\n']);
+    let lines: Vec<&str> = text.strip_suffix('\n').unwrap_or(text).split('\n').map(|line| {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        line.strip_prefix("- ").unwrap_or(line).trim_end_matches([' ', '\t'])
+    }).collect();
+    Ok((lines.join("\r\n").into_bytes(), parse_signature(&body.as_bytes()[signature_start..])?))
+}
+
+fn format_key_id(key_id: &KeyId) -> String {
+    hex::encode_upper(key_id.as_ref())
+}
+
+fn signature_timestamp(signature: &Signature) -> i64 {
+    signature.created().map(|created| created.timestamp()).unwrap_or_default()
+}
+
+//status line which is required by git to accept the signature
+fn sig_created_status(clearsign: bool, signature: &Signature) -> String {
+    format!("{} SIG_CREATED {} {} {} {:02X} {} {}", STATUS_PREFIX, if clearsign { "C" } else { "D" },
+            signature.config.pub_alg as u8, signature.config.hash_alg as u8, signature.typ() as u8,
+            signature_timestamp(signature),
+            signature.issuer().map(format_key_id).unwrap_or_default())
+}
+
+//the public keys which can be used to verify signatures, invalid files are skipped
+pub fn load_public_keys(path: &Path) -> Result<Vec<SignedPublicKey>> {
+    let files = if path.is_dir() {
+        let mut files: Vec<_> = path.read_dir()?.filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|file| file.is_file()).collect();
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+    let mut keys = Vec::new();
+    for file in files {
+        let content = std::fs::read(&file)?;
+        let key = match std::str::from_utf8(&content) {
+            Ok(text) => SignedPublicKey::from_string(text).map(|(key, _)| key),
+            Err(_) => SignedPublicKey::from_bytes(Cursor::new(&content)),
+        };
+        match key {
+            Ok(key) => keys.push(key),
+            Err(err) => warn!("skip invalid public key file {}: {}", file.display(), err),
+        }
+    }
+    Ok(keys)
+}
+
+fn get_user_id(key: &SignedPublicKey) -> String {
+    key.details.users.first().map(|user| user.id.id().to_string()).unwrap_or_default()
+}
+
+//verify signature with the primary key or the subkey which matches the issuer, status lines are returned
+fn verify_signature(keys: &[SignedPublicKey], signature: &Signature, content: &[u8]) -> (bool, Vec<String>) {
+    let issuer = signature.issuer().map(format_key_id).unwrap_or_default();
+    let mut status = vec![format!("{} NEWSIG", STATUS_PREFIX)];
+    let standalone = StandaloneSignature::new(signature.clone());
+    for key in keys {
+        let result = if signature.issuer() == Some(&key.key_id()) {
+            Some((standalone.verify(key, content), key.fingerprint()))
+        } else {
+            key.public_subkeys.iter().find(|sub| signature.issuer() == Some(&sub.key_id())).map(
+                |sub| (standalone.verify(sub, content), sub.fingerprint()))
+        };
+        let Some((result, fingerprint)) = result else {
+            continue
+        };
+        let user_id = get_user_id(key);
+        if result.is_err() {
+            eprintln!("gpg: BAD signature from \"{}\"", user_id);
+            status.push(format!("{} BADSIG {} {}", STATUS_PREFIX, issuer, user_id));
+            return (false, status)
+        }
+        let created = DateTime::<Utc>::from_timestamp(signature_timestamp(signature), 0).unwrap_or_default();
+        eprintln!("gpg: Signature made {}\ngpg: Good signature from \"{}\"", created.to_rfc2822(), user_id);
+        status.push(format!("{} GOODSIG {} {}", STATUS_PREFIX, issuer, user_id));
+        status.push(format!("{} VALIDSIG {} {} {} 0 4 0 {} {} {:02X} {}", STATUS_PREFIX, hex::encode_upper(fingerprint),
+                            created.format("%Y-%m-%d"), created.timestamp(), signature.config.pub_alg as u8,
+                            signature.config.hash_alg as u8, signature.typ() as u8, hex::encode_upper(key.fingerprint())));
+        //keys are explicitly configured by administrator, they are treated as fully trusted
+        status.push(format!("{} TRUST_FULLY 0 pgp", STATUS_PREFIX));
+        return (true, status)
+    }
+    eprintln!("gpg: Can't check signature: No public key {}", issuer);
+    status.push(format!("{} ERRSIG {} {} {} {:02X} {} 9", STATUS_PREFIX, issuer, signature.config.pub_alg as u8,
+                        signature.config.hash_alg as u8, signature.typ() as u8, signature_timestamp(signature)));
+    status.push(format!("{} NO_PUBKEY {}", STATUS_PREFIX, issuer));
+    (false, status)
+}
+
+fn read_input(file: Option<&String>) -> Result<Vec<u8>> {
+    let mut content = Vec::new();
+    match file {
+        Some(path) if path != "-" => content = std::fs::read(path)?,
+        _ => { io::stdin().read_to_end(&mut content)?; }
+    }
+    Ok(content)
+}
+
+#[derive(Clone)]
+pub struct CommandGpgHandler {
+    config: Arc<RwLock<Config>>,
+    arguments: GpgArguments,
+    buffer_size: usize,
+    token: Option<AsciiMetadataValue>,
+    public_keys: Option<String>,
+}
+
+impl CommandGpgHandler {
+    fn get_sign_options(&self) -> Result<HashMap<String, String>> {
+        let mut sign_options = HashMap::new();
+        if self.arguments.clearsign {
+            sign_options.insert(options::SIGNATURE_FORMAT.to_string(), options::SIGNATURE_FORMAT_CLEARTEXT.to_string());
+        } else {
+            //openpgp plugin produces armored signature for detached option, binary signature packet otherwise
+            sign_options.insert(options::DETACHED.to_string(), self.arguments.armor.to_string());
+        }
+        if let Some(digest_algo) = &self.arguments.digest_algo {
+            sign_options.insert(options::DIGEST_ALGORITHM.to_string(), get_digest_algorithm(digest_algo)?.to_string());
+        }
+        Ok(sign_options)
+    }
+
+    //all of the status lines are written at once, since the file descriptor is closed when dropped
+    fn write_status(&self, status: &[String]) -> Result<()> {
+        let mut writer: Box<dyn Write> = match self.arguments.status_fd {
+            None => return Ok(()),
+            Some(1) => Box::new(io::stdout()),
+            Some(2) => Box::new(io::stderr()),
+            Some(fd) => Box::new(unsafe { File::from_raw_fd(fd) }),
+        };
+        for line in status {
+            writeln!(writer, "{}", line)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn sign(&self) -> Result<bool> {
+        let content = read_input(self.arguments.files.first())?;
+        let key_name = self.arguments.local_user.clone().unwrap_or_default();
+        let lb_config = self.config.read()?.get_table("server")?;
+        let runtime = runtime::Builder::new_current_thread()
+            .enable_io()
+            .enable_time()
+            .build()?;
+        let signature = runtime.block_on(async {
+            let channel = ChannelFactory::new(&lb_config).await?.get_channel()?;
+            let mut signer = RemoteSigner::new(channel, self.buffer_size, self.token.clone(), false);
+            signer.sign(KeyType::Pgp.to_string(), key_name, self.get_sign_options()?, content).await
+        })?;
+        match &self.arguments.output {
+            Some(output) if output != "-" => std::fs::write(output, &signature)?,
+            _ => {
+                let mut stdout = io::stdout();
+                stdout.write_all(&signature)?;
+                stdout.flush()?;
+            }
+        }
+        self.write_status(&[sig_created_status(self.arguments.clearsign, &parse_signature(&signature)?)])?;
+        Ok(true)
+    }
+
+    fn verify(&self) -> Result<bool> {
+        let public_keys = self.public_keys.clone().ok_or_else(
+            || Error::ConfigError(format!("{} is required to verify signatures", PUBLIC_KEYS_CONFIG)))?;
+        let keys = load_public_keys(Path::new(&public_keys))?;
+        let signature_content = read_input(self.arguments.files.first())?;
+        //signed content is not specified, the signature must be a cleartext signed message
+        let (content, signature) = match self.arguments.files.get(1) {
+            Some(file) => (read_input(Some(file))?, parse_signature(&signature_content)?),
+            None => parse_cleartext(&String::from_utf8_lossy(&signature_content))?,
+        };
+        let (verified, status) = verify_signature(&keys, &signature, &content);
+        self.write_status(&status)?;
+        Ok(verified)
+    }
+}
+
+impl SignCommand for CommandGpgHandler {
+    type CommandValue = GpgArguments;
+
+    fn new(_signal: Arc<AtomicBool>, config: Arc<RwLock<Config>>, command: Self::CommandValue) -> Result<Self> {
+        Ok(CommandGpgHandler {
+            buffer_size: config.read()?.get_string("buffer_size")?.parse()?,
+            token: CommandAddHandler::get_api_token(&*config.read()?)?,
+            public_keys: config.read()?.get_string(PUBLIC_KEYS_CONFIG).ok(),
+            config: config.clone(),
+            arguments: command,
+        })
+    }
+
+    fn validate(&self) -> Result<()> {
+        self.arguments.validate()
+    }
+
+    fn handle(&self) -> Result<bool> {
+        if self.arguments.verify {
+            self.verify()
+        } else {
+            self.sign()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pgp::composed::{key::SecretKeyParamsBuilder, KeyType as PgpKeyType, SignedSecretKey};
+    use pgp::crypto::hash::HashAlgorithm;
+    use pgp::packet::{SignatureConfig, SignatureType, SignatureVersion, Subpacket};
+    use pgp::types::SecretKeyTrait;
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn generate_key() -> (SignedSecretKey, SignedPublicKey) {
+        let mut key_params = SecretKeyParamsBuilder::default();
+        key_params
+            .key_type(PgpKeyType::EdDSA)
+            .can_create_certificates(false)
+            .can_sign(true)
+            .primary_user_id("fake_name <fake_email@email.com>".to_string());
+        let secret_key = key_params.build().expect("build key params").generate().expect("generate key");
+        let signed_secret_key = secret_key.sign(String::new).expect("sign secret key");
+        let signed_public_key = signed_secret_key.public_key().sign(&signed_secret_key, String::new).expect("sign public key");
+        (signed_secret_key, signed_public_key)
+    }
+
+    fn sign_content(key: &SignedSecretKey, typ: SignatureType, content: &[u8]) -> Signature {
+        let now = Utc::now();
+        SignatureConfig {
+            version: SignatureVersion::V4,
+            typ,
+            pub_alg: key.algorithm(),
+            hash_alg: HashAlgorithm::SHA2_256,
+            issuer: Some(key.key_id()),
+            created: Some(now),
+            unhashed_subpackets: vec![],
+            hashed_subpackets: vec![
+                Subpacket::SignatureCreationTime(now),
+                Subpacket::Issuer(key.key_id()),
+            ],
+        }.sign(key, String::new, Cursor::new(content)).expect("sign content")
+    }
+
+    #[test]
+    fn test_parse_git_arguments() {
+        let arguments = GpgArguments::parse(&to_args(&["--status-fd=2", "-bsau", "key_name"])).expect("parse git arguments");
+        assert_eq!(GpgArguments {
+            sign: true,
+            detach: true,
+            armor: true,
+            local_user: Some("key_name".to_string()),
+            status_fd: Some(2),
+            ..Default::default()
+        }, arguments);
+        let arguments = GpgArguments::parse(&to_args(&["--keyid-format=long", "--status-fd=1", "--verify", "sig.asc", "-"]))
+            .expect("parse git verify arguments");
+        assert!(arguments.verify);
+        assert_eq!(vec!["sig.asc".to_string(), "-".to_string()], arguments.files);
+    }
+
+    #[test]
+    fn test_parse_rpmsign_and_debsign_arguments() {
+        let arguments = GpgArguments::parse(&to_args(&["--no-verbose", "--no-armor", "--batch", "--pinentry-mode", "loopback",
+            "--no-secmem-warning", "-u", "key_name", "--digest-algo", "sha256", "-sbo", "package.sig", "--", "package"]))
+            .expect("parse rpmsign arguments");
+        assert_eq!(GpgArguments {
+            sign: true,
+            detach: true,
+            local_user: Some("key_name".to_string()),
+            output: Some("package.sig".to_string()),
+            digest_algo: Some("sha256".to_string()),
+            files: vec!["package".to_string()],
+            ..Default::default()
+        }, arguments);
+        let arguments = GpgArguments::parse(&to_args(&["--local-user", "key_name", "--clearsign", "--output", "a.dsc.asc", "a.dsc"]))
+            .expect("parse debsign arguments");
+        assert!(arguments.clearsign);
+        assert_eq!(Some("a.dsc.asc".to_string()), arguments.output);
+        GpgArguments::parse(&to_args(&["--encrypt", "file"])).expect_err("unsupported long option");
+        GpgArguments::parse(&to_args(&["-e", "file"])).expect_err("unsupported short option");
+        GpgArguments::parse(&to_args(&["-bsau"])).expect_err("missing key name");
+        GpgArguments::parse(&to_args(&["--status-fd=a"])).expect_err("invalid status fd");
+    }
+
+    #[test]
+    fn test_validate_arguments() {
+        for args in [vec!["-bsau", "key_name"], vec!["--clearsign", "-u", "key_name", "a.dsc"], vec!["--verify", "sig.asc", "-"]] {
+            GpgArguments::parse(&to_args(&args)).expect("parse arguments").validate().expect("arguments are valid");
+        }
+        for args in [vec!["-sau", "key_name"], vec!["--sign", "-u", "key_name", "file"], vec!["-au", "key_name"],
+                     vec!["-bsa"], vec!["-bsu", "key_name", "a", "b"], vec!["--verify", "-bs", "sig.asc"], vec!["--verify"]] {
+            GpgArguments::parse(&to_args(&args)).expect("parse arguments").validate().expect_err("arguments are invalid");
+        }
+    }
+
+    #[test]
+    fn test_get_digest_algorithm() {
+        assert_eq!("sha2_256", get_digest_algorithm("SHA256").expect("sha256 is supported"));
+        assert_eq!("sha2_512", get_digest_algorithm("sha512").expect("sha512 is supported"));
+        get_digest_algorithm("md5").expect_err("md5 is unsupported");
+    }
+
+    #[test]
+    fn test_verify_detached_signature() {
+        let (secret_key, public_key) = generate_key();
+        let content = "hello world".as_bytes();
+        let armored = StandaloneSignature::new(sign_content(&secret_key, SignatureType::Binary, content))
+            .to_armored_bytes(None).expect("armor signature");
+        let signature = parse_signature(&armored).expect("parse armored signature");
+        let status = sig_created_status(false, &signature);
+        assert!(status.starts_with("[GNUPG:] SIG_CREATED D 22 8 00 "));
+        assert!(status.ends_with(&format_key_id(&secret_key.key_id())));
+
+        let keys = vec![public_key.clone()];
+        let (verified, status) = verify_signature(&keys, &signature, content);
+        assert!(verified);
+        assert!(status[1].starts_with(&format!("[GNUPG:] GOODSIG {} fake_name", format_key_id(&public_key.key_id()))));
+        assert!(status[2].contains(&hex::encode_upper(public_key.fingerprint())));
+        let (verified, status) = verify_signature(&keys, &signature, "hello world!".as_bytes());
+        assert!(!verified);
+        assert!(status[1].starts_with("[GNUPG:] BADSIG"));
+        let (verified, status) = verify_signature(&[], &signature, content);
+        assert!(!verified);
+        assert!(status[2].starts_with("[GNUPG:] NO_PUBKEY"));
+    }
+
+    #[test]
+    fn test_verify_cleartext_signature() {
+        let (secret_key, public_key) = generate_key();
+        let signature = StandaloneSignature::new(sign_content(
+            &secret_key, SignatureType::Text, "Format: 3.0\r\n-----\r\nFiles:".as_bytes()))
+            .to_armored_string(None).expect("armor signature");
+        let message = format!("{}\nHash: SHA256\n\nFormat: 3.0  \n- -----\nFiles:\n{}", PGP_CLEARTEXT_HEADER, signature);
+        let (content, signature) = parse_cleartext(&message).expect("parse cleartext message");
+        assert_eq!("Format: 3.0\r\n-----\r\nFiles:".as_bytes(), content);
+        assert!(verify_signature(&[public_key], &signature, &content).0);
+        parse_cleartext("Format: 3.0").expect_err("invalid cleartext message");
+    }
+}
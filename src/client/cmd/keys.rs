@@ -0,0 +1,246 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use clap::{Args, Subcommand, ValueEnum};
+use config::Config;
+use reqwest::{header, Certificate, Client, Response};
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::sync::{Arc, atomic::AtomicBool, RwLock};
+use tokio::runtime;
+
+use super::add::CommandAddHandler;
+use super::traits::SignCommand;
+use crate::util::error::{Error, Result};
+
+const KEYS_API_PATH: &str = "api/v1/keys";
+const KEY_TYPES: [&str; 4] = ["x509ca", "x509ica", "x509ee", "pgp"];
+
+#[derive(Args)]
+pub struct CommandKeys {
+    #[command(subcommand)]
+    action: KeysAction,
+}
+
+#[derive(Subcommand, Clone)]
+enum KeysAction {
+    #[command(about = "List the keys which are visible to the api token", long_about = None)]
+    List {
+        #[arg(long)]
+        #[arg(help = "specify the key type to filter, x509ca, x509ica, x509ee and pgp are supported")]
+        key_type: Option<String>,
+    },
+    #[command(about = "Show the detail of key in json format", long_about = None)]
+    Show {
+        #[arg(help = "specify the id or name of key")]
+        name: String,
+    },
+    #[command(about = "Export the public key, certificate or crl of key", long_about = None)]
+    Export {
+        #[arg(help = "specify the id or name of key")]
+        name: String,
+        #[arg(long)]
+        #[arg(value_enum)]
+        #[arg(help = "specify the content to export, public key for pgp key and certificate for x509 key are exported in default")]
+        content: Option<ExportContent>,
+        #[arg(short, long)]
+        #[arg(help = "specify the file to write, content is printed to stdout in default")]
+        output: Option<String>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+enum ExportContent {
+    PublicKey,
+    Certificate,
+    Crl,
+}
+
+impl ExportContent {
+    fn path(&self) -> &'static str {
+        match self {
+            ExportContent::PublicKey => "public_key",
+            ExportContent::Certificate => "certificate",
+            ExportContent::Crl => "crl",
+        }
+    }
+}
+
+//fields of the key returned by control server, which are displayed in key list
+#[derive(Deserialize, Debug)]
+struct KeyInfo {
+    id: i32,
+    name: String,
+    key_type: String,
+    fingerprint: String,
+    key_state: String,
+    expire_at: String,
+}
+
+#[derive(Clone)]
+pub struct CommandKeysHandler {
+    control_server: String,
+    token: String,
+    ca_certificate: Option<String>,
+    action: KeysAction,
+}
+
+impl CommandKeysHandler {
+    fn get_url(&self, path: &str) -> String {
+        format!("{}/{}/{}", self.control_server.trim_end_matches('/'), KEYS_API_PATH, path)
+    }
+
+    fn get_client(&self) -> Result<Client> {
+        let mut headers = header::HeaderMap::new();
+        if !self.token.is_empty() {
+            headers.insert(header::AUTHORIZATION, header::HeaderValue::from_str(&self.token)?);
+        }
+        let mut builder = Client::builder().default_headers(headers);
+        if let Some(ca_certificate) = &self.ca_certificate {
+            builder = builder.add_root_certificate(Certificate::from_pem(&std::fs::read(ca_certificate)?)?);
+        }
+        Ok(builder.build()?)
+    }
+
+    async fn get(&self, path: &str, query: &[(&str, &str)]) -> Result<Response> {
+        let url = self.get_url(path);
+        let response = self.get_client()?.get(&url).query(query).send().await?;
+        if !response.status().is_success() {
+            return Err(Error::HttpRequest(format!("request {} failed with status {}: {}", url, response.status(), response.text().await?)))
+        }
+        Ok(response)
+    }
+
+    async fn list_keys(&self, key_type: &Option<String>) -> Result<String> {
+        let query: Vec<(&str, &str)> = key_type.iter().map(|key_type| ("key_type", key_type.as_str())).collect();
+        let keys: Vec<KeyInfo> = self.get("", &query).await?.json().await?;
+        Ok(format_keys(&keys))
+    }
+
+    async fn show_key(&self, name: &str) -> Result<String> {
+        let key: serde_json::Value = self.get(name, &[]).await?.json().await?;
+        Ok(serde_json::to_string_pretty(&key)?)
+    }
+
+    async fn export_key(&self, name: &str, content: &Option<ExportContent>) -> Result<String> {
+        let content = match content {
+            Some(content) => content.clone(),
+            None => {
+                let key: KeyInfo = self.get(name, &[]).await?.json().await?;
+                if key.key_type == "pgp" { ExportContent::PublicKey } else { ExportContent::Certificate }
+            }
+        };
+        Ok(self.get(&format!("{}/{}", name, content.path()), &[]).await?.text().await?)
+    }
+}
+
+fn format_keys(keys: &[KeyInfo]) -> String {
+    let mut lines = vec![format!("{:<6}{:<22}{:<9}{:<10}{:<42}{}", "ID", "NAME", "TYPE", "STATE", "FINGERPRINT", "EXPIRE AT")];
+    for key in keys.iter() {
+        lines.push(format!("{:<6}{:<22}{:<9}{:<10}{:<42}{}", key.id, key.name, key.key_type, key.key_state, key.fingerprint, key.expire_at));
+    }
+    lines.join("\n")
+}
+
+impl SignCommand for CommandKeysHandler {
+    type CommandValue = CommandKeys;
+
+    fn new(_signal: Arc<AtomicBool>, config: Arc<RwLock<Config>>, command: Self::CommandValue) -> Result<Self> {
+        let config = config.read()?;
+        Ok(CommandKeysHandler {
+            control_server: config.get_string("control_server").unwrap_or_default(),
+            token: CommandAddHandler::read_api_token(&config),
+            ca_certificate: config.get_string("control_server_ca").ok(),
+            action: command.action,
+        })
+    }
+
+    fn validate(&self) -> Result<()> {
+        if !self.control_server.starts_with("http://") && !self.control_server.starts_with("https://") {
+            return Err(Error::ConfigError("control_server should be configured with http or https url".to_string()))
+        }
+        if let KeysAction::List { key_type: Some(key_type) } = &self.action {
+            if !KEY_TYPES.contains(&key_type.as_str()) {
+                return Err(Error::InvalidArgumentError(format!("key type {} is invalid, should be one of {:?}", key_type, KEY_TYPES)))
+            }
+        }
+        Ok(())
+    }
+
+    //result is printed to stdout unless output file is specified when exporting
+    fn handle(&self) -> Result<bool> {
+        let runtime = runtime::Builder::new_current_thread().enable_io().enable_time().build()?;
+        match &self.action {
+            KeysAction::List { key_type } => println!("{}", runtime.block_on(self.list_keys(key_type))?),
+            KeysAction::Show { name } => println!("{}", runtime.block_on(self.show_key(name))?),
+            KeysAction::Export { name, content, output } => {
+                let exported = runtime.block_on(self.export_key(name, content))?;
+                match output {
+                    Some(output) => {
+                        std::fs::write(PathBuf::from(output), exported)?;
+                        info!("key {} exported to {}", name, output);
+                    }
+                    None => print!("{}", exported),
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn get_handler(control_server: &str, action: KeysAction) -> CommandKeysHandler {
+        CommandKeysHandler {
+            control_server: control_server.to_string(),
+            token: "fake_token".to_string(),
+            ca_certificate: None,
+            action,
+        }
+    }
+
+    #[test]
+    fn test_validate_and_get_url() {
+        let handler = get_handler("https://signatrust.test.osinfra.cn/", KeysAction::Show { name: "fake_key".to_string() });
+        handler.validate().expect("validate should work");
+        assert_eq!(handler.get_url(""), "https://signatrust.test.osinfra.cn/api/v1/keys/");
+        assert_eq!(handler.get_url(&format!("fake_key/{}", ExportContent::Crl.path())),
+                   "https://signatrust.test.osinfra.cn/api/v1/keys/fake_key/crl");
+
+        let handler = get_handler("", KeysAction::Show { name: "fake_key".to_string() });
+        handler.validate().expect_err("control server is not configured");
+        let handler = get_handler("http://127.0.0.1:8080", KeysAction::List { key_type: Some("x509".to_string()) });
+        handler.validate().expect_err("key type is invalid");
+        let handler = get_handler("http://127.0.0.1:8080", KeysAction::List { key_type: Some("pgp".to_string()) });
+        handler.validate().expect("validate should work");
+    }
+
+    #[test]
+    fn test_format_keys() {
+        let keys: Vec<KeyInfo> = serde_json::from_str(r#"[{"id": 1, "name": "default-pgp", "description": "", "visibility": "public",
+            "user": 1, "attributes": {}, "key_type": "pgp", "parent_id": null, "fingerprint": "ABCD", "serial_number": null,
+            "create_at": "2023-04-08 13:36:35 UTC", "expire_at": "2025-04-08 13:36:35 UTC", "key_state": "enabled",
+            "user_email": null, "request_delete_users": null, "request_revoke_users": null}]"#).expect("parse keys should work");
+        let table = format_keys(&keys);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("ID    NAME"));
+        assert!(lines[1].starts_with("1     default-pgp           pgp      enabled   ABCD"));
+        assert!(lines[1].ends_with("2025-04-08 13:36:35 UTC"));
+    }
+}
@@ -1,2 +1,5 @@
 pub mod add;
-pub mod traits;
\ No newline at end of file
+pub mod gpg;
+pub mod keys;
+pub mod traits;
+pub mod verify;
\ No newline at end of file
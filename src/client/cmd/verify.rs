@@ -0,0 +1,407 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use clap::Args;
+use config::Config;
+use openssl::cms::{CMSOptions, CmsContentInfo};
+use openssl::stack::Stack;
+use openssl::x509::X509;
+use pgp::composed::{SignedPublicKey, StandaloneSignature};
+use pgp::types::KeyTrait;
+use crate::util::authenticode::AuthenticodeSignature;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, atomic::AtomicBool, RwLock};
+use tokio::runtime;
+
+use super::add;
+use super::gpg;
+use super::traits::SignCommand;
+use crate::client::file_handler::factory::FileHandlerFactory;
+use crate::util::error::{Error, Result};
+use crate::util::options;
+use crate::util::payload::PGP_CLEARTEXT_HEADER;
+use crate::util::sign::{FileType, KeyType};
+
+//file types whose signatures can be verified locally
+const VERIFY_FILE_TYPES: [FileType; 4] = [FileType::Rpm, FileType::KernelModule, FileType::EfiImage, FileType::Generic];
+//signature files of generic file, which are skipped when verifying directory
+const GENERIC_SIGNATURE_EXTENSIONS: [&str; 3] = ["asc", "sig", "p7s"];
+
+#[derive(Args)]
+pub struct CommandVerify {
+    #[arg(long)]
+    #[arg(value_enum)]
+    #[arg(help = "specify the file type for verifying, rpm, kernel-module, efi-image and generic are supported")]
+    file_type: FileType,
+    #[arg(long)]
+    #[arg(value_enum)]
+    #[arg(help = "specify the key type of signatures")]
+    key_type: KeyType,
+    #[arg(long)]
+    #[arg(help = "specify the public key file (or directory of public key files) for pgp key, or the certificate file for x509 key, which are exported from control server")]
+    public_key: String,
+    #[arg(help = "specify the path which will be verified, file and directory are supported")]
+    path: String,
+}
+
+#[derive(Clone)]
+enum VerificationKey {
+    Pgp(Vec<SignedPublicKey>),
+    X509(X509),
+}
+
+#[derive(Clone)]
+pub struct CommandVerifyHandler {
+    file_type: FileType,
+    key_type: KeyType,
+    path: PathBuf,
+    key: VerificationKey,
+}
+
+impl CommandVerifyHandler {
+    fn load_key(key_type: &KeyType, path: &Path) -> Result<VerificationKey> {
+        match key_type {
+            KeyType::Pgp => {
+                let keys = gpg::load_public_keys(path)?;
+                if keys.is_empty() {
+                    return Err(Error::InvalidArgumentError(format!("no valid public key found in {}", path.display())))
+                }
+                Ok(VerificationKey::Pgp(keys))
+            }
+            KeyType::X509 | KeyType::X509EE => {
+                let content = std::fs::read(path)?;
+                Ok(VerificationKey::X509(X509::from_pem(&content).or_else(|_| X509::from_der(&content))?))
+            }
+        }
+    }
+
+    fn get_sign_options(&self) -> HashMap<String, String> {
+        HashMap::from([(options::KEY_TYPE.to_string(), self.key_type.to_string())])
+    }
+
+    //signature files of generic file are matched as generic file as well
+    fn is_signature_file(&self, path: &Path) -> bool {
+        self.file_type == FileType::Generic && path.extension().and_then(|extension| extension.to_str()).map(
+            |extension| GENERIC_SIGNATURE_EXTENSIONS.contains(&extension) && path.with_extension("").is_file()).unwrap_or(false)
+    }
+
+    //the signed sections are reconstructed in the same way as signing and each signature is verified against its section,
+    //EFI image can be signed by several parties, it passes when any of the signatures is made with the specified key.
+    async fn verify_file(&self, path: &PathBuf) -> Result<()> {
+        let handler = FileHandlerFactory::get_handler(&self.file_type);
+        let mut sign_options = self.get_sign_options();
+        let signatures = handler.get_signatures(path, &mut sign_options).await?;
+        let sections = handler.split_data(path, &mut sign_options).await?;
+        if signatures.len() != sections.len() {
+            return Err(Error::VerifyError(format!(
+                "{} signatures found for {} signed sections", signatures.len(), sections.len())))
+        }
+        let mut errors = Vec::new();
+        for (index, (section, signature)) in sections.iter().zip(signatures.iter()).enumerate() {
+            if let Err(err) = self.verify_signature(section, signature) {
+                errors.push(format!("signature {}: {}", index, err));
+            }
+        }
+        let passed = match self.file_type {
+            FileType::EfiImage => errors.len() < signatures.len(),
+            _ => errors.is_empty(),
+        };
+        if !passed {
+            return Err(Error::VerifyError(errors.join("; ")))
+        }
+        Ok(())
+    }
+
+    fn verify_signature(&self, content: &[u8], signature: &[u8]) -> Result<()> {
+        match &self.key {
+            VerificationKey::Pgp(keys) => verify_pgp(keys, content, signature),
+            VerificationKey::X509(certificate) if self.file_type == FileType::EfiImage => verify_authenticode(certificate, content, signature),
+            VerificationKey::X509(certificate) => verify_cms(certificate, content, signature),
+        }
+    }
+}
+
+//canonical text of cleartext signed message, check https://www.rfc-editor.org/rfc/rfc4880#section-7.1
+fn canonical_text(content: &[u8]) -> Vec<u8> {
+    let text = String::from_utf8_lossy(content);
+    let text = text.strip_suffix('\n').unwrap_or(&text);
+    text.split('\n').map(|line| line.strip_suffix('\r').unwrap_or(line).trim_end_matches([' ', '\t']))
+        .collect::<Vec<&str>>().join("\r\n").into_bytes()
+}
+
+//the signature is verified with the primary key or the subkey which matches the issuer
+fn verify_pgp(keys: &[SignedPublicKey], content: &[u8], signature: &[u8]) -> Result<()> {
+    let message = String::from_utf8_lossy(signature);
+    let (content, signature) = if message.trim_start().starts_with(PGP_CLEARTEXT_HEADER) {
+        let (text, signature) = gpg::parse_cleartext(&message)?;
+        if text != canonical_text(content) {
+            return Err(Error::VerifyError("text of cleartext signed message mismatches file content".to_string()))
+        }
+        (text, signature)
+    } else {
+        (content.to_vec(), gpg::parse_signature(signature)?)
+    };
+    let standalone = StandaloneSignature::new(signature.clone());
+    for key in keys {
+        let result = if signature.issuer() == Some(&key.key_id()) {
+            Some(standalone.verify(key, &content))
+        } else {
+            key.public_subkeys.iter().find(|sub| signature.issuer() == Some(&sub.key_id())).map(
+                |sub| standalone.verify(sub, &content))
+        };
+        if let Some(result) = result {
+            return result.map_err(|e| Error::VerifyError(e.to_string()))
+        }
+    }
+    Err(Error::VerifyError(format!("public key of issuer {} not found",
+        signature.issuer().map(|issuer| hex::encode_upper(issuer.as_ref())).unwrap_or_default())))
+}
+
+//the signer is only looked up in the specified certificate, the certificate chain is not verified
+fn verify_cms(certificate: &X509, content: &[u8], signature: &[u8]) -> Result<()> {
+    let mut cms = CmsContentInfo::from_der(signature)?;
+    let mut certificates = Stack::new()?;
+    certificates.push(certificate.clone())?;
+    cms.verify(Some(&certificates), None, Some(content), None,
+               CMSOptions::NOINTERN | CMSOptions::NO_SIGNER_CERT_VERIFY | CMSOptions::BINARY)
+        .map_err(|e| Error::VerifyError(e.to_string()))
+}
+
+//the content is the authenticode digest of image, the signer must be the specified certificate
+fn verify_authenticode(certificate: &X509, digest: &[u8], signature: &[u8]) -> Result<()> {
+    AuthenticodeSignature::from_der(signature)?.verify(certificate, digest)
+}
+
+impl SignCommand for CommandVerifyHandler {
+    type CommandValue = CommandVerify;
+
+    fn new(_signal: Arc<AtomicBool>, _config: Arc<RwLock<Config>>, command: Self::CommandValue) -> Result<Self> {
+        Ok(CommandVerifyHandler {
+            key: CommandVerifyHandler::load_key(&command.key_type, Path::new(&command.public_key))?,
+            file_type: command.file_type,
+            key_type: command.key_type,
+            path: PathBuf::from(&command.path),
+        })
+    }
+
+    fn validate(&self) -> Result<()> {
+        if !VERIFY_FILE_TYPES.contains(&self.file_type) {
+            return Err(Error::InvalidArgumentError(format!("verifying {} file is not supported", self.file_type)))
+        }
+        FileHandlerFactory::get_handler(&self.file_type).validate_options(&self.get_sign_options())
+    }
+
+    //result of each file is printed, error is returned if any of the files fails to be verified
+    fn handle(&self) -> Result<bool> {
+        let files: Vec<PathBuf> = add::collect_files(&self.file_type, &self.path)?.into_iter().filter(
+            |file| !self.is_signature_file(file)).collect();
+        let runtime = runtime::Builder::new_current_thread().enable_io().build()?;
+        let mut failed = 0;
+        for file in files.iter() {
+            match runtime.block_on(self.verify_file(file)) {
+                Ok(_) => println!("{}: OK", file.display()),
+                Err(err) => {
+                    println!("{}: FAILED, {}", file.display(), err);
+                    failed += 1;
+                }
+            }
+        }
+        info!("verified {} files, failed {} files", files.len(), failed);
+        if failed != 0 {
+            return Err(Error::VerifyError(format!("{} of {} files failed verification", failed, files.len())))
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::Utc;
+    use openssl::asn1::Asn1Time;
+    use openssl::bn::BigNum;
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::{PKey, Private};
+    use openssl::rsa::Rsa;
+    use openssl::x509::{X509Builder, X509NameBuilder};
+    use pgp::composed::{key::SecretKeyParamsBuilder, KeyType as PgpKeyType, SignedSecretKey};
+    use pgp::crypto::hash::HashAlgorithm;
+    use pgp::packet::{SignatureConfig, SignatureType, SignatureVersion, Subpacket};
+    use pgp::types::SecretKeyTrait;
+    use crate::util::authenticode;
+    use const_oid::db::rfc5912;
+    use der::asn1::Null;
+    use spki::AlgorithmIdentifierOwned;
+    use std::env;
+    use std::io::Cursor;
+    use uuid::Uuid;
+
+    fn generate_pgp_key() -> (SignedSecretKey, SignedPublicKey) {
+        let mut key_params = SecretKeyParamsBuilder::default();
+        key_params
+            .key_type(PgpKeyType::EdDSA)
+            .can_create_certificates(false)
+            .can_sign(true)
+            .primary_user_id("fake_name <fake_email@email.com>".to_string());
+        let secret_key = key_params.build().expect("build key params").generate().expect("generate key");
+        let signed_secret_key = secret_key.sign(String::new).expect("sign secret key");
+        let signed_public_key = signed_secret_key.public_key().sign(&signed_secret_key, String::new).expect("sign public key");
+        (signed_secret_key, signed_public_key)
+    }
+
+    fn sign_pgp(key: &SignedSecretKey, typ: SignatureType, content: &[u8]) -> Vec<u8> {
+        let now = Utc::now();
+        let signature = SignatureConfig {
+            version: SignatureVersion::V4,
+            typ,
+            pub_alg: key.algorithm(),
+            hash_alg: HashAlgorithm::SHA2_256,
+            issuer: Some(key.key_id()),
+            created: Some(now),
+            unhashed_subpackets: vec![],
+            hashed_subpackets: vec![
+                Subpacket::SignatureCreationTime(now),
+                Subpacket::Issuer(key.key_id()),
+            ],
+        }.sign(key, String::new, Cursor::new(content)).expect("sign content");
+        StandaloneSignature::new(signature).to_armored_bytes(None).expect("armor signature")
+    }
+
+    fn generate_certificate() -> (X509, PKey<Private>) {
+        let private_key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut builder = X509Builder::new().unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "signatrust").unwrap();
+        let name = name.build();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&private_key).unwrap();
+        builder.set_serial_number(&BigNum::from_u32(rand::random::<u32>()).unwrap().to_asn1_integer().unwrap()).unwrap();
+        builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        builder.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+        builder.sign(&private_key, MessageDigest::sha256()).unwrap();
+        (builder.build(), private_key)
+    }
+
+    #[test]
+    fn test_verify_pgp() {
+        let (secret_key, public_key) = generate_pgp_key();
+        let (_, other_key) = generate_pgp_key();
+        let content = "SHA256 (file.iso) = 0123456789abcdef  \n".as_bytes();
+        let signature = sign_pgp(&secret_key, SignatureType::Binary, content);
+        verify_pgp(&[other_key.clone(), public_key.clone()], content, &signature).expect("signature matches");
+        verify_pgp(std::slice::from_ref(&public_key), "fake content".as_bytes(), &signature).expect_err("signature mismatches fake content");
+        verify_pgp(&[other_key], content, &signature).expect_err("public key not found");
+
+        let text = canonical_text(content);
+        assert_eq!(text, "SHA256 (file.iso) = 0123456789abcdef".as_bytes());
+        let message = format!("{}\nHash: SHA256\n\nSHA256 (file.iso) = 0123456789abcdef\n{}", PGP_CLEARTEXT_HEADER,
+                              String::from_utf8(sign_pgp(&secret_key, SignatureType::Text, &text)).unwrap());
+        verify_pgp(std::slice::from_ref(&public_key), content, message.as_bytes()).expect("cleartext signature matches");
+        verify_pgp(&[public_key], "fake content".as_bytes(), message.as_bytes()).expect_err("cleartext mismatches fake content");
+    }
+
+    #[tokio::test]
+    async fn test_verify_generic_file_with_cms() {
+        let (certificate, private_key) = generate_certificate();
+        let (other_certificate, _) = generate_certificate();
+        let path = env::temp_dir().join(format!("{}.bin", Uuid::new_v4()));
+        let content = "firmware".as_bytes();
+        std::fs::write(&path, content).unwrap();
+        let signature = CmsContentInfo::sign(
+            Some(&certificate), Some(&private_key), None, Some(content),
+            CMSOptions::DETACHED | CMSOptions::BINARY | CMSOptions::CMS_NOCERTS).unwrap().to_der().unwrap();
+        let signature_path = PathBuf::from(format!("{}.p7s", path.display()));
+        std::fs::write(&signature_path, &signature).unwrap();
+
+        let mut handler = CommandVerifyHandler {
+            file_type: FileType::Generic,
+            key_type: KeyType::X509EE,
+            path: path.clone(),
+            key: VerificationKey::X509(certificate),
+        };
+        handler.validate().expect("verify generic file with x509 key");
+        handler.verify_file(&path).await.expect("signature matches");
+        assert!(handler.is_signature_file(&signature_path));
+        assert!(!handler.is_signature_file(&path));
+        std::fs::write(&path, "fake content").unwrap();
+        handler.verify_file(&path).await.expect_err("signature mismatches fake content");
+        std::fs::write(&path, content).unwrap();
+        handler.key = VerificationKey::X509(other_certificate);
+        handler.verify_file(&path).await.expect_err("signed by another certificate");
+    }
+
+    #[test]
+    fn test_verify_authenticode() {
+        let (certificate, private_key) = generate_certificate();
+        let (other_certificate, _) = generate_certificate();
+        let digest = openssl::sha::sha256("image".as_bytes()).to_vec();
+        let signature = sign_authenticode_signed_data(&certificate, &private_key, &digest, "sha2_256");
+        verify_authenticode(&certificate, &digest, &signature).expect("signature matches");
+        verify_authenticode(&certificate, &[0; 32], &signature).expect_err("signature mismatches digest");
+        verify_authenticode(&other_certificate, &digest, &signature).expect_err("signed by another certificate");
+    }
+
+    fn sign_authenticode_signed_data(certificate: &X509, private_key: &PKey<Private>, digest: &[u8], algorithm: &str) -> Vec<u8> {
+        let signature_algorithm = AlgorithmIdentifierOwned { oid: rfc5912::RSA_ENCRYPTION, parameters: Some(Null.into()) };
+        let message_digest = match algorithm {
+            "sha2_384" => MessageDigest::sha384(),
+            _ => MessageDigest::sha256(),
+        };
+        authenticode::sign(certificate, algorithm, signature_algorithm, digest, |data| {
+            let mut signer = openssl::sign::Signer::new(message_digest, private_key)?;
+            Ok(signer.sign_oneshot_to_vec(data)?)
+        }).unwrap()
+    }
+
+    fn sign_authenticode(certificate: &X509, private_key: &PKey<Private>, digest: Vec<u8>, algorithm: &str) -> Vec<u8> {
+        authenticode::encode_win_certificate(&sign_authenticode_signed_data(certificate, private_key, &digest, algorithm))
+    }
+
+    #[tokio::test]
+    async fn test_verify_efi_image_with_multiple_signatures() {
+        let (certificate, private_key) = generate_certificate();
+        let (other_certificate, other_private_key) = generate_certificate();
+        let (unknown_certificate, _) = generate_certificate();
+        let efi_handler = FileHandlerFactory::get_handler(&FileType::EfiImage);
+        let path = env::current_dir().unwrap().join("test_assets").join("shimx64.efi");
+        let mut options = HashMap::from([(options::DIGEST_ALGORITHM.to_string(), "sha2_384".to_string())]);
+        let sha384 = efi_handler.split_data(&path, &mut options).await.unwrap().remove(0);
+        options.insert(options::DIGEST_ALGORITHM.to_string(), "sha2_256".to_string());
+        let sha256 = efi_handler.split_data(&path, &mut options).await.unwrap().remove(0);
+        //the image is signed with sha384 by one party and sha256 by another one
+        let signatures = vec![
+            sign_authenticode(&certificate, &private_key, sha384, "sha2_384"),
+            sign_authenticode(&other_certificate, &other_private_key, sha256, "sha2_256"),
+        ];
+        let (temp_file, _) = efi_handler.assemble_data(&path, signatures, &env::temp_dir(), &options).await.unwrap().remove(0);
+        let signed = PathBuf::from(temp_file);
+
+        let mut handler = CommandVerifyHandler {
+            file_type: FileType::EfiImage,
+            key_type: KeyType::X509EE,
+            path: signed.clone(),
+            key: VerificationKey::X509(certificate),
+        };
+        handler.verify_file(&signed).await.expect("sha384 signature matches");
+        handler.key = VerificationKey::X509(other_certificate);
+        handler.verify_file(&signed).await.expect("sha256 signature matches");
+        handler.key = VerificationKey::X509(unknown_certificate);
+        let err = handler.verify_file(&signed).await.expect_err("none of the signatures is made by the certificate");
+        assert!(err.to_string().contains("signature 0:") && err.to_string().contains("signature 1:"));
+    }
+}
@@ -0,0 +1,158 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use super::traits::FileHandler;
+use crate::util::sign::KeyType;
+use crate::util::error::{Error, Result};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::fs;
+use uuid::Uuid;
+
+use crate::util::options;
+
+const DETACHED_EXTENSION: &str = "gpg";
+const INLINE_FILE_NAME: &str = "InRelease";
+
+#[derive(Clone)]
+pub struct AptReleaseFileHandler {}
+
+impl AptReleaseFileHandler {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+/* the Release file is signed twice, the first section is used for detached armored signature 'Release.gpg'
+ * and the second section is used for cleartext signed message 'InRelease' */
+#[async_trait]
+impl FileHandler for AptReleaseFileHandler {
+    fn validate_options(&self, sign_options: &HashMap<String, String>) -> Result<()> {
+        if let Some(key_type) = sign_options.get(options::KEY_TYPE) {
+            if key_type != KeyType::Pgp.to_string().as_str() {
+                return Err(Error::InvalidArgumentError(
+                    "apt release file only support pgp key type".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn split_data(
+        &self,
+        path: &PathBuf,
+        _sign_options: &mut HashMap<String, String>,
+    ) -> Result<Vec<Vec<u8>>> {
+        let content = fs::read(path).await?;
+        if std::str::from_utf8(&content).is_err() {
+            return Err(Error::SplitFileError(format!("apt release file {} is not valid utf-8 text", path.display())))
+        }
+        Ok(vec![content.clone(), content])
+    }
+
+    fn section_options(&self, section: usize, sign_options: &HashMap<String, String>) -> HashMap<String, String> {
+        let mut section_options = sign_options.clone();
+        section_options.insert(options::DETACHED.to_string(), "true".to_string());
+        if section == 1 {
+            section_options.insert(options::SIGNATURE_FORMAT.to_string(), options::SIGNATURE_FORMAT_CLEARTEXT.to_string());
+        } else {
+            section_options.remove(options::SIGNATURE_FORMAT);
+        }
+        section_options
+    }
+
+    async fn assemble_data(
+        &self,
+        path: &PathBuf,
+        data: Vec<Vec<u8>>,
+        temp_dir: &PathBuf,
+        _sign_options: &HashMap<String, String>,
+    ) -> Result<Vec<(String, String)>> {
+        if data.len() != 2 {
+            return Err(Error::AssembleFileError(format!("apt release file expects 2 signatures, got {}", data.len())))
+        }
+        let detached_file = temp_dir.join(Uuid::new_v4().to_string());
+        fs::write(detached_file.clone(), &data[0]).await?;
+        let inline_file = temp_dir.join(Uuid::new_v4().to_string());
+        fs::write(inline_file.clone(), &data[1]).await?;
+        Ok(vec![
+            (
+                detached_file.as_path().display().to_string(),
+                format!("{}.{}", path.as_path().display(), DETACHED_EXTENSION),
+            ),
+            (
+                inline_file.as_path().display().to_string(),
+                path.with_file_name(INLINE_FILE_NAME).as_path().display().to_string(),
+            ),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_validate_options() {
+        let mut options = HashMap::new();
+        let handler = AptReleaseFileHandler::new();
+        options.insert(options::KEY_TYPE.to_string(), KeyType::Pgp.to_string());
+        assert!(handler.validate_options(&options).is_ok());
+
+        options.insert(options::KEY_TYPE.to_string(), KeyType::X509EE.to_string());
+        let result = handler.validate_options(&options);
+        assert!(result.is_err());
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "invalid argument: apt release file only support pgp key type"
+        );
+    }
+
+    #[test]
+    fn test_section_options() {
+        let handler = AptReleaseFileHandler::new();
+        let options = HashMap::from([(options::DETACHED.to_string(), "false".to_string())]);
+        let detached = handler.section_options(0, &options);
+        assert_eq!(detached.get(options::DETACHED), Some(&"true".to_string()));
+        assert_eq!(detached.get(options::SIGNATURE_FORMAT), None);
+        let cleartext = handler.section_options(1, &options);
+        assert_eq!(cleartext.get(options::SIGNATURE_FORMAT), Some(&options::SIGNATURE_FORMAT_CLEARTEXT.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_split_and_assemble_data() {
+        let handler = AptReleaseFileHandler::new();
+        let mut options = HashMap::new();
+        let dir = env::temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir(&dir).expect("create release dir should work");
+        let path = dir.join("Release");
+        std::fs::write(&path, "Origin: openEuler\n").expect("write release file should work");
+        let data = handler.split_data(&path, &mut options).await.expect("split release file should work");
+        assert_eq!(data, vec![b"Origin: openEuler\n".to_vec(), b"Origin: openEuler\n".to_vec()]);
+
+        let result = handler.assemble_data(
+            &path, vec![b"detached".to_vec(), b"cleartext".to_vec()], &env::temp_dir(), &options).await.expect("assemble release file should work");
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].1, dir.join("Release.gpg").display().to_string());
+        assert_eq!(std::fs::read(&result[0].0).unwrap(), b"detached".to_vec());
+        assert_eq!(result[1].1, dir.join("InRelease").display().to_string());
+        assert_eq!(std::fs::read(&result[1].0).unwrap(), b"cleartext".to_vec());
+
+        let _ = handler.assemble_data(&path, vec![b"detached".to_vec()], &env::temp_dir(), &options).await.expect_err("missing signature should fail");
+    }
+}
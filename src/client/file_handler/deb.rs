@@ -0,0 +1,253 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use super::traits::FileHandler;
+use crate::util::sign::KeyType;
+use crate::util::error::{Error, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::fs;
+use uuid::Uuid;
+
+use crate::util::options;
+
+//Stands for ASCII Armored file
+const FILE_EXTENSION: &str = "asc";
+const AR_MAGIC: &[u8] = b"!<arch>\n";
+const AR_HEADER_SIZE: usize = 60;
+const AR_HEADER_ENDING: &[u8] = b"`\n";
+const DEBIAN_BINARY: &str = "debian-binary";
+const CONTROL_PREFIX: &str = "control.tar";
+const DATA_PREFIX: &str = "data.tar";
+//member name used by debsigs for the signature of package origin
+const SIGNATURE_MEMBER: &str = "_gpgorigin";
+
+#[derive(Debug)]
+struct ArMember {
+    name: String,
+    header: Vec<u8>,
+    data: Vec<u8>,
+}
+
+impl ArMember {
+    fn new(name: &str, data: Vec<u8>) -> Self {
+        let header = format!("{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}",
+                             name, Utc::now().timestamp(), 0, 0, 100644, data.len());
+        let mut header = header.into_bytes();
+        header.extend_from_slice(AR_HEADER_ENDING);
+        Self {
+            name: name.to_string(),
+            header,
+            data,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct DebFileHandler {}
+
+impl DebFileHandler {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    fn parse_archive(content: &[u8]) -> Result<Vec<ArMember>> {
+        if !content.starts_with(AR_MAGIC) {
+            return Err(Error::SplitFileError("invalid deb package, ar magic number not found".to_string()))
+        }
+        let mut members = vec![];
+        let mut offset = AR_MAGIC.len();
+        while offset < content.len() {
+            if offset + AR_HEADER_SIZE > content.len() {
+                return Err(Error::SplitFileError("invalid deb package, truncated ar member header".to_string()))
+            }
+            let header = &content[offset..offset + AR_HEADER_SIZE];
+            if &header[58..] != AR_HEADER_ENDING {
+                return Err(Error::SplitFileError("invalid deb package, malformed ar member header".to_string()))
+            }
+            //GNU ar terminates the member name with '/'
+            let name = String::from_utf8_lossy(&header[0..16]).trim_end().trim_end_matches('/').to_string();
+            let size: usize = String::from_utf8_lossy(&header[48..58]).trim().parse().map_err(
+                |_| Error::SplitFileError(format!("invalid deb package, invalid size of member {}", name)))?;
+            let start = offset + AR_HEADER_SIZE;
+            if start + size > content.len() {
+                return Err(Error::SplitFileError(format!("invalid deb package, truncated member {}", name)))
+            }
+            members.push(ArMember {
+                name,
+                header: header.to_vec(),
+                data: content[start..start + size].to_vec(),
+            });
+            //member data is aligned to even byte boundary
+            offset = start + size + size % 2;
+        }
+        Ok(members)
+    }
+
+    fn write_archive(members: &[ArMember]) -> Vec<u8> {
+        let mut content = AR_MAGIC.to_vec();
+        for member in members.iter() {
+            content.extend_from_slice(&member.header);
+            content.extend_from_slice(&member.data);
+            if member.data.len() % 2 != 0 {
+                content.push(b'\n');
+            }
+        }
+        content
+    }
+
+    //debsigs signs the concatenation of 'debian-binary', 'control.tar*' and 'data.tar*' in order
+    fn get_signed_content(members: &[ArMember]) -> Result<Vec<u8>> {
+        let mut content = vec![];
+        for prefix in [DEBIAN_BINARY, CONTROL_PREFIX, DATA_PREFIX] {
+            let member = members.iter().find(|m| m.name.starts_with(prefix)).ok_or_else(
+                || Error::SplitFileError(format!("invalid deb package, member {} not found", prefix)))?;
+            content.extend_from_slice(&member.data);
+        }
+        Ok(content)
+    }
+}
+
+#[async_trait]
+impl FileHandler for DebFileHandler {
+    fn validate_options(&self, sign_options: &HashMap<String, String>) -> Result<()> {
+        if let Some(key_type) = sign_options.get(options::KEY_TYPE) {
+            if key_type != KeyType::Pgp.to_string().as_str() {
+                return Err(Error::InvalidArgumentError(
+                    "deb file only support pgp key type".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn split_data(
+        &self,
+        path: &PathBuf,
+        _sign_options: &mut HashMap<String, String>,
+    ) -> Result<Vec<Vec<u8>>> {
+        let content = fs::read(path).await?;
+        let members = DebFileHandler::parse_archive(&content)?;
+        Ok(vec![DebFileHandler::get_signed_content(&members)?])
+    }
+
+    /* detached signature is saved in another .asc file, otherwise the '_gpgorigin' member is added or replaced */
+    async fn assemble_data(
+        &self,
+        path: &PathBuf,
+        data: Vec<Vec<u8>>,
+        temp_dir: &PathBuf,
+        sign_options: &HashMap<String, String>,
+    ) -> Result<Vec<(String, String)>> {
+        let temp_file = temp_dir.join(Uuid::new_v4().to_string());
+        if let Some(detached) = sign_options.get(options::DETACHED) {
+            if detached == "true" {
+                fs::write(temp_file.clone(), &data[0]).await?;
+                return Ok(vec![(
+                    temp_file.as_path().display().to_string(),
+                    format!("{}.{}", path.as_path().display(), FILE_EXTENSION),
+                )])
+            }
+        }
+        let content = fs::read(path).await?;
+        let mut members: Vec<ArMember> = DebFileHandler::parse_archive(&content)?.into_iter().filter(
+            |m| m.name != SIGNATURE_MEMBER).collect();
+        members.push(ArMember::new(SIGNATURE_MEMBER, data[0].clone()));
+        fs::write(temp_file.clone(), DebFileHandler::write_archive(&members)).await?;
+        Ok(vec![(
+            temp_file.as_path().display().to_string(),
+            path.as_path().display().to_string(),
+        )])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+
+    fn generate_deb_package(name: &str) -> PathBuf {
+        let members = vec![
+            ArMember::new("debian-binary", b"2.0\n".to_vec()),
+            ArMember::new("control.tar.gz", b"fake control".to_vec()),
+            ArMember::new("data.tar.xz", b"fake data content".to_vec()),
+        ];
+        let path = env::temp_dir().join(name);
+        std::fs::write(&path, DebFileHandler::write_archive(&members)).expect("write deb package should work");
+        path
+    }
+
+    #[test]
+    fn test_validate_options() {
+        let mut options = HashMap::new();
+        let handler = DebFileHandler::new();
+        options.insert(options::KEY_TYPE.to_string(), KeyType::Pgp.to_string());
+        assert!(handler.validate_options(&options).is_ok());
+
+        options.insert(options::KEY_TYPE.to_string(), KeyType::X509EE.to_string());
+        let result = handler.validate_options(&options);
+        assert!(result.is_err());
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "invalid argument: deb file only support pgp key type"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_split_and_assemble_inline_signature() {
+        let handler = DebFileHandler::new();
+        let path = generate_deb_package(&format!("{}.deb", Uuid::new_v4()));
+        let mut options = HashMap::new();
+        let data = handler.split_data(&path, &mut options).await.expect("split deb package should work");
+        assert_eq!(data, vec![b"2.0\nfake controlfake data content".to_vec()]);
+
+        //sign twice and the signature member should be replaced
+        for signature in [b"fake signature".to_vec(), b"new signature".to_vec()] {
+            let (temp_file, file_name) = handler.assemble_data(
+                &path, vec![signature.clone()], &env::temp_dir(), &options).await.expect("assemble deb package should work").remove(0);
+            assert_eq!(file_name, path.display().to_string());
+            std::fs::copy(&temp_file, &path).expect("copy signed package should work");
+            let members = DebFileHandler::parse_archive(&std::fs::read(&path).unwrap()).expect("parse signed package should work");
+            assert_eq!(members.len(), 4);
+            assert_eq!(members[3].name, SIGNATURE_MEMBER);
+            assert_eq!(members[3].data, signature);
+        }
+        let data_after_signed = handler.split_data(&path, &mut options).await.expect("split signed deb package should work");
+        assert_eq!(data, data_after_signed);
+    }
+
+    #[tokio::test]
+    async fn test_assemble_detached_signature() {
+        let handler = DebFileHandler::new();
+        let path = generate_deb_package(&format!("{}.deb", Uuid::new_v4()));
+        let options = HashMap::from([(options::DETACHED.to_string(), "true".to_string())]);
+        let (temp_file, file_name) = handler.assemble_data(
+            &path, vec![b"fake signature".to_vec()], &env::temp_dir(), &options).await.expect("assemble detached signature should work").remove(0);
+        assert_eq!(file_name, format!("{}.asc", path.display()));
+        assert_eq!(std::fs::read(temp_file).unwrap(), b"fake signature".to_vec());
+    }
+
+    #[test]
+    fn test_parse_invalid_package() {
+        let _ = DebFileHandler::parse_archive(b"invalid content").expect_err("invalid magic should fail");
+        let mut content = AR_MAGIC.to_vec();
+        content.extend_from_slice(b"debian-binary");
+        let _ = DebFileHandler::parse_archive(&content).expect_err("truncated header should fail");
+        let _ = DebFileHandler::get_signed_content(&[ArMember::new("debian-binary", vec![])]).expect_err("missing members should fail");
+    }
+}
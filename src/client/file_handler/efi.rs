@@ -2,8 +2,11 @@ use super::traits::FileHandler;
 use crate::util::options;
 use crate::util::sign::{SignType, KeyType};
 use crate::util::error::{Error, Result};
+use crate::util::digest::SHA2_256;
 use async_trait::async_trait;
 use efi_signer::{DigestAlgorithm, EfiImage};
+use openssl::hash::{Hasher, MessageDigest};
+use crate::util::authenticode::AuthenticodeSignature;
 use std::collections::HashMap;
 use std::fs::read;
 use std::path::PathBuf;
@@ -15,6 +18,92 @@ impl EfiFileHandler {
     pub fn new() -> Self {
         Self {}
     }
+
+    fn is_append(sign_options: &HashMap<String, String>) -> bool {
+        sign_options.get(options::EFI_APPEND).map(|append| append == "true").unwrap_or(false)
+    }
+
+    //key names used for signing the image besides the primary key
+    fn get_extra_key_names(sign_options: &HashMap<String, String>) -> Vec<String> {
+        match sign_options.get(options::EFI_EXTRA_KEY_NAMES) {
+            Some(names) => names.split(',').map(|name| name.to_string()).collect(),
+            None => vec![],
+        }
+    }
+
+    fn get_message_digest(algorithm: &str) -> Result<MessageDigest> {
+        match algorithm {
+            "sha2_256" => Ok(MessageDigest::sha256()),
+            "sha2_384" => Ok(MessageDigest::sha384()),
+            "sha2_512" => Ok(MessageDigest::sha512()),
+            _ => Err(Error::InvalidArgumentError(format!(
+                "EFI image only support sha2_256, sha2_384 and sha2_512 digest algorithm, got {}", algorithm))),
+        }
+    }
+
+    //digest algorithm of the authenticode signature
+    fn get_signature_algorithm(signature: &[u8]) -> Result<&'static str> {
+        AuthenticodeSignature::from_der(signature)?.digest_algorithm()
+    }
+
+    //authenticode digest of image, it's identical to `EfiImage::compute_digest` which only supports up to SHA-256
+    fn compute_digest(pe: &EfiImage, algorithm: MessageDigest) -> Result<Vec<u8>> {
+        let header = pe.pe.header.optional_header.ok_or(
+            Error::EFIError("missing optional header in EFI image".to_string()))?;
+        let raw = &pe.raw;
+        let checksum = pe.checksum.offset;
+        let directory = pe.cert_data_directory.offset;
+        let size_of_headers = header.windows_fields.size_of_headers as usize;
+        //the offsets and sizes come from the image, truncated or malformed images are rejected instead of panicking
+        let slice = |start: usize, end: usize| -> Result<&[u8]> {
+            raw.get(start..end).ok_or_else(
+                || Error::EFIError(format!("invalid range {}..{} in EFI image of size {}", start, end, raw.len())))
+        };
+        let mut hasher = Hasher::new(algorithm)?;
+        //checksum and certificate table entry are excluded from the image header
+        hasher.update(slice(0, checksum)?)?;
+        hasher.update(slice(checksum + pe.checksum.data.len(), directory)?)?;
+        hasher.update(slice(directory + pe.cert_data_directory.data.len(), size_of_headers)?)?;
+        //sections are hashed in the order of their file offsets
+        let mut sections: Vec<_> = pe.pe.sections.iter().filter(|section| section.size_of_raw_data != 0).collect();
+        sections.sort_by_key(|section| section.pointer_to_raw_data);
+        let mut hashed = size_of_headers;
+        for section in sections {
+            let start = section.pointer_to_raw_data as usize;
+            hasher.update(slice(start, start + section.size_of_raw_data as usize)?)?;
+            hashed += section.size_of_raw_data as usize;
+        }
+        //extra data after sections are hashed except the certificate table
+        if raw.len() > hashed {
+            match header.data_directories.get_certificate_table() {
+                Some(table) => {
+                    hasher.update(slice(hashed, table.virtual_address as usize)?)?;
+                    hasher.update(slice(table.virtual_address as usize + table.size as usize, raw.len())?)?;
+                }
+                None => hasher.update(&raw[hashed..])?,
+            }
+        }
+        Ok(hasher.finish()?.to_vec())
+    }
+
+    //remove the existing signatures, both the certificate table and its data directory entry are cleared,
+    //the PE checksum is recomputed since it covers the whole image
+    fn strip_signatures(buf: &[u8]) -> Result<Vec<u8>> {
+        let pe = EfiImage::parse(buf)?;
+        let table = match pe.cert_table {
+            Some(ref table) => table,
+            None => return Ok(buf.to_vec()),
+        };
+        let end = buf.len().min(table.offset + table.data.len());
+        let mut stripped = buf[..table.offset].to_vec();
+        stripped.extend_from_slice(&buf[end..]);
+        let directory = pe.cert_data_directory.offset;
+        stripped[directory..directory + pe.cert_data_directory.data.len()].fill(0);
+        let checksum = EfiImage::parse(&stripped)?.compute_check_sum()?;
+        let offset = pe.checksum.offset;
+        stripped[offset..offset + pe.checksum.data.len()].copy_from_slice(&checksum.to_le_bytes());
+        Ok(stripped)
+    }
 }
 
 #[async_trait]
@@ -43,22 +132,90 @@ impl FileHandler for EfiFileHandler {
                 ));
             }
         }
+
+        if let Some(algorithm) = sign_options.get(options::DIGEST_ALGORITHM) {
+            EfiFileHandler::get_message_digest(algorithm)?;
+        }
+
+        if EfiFileHandler::get_extra_key_names(sign_options).iter().any(|name| name.is_empty()) {
+            return Err(Error::InvalidArgumentError(
+                "EFI image extra key name can't be empty".to_string(),
+            ));
+        }
         Ok(())
     }
 
     async fn split_data(
         &self,
         path: &PathBuf,
-        _sign_options: &mut HashMap<String, String>,
+        sign_options: &mut HashMap<String, String>,
     ) -> Result<Vec<Vec<u8>>> {
         let buf = read(path)?;
         let pe = EfiImage::parse(&buf)?;
-        let digest = match pe.get_digest_algo()? {
-            Some(algo) => pe.compute_digest(algo)?,
-            None => pe.compute_digest(DigestAlgorithm::Sha256)?,
+        //the existing signatures are verified against the digests computed with their own algorithms
+        if let Some(algorithms) = sign_options.get(options::EFI_SIGNATURE_ALGORITHMS) {
+            return algorithms.split(',').map(|algorithm| EfiFileHandler::compute_digest(
+                &pe, EfiFileHandler::get_message_digest(algorithm)?)).collect();
+        }
+        //the digest algorithm of existing signatures is followed only when they are kept and no algorithm is specified,
+        //the chosen algorithm is passed to server for generating the authenticode signature.
+        let algorithm = match sign_options.get(options::DIGEST_ALGORITHM) {
+            Some(algorithm) => algorithm.clone(),
+            None if EfiFileHandler::is_append(sign_options) => match pe.get_digest_algo()? {
+                Some(DigestAlgorithm::Sha256) | None => SHA2_256.to_string(),
+                Some(algo) => return Err(Error::SplitFileError(format!(
+                    "existing {} signature can't be appended, please specify the digest algorithm", algo))),
+            },
+            None => SHA2_256.to_string(),
         };
+        let digest = EfiFileHandler::compute_digest(&pe, EfiFileHandler::get_message_digest(&algorithm)?)?;
+        sign_options.insert(options::DIGEST_ALGORITHM.to_string(), algorithm);
         info!("file {} digest {:x?}", path.as_path().display().to_string(), digest.as_slice());
-        Ok(vec![digest])
+        //the same digest is signed by the primary key and each of the extra keys
+        Ok(vec![digest; EfiFileHandler::get_extra_key_names(sign_options).len() + 1])
+    }
+
+    //authenticode signatures of image, the digest algorithm of each signature is recorded so that the image digest
+    //is computed with it, signatures with unsupported digest algorithm (e.g. sha1) are skipped.
+    async fn get_signatures(
+        &self,
+        path: &PathBuf,
+        sign_options: &mut HashMap<String, String>,
+    ) -> Result<Vec<Vec<u8>>> {
+        let buf = read(path)?;
+        let pe = EfiImage::parse(&buf)?;
+        if pe.signatures.is_empty() {
+            return Err(Error::SplitFileError(format!("EFI image {} is not signed", path.display())));
+        }
+        let mut signatures = Vec::new();
+        let mut algorithms = Vec::new();
+        for signature in pe.signatures.iter() {
+            let signature = signature.0.get_certificate().to_vec();
+            match EfiFileHandler::get_signature_algorithm(&signature) {
+                Ok(algorithm) => {
+                    signatures.push(signature);
+                    algorithms.push(algorithm);
+                }
+                Err(err) => warn!("skip signature of EFI image {}: {}", path.display(), err),
+            }
+        }
+        if signatures.is_empty() {
+            return Err(Error::SplitFileError(format!(
+                "no signature with supported digest algorithm found in EFI image {}", path.display())));
+        }
+        sign_options.insert(options::EFI_SIGNATURE_ALGORITHMS.to_string(), algorithms.join(","));
+        sign_options.remove(options::EFI_EXTRA_KEY_NAMES);
+        Ok(signatures)
+    }
+
+    fn section_options(&self, section: usize, sign_options: &HashMap<String, String>) -> HashMap<String, String> {
+        let mut options = sign_options.clone();
+        if section > 0 {
+            if let Some(key_name) = EfiFileHandler::get_extra_key_names(sign_options).get(section - 1) {
+                options.insert(options::KEY_NAME.to_string(), key_name.clone());
+            }
+        }
+        options
     }
 
     async fn assemble_data(
@@ -66,10 +223,13 @@ impl FileHandler for EfiFileHandler {
         path: &PathBuf,
         data: Vec<Vec<u8>>,
         temp_dir: &PathBuf,
-        _sign_options: &HashMap<String, String>,
-    ) -> Result<(String, String)> {
+        sign_options: &HashMap<String, String>,
+    ) -> Result<Vec<(String, String)>> {
         let temp_file = temp_dir.join(Uuid::new_v4().to_string());
-        let buf = read(path)?;
+        let buf = match EfiFileHandler::is_append(sign_options) {
+            true => read(path)?,
+            false => EfiFileHandler::strip_signatures(&read(path)?)?,
+        };
         let pe = EfiImage::parse(&buf)?;
 
         let mut signatures :Vec<efi_signer::Signature> = Vec::new();
@@ -83,10 +243,10 @@ impl FileHandler for EfiFileHandler {
 
         file.write_all(&new_pe)?;
 
-        Ok((
+        Ok(vec![(
             temp_file.as_path().display().to_string(),
             path.display().to_string(),
-        ))
+        )])
     }
 }
 
@@ -165,7 +325,7 @@ mod test {
             .assemble_data(&path, vec![signature_buf], &temp_dir, &options)
             .await;
         assert!(result.is_ok());
-        let (temp_file, file_name) = result.expect("efi sign should work");
+        let (temp_file, file_name) = result.expect("efi sign should work").remove(0);
         assert_eq!(temp_file.starts_with(temp_dir.to_str().unwrap()), true);
         assert_eq!(file_name.ends_with("shimx64.efi"), true);
         assert_eq!(
@@ -173,4 +333,137 @@ mod test {
             read(current_dir.join("test_assets").join("shimx64.efi.signed")).unwrap()
         );
     }
+
+    #[tokio::test]
+    async fn test_assemble_data_with_signed_image() {
+        let current_dir = env::current_dir().expect("get current dir failed");
+        let signature_buf = read(current_dir.join("test_assets").join("efi.sign")).unwrap();
+        let path = current_dir.join("test_assets").join("shimx64.efi.signed");
+        let handler = EfiFileHandler::new();
+        let mut options = HashMap::new();
+        let temp_dir = env::temp_dir();
+
+        //existing signature is replaced by default
+        let (temp_file, _) = handler
+            .assemble_data(&path, vec![signature_buf.clone()], &temp_dir, &options)
+            .await.expect("efi sign should work").remove(0);
+        assert_eq!(read(temp_file).unwrap(), read(&path).unwrap());
+
+        //existing signature is kept in append mode
+        options.insert(options::EFI_APPEND.to_string(), "true".to_string());
+        let (temp_file, _) = handler
+            .assemble_data(&path, vec![signature_buf.clone()], &temp_dir, &options)
+            .await.expect("efi sign should work").remove(0);
+        let buf = read(temp_file).unwrap();
+        let pe = EfiImage::parse(&buf).expect("parse signed image failed");
+        assert_eq!(pe.signatures.len(), 2);
+        assert_eq!(
+            pe.compute_digest(DigestAlgorithm::Sha256).unwrap(),
+            EfiImage::parse(&read(&path).unwrap()).unwrap().compute_digest(DigestAlgorithm::Sha256).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_strip_signatures() {
+        let current_dir = env::current_dir().expect("get current dir failed");
+        let signed = read(current_dir.join("test_assets").join("shimx64.efi.signed")).unwrap();
+        let stripped = EfiFileHandler::strip_signatures(&signed).expect("strip signatures should work");
+        let pe = EfiImage::parse(&stripped).expect("parse stripped image failed");
+        assert!(pe.signatures.is_empty());
+        assert!(pe.cert_table.is_none());
+        assert_eq!(pe.get_checksum_from_header().unwrap(), pe.compute_check_sum().unwrap());
+        assert_ne!(pe.get_checksum_from_header().unwrap(), EfiImage::parse(&signed).unwrap().get_checksum_from_header().unwrap());
+        //apart from the checksum, the stripped image is the unsigned one with the alignment padding of certificate table
+        let unsigned = read(current_dir.join("test_assets").join("shimx64.efi")).unwrap();
+        let offset = pe.checksum.offset;
+        assert_eq!(stripped[..offset], unsigned[..offset]);
+        assert_eq!(stripped[offset + 4..unsigned.len()], unsigned[offset + 4..]);
+        assert!(stripped[unsigned.len()..].iter().all(|byte| *byte == 0));
+    }
+
+    #[tokio::test]
+    async fn test_sign_with_extra_keys() {
+        let current_dir = env::current_dir().expect("get current dir failed");
+        let signature_buf = read(current_dir.join("test_assets").join("efi.sign")).unwrap();
+        let path = current_dir.join("test_assets").join("shimx64.efi");
+        let handler = EfiFileHandler::new();
+        let mut options = HashMap::from([
+            (options::KEY_NAME.to_string(), "old".to_string()),
+            (options::EFI_EXTRA_KEY_NAMES.to_string(), "new".to_string()),
+        ]);
+        assert!(handler.validate_options(&options).is_ok());
+
+        let data = handler.split_data(&path, &mut options).await.expect("split data failed");
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[0], data[1]);
+        assert_eq!(handler.section_options(0, &options).get(options::KEY_NAME), Some(&"old".to_string()));
+        assert_eq!(handler.section_options(1, &options).get(options::KEY_NAME), Some(&"new".to_string()));
+
+        let (temp_file, _) = handler
+            .assemble_data(&path, vec![signature_buf.clone(), signature_buf], &env::temp_dir(), &options)
+            .await.expect("efi sign should work").remove(0);
+        let buf = read(temp_file).unwrap();
+        assert_eq!(EfiImage::parse(&buf).expect("parse signed image failed").signatures.len(), 2);
+
+        options.insert(options::EFI_EXTRA_KEY_NAMES.to_string(), "new,".to_string());
+        assert_eq!(
+            handler.validate_options(&options).unwrap_err().to_string(),
+            Error::InvalidArgumentError("EFI image extra key name can't be empty".to_string()).to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_signatures() {
+        let current_dir = env::current_dir().expect("get current dir failed");
+        let handler = EfiFileHandler::new();
+        let mut options = HashMap::from([(options::DIGEST_ALGORITHM.to_string(), "sha2_512".to_string())]);
+        let path = current_dir.join("test_assets").join("shimx64.efi.signed");
+        let signatures = handler.get_signatures(&path, &mut options).await.expect("get signatures successfully");
+        assert_eq!(signatures.len(), 1);
+        assert_eq!(options.get(options::EFI_SIGNATURE_ALGORITHMS), Some(&"sha2_256".to_string()));
+        let digest = handler.split_data(&path, &mut options).await.expect("split data successfully").remove(0);
+        let authenticode = AuthenticodeSignature::from_der(&signatures[0]).expect("parse authenticode signature successfully");
+        assert_eq!(authenticode.file_hash(), digest.as_slice());
+
+        let path = current_dir.join("test_assets").join("shimx64.efi");
+        handler.get_signatures(&path, &mut options).await.expect_err("EFI image is not signed");
+    }
+
+    #[tokio::test]
+    async fn test_split_data_with_digest_algorithm() {
+        let current_dir = env::current_dir().expect("get current dir failed");
+        let handler = EfiFileHandler::new();
+        for name in ["shimx64.efi", "shimx64.efi.signed"] {
+            let buf = read(current_dir.join("test_assets").join(name)).unwrap();
+            let pe = EfiImage::parse(&buf).unwrap();
+            assert_eq!(
+                EfiFileHandler::compute_digest(&pe, MessageDigest::sha256()).unwrap(),
+                pe.compute_digest(DigestAlgorithm::Sha256).unwrap()
+            );
+        }
+        let buf = read(current_dir.join("test_assets").join("shimx64.efi")).unwrap();
+        let mut pe = EfiImage::parse(&buf).unwrap();
+        pe.raw.truncate(pe.raw.len() / 2);
+        EfiFileHandler::compute_digest(&pe, MessageDigest::sha256()).expect_err("truncated image should be rejected");
+
+        let path = current_dir.join("test_assets").join("shimx64.efi");
+        let mut options = HashMap::new();
+        let data = handler.split_data(&path, &mut options).await.expect("split data failed");
+        assert_eq!(data[0].len(), 32);
+        assert_eq!(options.get(options::DIGEST_ALGORITHM), Some(&"sha2_256".to_string()));
+
+        for (algorithm, length) in [("sha2_384", 48), ("sha2_512", 64)] {
+            options.insert(options::DIGEST_ALGORITHM.to_string(), algorithm.to_string());
+            assert!(handler.validate_options(&options).is_ok());
+            let data = handler.split_data(&path, &mut options).await.expect("split data failed");
+            assert_eq!(data[0].len(), length);
+        }
+
+        options.insert(options::DIGEST_ALGORITHM.to_string(), "sha1".to_string());
+        assert_eq!(
+            handler.validate_options(&options).unwrap_err().to_string(),
+            Error::InvalidArgumentError(
+                "EFI image only support sha2_256, sha2_384 and sha2_512 digest algorithm, got sha1".to_string()).to_string()
+        );
+    }
 }
@@ -0,0 +1,313 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use super::traits::FileHandler;
+use crate::util::sign::{KeyType, SignType};
+use crate::util::error::{Error, Result};
+use async_trait::async_trait;
+use chrono::{Datelike, TimeZone, Timelike, Utc};
+use openssl::x509::X509;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use uuid::Uuid;
+
+use crate::util::options;
+
+//reference: UEFI specification, section 32.4 Firmware/OS Key Exchange and 8.2 Variable Services
+const EFI_GLOBAL_VARIABLE_GUID: &str = "8be4df61-93ca-11d2-aa0d-00e098032b8c";
+const EFI_IMAGE_SECURITY_DATABASE_GUID: &str = "d719b2cb-3d3a-4596-a3bc-dad00e67656f";
+const EFI_CERT_X509_GUID: &str = "a5c059a1-94e4-4aa7-87b5-ab155c2bf072";
+const EFI_CERT_SHA256_GUID: &str = "c1c41626-504c-4092-aca9-41f936934328";
+const EFI_CERT_TYPE_PKCS7_GUID: &str = "4aafd29d-68df-49ee-8aa9-347d375665a7";
+//NON_VOLATILE | BOOTSERVICE_ACCESS | RUNTIME_ACCESS | TIME_BASED_AUTHENTICATED_WRITE_ACCESS
+const EFI_VARIABLE_ATTRIBUTES: u32 = 0x00000027;
+const WIN_CERT_REVISION: u16 = 0x0200;
+const WIN_CERT_TYPE_EFI_GUID: u16 = 0x0EF1;
+const SHA256_EXTENSION: &str = "sha256";
+const ESL_EXTENSION: &str = "esl";
+const AUTH_EXTENSION: &str = "auth";
+
+#[derive(Clone)]
+pub struct EfiVariableFileHandler {}
+
+impl EfiVariableFileHandler {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    //EFI_GUID stores the first three fields in little endian
+    fn guid(value: &str) -> Result<[u8; 16]> {
+        Ok(Uuid::parse_str(value).map_err(|_| Error::InvalidArgumentError(format!("invalid guid {}", value)))?.to_bytes_le())
+    }
+
+    fn vendor_guid(variable: &str) -> Result<&'static str> {
+        match variable {
+            "PK" | "KEK" => Ok(EFI_GLOBAL_VARIABLE_GUID),
+            "db" | "dbx" => Ok(EFI_IMAGE_SECURITY_DATABASE_GUID),
+            _ => Err(Error::InvalidArgumentError(format!(
+                "unsupported efi variable {}, possible values are PK/KEK/db/dbx", variable))),
+        }
+    }
+
+    fn signature_list(signature_type: &str, owner: &[u8; 16], entries: &[Vec<u8>]) -> Result<Vec<u8>> {
+        let signature_size = 16 + entries.first().map(|e| e.len()).unwrap_or_default();
+        let mut list = Vec::new();
+        list.extend_from_slice(&EfiVariableFileHandler::guid(signature_type)?);
+        list.extend_from_slice(&((28 + signature_size * entries.len()) as u32).to_le_bytes());
+        list.extend_from_slice(&0u32.to_le_bytes());
+        list.extend_from_slice(&(signature_size as u32).to_le_bytes());
+        for entry in entries {
+            list.extend_from_slice(owner);
+            list.extend_from_slice(entry);
+        }
+        Ok(list)
+    }
+
+    //build EFI_SIGNATURE_LIST from the certificates in PEM/DER format or the SHA-256 hashes in hex format (one per line),
+    //each certificate is placed in a separated list since the size of certificates varies.
+    fn build_signature_lists(path: &Path, content: &[u8], owner: &[u8; 16]) -> Result<Vec<u8>> {
+        let mut lists = Vec::new();
+        if path.extension().and_then(|e| e.to_str()) == Some(SHA256_EXTENSION) {
+            let mut hashes = Vec::new();
+            for line in String::from_utf8_lossy(content).lines().map(|line| line.trim()).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+                let hash = hex::decode(line)?;
+                if hash.len() != 32 {
+                    return Err(Error::SplitFileError(format!("invalid sha256 hash {}", line)));
+                }
+                hashes.push(hash);
+            }
+            if !hashes.is_empty() {
+                lists.extend(EfiVariableFileHandler::signature_list(EFI_CERT_SHA256_GUID, owner, &hashes)?);
+            }
+        } else {
+            let certificates = match X509::stack_from_pem(content) {
+                Ok(certificates) if !certificates.is_empty() => certificates,
+                _ => vec![X509::from_der(content)?],
+            };
+            for certificate in certificates {
+                lists.extend(EfiVariableFileHandler::signature_list(EFI_CERT_X509_GUID, owner, &[certificate.to_der()?])?);
+            }
+        }
+        if lists.is_empty() {
+            return Err(Error::SplitFileError(format!("no certificate or hash found in {}", path.display())));
+        }
+        Ok(lists)
+    }
+
+    fn efi_time(timestamp: i64) -> Result<Vec<u8>> {
+        let time = Utc.timestamp_opt(timestamp, 0).single().ok_or(
+            Error::InvalidArgumentError(format!("invalid timestamp {}", timestamp)))?;
+        let mut result = Vec::with_capacity(16);
+        result.extend_from_slice(&(time.year() as u16).to_le_bytes());
+        result.extend_from_slice(&[time.month() as u8, time.day() as u8, time.hour() as u8, time.minute() as u8, time.second() as u8, 0]);
+        //nanosecond, timezone, daylight and pad are zero
+        result.extend_from_slice(&[0; 8]);
+        Ok(result)
+    }
+
+    async fn load(path: &PathBuf, sign_options: &HashMap<String, String>) -> Result<(Vec<u8>, Vec<u8>)> {
+        let owner = match sign_options.get(options::EFI_OWNER_GUID) {
+            Some(owner) => EfiVariableFileHandler::guid(owner)?,
+            None => [0; 16],
+        };
+        let timestamp = sign_options.get(options::EFI_TIMESTAMP).ok_or(
+            Error::InvalidArgumentError("efi timestamp is missing".to_string()))?.parse::<i64>()?;
+        let lists = EfiVariableFileHandler::build_signature_lists(path, &fs::read(path).await?, &owner)?;
+        Ok((EfiVariableFileHandler::efi_time(timestamp)?, lists))
+    }
+}
+
+#[async_trait]
+impl FileHandler for EfiVariableFileHandler {
+    fn validate_options(&self, sign_options: &HashMap<String, String>) -> Result<()> {
+        if let Some(key_type) = sign_options.get(options::KEY_TYPE) {
+            if key_type != KeyType::X509EE.to_string().as_str() {
+                return Err(Error::InvalidArgumentError("efi variable only support x509 key type".to_string()))
+            }
+        }
+        match sign_options.get(options::EFI_VARIABLE) {
+            Some(variable) => EfiVariableFileHandler::vendor_guid(variable)?,
+            None => return Err(Error::InvalidArgumentError("efi variable name is required".to_string())),
+        };
+        if let Some(owner) = sign_options.get(options::EFI_OWNER_GUID) {
+            EfiVariableFileHandler::guid(owner)?;
+        }
+        Ok(())
+    }
+
+    //the signed data is the concatenation of variable name(UCS-2 without terminator), vendor guid, attributes,
+    //timestamp and signature lists, the timestamp is kept in options for assembling.
+    async fn split_data(
+        &self,
+        path: &PathBuf,
+        sign_options: &mut HashMap<String, String>,
+    ) -> Result<Vec<Vec<u8>>> {
+        sign_options.insert(options::SIGN_TYPE.to_string(), SignType::PKCS7.to_string());
+        sign_options.entry(options::EFI_TIMESTAMP.to_string()).or_insert(Utc::now().timestamp().to_string());
+        let variable = sign_options.get(options::EFI_VARIABLE).cloned().unwrap_or_default();
+        let (time, lists) = EfiVariableFileHandler::load(path, sign_options).await?;
+        let mut content = Vec::new();
+        for c in variable.encode_utf16() {
+            content.extend_from_slice(&c.to_le_bytes());
+        }
+        content.extend_from_slice(&EfiVariableFileHandler::guid(EfiVariableFileHandler::vendor_guid(&variable)?)?);
+        content.extend_from_slice(&EFI_VARIABLE_ATTRIBUTES.to_le_bytes());
+        content.extend(time);
+        content.extend(lists);
+        Ok(vec![content])
+    }
+
+    //generate `<name>.esl` with the signature lists and `<name>.auth` with EFI_VARIABLE_AUTHENTICATION_2 prepended
+    async fn assemble_data(
+        &self,
+        path: &PathBuf,
+        data: Vec<Vec<u8>>,
+        temp_dir: &PathBuf,
+        sign_options: &HashMap<String, String>,
+    ) -> Result<Vec<(String, String)>> {
+        let signature = data.first().ok_or(Error::AssembleFileError("signature of efi variable is missing".to_string()))?;
+        let (time, lists) = EfiVariableFileHandler::load(path, sign_options).await?;
+        let mut auth = time;
+        auth.extend_from_slice(&((4 + 2 + 2 + 16 + signature.len()) as u32).to_le_bytes());
+        auth.extend_from_slice(&WIN_CERT_REVISION.to_le_bytes());
+        auth.extend_from_slice(&WIN_CERT_TYPE_EFI_GUID.to_le_bytes());
+        auth.extend_from_slice(&EfiVariableFileHandler::guid(EFI_CERT_TYPE_PKCS7_GUID)?);
+        auth.extend_from_slice(signature);
+        auth.extend_from_slice(&lists);
+        let temp_esl = temp_dir.join(Uuid::new_v4().to_string());
+        fs::write(&temp_esl, &lists).await?;
+        let temp_auth = temp_dir.join(Uuid::new_v4().to_string());
+        fs::write(&temp_auth, &auth).await?;
+        Ok(vec![
+            (temp_esl.display().to_string(), path.with_extension(ESL_EXTENSION).display().to_string()),
+            (temp_auth.display().to_string(), path.with_extension(AUTH_EXTENSION).display().to_string()),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+    use openssl::asn1::Asn1Time;
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::PKey;
+    use openssl::rsa::Rsa;
+    use openssl::x509::X509NameBuilder;
+
+    fn generate_certificate() -> X509 {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "efi variable").unwrap();
+        let name = name.build();
+        let mut builder = X509::builder().unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        builder.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        builder.build()
+    }
+
+    fn get_options() -> HashMap<String, String> {
+        HashMap::from([
+            (options::KEY_TYPE.to_string(), KeyType::X509EE.to_string()),
+            (options::EFI_VARIABLE.to_string(), "db".to_string()),
+            (options::EFI_OWNER_GUID.to_string(), "77fa9abd-0359-4d32-bd60-28f4e78f784b".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_validate_options() {
+        let handler = EfiVariableFileHandler::new();
+        let mut options = get_options();
+        assert!(handler.validate_options(&options).is_ok());
+        options.insert(options::EFI_VARIABLE.to_string(), "MokList".to_string());
+        assert_eq!(handler.validate_options(&options).unwrap_err().to_string(),
+                   "invalid argument: unsupported efi variable MokList, possible values are PK/KEK/db/dbx");
+        options.remove(options::EFI_VARIABLE);
+        assert!(handler.validate_options(&options).is_err());
+        let mut options = get_options();
+        options.insert(options::EFI_OWNER_GUID.to_string(), "invalid".to_string());
+        assert!(handler.validate_options(&options).is_err());
+        let mut options = get_options();
+        options.insert(options::KEY_TYPE.to_string(), KeyType::Pgp.to_string());
+        assert_eq!(handler.validate_options(&options).unwrap_err().to_string(),
+                   "invalid argument: efi variable only support x509 key type");
+    }
+
+    #[test]
+    fn test_efi_time() {
+        //2023-05-06 07:08:09 UTC
+        assert_eq!(EfiVariableFileHandler::efi_time(1683356889).unwrap(),
+                   vec![0xe7, 0x07, 5, 6, 7, 8, 9, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[tokio::test]
+    async fn test_split_and_assemble_certificate() {
+        let handler = EfiVariableFileHandler::new();
+        let certificate = generate_certificate();
+        let der = certificate.to_der().unwrap();
+        let path = env::temp_dir().join(format!("{}.pem", Uuid::new_v4()));
+        fs::write(&path, certificate.to_pem().unwrap()).await.unwrap();
+        let mut options = get_options();
+        let content = handler.split_data(&path, &mut options).await.expect("split efi variable successfully").remove(0);
+        assert_eq!(options.get(options::SIGN_TYPE), Some(&SignType::PKCS7.to_string()));
+        assert!(options.contains_key(options::EFI_TIMESTAMP));
+        //"db" in UCS-2 followed by image security database guid and attributes
+        assert_eq!(content[0..4], [b'd', 0, b'b', 0]);
+        assert_eq!(content[4..20], EfiVariableFileHandler::guid(EFI_IMAGE_SECURITY_DATABASE_GUID).unwrap());
+        assert_eq!(content[20..24], [0x27, 0, 0, 0]);
+        let esl = &content[40..];
+        assert_eq!(esl[0..16], EfiVariableFileHandler::guid(EFI_CERT_X509_GUID).unwrap());
+        assert_eq!(u32::from_le_bytes(esl[16..20].try_into().unwrap()) as usize, esl.len());
+        assert_eq!(u32::from_le_bytes(esl[24..28].try_into().unwrap()) as usize, 16 + der.len());
+        assert_eq!(esl[28..44], EfiVariableFileHandler::guid("77fa9abd-0359-4d32-bd60-28f4e78f784b").unwrap());
+        assert_eq!(esl[44..], der);
+
+        let outputs = handler.assemble_data(&path, vec![vec![1, 2, 3]], &env::temp_dir(), &options).await.expect("assemble efi variable successfully");
+        assert_eq!(outputs[0].1, path.with_extension("esl").display().to_string());
+        assert_eq!(outputs[1].1, path.with_extension("auth").display().to_string());
+        assert_eq!(std::fs::read(&outputs[0].0).unwrap(), esl);
+        let auth = std::fs::read(&outputs[1].0).unwrap();
+        assert_eq!(auth[0..16], content[24..40]);
+        assert_eq!(u32::from_le_bytes(auth[16..20].try_into().unwrap()), 27);
+        assert_eq!(auth[20..24], [0x00, 0x02, 0xf1, 0x0e]);
+        assert_eq!(auth[24..40], EfiVariableFileHandler::guid(EFI_CERT_TYPE_PKCS7_GUID).unwrap());
+        assert_eq!(auth[40..43], [1, 2, 3]);
+        assert_eq!(&auth[43..], esl);
+    }
+
+    #[tokio::test]
+    async fn test_split_hashes() {
+        let handler = EfiVariableFileHandler::new();
+        let path = env::temp_dir().join(format!("{}.sha256", Uuid::new_v4()));
+        fs::write(&path, format!("# revoked images\n{}\n{}\n", "a".repeat(64), "b".repeat(64))).await.unwrap();
+        let mut options = get_options();
+        options.insert(options::EFI_VARIABLE.to_string(), "dbx".to_string());
+        let content = handler.split_data(&path, &mut options).await.expect("split efi variable successfully").remove(0);
+        let esl = &content[42..];
+        assert_eq!(esl[0..16], EfiVariableFileHandler::guid(EFI_CERT_SHA256_GUID).unwrap());
+        assert_eq!(u32::from_le_bytes(esl[16..20].try_into().unwrap()), 28 + 48 * 2);
+        assert_eq!(u32::from_le_bytes(esl[24..28].try_into().unwrap()), 48);
+        assert_eq!(esl[44..76], [0xaa; 32]);
+
+        fs::write(&path, "1234").await.unwrap();
+        assert!(handler.split_data(&path, &mut options).await.is_err());
+    }
+}
@@ -16,8 +16,16 @@
 
 use super::rpm::RpmFileHandler;
 use super::efi::EfiFileHandler;
+use super::efi_variable::EfiVariableFileHandler;
 use super::generic::GenericFileHandler;
 use super::kernel_module::KernelModuleFileHandler;
+use super::deb::DebFileHandler;
+use super::apt_release::AptReleaseFileHandler;
+use super::repomd::RepomdFileHandler;
+use super::sbom::SbomFileHandler;
+use super::oci::OciImageFileHandler;
+use super::ostree::OstreeFileHandler;
+use super::jar::JarFileHandler;
 use crate::util::sign::FileType;
 use super::traits::FileHandler;
 
@@ -39,6 +47,30 @@ impl FileHandlerFactory {
             FileType::EfiImage => {
                 Box::new(EfiFileHandler::new())
             },
+            FileType::Deb => {
+                Box::new(DebFileHandler::new())
+            },
+            FileType::AptRelease => {
+                Box::new(AptReleaseFileHandler::new())
+            },
+            FileType::Repomd => {
+                Box::new(RepomdFileHandler::new())
+            },
+            FileType::Sbom => {
+                Box::new(SbomFileHandler::new())
+            },
+            FileType::OciImage => {
+                Box::new(OciImageFileHandler::new())
+            },
+            FileType::Ostree => {
+                Box::new(OstreeFileHandler::new())
+            },
+            FileType::EfiVariable => {
+                Box::new(EfiVariableFileHandler::new())
+            },
+            FileType::Jar => {
+                Box::new(JarFileHandler::new())
+            },
         }
     }
 }
\ No newline at end of file
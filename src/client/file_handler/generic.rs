@@ -15,9 +15,10 @@
  */
 
 use super::traits::FileHandler;
-use crate::util::sign::{KeyType};
+use crate::util::sign::{KeyType, SignType};
 use crate::util::error::Result;
 use async_trait::async_trait;
+use openssl::cms::CmsContentInfo;
 use std::path::PathBuf;
 use tokio::fs;
 use uuid::Uuid;
@@ -28,6 +29,10 @@ use std::collections::HashMap;
 
 //Stands for ASCII Armored file
 const FILE_EXTENSION: &str = "asc";
+//Binary OpenPGP signature file
+const BINARY_FILE_EXTENSION: &str = "sig";
+//Detached cms signature file for x509 keys
+const CMS_FILE_EXTENSION: &str = "p7s";
 
 #[derive(Clone)]
 pub struct GenericFileHandler {}
@@ -36,13 +41,22 @@ impl GenericFileHandler {
     pub fn new() -> Self {
         Self {}
     }
+
+    fn is_x509(sign_options: &HashMap<String, String>) -> bool {
+        sign_options.get(options::KEY_TYPE).map(|key_type| key_type == KeyType::X509EE.to_string().as_str()).unwrap_or(false)
+    }
+
+    fn get_signature_format(sign_options: &HashMap<String, String>) -> Option<&str> {
+        sign_options.get(options::SIGNATURE_FORMAT).map(|format| format.as_str())
+    }
 }
 
 #[async_trait]
 impl FileHandler for GenericFileHandler {
     fn validate_options(&self, sign_options: &HashMap<String, String>) -> Result<()> {
+        //cleartext signed message contains the content itself, it's not a detached signature
         if let Some(detached) = sign_options.get(options::DETACHED) {
-            if detached == "false" {
+            if detached == "false" && GenericFileHandler::get_signature_format(sign_options) != Some(options::SIGNATURE_FORMAT_CLEARTEXT) {
                 return Err(Error::InvalidArgumentError(
                     "generic file only support detached signature".to_string(),
                 ));
@@ -50,31 +64,105 @@ impl FileHandler for GenericFileHandler {
         }
 
         if let Some(key_type) = sign_options.get(options::KEY_TYPE) {
-            if key_type != KeyType::Pgp.to_string().as_str() {
+            if key_type != KeyType::Pgp.to_string().as_str() && key_type != KeyType::X509EE.to_string().as_str() {
                 return Err(Error::InvalidArgumentError(
-                    "generic file only support pgp key type".to_string(),
+                    "generic file only support pgp and x509ee key type".to_string(),
                 ));
             }
         }
+
+        //the certificate chain is embedded into cms signature, which is only generated for x509ee key
+        if sign_options.contains_key(options::CMS_CERTIFICATE_CHAIN) && !GenericFileHandler::is_x509(sign_options) {
+            return Err(Error::InvalidArgumentError(
+                "cms certificate chain is only supported for x509ee key".to_string(),
+            ));
+        }
+
+        let formats = if GenericFileHandler::is_x509(sign_options) {
+            if let Some(sign_type) = sign_options.get(options::SIGN_TYPE) {
+                if sign_type != SignType::Cms.to_string().as_str() {
+                    return Err(Error::InvalidArgumentError(
+                        "generic file only support cms signature for x509ee key".to_string(),
+                    ));
+                }
+            }
+            vec![options::SIGNATURE_FORMAT_DER, options::SIGNATURE_FORMAT_PEM]
+        } else {
+            //cms is the default sign type of client, which is ignored by openpgp key
+            if let Some(sign_type) = sign_options.get(options::SIGN_TYPE) {
+                if sign_type != SignType::Cms.to_string().as_str() {
+                    return Err(Error::InvalidArgumentError(
+                        format!("generic file doesn't support {} sign type for pgp key", sign_type),
+                    ));
+                }
+            }
+            vec![options::SIGNATURE_FORMAT_ARMORED, options::SIGNATURE_FORMAT_BINARY, options::SIGNATURE_FORMAT_CLEARTEXT]
+        };
+        if let Some(format) = GenericFileHandler::get_signature_format(sign_options) {
+            if !formats.contains(&format) {
+                return Err(Error::InvalidArgumentError(format!(
+                    "signature format {} is not supported for the key type, possible values are {}", format, formats.join("/"))));
+            }
+        }
         Ok(())
     }
 
-    /* when assemble generic signature when only create another .asc file separately */
+    //the signature file next to the generic file, `.p7s` for x509 key, `.asc` or `.sig` for pgp key
+    async fn get_signatures(
+        &self,
+        path: &PathBuf,
+        sign_options: &mut HashMap<String, String>,
+    ) -> Result<Vec<Vec<u8>>> {
+        let extensions = if GenericFileHandler::is_x509(sign_options) {
+            vec![CMS_FILE_EXTENSION]
+        } else {
+            vec![FILE_EXTENSION, BINARY_FILE_EXTENSION]
+        };
+        for extension in extensions.iter() {
+            let signature = PathBuf::from(format!("{}.{}", path.as_path().display(), extension));
+            if signature.exists() {
+                return Ok(vec![fs::read(signature).await?]);
+            }
+        }
+        Err(Error::SplitFileError(format!(
+            "signature file {}.{} not found", path.as_path().display(), extensions.join("/"))))
+    }
+
+    /* when assemble generic signature when only create another .asc file separately, .sig file for binary
+    openpgp signature, or .p7s file which contains the DER or PEM encoded cms signature for x509 key */
     async fn assemble_data(
         &self,
         path: &PathBuf,
         data: Vec<Vec<u8>>,
         temp_dir: &PathBuf,
-        _sign_options: &HashMap<String, String>,
-    ) -> Result<(String, String)> {
+        sign_options: &HashMap<String, String>,
+    ) -> Result<Vec<(String, String)>> {
         let temp_file = temp_dir.join(Uuid::new_v4().to_string());
+        if GenericFileHandler::is_x509(sign_options) {
+            let content = match GenericFileHandler::get_signature_format(sign_options) {
+                Some(options::SIGNATURE_FORMAT_PEM) => CmsContentInfo::from_der(&data[0])?.to_pem()?,
+                _ => data[0].clone(),
+            };
+            fs::write(temp_file.clone(), content).await?;
+            return Ok(vec![(
+                temp_file.as_path().display().to_string(),
+                format!("{}.{}", path.as_path().display(), CMS_FILE_EXTENSION),
+            )]);
+        }
+        if GenericFileHandler::get_signature_format(sign_options) == Some(options::SIGNATURE_FORMAT_BINARY) {
+            fs::write(temp_file.clone(), &data[0]).await?;
+            return Ok(vec![(
+                temp_file.as_path().display().to_string(),
+                format!("{}.{}", path.as_path().display(), BINARY_FILE_EXTENSION),
+            )]);
+        }
         //convert bytes into string
         let result = String::from_utf8_lossy(&data[0]);
         fs::write(temp_file.clone(), result.as_bytes()).await?;
-        Ok((
+        Ok(vec![(
             temp_file.as_path().display().to_string(),
             format!("{}.{}", path.as_path().display(), FILE_EXTENSION),
-        ))
+        )])
     }
 }
 
@@ -82,6 +170,12 @@ impl FileHandler for GenericFileHandler {
 mod test {
     use super::*;
     use std::env;
+    use openssl::asn1::Asn1Time;
+    use openssl::cms::CMSOptions;
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::PKey;
+    use openssl::rsa::Rsa;
+    use openssl::x509::{X509Builder, X509NameBuilder};
 
     #[test]
     fn test_validate_options() {
@@ -104,12 +198,60 @@ mod test {
         let result = handler.validate_options(&options);
         assert!(result.is_ok());
 
+        options.insert(options::SIGNATURE_FORMAT.to_string(), "pem".to_string());
+        let result = handler.validate_options(&options);
+        assert!(result.is_err());
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "invalid argument: signature format pem is not supported for the key type, possible values are armored/binary/cleartext"
+        );
+
+        options.insert(options::SIGNATURE_FORMAT.to_string(), "cleartext".to_string());
+        options.insert(options::DETACHED.to_string(), "false".to_string());
+        let result = handler.validate_options(&options);
+        assert!(result.is_ok());
+        options.insert(options::DETACHED.to_string(), "true".to_string());
+        options.insert(options::SIGNATURE_FORMAT.to_string(), "pem".to_string());
+
+        options.insert(options::CMS_CERTIFICATE_CHAIN.to_string(), "true".to_string());
+        let result = handler.validate_options(&options);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "invalid argument: cms certificate chain is only supported for x509ee key"
+        );
+        options.remove(options::CMS_CERTIFICATE_CHAIN);
+        options.insert(options::SIGN_TYPE.to_string(), SignType::Raw.to_string());
+        let result = handler.validate_options(&options);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "invalid argument: generic file doesn't support raw sign type for pgp key"
+        );
+        options.insert(options::CMS_CERTIFICATE_CHAIN.to_string(), "true".to_string());
+
         options.insert(options::KEY_TYPE.to_string(), KeyType::X509EE.to_string());
+        options.insert(options::SIGN_TYPE.to_string(), SignType::Cms.to_string());
+        let result = handler.validate_options(&options);
+        assert!(result.is_ok());
+
+        options.insert(options::SIGNATURE_FORMAT.to_string(), "armored".to_string());
+        let result = handler.validate_options(&options);
+        assert!(result.is_err());
+
+        options.insert(options::SIGNATURE_FORMAT.to_string(), "der".to_string());
+        options.insert(options::SIGN_TYPE.to_string(), SignType::PKCS7.to_string());
         let result = handler.validate_options(&options);
         assert!(result.is_err());
         assert_eq!(
             result.unwrap_err().to_string(),
-            "invalid argument: generic file only support pgp key type"
+            "invalid argument: generic file only support cms signature for x509ee key"
+        );
+
+        options.insert(options::KEY_TYPE.to_string(), "x509ca".to_string());
+        let result = handler.validate_options(&options);
+        assert!(result.is_err());
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "invalid argument: generic file only support pgp and x509ee key type"
         );
     }
 
@@ -122,8 +264,78 @@ mod test {
         let temp_dir = env::temp_dir();
         let result = handler.assemble_data(&path, data, &temp_dir, &options).await;
         assert!(result.is_ok());
-        let (temp_file, file_name) = result.expect("invoke assemble data should work");
+        let (temp_file, file_name) = result.expect("invoke assemble data should work").remove(0);
         assert_eq!(temp_file.starts_with(temp_dir.to_str().unwrap()), true);
         assert_eq!(file_name, "./test_data/test.txt.asc");
     }
+
+    #[tokio::test]
+    async fn test_assemble_binary_signature() {
+        let handler = GenericFileHandler::new();
+        let options = HashMap::from([
+            (options::KEY_TYPE.to_string(), KeyType::Pgp.to_string()),
+            (options::SIGNATURE_FORMAT.to_string(), "binary".to_string()),
+        ]);
+        let path = PathBuf::from("./test_data/test.txt");
+        let data = vec![vec![0x88, 0xff, 0x00, 0x80]];
+        let (temp_file, file_name) = handler.assemble_data(&path, data.clone(), &env::temp_dir(), &options).await
+            .expect("invoke assemble data should work").remove(0);
+        assert_eq!(file_name, "./test_data/test.txt.sig");
+        assert_eq!(fs::read(&temp_file).await.unwrap(), data[0]);
+    }
+
+    #[tokio::test]
+    async fn test_get_signatures() {
+        let handler = GenericFileHandler::new();
+        let path = env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::write(&path, "content").await.unwrap();
+        let mut options = HashMap::from([(options::KEY_TYPE.to_string(), KeyType::Pgp.to_string())]);
+        handler.get_signatures(&path, &mut options).await.expect_err("signature file not found");
+        fs::write(format!("{}.sig", path.display()), [1, 2, 3]).await.unwrap();
+        assert_eq!(handler.get_signatures(&path, &mut options).await.unwrap(), vec![vec![1, 2, 3]]);
+        fs::write(format!("{}.asc", path.display()), [4, 5, 6]).await.unwrap();
+        assert_eq!(handler.get_signatures(&path, &mut options).await.unwrap(), vec![vec![4, 5, 6]]);
+
+        options.insert(options::KEY_TYPE.to_string(), KeyType::X509EE.to_string());
+        handler.get_signatures(&path, &mut options).await.expect_err("signature file not found");
+        fs::write(format!("{}.p7s", path.display()), [7, 8, 9]).await.unwrap();
+        assert_eq!(handler.get_signatures(&path, &mut options).await.unwrap(), vec![vec![7, 8, 9]]);
+    }
+
+    #[tokio::test]
+    async fn test_assemble_cms_signature() {
+        let handler = GenericFileHandler::new();
+        let private_key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut builder = X509Builder::new().unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "signatrust").unwrap();
+        let name = name.build();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&private_key).unwrap();
+        builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        builder.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+        builder.sign(&private_key, MessageDigest::sha256()).unwrap();
+        let certificate = builder.build();
+        let signature = CmsContentInfo::sign(
+            Some(&certificate), Some(&private_key), None, Some("firmware".as_bytes()),
+            CMSOptions::DETACHED | CMSOptions::BINARY).unwrap().to_der().unwrap();
+
+        let mut options = HashMap::from([
+            (options::KEY_TYPE.to_string(), KeyType::X509EE.to_string()),
+        ]);
+        let path = PathBuf::from("./test_data/test.txt");
+        let temp_dir = env::temp_dir();
+        let (temp_file, file_name) = handler.assemble_data(&path, vec![signature.clone()], &temp_dir, &options).await
+            .expect("invoke assemble data should work").remove(0);
+        assert_eq!(file_name, "./test_data/test.txt.p7s");
+        assert_eq!(fs::read(&temp_file).await.unwrap(), signature);
+
+        options.insert(options::SIGNATURE_FORMAT.to_string(), "pem".to_string());
+        let (temp_file, _) = handler.assemble_data(&path, vec![signature.clone()], &temp_dir, &options).await
+            .expect("invoke assemble data should work").remove(0);
+        let content = fs::read(&temp_file).await.unwrap();
+        assert!(content.starts_with(b"-----BEGIN CMS-----"));
+        assert_eq!(CmsContentInfo::from_pem(&content).unwrap().to_der().unwrap(), signature);
+    }
 }
@@ -0,0 +1,371 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use super::traits::FileHandler;
+use crate::util::sign::{KeyType, SignType};
+use crate::util::error::{Error, Result};
+use async_trait::async_trait;
+use data_encoding::BASE64;
+use openssl::pkcs7::Pkcs7;
+use openssl::pkey::Id;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::util::options;
+
+//jar file specification: https://docs.oracle.com/en/java/javase/17/docs/specs/jar/jar.html#signed-jar-file
+const META_INF: &str = "META-INF/";
+const MANIFEST_PATH: &str = "META-INF/MANIFEST.MF";
+const SIGNER_NAME: &str = "SIGNATRUST";
+const CREATED_BY: &str = "Signatrust";
+const NAME_ATTRIBUTE: &str = "Name";
+const DIGEST_ATTRIBUTE: &str = "SHA-256-Digest";
+//maximum bytes of a manifest line excluding the line break
+const MAX_LINE_LENGTH: usize = 72;
+
+//attributes of a manifest section in order
+type Section = Vec<(String, String)>;
+
+#[derive(Clone)]
+pub struct JarFileHandler {}
+
+impl JarFileHandler {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    fn digest(content: &[u8]) -> String {
+        BASE64.encode(&openssl::sha::sha256(content))
+    }
+
+    //signature related files in META-INF are excluded from manifest
+    fn is_signature_file(name: &str) -> bool {
+        let upper = name.to_uppercase();
+        match upper.strip_prefix(META_INF) {
+            Some(file) if !file.contains('/') => {
+                upper == MANIFEST_PATH || file.starts_with("SIG-") ||
+                    [".SF", ".RSA", ".DSA", ".EC"].iter().any(|extension| file.ends_with(extension))
+            }
+            _ => false,
+        }
+    }
+
+    fn parse_manifest(content: &[u8]) -> Result<Vec<Section>> {
+        let text = String::from_utf8(content.to_vec())?;
+        let mut sections = Vec::new();
+        let mut section: Section = Vec::new();
+        for line in text.split("\r\n").flat_map(|line| line.split('\n')).flat_map(|line| line.split('\r')) {
+            if line.is_empty() {
+                if !section.is_empty() {
+                    sections.push(std::mem::take(&mut section));
+                }
+            } else if let Some(continuation) = line.strip_prefix(' ') {
+                let (_, value) = section.last_mut().ok_or(
+                    Error::SplitFileError("invalid continuation line in manifest".to_string()))?;
+                value.push_str(continuation);
+            } else {
+                let (key, value) = line.split_once(": ").ok_or(
+                    Error::SplitFileError(format!("invalid manifest line {}", line)))?;
+                section.push((key.to_string(), value.to_string()));
+            }
+        }
+        if !section.is_empty() {
+            sections.push(section);
+        }
+        Ok(sections)
+    }
+
+    //lines longer than 72 bytes are wrapped with continuation lines, the section ends with an empty line
+    fn write_section(section: &Section) -> Vec<u8> {
+        let mut result = Vec::new();
+        for (key, value) in section {
+            let line = format!("{}: {}", key, value);
+            let mut remain = line.as_str();
+            let mut limit = MAX_LINE_LENGTH;
+            loop {
+                let mut end = remain.len().min(limit);
+                while !remain.is_char_boundary(end) {
+                    end -= 1;
+                }
+                result.extend_from_slice(&remain.as_bytes()[..end]);
+                result.extend_from_slice(b"\r\n");
+                remain = &remain[end..];
+                if remain.is_empty() {
+                    break;
+                }
+                result.push(b' ');
+                limit = MAX_LINE_LENGTH - 1;
+            }
+        }
+        result.extend_from_slice(b"\r\n");
+        result
+    }
+
+    fn section_name(section: &Section) -> Option<&str> {
+        section.iter().find(|(key, _)| key == NAME_ATTRIBUTE).map(|(_, value)| value.as_str())
+    }
+
+    //generate the manifest with digests of all entries and the signature file which contains digests of manifest sections
+    fn generate_manifest(path: &Path) -> Result<(Vec<u8>, Vec<u8>)> {
+        let mut archive = ZipArchive::new(File::open(path)?)?;
+        let mut sections = match archive.by_name(MANIFEST_PATH) {
+            Ok(mut manifest) => {
+                let mut content = Vec::new();
+                manifest.read_to_end(&mut content)?;
+                JarFileHandler::parse_manifest(&content)?
+            }
+            Err(zip::result::ZipError::FileNotFound) => vec![],
+            Err(err) => return Err(err.into()),
+        };
+        if sections.is_empty() {
+            sections.push(vec![
+                ("Manifest-Version".to_string(), "1.0".to_string()),
+                ("Created-By".to_string(), CREATED_BY.to_string())]);
+        }
+        let mut entries = Vec::new();
+        for index in 0..archive.len() {
+            let mut file = archive.by_index(index)?;
+            if file.is_dir() || JarFileHandler::is_signature_file(file.name()) {
+                continue
+            }
+            let mut content = Vec::new();
+            file.read_to_end(&mut content)?;
+            let digest = JarFileHandler::digest(&content);
+            let name = file.name().to_string();
+            match sections.iter_mut().skip(1).find(|section| JarFileHandler::section_name(section) == Some(name.as_str())) {
+                Some(section) => match section.iter_mut().find(|(key, _)| key == DIGEST_ATTRIBUTE) {
+                    Some((_, value)) => *value = digest,
+                    None => section.push((DIGEST_ATTRIBUTE.to_string(), digest)),
+                },
+                None => sections.push(vec![(NAME_ATTRIBUTE.to_string(), name.clone()), (DIGEST_ATTRIBUTE.to_string(), digest)]),
+            }
+            entries.push(name);
+        }
+        let main = JarFileHandler::write_section(&sections[0]);
+        let mut manifest = main.clone();
+        let mut signature_sections = Vec::new();
+        for section in sections.iter().skip(1) {
+            let content = JarFileHandler::write_section(section);
+            if let Some(name) = JarFileHandler::section_name(section) {
+                if entries.iter().any(|entry| entry == name) {
+                    signature_sections.push(vec![
+                        (NAME_ATTRIBUTE.to_string(), name.to_string()),
+                        (DIGEST_ATTRIBUTE.to_string(), JarFileHandler::digest(&content))]);
+                }
+            }
+            manifest.extend(content);
+        }
+        let mut signature_file = JarFileHandler::write_section(&vec![
+            ("Signature-Version".to_string(), "1.0".to_string()),
+            ("Created-By".to_string(), CREATED_BY.to_string()),
+            (format!("{}-Manifest", DIGEST_ATTRIBUTE), JarFileHandler::digest(&manifest)),
+            (format!("{}-Manifest-Main-Attributes", DIGEST_ATTRIBUTE), JarFileHandler::digest(&main))]);
+        for section in signature_sections.iter() {
+            signature_file.extend(JarFileHandler::write_section(section));
+        }
+        Ok((manifest, signature_file))
+    }
+
+    //the extension of signature block file is decided by the algorithm of signer key
+    fn block_extension(signature: &[u8]) -> Result<&'static str> {
+        let pkcs7 = Pkcs7::from_der(signature)?;
+        let certificate = pkcs7.signed().and_then(|signed| signed.certificates()).and_then(|certs| certs.iter().next()).ok_or(
+            Error::AssembleFileError("signer certificate is absent in signature".to_string()))?;
+        match certificate.public_key()?.id() {
+            Id::RSA => Ok("RSA"),
+            Id::DSA => Ok("DSA"),
+            Id::EC => Ok("EC"),
+            _ => Err(Error::AssembleFileError("unsupported signer key algorithm".to_string())),
+        }
+    }
+}
+
+#[async_trait]
+impl FileHandler for JarFileHandler {
+    fn validate_options(&self, sign_options: &HashMap<String, String>) -> Result<()> {
+        if let Some(detached) = sign_options.get(options::DETACHED) {
+            if detached == "true" {
+                return Err(Error::InvalidArgumentError("jar file only support inside signature".to_string()))
+            }
+        }
+        if let Some(key_type) = sign_options.get(options::KEY_TYPE) {
+            if key_type != KeyType::X509EE.to_string().as_str() {
+                return Err(Error::InvalidArgumentError("jar file only support x509 signature".to_string()))
+            }
+        }
+        Ok(())
+    }
+
+    //the signature file `META-INF/SIGNATRUST.SF` is signed
+    async fn split_data(
+        &self,
+        path: &PathBuf,
+        sign_options: &mut HashMap<String, String>,
+    ) -> Result<Vec<Vec<u8>>> {
+        sign_options.insert(options::SIGN_TYPE.to_string(), SignType::PKCS7.to_string());
+        sign_options.insert(options::INCLUDE_CERTIFICATE.to_string(), "true".to_string());
+        let (_, signature_file) = JarFileHandler::generate_manifest(path)?;
+        Ok(vec![signature_file])
+    }
+
+    //manifest, signature file and signature block are placed at the beginning of jar, other entries are copied as is.
+    async fn assemble_data(
+        &self,
+        path: &PathBuf,
+        data: Vec<Vec<u8>>,
+        temp_dir: &PathBuf,
+        _sign_options: &HashMap<String, String>,
+    ) -> Result<Vec<(String, String)>> {
+        let signature = data.first().ok_or(Error::AssembleFileError("signature of jar is missing".to_string()))?;
+        let (manifest, signature_file) = JarFileHandler::generate_manifest(path)?;
+        let signature_path = format!("{}{}.SF", META_INF, SIGNER_NAME);
+        let block_path = format!("{}{}.{}", META_INF, SIGNER_NAME, JarFileHandler::block_extension(signature)?);
+        let prefix = format!("{}{}.", META_INF, SIGNER_NAME);
+
+        let temp_file = temp_dir.join(Uuid::new_v4().to_string());
+        let mut archive = ZipArchive::new(File::open(path)?)?;
+        let mut writer = ZipWriter::new(File::create(&temp_file)?);
+        let file_options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        writer.add_directory(META_INF, FileOptions::default())?;
+        for (name, content) in [(MANIFEST_PATH, &manifest), (signature_path.as_str(), &signature_file), (block_path.as_str(), signature)] {
+            writer.start_file(name, file_options)?;
+            writer.write_all(content)?;
+        }
+        for index in 0..archive.len() {
+            let file = archive.by_index_raw(index)?;
+            let upper = file.name().to_uppercase();
+            if upper == META_INF || upper == MANIFEST_PATH || upper.starts_with(&prefix) {
+                continue
+            }
+            writer.raw_copy_file(file)?;
+        }
+        writer.finish()?;
+        Ok(vec![(temp_file.display().to_string(), path.display().to_string())])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+
+    fn generate_jar(manifest: Option<&str>) -> PathBuf {
+        let path = env::temp_dir().join(format!("{}.jar", Uuid::new_v4()));
+        let mut writer = ZipWriter::new(File::create(&path).unwrap());
+        if let Some(manifest) = manifest {
+            writer.start_file(MANIFEST_PATH, FileOptions::default()).unwrap();
+            writer.write_all(manifest.as_bytes()).unwrap();
+        }
+        writer.add_directory("org/", FileOptions::default()).unwrap();
+        writer.start_file("org/Hello.class", FileOptions::default()).unwrap();
+        writer.write_all(b"hello").unwrap();
+        writer.start_file("META-INF/OTHER.SF", FileOptions::default()).unwrap();
+        writer.write_all(b"other signer").unwrap();
+        writer.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn test_write_section_wrap_lines() {
+        let name = "a".repeat(100);
+        let content = JarFileHandler::write_section(&vec![(NAME_ATTRIBUTE.to_string(), name.clone())]);
+        let text = String::from_utf8(content.clone()).unwrap();
+        let lines: Vec<&str> = text.split("\r\n").collect();
+        assert_eq!(lines[0].len(), 72);
+        assert_eq!(lines[1].len(), 35);
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(lines[2], "");
+        let sections = JarFileHandler::parse_manifest(&content).unwrap();
+        assert_eq!(sections, vec![vec![(NAME_ATTRIBUTE.to_string(), name)]]);
+    }
+
+    #[test]
+    fn test_is_signature_file() {
+        assert!(JarFileHandler::is_signature_file("META-INF/MANIFEST.MF"));
+        assert!(JarFileHandler::is_signature_file("META-INF/SIGNER.SF"));
+        assert!(JarFileHandler::is_signature_file("meta-inf/signer.rsa"));
+        assert!(JarFileHandler::is_signature_file("META-INF/SIG-SIGNER.BLK"));
+        assert!(!JarFileHandler::is_signature_file("META-INF/services/org.Hello"));
+        assert!(!JarFileHandler::is_signature_file("org/Hello.class"));
+    }
+
+    #[tokio::test]
+    async fn test_split_data() {
+        let handler = JarFileHandler::new();
+        let mut options = HashMap::new();
+        let path = generate_jar(Some("Manifest-Version: 1.0\r\nMain-Class: org.Hello\r\n\r\nName: org/Hello.class\r\nSealed: true\r\n\r\n"));
+        let signature_file = handler.split_data(&path, &mut options).await.expect("split jar successfully").remove(0);
+        assert_eq!(options.get(options::SIGN_TYPE), Some(&SignType::PKCS7.to_string()));
+        assert_eq!(options.get(options::INCLUDE_CERTIFICATE), Some(&"true".to_string()));
+        let (manifest, _) = JarFileHandler::generate_manifest(&path).unwrap();
+        let digest = JarFileHandler::digest(b"hello");
+        let entry_section = format!("Name: org/Hello.class\r\nSealed: true\r\nSHA-256-Digest: {}\r\n\r\n", digest);
+        assert_eq!(String::from_utf8(manifest.clone()).unwrap(),
+                   format!("Manifest-Version: 1.0\r\nMain-Class: org.Hello\r\n\r\n{}", entry_section));
+        //the line of main attributes digest is wrapped
+        let main_attributes = format!("SHA-256-Digest-Manifest-Main-Attributes: {}",
+                                      JarFileHandler::digest(b"Manifest-Version: 1.0\r\nMain-Class: org.Hello\r\n\r\n"));
+        assert_eq!(String::from_utf8(signature_file).unwrap(), format!(
+            "Signature-Version: 1.0\r\nCreated-By: Signatrust\r\nSHA-256-Digest-Manifest: {}\r\n{}\r\n {}\r\n\r\nName: org/Hello.class\r\nSHA-256-Digest: {}\r\n\r\n",
+            JarFileHandler::digest(&manifest),
+            &main_attributes[..MAX_LINE_LENGTH],
+            &main_attributes[MAX_LINE_LENGTH..],
+            JarFileHandler::digest(entry_section.as_bytes())));
+
+        let path = generate_jar(None);
+        let (manifest, _) = JarFileHandler::generate_manifest(&path).unwrap();
+        assert!(String::from_utf8(manifest).unwrap().starts_with("Manifest-Version: 1.0\r\nCreated-By: Signatrust\r\n\r\nName: org/Hello.class\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_assemble_data() {
+        let handler = JarFileHandler::new();
+        let path = generate_jar(None);
+        let result = handler.assemble_data(&path, vec![vec![1, 2, 3]], &env::temp_dir(), &HashMap::new()).await;
+        assert!(result.is_err());
+
+        let key = openssl::pkey::PKey::from_rsa(openssl::rsa::Rsa::generate(2048).unwrap()).unwrap();
+        let mut builder = openssl::x509::X509::builder().unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder.set_not_before(&openssl::asn1::Asn1Time::days_from_now(0).unwrap()).unwrap();
+        builder.set_not_after(&openssl::asn1::Asn1Time::days_from_now(1).unwrap()).unwrap();
+        builder.sign(&key, openssl::hash::MessageDigest::sha256()).unwrap();
+        let certificate = builder.build();
+        let (_, signature_file) = JarFileHandler::generate_manifest(&path).unwrap();
+        let signature = Pkcs7::sign(&certificate, &key, &openssl::stack::Stack::new().unwrap(), &signature_file,
+                                    openssl::pkcs7::Pkcs7Flags::DETACHED | openssl::pkcs7::Pkcs7Flags::BINARY).unwrap().to_der().unwrap();
+        let (temp_file, target) = handler.assemble_data(&path, vec![signature.clone()], &env::temp_dir(), &HashMap::new()).await.expect("assemble jar successfully").remove(0);
+        assert_eq!(target, path.display().to_string());
+        let mut archive = ZipArchive::new(File::open(&temp_file).unwrap()).unwrap();
+        let names: Vec<String> = archive.file_names().map(|name| name.to_string()).collect();
+        assert_eq!(archive.by_index(0).unwrap().name(), META_INF);
+        assert_eq!(archive.by_index(1).unwrap().name(), MANIFEST_PATH);
+        assert_eq!(archive.by_index(2).unwrap().name(), "META-INF/SIGNATRUST.SF");
+        assert_eq!(archive.by_index(3).unwrap().name(), "META-INF/SIGNATRUST.RSA");
+        assert_eq!(names.len(), 7);
+        let mut block = Vec::new();
+        archive.by_name("META-INF/SIGNATRUST.RSA").unwrap().read_to_end(&mut block).unwrap();
+        assert_eq!(block, signature);
+        let mut class = Vec::new();
+        archive.by_name("org/Hello.class").unwrap().read_to_end(&mut class).unwrap();
+        assert_eq!(class, b"hello");
+    }
+}
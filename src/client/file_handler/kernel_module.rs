@@ -18,17 +18,16 @@ use super::traits::FileHandler;
 use crate::util::error::Result;
 use async_trait::async_trait;
 use std::fs;
-use std::io;
 use std::path::PathBuf;
-use std::str;
 
 use bincode::{config, Decode, Encode};
 use std::collections::HashMap;
-use std::io::{Read, Seek, Write};
+use std::io::Write;
 use std::os::raw::{c_uchar, c_uint};
 use uuid::Uuid;
 
 use crate::util::options;
+use crate::util::compression;
 use crate::util::sign::{SignType, KeyType};
 use crate::util::error::Error;
 use crate::util::options::DETACHED;
@@ -65,6 +64,141 @@ impl ModuleSignature {
     }
 }
 
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const XZ_MAGIC: &[u8] = &[0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const XZ_STREAM_HEADER_SIZE: usize = 12;
+const XZ_FILTER_LZMA2: u64 = 0x21;
+
+// Compressed kernel modules (.ko.gz, .ko.xz and .ko.zst) are decompressed before signing and recompressed
+// afterwards, the parameters are collected from the original file so that the signed module is compressed in the same way.
+// The level of zstd isn't recorded in the frame header, it's specified with the zstd_level option instead.
+// Reference https://git.kernel.org/pub/scm/linux/kernel/git/stable/linux.git/tree/scripts/Makefile.modinst
+#[derive(PartialEq, Debug)]
+enum Compression {
+    None,
+    Gzip { level: u32, mtime: u32, filename: Option<Vec<u8>> },
+    Xz { check: u8, dict_size: u32 },
+    Zstd { checksum: bool, window_log: Option<u32> },
+}
+
+impl Compression {
+    fn detect(content: &[u8]) -> Result<Compression> {
+        if content.starts_with(GZIP_MAGIC) {
+            Self::parse_gzip_header(content)
+        } else if content.starts_with(XZ_MAGIC) {
+            Self::parse_xz_header(content)
+        } else if content.starts_with(ZSTD_MAGIC) {
+            Self::parse_zstd_header(content)
+        } else {
+            Ok(Compression::None)
+        }
+    }
+
+    fn parse_gzip_header(content: &[u8]) -> Result<Compression> {
+        let invalid = || Error::SplitFileError("invalid gzip header of kernel module".to_owned());
+        let header = content.get(0..10).ok_or_else(invalid)?;
+        let flags = header[3];
+        let mtime = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        //extra flags only records the compression level of best(2) and fastest(4)
+        let level = match header[8] {
+            2 => 9,
+            4 => 1,
+            _ => 6,
+        };
+        let mut filename = None;
+        if flags & 0x08 != 0 {
+            let mut offset = 10;
+            if flags & 0x04 != 0 {
+                let extra = content.get(10..12).ok_or_else(invalid)?;
+                offset += 2 + u16::from_le_bytes([extra[0], extra[1]]) as usize;
+            }
+            let name = content.get(offset..).ok_or_else(invalid)?;
+            let end = name.iter().position(|b| *b == 0).ok_or_else(invalid)?;
+            filename = Some(name[..end].to_vec());
+        }
+        Ok(Compression::Gzip { level, mtime, filename })
+    }
+
+    fn parse_xz_header(content: &[u8]) -> Result<Compression> {
+        let invalid = || Error::SplitFileError("invalid xz header of kernel module".to_owned());
+        let check = content.get(7).ok_or_else(invalid)? & 0x0f;
+        //the dictionary size is stored in the LZMA2 filter flags of the first block header
+        let block = content.get(XZ_STREAM_HEADER_SIZE..).ok_or_else(invalid)?;
+        let size = (*block.first().ok_or_else(invalid)? as usize + 1) * 4;
+        let header = block.get(..size).ok_or_else(invalid)?;
+        let flags = *header.get(1).ok_or_else(invalid)?;
+        let mut offset = 2;
+        let read_varint = |offset: &mut usize| -> Result<u64> {
+            let mut value: u64 = 0;
+            for i in 0..9 {
+                let byte = *header.get(*offset).ok_or_else(invalid)?;
+                *offset += 1;
+                value |= ((byte & 0x7f) as u64) << (i * 7);
+                if byte & 0x80 == 0 {
+                    return Ok(value);
+                }
+            }
+            Err(invalid())
+        };
+        //skip compressed size and uncompressed size
+        if flags & 0x40 != 0 {
+            read_varint(&mut offset)?;
+        }
+        if flags & 0x80 != 0 {
+            read_varint(&mut offset)?;
+        }
+        for _ in 0..(flags & 0x03) + 1 {
+            let id = read_varint(&mut offset)?;
+            let length = read_varint(&mut offset)? as usize;
+            let properties = header.get(offset..offset + length).ok_or_else(invalid)?;
+            offset += length;
+            if id == XZ_FILTER_LZMA2 && length == 1 {
+                let bits = (properties[0] & 0x3f) as u32;
+                let dict_size = match bits {
+                    0..=39 => (2 | (bits & 1)) << (bits / 2 + 11),
+                    40 => u32::MAX,
+                    _ => return Err(invalid()),
+                };
+                return Ok(Compression::Xz { check, dict_size });
+            }
+        }
+        Err(Error::SplitFileError("only LZMA2 compressed xz kernel module is supported".to_owned()))
+    }
+
+    fn parse_zstd_header(content: &[u8]) -> Result<Compression> {
+        let invalid = || Error::SplitFileError("invalid zstd header of kernel module".to_owned());
+        let descriptor = *content.get(4).ok_or_else(invalid)?;
+        let checksum = descriptor & 0x04 != 0;
+        //window descriptor is absent when the whole content is in a single segment
+        let window_log = match descriptor & 0x20 {
+            0 => Some(10 + (*content.get(5).ok_or_else(invalid)? >> 3) as u32),
+            _ => None,
+        };
+        Ok(Compression::Zstd { checksum, window_log })
+    }
+
+    fn decompress(&self, content: Vec<u8>) -> Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(content),
+            Compression::Gzip { .. } => compression::decompress(compression::GZIP, &content),
+            Compression::Xz { .. } => compression::decompress(compression::XZ, &content),
+            Compression::Zstd { .. } => compression::decompress(compression::ZSTD, &content),
+        }
+    }
+
+    fn compress(&self, content: Vec<u8>, zstd_level: i32) -> Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(content),
+            Compression::Gzip { level, mtime, filename } => compression::compress_gzip(
+                &content, *level, *mtime, filename.as_deref()),
+            Compression::Xz { check, dict_size } => compression::compress_xz(&content, *check, *dict_size),
+            Compression::Zstd { checksum, window_log } => compression::compress_zstd(
+                &content, zstd_level, *checksum, *window_log),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct KernelModuleFileHandler {}
 
@@ -84,69 +218,83 @@ impl KernelModuleFileHandler {
         module: &PathBuf,
         tempfile: &PathBuf,
         signature: &[u8],
+        zstd_level: i32,
     ) -> Result<()> {
-        let mut signed = fs::File::create(tempfile)?;
-        signed.write_all(&self.get_raw_content(module, &mut HashMap::new())?)?;
-        signed.write_all(signature)?;
+        let content = fs::read(module)?;
+        let compression = Compression::detect(&content)?;
+        let mut signed = self.strip_signature(compression.decompress(content)?, &mut HashMap::new())?;
+        signed.extend_from_slice(signature);
         let sig_struct = ModuleSignature::new(signature.len() as c_uint);
-        signed.write_all(&bincode::encode_to_vec(
+        signed.extend_from_slice(&bincode::encode_to_vec(
             sig_struct,
             config::standard()
                 .with_fixed_int_encoding()
                 .with_big_endian(),
-        )?)?;
-        signed.write_all(MAGIC_NUMBER.as_bytes())?;
+        )?);
+        signed.extend_from_slice(MAGIC_NUMBER.as_bytes());
+        fs::write(tempfile, compression.compress(signed, zstd_level)?)?;
         Ok(())
     }
 
     pub fn get_raw_content(&self, path: &PathBuf, sign_options: &mut HashMap<String, String>) -> Result<Vec<u8>> {
-        let raw_content = fs::read(path)?;
-        let mut file = fs::File::open(path)?;
-        if file.metadata()?.len() <= SIGNATURE_SIZE as u64 {
-            return Ok(raw_content);
+        let content = fs::read(path)?;
+        let content = Compression::detect(&content)?.decompress(content)?;
+        self.strip_signature(content, sign_options)
+    }
+
+    //length of the appended signature, None is returned if the module is not signed
+    fn signature_length(raw_content: &[u8]) -> Result<Option<usize>> {
+        if raw_content.len() <= SIGNATURE_SIZE {
+            return Ok(None);
         }
         //identify magic string and end of the file
-        file.seek(io::SeekFrom::End(-(MAGIC_NUMBER_SIZE as i64)))?;
-        let mut signature_ending: [u8; MAGIC_NUMBER_SIZE] = [0; MAGIC_NUMBER_SIZE];
-        let _ = file.read(&mut signature_ending)?;
-        return match str::from_utf8(signature_ending.as_ref()) {
-            Ok(ending) => {
-                return if ending == MAGIC_NUMBER {
-                    file.seek(io::SeekFrom::End(-(SIGNATURE_SIZE as i64)))?;
-                    let mut signature_meta: [u8; SIGNATURE_SIZE - MAGIC_NUMBER_SIZE] = [0; SIGNATURE_SIZE - MAGIC_NUMBER_SIZE];
-                    let _ = file.read(&mut signature_meta)?;
-                    //decode kernel module signature struct
-                    let signature: ModuleSignature = bincode::decode_from_slice(
-                        &signature_meta,
-                        config::standard()
-                            .with_fixed_int_encoding()
-                            .with_big_endian(),
-                    )?
-                    .0;
-                    if raw_content.len() < SIGNATURE_SIZE + signature.sig_len as usize {
-                        return Err(Error::SplitFileError(
-                            "invalid kernel module signature size found".to_owned(),
-                        ));
-                    }
-                    if let Some(detached) = sign_options.get(DETACHED) {
-                        if detached == "true" {
-                            return Err(Error::SplitFileError(
-                                "already signed kernel module file doesn't support detached signature".to_owned()));
-                        }
-                    }
-                    //read raw content
-                    Ok(raw_content
-                        [0..(raw_content.len() - SIGNATURE_SIZE - signature.sig_len as usize)]
-                        .to_owned())
-                } else {
-                    Ok(raw_content)
-                };
-            }
-            Err(_) => {
-                //try to read whole content
-                Ok(raw_content)
-            }
+        if !raw_content.ends_with(MAGIC_NUMBER.as_bytes()) {
+            return Ok(None);
+        }
+        //decode kernel module signature struct
+        let signature: ModuleSignature = bincode::decode_from_slice(
+            &raw_content[raw_content.len() - SIGNATURE_SIZE..raw_content.len() - MAGIC_NUMBER_SIZE],
+            config::standard()
+                .with_fixed_int_encoding()
+                .with_big_endian(),
+        )?
+        .0;
+        if raw_content.len() < SIGNATURE_SIZE + signature.sig_len as usize {
+            return Err(Error::SplitFileError(
+                "invalid kernel module signature size found".to_owned(),
+            ));
+        }
+        Ok(Some(signature.sig_len as usize))
+    }
+
+    //the default level is the same as zstd command which is used when compressing kernel modules
+    fn zstd_level(sign_options: &HashMap<String, String>) -> Result<i32> {
+        let level = match sign_options.get(options::ZSTD_LEVEL) {
+            Some(level) => level.parse::<i32>().map_err(|_| Error::InvalidArgumentError(
+                format!("invalid zstd level {}", level)))?,
+            None => return Ok(zstd::DEFAULT_COMPRESSION_LEVEL),
         };
+        if !zstd::compression_level_range().contains(&level) {
+            return Err(Error::InvalidArgumentError(format!("zstd level {} is out of range", level)));
+        }
+        Ok(level)
+    }
+
+    fn strip_signature(&self, raw_content: Vec<u8>, sign_options: &mut HashMap<String, String>) -> Result<Vec<u8>> {
+        let length = match KernelModuleFileHandler::signature_length(&raw_content)? {
+            Some(length) => length,
+            None => return Ok(raw_content),
+        };
+        if let Some(detached) = sign_options.get(DETACHED) {
+            if detached == "true" {
+                return Err(Error::SplitFileError(
+                    "already signed kernel module file doesn't support detached signature".to_owned()));
+            }
+        }
+        //read raw content
+        Ok(raw_content
+            [0..(raw_content.len() - SIGNATURE_SIZE - length)]
+            .to_owned())
     }
 }
 
@@ -169,6 +317,7 @@ impl FileHandler for KernelModuleFileHandler {
                 ));
             }
         }
+        KernelModuleFileHandler::zstd_level(sign_options)?;
         Ok(())
     }
 
@@ -181,6 +330,25 @@ impl FileHandler for KernelModuleFileHandler {
         Ok(vec![self.get_raw_content(path, sign_options)?])
     }
 
+    //the appended signature, or the detached signature file when the module is not signed
+    async fn get_signatures(
+        &self,
+        path: &PathBuf,
+        _sign_options: &mut HashMap<String, String>,
+    ) -> Result<Vec<Vec<u8>>> {
+        let content = fs::read(path)?;
+        let content = Compression::detect(&content)?.decompress(content)?;
+        if let Some(length) = KernelModuleFileHandler::signature_length(&content)? {
+            let end = content.len() - SIGNATURE_SIZE;
+            return Ok(vec![content[end - length..end].to_vec()]);
+        }
+        let detached = PathBuf::from(format!("{}.{}", path.display(), FILE_EXTENSION));
+        if detached.exists() {
+            return Ok(vec![fs::read(detached)?]);
+        }
+        Err(Error::SplitFileError(format!("kernel module {} is not signed", path.display())))
+    }
+
     /* when assemble generic signature when only create another .asc file separately */
     async fn assemble_data(
         &self,
@@ -188,23 +356,23 @@ impl FileHandler for KernelModuleFileHandler {
         data: Vec<Vec<u8>>,
         temp_dir: &PathBuf,
         sign_options: &HashMap<String, String>,
-    ) -> Result<(String, String)> {
+    ) -> Result<Vec<(String, String)>> {
         let temp_file = temp_dir.join(Uuid::new_v4().to_string());
         //convert bytes into string
         if let Some(detached) = sign_options.get(DETACHED) {
             if detached == "true" {
                 self.generate_detached_signature(&temp_file.display().to_string(), &data[0])?;
-                return Ok((
+                return Ok(vec![(
                     temp_file.as_path().display().to_string(),
                     format!("{}.{}", path.display(), FILE_EXTENSION),
-                ));
+                )]);
             }
         }
-        self.append_inline_signature(path, &temp_file, &data[0])?;
-        return Ok((
+        self.append_inline_signature(path, &temp_file, &data[0], KernelModuleFileHandler::zstd_level(sign_options)?)?;
+        return Ok(vec![(
             temp_file.as_path().display().to_string(),
             path.display().to_string(),
-        ));
+        )]);
     }
 }
 
@@ -322,6 +490,24 @@ mod test {
         options.insert(options::SIGN_TYPE.to_string(), SignType::PKCS7.to_string());
         let result = handler.validate_options(&options);
         assert!(result.is_ok());
+
+        options.insert(options::ZSTD_LEVEL.to_string(), "fast".to_string());
+        let result = handler.validate_options(&options);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "invalid argument: invalid zstd level fast"
+        );
+
+        options.insert(options::ZSTD_LEVEL.to_string(), "100".to_string());
+        let result = handler.validate_options(&options);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "invalid argument: zstd level 100 is out of range"
+        );
+
+        options.insert(options::ZSTD_LEVEL.to_string(), "19".to_string());
+        let result = handler.validate_options(&options);
+        assert!(result.is_ok());
     }
 
     #[tokio::test]
@@ -334,7 +520,7 @@ mod test {
         let temp_dir = env::temp_dir();
         let result = handler.assemble_data(&path, data, &temp_dir, &options).await;
         assert!(result.is_ok());
-        let (temp_file, file_name) = result.expect("invoke assemble data should work");
+        let (temp_file, file_name) = result.expect("invoke assemble data should work").remove(0);
         assert_eq!(temp_file.starts_with(temp_dir.to_str().unwrap()), true);
         assert_eq!(file_name, "./test_data/test.ko.p7s");
         let result = fs::read(temp_file).expect("read temp file failed");
@@ -352,13 +538,92 @@ mod test {
         let temp_dir = env::temp_dir();
         let result = handler.assemble_data(&path, data, &temp_dir, &options).await;
         assert!(result.is_ok());
-        let (temp_file, file_name) = result.expect("invoke assemble data should work");
+        let (temp_file, file_name) = result.expect("invoke assemble data should work").remove(0);
         assert_eq!(temp_file.starts_with(temp_dir.to_str().unwrap()), true);
         assert_eq!(file_name, name);
         let result = handler.get_raw_content(&PathBuf::from(temp_file), &mut options).expect("get raw content failed");
         assert_eq!(result, raw_content);
     }
 
+    fn compress_kernel_module(raw_content: &[u8], extension: &str) -> Result<String> {
+        let compressed = match extension {
+            "gz" => {
+                let mut encoder = flate2::GzBuilder::new().mtime(1686787200).filename("test.ko")
+                    .write(Vec::new(), flate2::Compression::best());
+                encoder.write_all(raw_content)?;
+                encoder.finish()?
+            }
+            "xz" => {
+                let mut options = xz2::stream::LzmaOptions::new_preset(6).expect("create lzma options failed");
+                options.dict_size(1024 * 1024);
+                let mut filters = xz2::stream::Filters::new();
+                filters.lzma2(&options);
+                let stream = xz2::stream::Stream::new_stream_encoder(
+                    &filters, xz2::stream::Check::Crc32).expect("create xz stream failed");
+                let mut encoder = xz2::write::XzEncoder::new_stream(Vec::new(), stream);
+                encoder.write_all(raw_content)?;
+                encoder.finish()?
+            }
+            _ => {
+                let mut encoder = zstd::stream::Encoder::new(Vec::new(), 19)?;
+                encoder.include_checksum(true)?;
+                encoder.set_pledged_src_size(Some(raw_content.len() as u64))?;
+                encoder.window_log(10)?;
+                encoder.write_all(raw_content)?;
+                encoder.finish()?
+            }
+        };
+        let temp_file = env::temp_dir().join(format!("{}.ko.{}", Uuid::new_v4(), extension));
+        fs::write(&temp_file, compressed)?;
+        Ok(temp_file.display().to_string())
+    }
+
+    #[test]
+    fn test_detect_compression() {
+        let (_, raw_content) = generate_unsigned_kernel_module(4096).expect("generate unsigned kernel module failed");
+        assert_eq!(Compression::detect(&raw_content).expect("detect compression failed"), Compression::None);
+        let expected = vec![
+            ("gz", Compression::Gzip { level: 9, mtime: 1686787200, filename: Some(b"test.ko".to_vec()) }),
+            ("xz", Compression::Xz { check: 1, dict_size: 1024 * 1024 }),
+            ("zst", Compression::Zstd { checksum: true, window_log: Some(10) }),
+        ];
+        for (extension, compression) in expected {
+            let name = compress_kernel_module(&raw_content, extension).expect("compress kernel module failed");
+            let content = fs::read(name).expect("read compressed kernel module failed");
+            assert_eq!(Compression::detect(&content).expect("detect compression failed"), compression);
+            let recompressed = compression.compress(
+                compression.decompress(content).expect("decompress failed"), 19).expect("compress failed");
+            assert_eq!(Compression::detect(&recompressed).expect("detect compression failed"), compression);
+        }
+        assert!(Compression::detect(&[0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00, 0x00]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_assemble_data_with_compressed_kernel_module() {
+        let handler = KernelModuleFileHandler::new();
+        let mut options = HashMap::new();
+        options.insert(options::ZSTD_LEVEL.to_string(), "19".to_string());
+        let (_, raw_content) = generate_signed_kernel_module(4096, false).expect("generate signed kernel module failed");
+        for extension in ["gz", "xz", "zst"] {
+            let name = compress_kernel_module(&raw_content, extension).expect("compress kernel module failed");
+            let path = PathBuf::from(name.clone());
+            let data = handler.split_data(&path, &mut options).await.expect("split data failed");
+            assert_eq!(data[0], raw_content[..4096]);
+            let result = handler.assemble_data(&path, vec![vec![1, 2, 3]], &env::temp_dir(), &options).await;
+            let (temp_file, file_name) = result.expect("invoke assemble data should work").remove(0);
+            assert_eq!(file_name, name);
+            let signed = fs::read(&temp_file).expect("read temp file failed");
+            let compression = Compression::detect(&fs::read(&name).expect("read kernel module failed")).expect("detect compression failed");
+            assert_eq!(Compression::detect(&signed).expect("detect compression failed"), compression);
+            let decompressed = compression.decompress(signed.clone()).expect("decompress failed");
+            assert_eq!(compression.compress(decompressed.clone(), 19).expect("compress failed"), signed);
+            assert!(decompressed.ends_with(MAGIC_NUMBER.as_bytes()));
+            assert_eq!(decompressed[4096..4099], [1, 2, 3]);
+            let result = handler.get_raw_content(&PathBuf::from(temp_file), &mut options).expect("get raw content failed");
+            assert_eq!(result, raw_content[..4096]);
+        }
+    }
+
     #[tokio::test]
     async fn test_split_content() {
         let mut sign_options = HashMap::new();
@@ -370,5 +635,20 @@ mod test {
         assert_eq!(original_content, raw_content[0]);
     }
 
+    #[tokio::test]
+    async fn test_get_signatures() {
+        let mut sign_options = HashMap::new();
+        let file_handler = KernelModuleFileHandler::new();
+        let (name, _) = generate_signed_kernel_module(4096, false).expect("generate signed kernel module failed");
+        let signatures = file_handler.get_signatures(&PathBuf::from(name), &mut sign_options).await.expect("get signatures successfully");
+        assert_eq!(signatures, vec![vec![1, 2, 3, 4, 5, 6]]);
+
+        let (name, _) = generate_unsigned_kernel_module(4096).expect("generate unsigned kernel module failed");
+        let path = PathBuf::from(name.clone());
+        file_handler.get_signatures(&path, &mut sign_options).await.expect_err("kernel module is not signed");
+        fs::write(format!("{}.{}", name, FILE_EXTENSION), [7, 8, 9]).expect("write detached signature failed");
+        let signatures = file_handler.get_signatures(&path, &mut sign_options).await.expect("get signatures successfully");
+        assert_eq!(signatures, vec![vec![7, 8, 9]]);
+    }
 }
 
@@ -1,7 +1,17 @@
 pub mod rpm;
 pub mod efi;
+pub mod efi_variable;
 pub mod traits;
 pub mod factory;
 pub mod generic;
 pub mod kernel_module;
-pub mod sequential_cursor;
\ No newline at end of file
+pub mod deb;
+pub mod apt_release;
+pub mod repomd;
+pub mod sbom;
+pub mod oci;
+pub mod ostree;
+pub mod jar;
+pub mod sequential_cursor;
+pub mod rpm_ima;
+pub mod rpm_header;
\ No newline at end of file
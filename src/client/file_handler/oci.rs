@@ -0,0 +1,345 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use super::traits::FileHandler;
+use crate::util::payload;
+use crate::util::sign::{KeyType, SignType};
+use crate::util::error::{Error, Result};
+use async_trait::async_trait;
+use data_encoding::BASE64;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use uuid::Uuid;
+
+use crate::util::options;
+
+//layout reference: https://github.com/opencontainers/image-spec/blob/main/image-layout.md
+const OCI_LAYOUT_FILE: &str = "oci-layout";
+const OCI_INDEX_FILE: &str = "index.json";
+const OCI_BLOB_DIR: &str = "blobs";
+const SHA256_ALGORITHM: &str = "sha256";
+const OCI_MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+const OCI_INDEX_MEDIA_TYPE: &str = "application/vnd.oci.image.index.v1+json";
+const DOCKER_MANIFEST_MEDIA_TYPE: &str = "application/vnd.docker.distribution.manifest.v2+json";
+const DOCKER_MANIFEST_LIST_MEDIA_TYPE: &str = "application/vnd.docker.distribution.manifest.list.v2+json";
+const OCI_CONFIG_MEDIA_TYPE: &str = "application/vnd.oci.image.config.v1+json";
+const REF_NAME_ANNOTATION: &str = "org.opencontainers.image.ref.name";
+const IMAGE_NAME_ANNOTATION: &str = "io.containerd.image.name";
+//cosign signature format, check https://github.com/sigstore/cosign/blob/main/specs/SIGNATURE_SPEC.md
+const SIMPLE_SIGNING_MEDIA_TYPE: &str = "application/vnd.dev.cosign.simplesigning.v1+json";
+const SIGNATURE_ANNOTATION: &str = "dev.cosignproject.cosign/signature";
+const SIGNATURE_TAG_SUFFIX: &str = ".sig";
+const ZERO_TIME: &str = "0001-01-01T00:00:00Z";
+
+//manifest referenced in the index of layout which will be signed
+#[derive(Debug, PartialEq)]
+struct SignTarget {
+    digest: String,
+    repository: String,
+}
+
+impl SignTarget {
+    //the signature manifest is tagged with `sha256-<hex>.sig` as cosign does
+    fn signature_tag(&self) -> String {
+        format!("{}{}", self.digest.replacen(':', "-", 1), SIGNATURE_TAG_SUFFIX)
+    }
+
+    fn payload(&self) -> Result<Vec<u8>> {
+        payload::cosign_payload(&self.repository, &self.digest)
+    }
+}
+
+#[derive(Clone)]
+pub struct OciImageFileHandler {}
+
+impl OciImageFileHandler {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    fn blob_path(layout: &Path, digest: &str) -> Result<PathBuf> {
+        match digest.split_once(':') {
+            Some((SHA256_ALGORITHM, hex)) if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) => {
+                Ok(layout.join(OCI_BLOB_DIR).join(SHA256_ALGORITHM).join(hex))
+            }
+            _ => Err(Error::SplitFileError(format!("unsupported manifest digest {}", digest))),
+        }
+    }
+
+    fn sha256_digest(content: &[u8]) -> String {
+        format!("{}:{}", SHA256_ALGORITHM, hex::encode(openssl::sha::sha256(content)))
+    }
+
+    //get the repository from full image reference, tag and digest are removed, i.e. `registry.io/app:1.0` to `registry.io/app`
+    fn get_repository(annotations: Option<&Value>) -> Option<String> {
+        [IMAGE_NAME_ANNOTATION, REF_NAME_ANNOTATION].iter().filter_map(|key| {
+            annotations?.get(key)?.as_str()
+        }).find(|name| name.contains('/')).map(|name| {
+            let name = name.split('@').next().unwrap_or(name);
+            match name.rfind(':') {
+                Some(index) if index > name.rfind('/').unwrap_or(0) => name[..index].to_string(),
+                _ => name.to_string(),
+            }
+        })
+    }
+
+    async fn load_index(layout: &Path) -> Result<Value> {
+        if !layout.join(OCI_LAYOUT_FILE).is_file() {
+            return Err(Error::SplitFileError(format!("{} is not an oci image layout", layout.display())));
+        }
+        Ok(serde_json::from_slice(&fs::read(layout.join(OCI_INDEX_FILE)).await?)?)
+    }
+
+    //all image manifests and indexes in the layout are signed, except the signatures generated before
+    fn get_sign_targets(layout: &Path, index: &Value) -> Result<Vec<SignTarget>> {
+        let mut targets = Vec::new();
+        for descriptor in index.get("manifests").and_then(|m| m.as_array()).into_iter().flatten() {
+            let media_type = descriptor.get("mediaType").and_then(|m| m.as_str()).unwrap_or_default();
+            if ![OCI_MANIFEST_MEDIA_TYPE, OCI_INDEX_MEDIA_TYPE, DOCKER_MANIFEST_MEDIA_TYPE, DOCKER_MANIFEST_LIST_MEDIA_TYPE].contains(&media_type) {
+                continue
+            }
+            let annotations = descriptor.get("annotations");
+            if let Some(name) = annotations.and_then(|a| a.get(REF_NAME_ANNOTATION)).and_then(|n| n.as_str()) {
+                if name.ends_with(SIGNATURE_TAG_SUFFIX) {
+                    continue
+                }
+            }
+            let digest = descriptor.get("digest").and_then(|d| d.as_str()).ok_or(
+                Error::SplitFileError("manifest digest is missing in index".to_string()))?;
+            if !OciImageFileHandler::blob_path(layout, digest)?.is_file() {
+                return Err(Error::SplitFileError(format!("manifest {} is missing in blobs", digest)));
+            }
+            let repository = OciImageFileHandler::get_repository(annotations).ok_or(Error::SplitFileError(format!(
+                "unable to determine the repository of manifest {}, full image reference is required in `{}` or `{}` annotation",
+                digest, REF_NAME_ANNOTATION, IMAGE_NAME_ANNOTATION)))?;
+            targets.push(SignTarget { digest: digest.to_string(), repository });
+        }
+        if targets.is_empty() {
+            return Err(Error::SplitFileError("no image manifest found in oci image layout".to_string()));
+        }
+        Ok(targets)
+    }
+
+    fn descriptor(media_type: &str, content: &[u8]) -> Value {
+        json!({
+            "mediaType": media_type,
+            "size": content.len(),
+            "digest": OciImageFileHandler::sha256_digest(content),
+        })
+    }
+
+    //generate the payload, config and manifest blobs of signature image
+    fn signature_blobs(target: &SignTarget, signature: &[u8]) -> Result<Vec<Vec<u8>>> {
+        let payload = target.payload()?;
+        let config = serde_json::to_vec(&json!({
+            "architecture": "",
+            "config": {},
+            "created": ZERO_TIME,
+            "history": [{"created": ZERO_TIME}],
+            "os": "",
+            "rootfs": {"type": "layers", "diff_ids": [OciImageFileHandler::sha256_digest(&payload)]},
+        }))?;
+        let mut layer = OciImageFileHandler::descriptor(SIMPLE_SIGNING_MEDIA_TYPE, &payload);
+        layer["annotations"] = json!({SIGNATURE_ANNOTATION: BASE64.encode(signature)});
+        let manifest = serde_json::to_vec(&json!({
+            "schemaVersion": 2,
+            "mediaType": OCI_MANIFEST_MEDIA_TYPE,
+            "config": OciImageFileHandler::descriptor(OCI_CONFIG_MEDIA_TYPE, &config),
+            "layers": [layer],
+        }))?;
+        Ok(vec![payload, config, manifest])
+    }
+}
+
+#[async_trait]
+impl FileHandler for OciImageFileHandler {
+    fn validate_options(&self, sign_options: &HashMap<String, String>) -> Result<()> {
+        if let Some(key_type) = sign_options.get(options::KEY_TYPE) {
+            if key_type != KeyType::X509EE.to_string().as_str() {
+                return Err(Error::InvalidArgumentError("oci image only support x509 signature".to_string()))
+            }
+        }
+        Ok(())
+    }
+
+    //one section for each manifest, which is the image reference followed by the manifest, server builds the simple
+    //signing payload from it
+    async fn split_data(
+        &self,
+        path: &PathBuf,
+        sign_options: &mut HashMap<String, String>,
+    ) -> Result<Vec<Vec<u8>>> {
+        let index = OciImageFileHandler::load_index(path).await?;
+        sign_options.insert(options::SIGN_TYPE.to_string(), SignType::Cosign.to_string());
+        let mut sections = Vec::new();
+        for target in OciImageFileHandler::get_sign_targets(path, &index)? {
+            let manifest = fs::read(OciImageFileHandler::blob_path(path, &target.digest)?).await?;
+            sections.push(payload::cosign_content(&target.repository, &manifest));
+        }
+        Ok(sections)
+    }
+
+    //the blobs of signature images are added into layout and the index is updated at last
+    async fn assemble_data(
+        &self,
+        path: &PathBuf,
+        data: Vec<Vec<u8>>,
+        temp_dir: &PathBuf,
+        _sign_options: &HashMap<String, String>,
+    ) -> Result<Vec<(String, String)>> {
+        let mut index = OciImageFileHandler::load_index(path).await?;
+        let targets = OciImageFileHandler::get_sign_targets(path, &index)?;
+        if targets.len() != data.len() {
+            return Err(Error::AssembleFileError(format!(
+                "expect {} signatures for oci image, got {}", targets.len(), data.len())));
+        }
+        let mut outputs = Vec::new();
+        let mut descriptors = Vec::new();
+        for (target, signature) in targets.iter().zip(data.iter()) {
+            let blobs = OciImageFileHandler::signature_blobs(target, signature)?;
+            for blob in blobs.iter() {
+                let temp_file = temp_dir.join(Uuid::new_v4().to_string());
+                fs::write(&temp_file, blob).await?;
+                let blob_path = OciImageFileHandler::blob_path(path, &OciImageFileHandler::sha256_digest(blob))?;
+                outputs.push((temp_file.display().to_string(), blob_path.display().to_string()));
+            }
+            let mut descriptor = OciImageFileHandler::descriptor(OCI_MANIFEST_MEDIA_TYPE, &blobs[2]);
+            descriptor["annotations"] = json!({REF_NAME_ANNOTATION: target.signature_tag()});
+            descriptors.push(descriptor);
+        }
+        //signature generated before is replaced
+        let manifests = index.get_mut("manifests").and_then(|m| m.as_array_mut()).ok_or(
+            Error::AssembleFileError("manifests are missing in index".to_string()))?;
+        manifests.retain(|manifest| !descriptors.iter().any(|descriptor| {
+            manifest.get("annotations").and_then(|a| a.get(REF_NAME_ANNOTATION)).is_some() &&
+                manifest["annotations"][REF_NAME_ANNOTATION] == descriptor["annotations"][REF_NAME_ANNOTATION]
+        }));
+        manifests.extend(descriptors);
+        let temp_index = temp_dir.join(Uuid::new_v4().to_string());
+        fs::write(&temp_index, serde_json::to_vec(&index)?).await?;
+        outputs.push((temp_index.display().to_string(), path.join(OCI_INDEX_FILE).display().to_string()));
+        Ok(outputs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+
+    const MANIFEST: &str = r#"{"schemaVersion":2,"mediaType":"application/vnd.oci.image.manifest.v1+json","config":{"mediaType":"application/vnd.oci.image.config.v1+json","size":2,"digest":"sha256:44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"},"layers":[]}"#;
+
+    async fn generate_layout(ref_name: &str) -> (PathBuf, String) {
+        let layout = env::temp_dir().join(Uuid::new_v4().to_string());
+        let digest = OciImageFileHandler::sha256_digest(MANIFEST.as_bytes());
+        let blob = OciImageFileHandler::blob_path(&layout, &digest).expect("get blob path successfully");
+        fs::create_dir_all(blob.parent().unwrap()).await.expect("create blob directory successfully");
+        fs::write(&blob, MANIFEST).await.expect("write manifest successfully");
+        fs::write(layout.join(OCI_LAYOUT_FILE), r#"{"imageLayoutVersion":"1.0.0"}"#).await.expect("write layout successfully");
+        let index = json!({
+            "schemaVersion": 2,
+            "manifests": [{
+                "mediaType": OCI_MANIFEST_MEDIA_TYPE,
+                "size": MANIFEST.len(),
+                "digest": digest,
+                "annotations": {REF_NAME_ANNOTATION: ref_name},
+            }],
+        });
+        fs::write(layout.join(OCI_INDEX_FILE), serde_json::to_vec(&index).unwrap()).await.expect("write index successfully");
+        (layout, digest)
+    }
+
+    #[test]
+    fn test_get_repository() {
+        for (name, expected) in [
+            ("registry.io/app:1.0", Some("registry.io/app")),
+            ("localhost:5000/team/app", Some("localhost:5000/team/app")),
+            ("localhost:5000/app:latest@sha256:1234", Some("localhost:5000/app")),
+            ("latest", None)] {
+            let annotations = json!({REF_NAME_ANNOTATION: name});
+            assert_eq!(OciImageFileHandler::get_repository(Some(&annotations)).as_deref(), expected);
+        }
+        let annotations = json!({REF_NAME_ANNOTATION: "latest", IMAGE_NAME_ANNOTATION: "docker.io/library/app:latest"});
+        assert_eq!(OciImageFileHandler::get_repository(Some(&annotations)), Some("docker.io/library/app".to_string()));
+        assert_eq!(OciImageFileHandler::get_repository(None), None);
+    }
+
+    #[test]
+    fn test_validate_options() {
+        let handler = OciImageFileHandler::new();
+        let mut options = HashMap::new();
+        options.insert(options::KEY_TYPE.to_string(), KeyType::Pgp.to_string());
+        let result = handler.validate_options(&options);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "invalid argument: oci image only support x509 signature"
+        );
+        options.insert(options::KEY_TYPE.to_string(), KeyType::X509EE.to_string());
+        assert!(handler.validate_options(&options).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_split_data() {
+        let handler = OciImageFileHandler::new();
+        let mut options = HashMap::new();
+        let (layout, digest) = generate_layout("registry.io/app:1.0").await;
+        let sections = handler.split_data(&layout, &mut options).await.expect("split oci layout successfully");
+        assert_eq!(options.get(options::SIGN_TYPE), Some(&SignType::Cosign.to_string()));
+        assert_eq!(sections, vec![format!("registry.io/app\n{}", MANIFEST).into_bytes()]);
+        //server signs the payload built from the section
+        let signed = payload::sign_cosign(&sections[0], |content| Ok(content.to_vec())).expect("build cosign payload successfully");
+        let payload: Value = serde_json::from_slice(&signed).expect("payload is valid json");
+        assert_eq!(payload["critical"]["identity"]["docker-reference"], "registry.io/app");
+        assert_eq!(payload["critical"]["image"]["docker-manifest-digest"], digest.as_str());
+        assert_eq!(payload["critical"]["type"], "cosign container image signature");
+
+        let (layout, _) = generate_layout("latest").await;
+        assert!(handler.split_data(&layout, &mut options).await.is_err());
+        assert!(handler.split_data(&env::temp_dir(), &mut options).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_assemble_data() {
+        let handler = OciImageFileHandler::new();
+        let mut options = HashMap::new();
+        let (layout, digest) = generate_layout("registry.io/app:1.0").await;
+        let sections = handler.split_data(&layout, &mut options).await.expect("split oci layout successfully");
+        assert!(handler.assemble_data(&layout, vec![], &env::temp_dir(), &options).await.is_err());
+        //sign twice and the signature manifest is replaced
+        for _ in 0..2 {
+            let outputs = handler.assemble_data(&layout, vec![vec![1, 2, 3]], &env::temp_dir(), &options).await.expect("assemble oci layout successfully");
+            assert_eq!(outputs.len(), 4);
+            for (temp_file, target) in outputs.iter() {
+                std::fs::copy(temp_file, target).expect("copy file successfully");
+            }
+        }
+        let index: Value = serde_json::from_slice(&fs::read(layout.join(OCI_INDEX_FILE)).await.unwrap()).unwrap();
+        let manifests = index["manifests"].as_array().unwrap();
+        assert_eq!(manifests.len(), 2);
+        assert_eq!(manifests[1]["annotations"][REF_NAME_ANNOTATION], digest.replacen(':', "-", 1) + SIGNATURE_TAG_SUFFIX);
+        let manifest_path = OciImageFileHandler::blob_path(&layout, manifests[1]["digest"].as_str().unwrap()).unwrap();
+        let manifest: Value = serde_json::from_slice(&fs::read(manifest_path).await.unwrap()).unwrap();
+        let layer = &manifest["layers"][0];
+        assert_eq!(layer["mediaType"], SIMPLE_SIGNING_MEDIA_TYPE);
+        assert_eq!(layer["annotations"][SIGNATURE_ANNOTATION], BASE64.encode(&[1, 2, 3]));
+        let payload = fs::read(OciImageFileHandler::blob_path(&layout, layer["digest"].as_str().unwrap()).unwrap()).await.unwrap();
+        assert_eq!(payload, payload::sign_cosign(&sections[0], |content| Ok(content.to_vec())).unwrap());
+        //signature manifest itself is not signed again
+        assert_eq!(handler.split_data(&layout, &mut options).await.expect("split oci layout successfully").len(), 1);
+    }
+}
@@ -0,0 +1,349 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use super::traits::FileHandler;
+use crate::util::sign::{KeyType, SignType};
+use crate::util::error::{Error, Result};
+use async_trait::async_trait;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use uuid::Uuid;
+
+use crate::util::options;
+
+//repository layout reference: https://ostreedev.github.io/ostree/repo/
+const REPO_CONFIG_FILE: &str = "config";
+const OBJECTS_DIR: &str = "objects";
+const HEADS_DIR: &str = "refs/heads";
+const COMMIT_SUFFIX: &str = "commit";
+const COMMIT_META_SUFFIX: &str = "commitmeta";
+//detached metadata key and format used by `ostree sign`, check `ostree_sign_ed25519_metadata_key`
+const ED25519_METADATA_KEY: &str = "ostree.sign.ed25519";
+const ED25519_METADATA_FORMAT: &str = "aay";
+const ED25519_SIGNATURE_LENGTH: usize = 64;
+const DICT_ENTRY_ALIGNMENT: usize = 8;
+
+//minimal GVariant serialization for the `a{sv}` detached metadata, check https://people.gnome.org/~desrt/gvariant-serialisation.pdf
+//values except the ed25519 signatures are kept as they are.
+mod gvariant {
+    use crate::util::error::{Error, Result};
+
+    fn invalid() -> Error {
+        Error::SplitFileError("invalid gvariant serialized data".to_string())
+    }
+
+    fn align(offset: usize, alignment: usize) -> usize {
+        offset.div_ceil(alignment) * alignment
+    }
+
+    //size of framing offsets is determined by the size of container
+    fn offset_size(container_size: usize) -> usize {
+        match container_size {
+            0 => 0,
+            1..=0xff => 1,
+            0x100..=0xffff => 2,
+            0x10000..=0xffffffff => 4,
+            _ => 8,
+        }
+    }
+
+    fn read_offset(data: &[u8], position: usize, size: usize) -> Result<usize> {
+        let bytes = data.get(position..position + size).ok_or_else(invalid)?;
+        Ok(bytes.iter().rev().fold(0usize, |value, byte| (value << 8) | *byte as usize))
+    }
+
+    //append framing offsets with the smallest size which can address the whole container
+    fn append_offsets(mut body: Vec<u8>, offsets: &[usize]) -> Vec<u8> {
+        if body.is_empty() && offsets.is_empty() {
+            return body
+        }
+        let size = [1usize, 2, 4, 8].into_iter().find(
+            |size| offset_size(body.len() + offsets.len() * size) == *size).unwrap_or(8);
+        for offset in offsets {
+            body.extend_from_slice(&offset.to_le_bytes()[..size]);
+        }
+        body
+    }
+
+    //array of variable sized elements, i.e. `ay` in `aay` and `{sv}` in `a{sv}`
+    pub fn parse_array(data: &[u8], alignment: usize) -> Result<Vec<&[u8]>> {
+        let size = offset_size(data.len());
+        if size == 0 {
+            return Ok(vec![])
+        }
+        let offsets_start = read_offset(data, data.len() - size, size)?;
+        if offsets_start > data.len() || !(data.len() - offsets_start).is_multiple_of(size) {
+            return Err(invalid())
+        }
+        let mut elements = Vec::new();
+        let mut start = 0;
+        for position in (offsets_start..data.len()).step_by(size) {
+            let end = read_offset(data, position, size)?;
+            if start > end || end > offsets_start {
+                return Err(invalid())
+            }
+            elements.push(&data[start..end]);
+            start = align(end, alignment);
+        }
+        Ok(elements)
+    }
+
+    pub fn serialize_array(elements: &[Vec<u8>], alignment: usize) -> Vec<u8> {
+        let mut body = Vec::new();
+        let mut offsets = Vec::new();
+        for element in elements {
+            body.resize(align(body.len(), alignment), 0);
+            body.extend_from_slice(element);
+            offsets.push(body.len());
+        }
+        append_offsets(body, &offsets)
+    }
+
+    //dict entry `{sv}`, the end of key is stored as framing offset
+    pub fn parse_entry(data: &[u8]) -> Result<(String, &[u8])> {
+        let size = offset_size(data.len());
+        if size == 0 || data.len() < size {
+            return Err(invalid())
+        }
+        let key_end = read_offset(data, data.len() - size, size)?;
+        if key_end == 0 || key_end > data.len() - size {
+            return Err(invalid())
+        }
+        let key = std::str::from_utf8(&data[..key_end - 1]).map_err(|_| invalid())?.to_string();
+        let value = data.get(align(key_end, 8)..data.len() - size).ok_or_else(invalid)?;
+        Ok((key, value))
+    }
+
+    pub fn serialize_entry(key: &str, value: &[u8]) -> Vec<u8> {
+        let mut body = key.as_bytes().to_vec();
+        body.push(0);
+        let key_end = body.len();
+        body.resize(align(key_end, 8), 0);
+        body.extend_from_slice(value);
+        append_offsets(body, &[key_end])
+    }
+
+    //variant `v` is the serialized value followed by a zero byte and the type string
+    pub fn parse_variant(data: &[u8]) -> Result<(&[u8], &str)> {
+        let separator = data.iter().rposition(|byte| *byte == 0).ok_or_else(invalid)?;
+        Ok((&data[..separator], std::str::from_utf8(&data[separator + 1..]).map_err(|_| invalid())?))
+    }
+
+    pub fn serialize_variant(value: &[u8], type_string: &str) -> Vec<u8> {
+        let mut data = value.to_vec();
+        data.push(0);
+        data.extend_from_slice(type_string.as_bytes());
+        data
+    }
+}
+
+#[derive(Clone)]
+pub struct OstreeFileHandler {}
+
+impl OstreeFileHandler {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    //loose objects are stored in `objects/<first 2 characters of checksum>/<remaining characters>.<type>`
+    fn object_path(repo: &Path, checksum: &str, suffix: &str) -> PathBuf {
+        repo.join(OBJECTS_DIR).join(&checksum[..2]).join(format!("{}.{}", &checksum[2..], suffix))
+    }
+
+    //commits of all branches in repository, duplicated ones are signed once
+    async fn get_commits(repo: &Path) -> Result<Vec<String>> {
+        if !repo.join(REPO_CONFIG_FILE).is_file() || !repo.join(OBJECTS_DIR).is_dir() {
+            return Err(Error::SplitFileError(format!("{} is not an ostree repository", repo.display())));
+        }
+        if !repo.join(HEADS_DIR).is_dir() {
+            return Err(Error::SplitFileError("no branch found in ostree repository".to_string()));
+        }
+        let mut commits = BTreeSet::new();
+        for entry in walkdir::WalkDir::new(repo.join(HEADS_DIR)).sort_by_file_name() {
+            let entry = entry.map_err(|e| Error::WalkDirectoryError(e.to_string()))?;
+            if !entry.file_type().is_file() {
+                continue
+            }
+            let checksum = fs::read_to_string(entry.path()).await?.trim().to_string();
+            if checksum.len() != 64 || !checksum.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(Error::SplitFileError(format!("invalid commit checksum in ref {}", entry.path().display())));
+            }
+            commits.insert(checksum.to_lowercase());
+        }
+        if commits.is_empty() {
+            return Err(Error::SplitFileError("no branch found in ostree repository".to_string()));
+        }
+        Ok(commits.into_iter().collect())
+    }
+
+    //add the signature into `ostree.sign.ed25519` of detached metadata, other metadata entries are preserved
+    fn append_signature(metadata: &[u8], signature: &[u8]) -> Result<Vec<u8>> {
+        let mut entries = Vec::new();
+        let mut signatures: Vec<Vec<u8>> = Vec::new();
+        for entry in gvariant::parse_array(metadata, DICT_ENTRY_ALIGNMENT)? {
+            let (key, value) = gvariant::parse_entry(entry)?;
+            if key != ED25519_METADATA_KEY {
+                entries.push((key, value.to_vec()));
+                continue
+            }
+            let (value, type_string) = gvariant::parse_variant(value)?;
+            if type_string != ED25519_METADATA_FORMAT {
+                return Err(Error::AssembleFileError(format!("unexpected format {} of {}", type_string, ED25519_METADATA_KEY)));
+            }
+            signatures = gvariant::parse_array(value, 1)?.into_iter().map(|s| s.to_vec()).collect();
+        }
+        //ed25519 signature is deterministic, the same signature won't be added twice
+        if !signatures.iter().any(|s| s == signature) {
+            signatures.push(signature.to_vec());
+        }
+        entries.push((ED25519_METADATA_KEY.to_string(), gvariant::serialize_variant(
+            &gvariant::serialize_array(&signatures, 1), ED25519_METADATA_FORMAT)));
+        //entries are sorted by key for a stable output
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(gvariant::serialize_array(&entries.iter().map(
+            |(key, value)| gvariant::serialize_entry(key, value)).collect::<Vec<Vec<u8>>>(), DICT_ENTRY_ALIGNMENT))
+    }
+}
+
+#[async_trait]
+impl FileHandler for OstreeFileHandler {
+    fn validate_options(&self, sign_options: &HashMap<String, String>) -> Result<()> {
+        if let Some(key_type) = sign_options.get(options::KEY_TYPE) {
+            if key_type != KeyType::X509EE.to_string().as_str() {
+                return Err(Error::InvalidArgumentError("ostree only support ed25519 signature of x509 key".to_string()))
+            }
+        }
+        Ok(())
+    }
+
+    //one section for each commit, the serialized commit object is signed
+    async fn split_data(
+        &self,
+        path: &PathBuf,
+        sign_options: &mut HashMap<String, String>,
+    ) -> Result<Vec<Vec<u8>>> {
+        sign_options.insert(options::SIGN_TYPE.to_string(), SignType::Raw.to_string());
+        let mut sections = Vec::new();
+        for commit in OstreeFileHandler::get_commits(path).await? {
+            let commit_path = OstreeFileHandler::object_path(path, &commit, COMMIT_SUFFIX);
+            if !commit_path.is_file() {
+                return Err(Error::SplitFileError(format!("commit object {} is missing", commit)));
+            }
+            sections.push(fs::read(commit_path).await?);
+        }
+        Ok(sections)
+    }
+
+    //the detached metadata `.commitmeta` is written next to the commit object
+    async fn assemble_data(
+        &self,
+        path: &PathBuf,
+        data: Vec<Vec<u8>>,
+        temp_dir: &PathBuf,
+        _sign_options: &HashMap<String, String>,
+    ) -> Result<Vec<(String, String)>> {
+        let commits = OstreeFileHandler::get_commits(path).await?;
+        if commits.len() != data.len() {
+            return Err(Error::AssembleFileError(format!(
+                "expect {} signatures for ostree repository, got {}", commits.len(), data.len())));
+        }
+        let mut outputs = Vec::new();
+        for (commit, signature) in commits.iter().zip(data.iter()) {
+            if signature.len() != ED25519_SIGNATURE_LENGTH {
+                return Err(Error::AssembleFileError("ostree commit must be signed with ed25519 key".to_string()));
+            }
+            let meta_path = OstreeFileHandler::object_path(path, commit, COMMIT_META_SUFFIX);
+            let metadata = if meta_path.is_file() { fs::read(&meta_path).await? } else { vec![] };
+            let temp_file = temp_dir.join(Uuid::new_v4().to_string());
+            fs::write(&temp_file, OstreeFileHandler::append_signature(&metadata, signature)?).await?;
+            outputs.push((temp_file.display().to_string(), meta_path.display().to_string()));
+        }
+        Ok(outputs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+
+    const COMMIT: &str = "3a1b5e8dbd9f4b5c2e1d0f6a7b8c9d0e1f2a3b4c5d6e7f8091a2b3c4d5e6f708";
+
+    async fn generate_repo() -> PathBuf {
+        let repo = env::temp_dir().join(Uuid::new_v4().to_string());
+        let commit_path = OstreeFileHandler::object_path(&repo, COMMIT, COMMIT_SUFFIX);
+        fs::create_dir_all(commit_path.parent().unwrap()).await.expect("create objects directory successfully");
+        fs::write(&commit_path, "fake commit object").await.expect("write commit successfully");
+        fs::create_dir_all(repo.join(HEADS_DIR).join("openeuler/x86_64")).await.expect("create refs directory successfully");
+        for branch in ["openeuler/x86_64/stable", "openeuler/x86_64/latest"] {
+            fs::write(repo.join(HEADS_DIR).join(branch), format!("{}\n", COMMIT)).await.expect("write ref successfully");
+        }
+        fs::write(repo.join(REPO_CONFIG_FILE), "[core]\nrepo_version=1\nmode=archive-z2\n").await.expect("write config successfully");
+        repo
+    }
+
+    #[test]
+    fn test_append_signature() {
+        //expected data are serialized by glib `g_variant_get_data`
+        let version = hex::decode("76657273696f6e00312e30000073080f").unwrap();
+        let first = hex::decode(format!(
+            "6f73747265652e7369676e2e656432353531390000000000{}400061617914000076657273696f6e00312e30000073085e6f",
+            "01".repeat(64))).unwrap();
+        let second = hex::decode(format!(
+            "6f73747265652e7369676e2e656432353531390000000000{}{}408000616179140076657273696f6e00312e30000073089faf",
+            "01".repeat(64), "02".repeat(64))).unwrap();
+        assert_eq!(first, OstreeFileHandler::append_signature(&version, &[1; 64]).expect("append signature successfully"));
+        assert_eq!(second, OstreeFileHandler::append_signature(&first, &[2; 64]).expect("append signature successfully"));
+        assert_eq!(second, OstreeFileHandler::append_signature(&second, &[2; 64]).expect("append signature successfully"));
+        assert_eq!(hex::decode(format!("6f73747265652e7369676e2e656432353531390000000000{}4000616179145e", "02".repeat(64))).unwrap(),
+                   OstreeFileHandler::append_signature(&[], &[2; 64]).expect("append signature successfully"));
+        OstreeFileHandler::append_signature(&[0xff, 0xff], &[2; 64]).expect_err("invalid metadata");
+    }
+
+    #[tokio::test]
+    async fn test_split_data() {
+        let handler = OstreeFileHandler::new();
+        let repo = generate_repo().await;
+        let mut sign_options = HashMap::new();
+        let sections = handler.split_data(&repo, &mut sign_options).await.expect("split ostree repository successfully");
+        assert_eq!(vec!["fake commit object".as_bytes().to_vec()], sections);
+        assert_eq!(Some(&SignType::Raw.to_string()), sign_options.get(options::SIGN_TYPE));
+        handler.split_data(&repo.join(OBJECTS_DIR), &mut sign_options).await.expect_err("not an ostree repository");
+    }
+
+    #[tokio::test]
+    async fn test_assemble_data() {
+        let handler = OstreeFileHandler::new();
+        let repo = generate_repo().await;
+        let temp_dir = env::temp_dir();
+        let outputs = handler.assemble_data(&repo, vec![vec![2; 64]], &temp_dir, &HashMap::new()).await.expect("assemble successfully");
+        assert_eq!(1, outputs.len());
+        assert_eq!(OstreeFileHandler::object_path(&repo, COMMIT, COMMIT_META_SUFFIX).display().to_string(), outputs[0].1);
+        assert_eq!(OstreeFileHandler::append_signature(&[], &[2; 64]).unwrap(), fs::read(&outputs[0].0).await.unwrap());
+        handler.assemble_data(&repo, vec![vec![2; 256]], &temp_dir, &HashMap::new()).await.expect_err("rsa signature is rejected");
+        handler.assemble_data(&repo, vec![], &temp_dir, &HashMap::new()).await.expect_err("signature is missing");
+    }
+
+    #[test]
+    fn test_validate_options() {
+        let handler = OstreeFileHandler::new();
+        let mut options = HashMap::from([(options::KEY_TYPE.to_string(), KeyType::X509EE.to_string())]);
+        handler.validate_options(&options).expect("x509 key is supported");
+        options.insert(options::KEY_TYPE.to_string(), KeyType::Pgp.to_string());
+        handler.validate_options(&options).expect_err("pgp key is unsupported");
+    }
+}
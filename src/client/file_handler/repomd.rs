@@ -0,0 +1,303 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use super::traits::FileHandler;
+use crate::util::sign::KeyType;
+use crate::util::error::{Error, Result};
+use async_trait::async_trait;
+use openssl::hash::{hash, MessageDigest};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::reader::Reader;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use tokio::fs;
+use url::Url;
+use uuid::Uuid;
+
+use crate::util::options;
+
+//Stands for ASCII Armored file
+const FILE_EXTENSION: &str = "asc";
+
+#[derive(Debug, PartialEq, Default)]
+struct RepoMetadata {
+    data_type: String,
+    checksum_type: String,
+    checksum: String,
+    location: String,
+    base: Option<PathBuf>,
+}
+
+#[derive(Clone)]
+pub struct RepomdFileHandler {}
+
+impl RepomdFileHandler {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    fn get_attribute(element: &BytesStart, name: &str) -> Result<Option<String>> {
+        match element.try_get_attribute(name).map_err(|e| Error::SplitFileError(format!("invalid repomd.xml: {}", e)))? {
+            Some(attribute) => Ok(Some(attribute.unescape_value().map_err(
+                |e| Error::SplitFileError(format!("invalid repomd.xml: {}", e)))?.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    //metadata can only be validated when xml:base points to a local folder, it's either a relative path or a file url
+    fn parse_base(base: &str) -> Result<PathBuf> {
+        match Url::parse(base) {
+            Ok(url) if url.scheme() == "file" => url.to_file_path().map_err(
+                |_| Error::SplitFileError(format!("invalid xml:base {} in repomd.xml", base))),
+            Ok(_) => Err(Error::SplitFileError(format!("remote xml:base {} in repomd.xml is not supported", base))),
+            Err(_) => Ok(PathBuf::from(base)),
+        }
+    }
+
+    fn parse_metadata(content: &str) -> Result<Vec<RepoMetadata>> {
+        let mut metadata = vec![];
+        let mut reader = Reader::from_str(content);
+        reader.trim_text(true);
+        let mut current: Option<RepoMetadata> = None;
+        let mut in_checksum = false;
+        loop {
+            match reader.read_event().map_err(|e| Error::SplitFileError(format!("invalid repomd.xml: {}", e)))? {
+                Event::Start(element) if element.local_name().as_ref() == b"data" => {
+                    current = Some(RepoMetadata {
+                        data_type: RepomdFileHandler::get_attribute(&element, "type")?.unwrap_or_default(),
+                        ..Default::default()
+                    });
+                }
+                Event::Start(element) if element.local_name().as_ref() == b"checksum" => {
+                    if let Some(data) = current.as_mut() {
+                        data.checksum_type = RepomdFileHandler::get_attribute(&element, "type")?.unwrap_or_default();
+                        in_checksum = true;
+                    }
+                }
+                Event::Start(element) | Event::Empty(element) if element.local_name().as_ref() == b"location" => {
+                    if let Some(data) = current.as_mut() {
+                        data.location = RepomdFileHandler::get_attribute(&element, "href")?.unwrap_or_default();
+                        if let Some(base) = RepomdFileHandler::get_attribute(&element, "xml:base")? {
+                            data.base = Some(RepomdFileHandler::parse_base(&base)?);
+                        }
+                    }
+                }
+                Event::Text(text) if in_checksum => {
+                    if let Some(data) = current.as_mut() {
+                        data.checksum = text.unescape().map_err(
+                            |e| Error::SplitFileError(format!("invalid repomd.xml: {}", e)))?.to_lowercase();
+                    }
+                }
+                Event::End(element) if element.local_name().as_ref() == b"checksum" => in_checksum = false,
+                Event::End(element) if element.local_name().as_ref() == b"data" => {
+                    if let Some(data) = current.take() {
+                        if data.checksum.is_empty() {
+                            return Err(Error::SplitFileError(format!("checksum of metadata {} not found in repomd.xml", data.data_type)))
+                        }
+                        if data.location.is_empty() {
+                            return Err(Error::SplitFileError(format!("location of metadata {} not found in repomd.xml", data.data_type)))
+                        }
+                        metadata.push(data);
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(metadata)
+    }
+
+    //md5 and sha1 are not collision resistant, metadata protected by them can't be trusted
+    fn get_message_digest(checksum_type: &str) -> Result<MessageDigest> {
+        match checksum_type {
+            "sha224" => Ok(MessageDigest::sha224()),
+            "sha256" => Ok(MessageDigest::sha256()),
+            "sha384" => Ok(MessageDigest::sha384()),
+            "sha512" => Ok(MessageDigest::sha512()),
+            _ => Err(Error::SplitFileError(format!("unsupported checksum type {} in repomd.xml", checksum_type))),
+        }
+    }
+
+    //the location of metadata is relative to the repository root which is the parent of 'repodata' folder,
+    //or relative to the local xml:base if specified
+    async fn validate_checksum(path: &Path, metadata: &[RepoMetadata]) -> Result<()> {
+        let root = path.parent().and_then(|repodata| repodata.parent()).ok_or_else(
+            || Error::SplitFileError(format!("failed to get repository root of {}", path.display())))?;
+        for data in metadata.iter() {
+            let location = Path::new(&data.location);
+            if location.components().any(|c| !matches!(c, Component::Normal(_))) {
+                return Err(Error::SplitFileError(format!("invalid location {} of metadata {}", data.location, data.data_type)))
+            }
+            let digest = RepomdFileHandler::get_message_digest(&data.checksum_type)?;
+            let content = match &data.base {
+                Some(base) => fs::read(root.join(base).join(location)).await?,
+                None => fs::read(root.join(location)).await?,
+            };
+            let checksum = hex::encode(hash(digest, &content)?);
+            if checksum != data.checksum {
+                return Err(Error::SplitFileError(format!(
+                    "checksum of metadata {} mismatched, expected {} got {}", data.location, data.checksum, checksum)))
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FileHandler for RepomdFileHandler {
+    fn validate_options(&self, sign_options: &HashMap<String, String>) -> Result<()> {
+        if let Some(key_type) = sign_options.get(options::KEY_TYPE) {
+            if key_type != KeyType::Pgp.to_string().as_str() {
+                return Err(Error::InvalidArgumentError(
+                    "repomd file only support pgp key type".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn split_data(
+        &self,
+        path: &PathBuf,
+        sign_options: &mut HashMap<String, String>,
+    ) -> Result<Vec<Vec<u8>>> {
+        let content = fs::read(path).await?;
+        if let Some(validate) = sign_options.get(options::VALIDATE_CHECKSUM) {
+            if validate == "true" {
+                let text = std::str::from_utf8(&content).map_err(
+                    |e| Error::SplitFileError(format!("invalid repomd.xml {}: {}", path.display(), e)))?;
+                RepomdFileHandler::validate_checksum(path, &RepomdFileHandler::parse_metadata(text)?).await?;
+            }
+        }
+        //repomd.xml.asc is always an armored detached signature
+        sign_options.insert(options::DETACHED.to_string(), "true".to_string());
+        Ok(vec![content])
+    }
+
+    async fn assemble_data(
+        &self,
+        path: &PathBuf,
+        data: Vec<Vec<u8>>,
+        temp_dir: &PathBuf,
+        _sign_options: &HashMap<String, String>,
+    ) -> Result<Vec<(String, String)>> {
+        let temp_file = temp_dir.join(Uuid::new_v4().to_string());
+        fs::write(temp_file.clone(), &data[0]).await?;
+        Ok(vec![(
+            temp_file.as_path().display().to_string(),
+            format!("{}.{}", path.as_path().display(), FILE_EXTENSION),
+        )])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+
+    fn generate_repository(primary: &[u8]) -> PathBuf {
+        let root = env::temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(root.join("repodata")).expect("create repodata should work");
+        std::fs::write(root.join("repodata").join("primary.xml.gz"), primary).expect("write primary should work");
+        let repomd = format!(r#"<?xml version="1.0" encoding="UTF-8"?>
+<repomd xmlns="http://linux.duke.edu/metadata/repo" xmlns:rpm="http://linux.duke.edu/metadata/rpm">
+  <revision>1687660000</revision>
+  <data type="primary">
+    <checksum type="sha256">{}</checksum>
+    <open-checksum type="sha256">0000</open-checksum>
+    <location href="repodata/primary.xml.gz"/>
+    <size>13</size>
+  </data>
+</repomd>
+"#, hex::encode(hash(MessageDigest::sha256(), b"fake primary").unwrap()));
+        let path = root.join("repodata").join("repomd.xml");
+        std::fs::write(&path, repomd).expect("write repomd should work");
+        path
+    }
+
+    #[test]
+    fn test_validate_options() {
+        let mut options = HashMap::new();
+        let handler = RepomdFileHandler::new();
+        options.insert(options::KEY_TYPE.to_string(), KeyType::Pgp.to_string());
+        assert!(handler.validate_options(&options).is_ok());
+
+        options.insert(options::KEY_TYPE.to_string(), KeyType::X509EE.to_string());
+        let result = handler.validate_options(&options);
+        assert!(result.is_err());
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "invalid argument: repomd file only support pgp key type"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_split_data_with_checksum_validation() {
+        let handler = RepomdFileHandler::new();
+        let mut options = HashMap::from([(options::VALIDATE_CHECKSUM.to_string(), "true".to_string())]);
+        let path = generate_repository(b"fake primary");
+        let data = handler.split_data(&path, &mut options).await.expect("split repomd should work");
+        assert_eq!(data[0], std::fs::read(&path).unwrap());
+        assert_eq!(options.get(options::DETACHED), Some(&"true".to_string()));
+
+        let path = generate_repository(b"modified primary");
+        let _ = handler.split_data(&path, &mut options).await.expect_err("mismatched checksum should fail");
+        options.insert(options::VALIDATE_CHECKSUM.to_string(), "false".to_string());
+        let _ = handler.split_data(&path, &mut options).await.expect("split repomd without validation should work");
+    }
+
+    #[test]
+    fn test_parse_metadata() {
+        let metadata = RepomdFileHandler::parse_metadata(r#"<repomd><data type="filelists">
+            <checksum type="sha256">ABCD</checksum><open-checksum type="sha256">0000</open-checksum>
+            <location xml:base="file:///srv/repo" href="repodata/filelists.xml.gz"/></data></repomd>"#).expect("parse repomd should work");
+        assert_eq!(metadata, vec![RepoMetadata {
+            data_type: "filelists".to_string(),
+            checksum_type: "sha256".to_string(),
+            checksum: "abcd".to_string(),
+            location: "repodata/filelists.xml.gz".to_string(),
+            base: Some(PathBuf::from("/srv/repo")),
+        }]);
+        //regular expression could be fooled by commented out elements
+        let metadata = RepomdFileHandler::parse_metadata(r#"<repomd><!-- <data type="fake"></data> --><data type="primary">
+            <checksum type="sha256">1234</checksum><location href="repodata/primary.xml.gz"/></data></repomd>"#).expect("parse repomd should work");
+        assert_eq!(metadata.len(), 1);
+        assert_eq!(metadata[0].data_type, "primary");
+        assert_eq!(metadata[0].base, None);
+        let _ = RepomdFileHandler::parse_metadata(r#"<data type="primary"><location href="primary.xml.gz"/></data>"#).expect_err("missing checksum should fail");
+        let _ = RepomdFileHandler::parse_metadata(r#"<data type="primary"><checksum type="sha256">1234</checksum>
+            <location xml:base="http://mirror" href="primary.xml.gz"/></data>"#).expect_err("remote xml:base should fail");
+        let _ = RepomdFileHandler::parse_metadata(r#"<data type="primary"><checksum type="sha256">1234</data>"#).expect_err("malformed xml should fail");
+    }
+
+    #[test]
+    fn test_get_message_digest() {
+        assert!(RepomdFileHandler::get_message_digest("sha256").is_ok());
+        assert!(RepomdFileHandler::get_message_digest("sha512").is_ok());
+        for checksum_type in ["md5", "sha", "sha1"] {
+            assert!(RepomdFileHandler::get_message_digest(checksum_type).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_assemble_data() {
+        let handler = RepomdFileHandler::new();
+        let path = PathBuf::from("./repodata/repomd.xml");
+        let result = handler.assemble_data(&path, vec![vec![1, 2, 3]], &env::temp_dir(), &HashMap::new()).await.expect("assemble repomd should work");
+        assert_eq!(result[0].1, "./repodata/repomd.xml.asc");
+        assert_eq!(std::fs::read(&result[0].0).unwrap(), vec![1, 2, 3]);
+    }
+}
@@ -20,16 +20,23 @@ use super::traits::FileHandler;
 use async_trait::async_trait;
 use crate::util::error::Result;
 use std::fs::File;
-use std::io::{BufReader, Read};
-use rpm::{Header, IndexSignatureTag, RPMPackage};
+use std::io::{BufReader, Cursor, Read, Seek};
+use rpm::{Header, IndexSignatureTag, IndexTag, RPMPackage, RPMPackageMetadata};
 
+use super::rpm_header::{self, HeaderEntry};
+use super::rpm_ima;
 use super::sequential_cursor::SeqCursor;
 use uuid::Uuid;
+use openssl::hash::{hash, MessageDigest};
 use sha1;
 use crate::util::options;
-use crate::util::sign::KeyType;
+use crate::util::compression;
+use crate::util::sign::{KeyType, SignType};
 use crate::util::error::Error;
 
+//count of all files and the indexes of files to be signed with IMA signature
+type ImaFiles = (usize, Vec<usize>);
+
 #[derive(Clone)]
 pub struct RpmFileHandler {
 
@@ -42,6 +49,116 @@ impl RpmFileHandler {
 
         }
     }
+
+    //collect the indexes of files which require IMA signatures from the main header, files without digest (directories,
+    //symbolic links, ghost files and etc) are skipped as `rpmsign --signfiles` does, empty files are skipped as well.
+    fn get_ima_files(header: &[u8]) -> Result<ImaFiles> {
+        let entries = rpm_header::parse_entries(header)?;
+        let find = |tag: IndexTag| entries.iter().find(|entry| entry.0 == tag as u32);
+        let digests = match find(IndexTag::RPMTAG_FILEDIGESTS) {
+            Some(entry) => rpm_header::string_array_values(entry),
+            None => return Ok((0, vec![])),
+        };
+        let sizes = find(IndexTag::RPMTAG_LONGFILESIZES).or_else(|| find(IndexTag::RPMTAG_FILESIZES))
+            .map(rpm_header::integer_values).unwrap_or_default();
+        if sizes.len() != digests.len() {
+            return Err(Error::RpmParseError("file sizes don't match the file digests in rpm header".to_string()));
+        }
+        let indexes = digests.iter().zip(sizes.iter()).enumerate().filter(
+            |(_, (digest, size))| !digest.is_empty() && **size > 0).map(|(index, _)| index).collect();
+        Ok((digests.len(), indexes))
+    }
+
+    //extract the content of files from the (compressed) cpio payload
+    fn get_ima_contents(metadata: &RPMPackageMetadata, payload: &[u8], indexes: &[usize]) -> Result<Vec<Vec<u8>>> {
+        let paths = metadata.get_file_paths()?;
+        let compressor = metadata.get_payload_compressor().unwrap_or(compression::GZIP);
+        let archive = compression::decompress(compressor, payload)?;
+        let contents = rpm_ima::read_cpio_files(&archive)?;
+        indexes.iter().map(|index| {
+            let path = paths.get(*index).ok_or_else(
+                || Error::RpmParseError(format!("file path of index {} not found in rpm header", index)))?;
+            contents.get(&path.display().to_string()).map(|content| content.to_vec()).ok_or_else(
+                || Error::RpmParseError(format!("content of file {} not found in rpm payload", path.display())))
+        }).collect()
+    }
+
+    //IMA signatures are saved into the main header as `rpmsign --signfiles` does, files without signature are left empty
+    fn add_ima_signatures(header: &[u8], signatures: &[Vec<u8>]) -> Result<Vec<u8>> {
+        let (file_count, indexes) = RpmFileHandler::get_ima_files(header)?;
+        if indexes.len() != signatures.len() {
+            return Err(Error::AssembleFileError(format!(
+                "expect {} IMA signatures for rpm files, got {}", indexes.len(), signatures.len())));
+        }
+        let mut values = vec![String::new(); file_count];
+        for (index, signature) in indexes.into_iter().zip(signatures.iter()) {
+            values[index] = hex::encode(signature);
+        }
+        let mut entries: Vec<HeaderEntry> = rpm_header::parse_entries(header)?.into_iter().filter(
+            |entry| entry.0 != IndexTag::RPMTAG_FILESIGNATURES as u32 &&
+                entry.0 != IndexTag::RPMTAG_FILESIGNATURELENGTH as u32).collect();
+        entries.extend(rpm_ima::file_signature_entries(
+            &values,
+            IndexTag::RPMTAG_FILESIGNATURES as u32,
+            IndexTag::RPMTAG_FILESIGNATURELENGTH as u32));
+        Ok(rpm_header::build_main_header(entries))
+    }
+
+    //count of the file sections which are placed before header sections, none if IMA signatures are not required
+    fn ima_file_count(sign_options: &HashMap<String, String>) -> Option<usize> {
+        if !sign_options.contains_key(options::IMA_KEY_NAME) {
+            return None
+        }
+        sign_options.get(options::IMA_FILE_COUNT).and_then(|count| count.parse().ok()).filter(|count| *count > 0)
+    }
+
+    //the legacy header and payload signature is omitted in header only mode
+    fn is_header_only(sign_options: &HashMap<String, String>) -> bool {
+        sign_options.get(options::RPM_HEADER_ONLY).map(|value| value == "true").unwrap_or(false)
+    }
+
+    fn header_section_count(sign_options: &HashMap<String, String>) -> usize {
+        if RpmFileHandler::is_header_only(sign_options) { 1 } else { 2 }
+    }
+
+    //header signature doesn't cover the payload, which is bound to the signed header by `RPMTAG_PAYLOADDIGEST`, so both
+    //the header and payload digests are checked as rpm does before the signatures are trusted.
+    fn validate_digests(header: &[u8], signature_entries: &HashMap<u32, Vec<u8>>, payload: &[u8], header_only: bool) -> Result<()> {
+        if let Some(digest) = signature_entries.get(&(IndexSignatureTag::RPMSIGTAG_SHA256 as u32)) {
+            if digest.split(|byte| *byte == 0).next() != Some(hex::encode(openssl::sha::sha256(header)).as_bytes()) {
+                return Err(Error::VerifyError("SHA-256 digest of rpm header mismatches".to_string()));
+            }
+        }
+        let entries = rpm_header::parse_entries(header)?;
+        let find = |tag: IndexTag| entries.iter().find(|entry| entry.0 == tag as u32);
+        let digest = match find(IndexTag::RPMTAG_PAYLOADDIGEST) {
+            Some(entry) => rpm_header::string_array_values(entry).into_iter().next().unwrap_or_default(),
+            None if header_only => return Err(Error::VerifyError(
+                "payload digest not found in rpm header, the payload isn't covered by header signature".to_string())),
+            None => return Ok(()),
+        };
+        //algorithms are identified with the openpgp hash algorithm ids
+        let message_digest = match find(IndexTag::RPMTAG_PAYLOADDIGESTALGO).map(rpm_header::integer_values)
+            .and_then(|values| values.first().copied()) {
+            Some(8) => MessageDigest::sha256(),
+            Some(9) => MessageDigest::sha384(),
+            Some(10) => MessageDigest::sha512(),
+            Some(11) => MessageDigest::sha224(),
+            algorithm => return Err(Error::VerifyError(format!("unsupported payload digest algorithm {:?} in rpm header", algorithm))),
+        };
+        if hex::encode(hash(message_digest, payload)?) != digest {
+            return Err(Error::VerifyError("payload digest of rpm package mismatches".to_string()));
+        }
+        Ok(())
+    }
+
+    //`RPMSIGTAG_LONGSIZE` is used instead when headers and payload are larger than 4GB
+    fn size_entry(size: u64) -> HeaderEntry {
+        match u32::try_from(size) {
+            Ok(size) => rpm_header::int32_entry(IndexSignatureTag::RPMSIGTAG_SIZE as u32, size),
+            Err(_) => rpm_header::int64_entry(IndexSignatureTag::RPMSIGTAG_LONGSIGSIZE as u32, size),
+        }
+    }
 }
 
 //todo: figure our why is much slower when async read & write with tokio is enabled.
@@ -64,32 +181,124 @@ impl FileHandler for RpmFileHandler {
 
     //rpm has two sections need to be signed
     //1. header
-    //2. header and content
-    async fn split_data(&self, path: &PathBuf, _sign_options: &mut HashMap<String, String>) -> Result<Vec<Vec<u8>>> {
+    //2. header and content, which is skipped in header only mode
+    //and the content of each file is placed before them when IMA key is specified, since the IMA signatures are saved
+    //into header before the header is signed, check `prepare_section`.
+    async fn split_data(&self, path: &PathBuf, sign_options: &mut HashMap<String, String>) -> Result<Vec<Vec<u8>>> {
         let file = File::open(path)?;
-        let package = RPMPackage::parse(&mut BufReader::new(file))?;
+        let file_size = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+        //only the metadata is parsed, the payload is streamed after header without being copied again
+        let metadata = RPMPackageMetadata::parse(&mut reader)?;
         let mut header_bytes = Vec::<u8>::with_capacity(1024);
-        //collect head and head&payload arrays
-        package.metadata.header.write(&mut header_bytes)?;
+        metadata.header.write(&mut header_bytes)?;
+        let header_only = RpmFileHandler::is_header_only(sign_options);
+        let is_ima = sign_options.contains_key(options::IMA_KEY_NAME);
         let mut header_and_content = Vec::new();
-        header_and_content.extend(header_bytes.clone());
-        header_and_content.extend(package.content.clone());
-        Ok(vec![header_bytes, header_and_content])
+        if !header_only || is_ima {
+            let payload_size = file_size.saturating_sub(reader.stream_position()?) as usize;
+            header_and_content.reserve_exact(header_bytes.len() + payload_size);
+            header_bytes.as_slice().chain(reader).read_to_end(&mut header_and_content)?;
+        }
+        let mut sections = Vec::new();
+        if is_ima {
+            let (_, indexes) = RpmFileHandler::get_ima_files(&header_bytes)?;
+            sections = RpmFileHandler::get_ima_contents(&metadata, &header_and_content[header_bytes.len()..], &indexes)?;
+            sign_options.insert(options::IMA_FILE_COUNT.to_string(), sections.len().to_string());
+        }
+        sections.push(header_bytes);
+        if !header_only {
+            sections.push(header_and_content);
+        }
+        Ok(sections)
+    }
+
+    //header signature and the legacy header and payload signature if exists, IMA signatures are not verified
+    async fn get_signatures(&self, path: &PathBuf, sign_options: &mut HashMap<String, String>) -> Result<Vec<Vec<u8>>> {
+        let file = File::open(path)?;
+        let package = RPMPackage::parse(&mut BufReader::new(file))?;
+        let mut signature_header = Vec::new();
+        package.metadata.signature.write(&mut signature_header)?;
+        let entries: HashMap<u32, Vec<u8>> = rpm_header::parse_entries(&signature_header)?.into_iter().map(
+            |entry| (entry.0, entry.3)).collect();
+        //DSA signatures can't be verified with the openpgp implementation in use
+        if entries.contains_key(&(IndexSignatureTag::RPMSIGTAG_DSA as u32)) || entries.contains_key(&(IndexSignatureTag::RPMSIGTAG_GPG as u32)) {
+            return Err(Error::RpmParseError("DSA signature (RPMSIGTAG_DSA/RPMSIGTAG_GPG) of rpm package is not supported".to_string()));
+        }
+        let header_signature = entries.get(&(IndexSignatureTag::RPMSIGTAG_RSA as u32)).ok_or_else(
+            || Error::RpmParseError("header signature not found in rpm package".to_string()))?;
+        let mut signatures = vec![header_signature.clone()];
+        match entries.get(&(IndexSignatureTag::RPMSIGTAG_PGP as u32)) {
+            Some(signature) => signatures.push(signature.clone()),
+            None => {
+                sign_options.insert(options::RPM_HEADER_ONLY.to_string(), "true".to_string());
+            }
+        }
+        let mut header = Vec::new();
+        package.metadata.header.write(&mut header)?;
+        RpmFileHandler::validate_digests(&header, &entries, &package.content, signatures.len() == 1)?;
+        sign_options.remove(options::IMA_KEY_NAME);
+        sign_options.remove(options::IMA_FILE_COUNT);
+        Ok(signatures)
+    }
+
+    fn section_options(&self, section: usize, sign_options: &HashMap<String, String>) -> HashMap<String, String> {
+        let mut options = sign_options.clone();
+        if section < RpmFileHandler::ima_file_count(sign_options).unwrap_or(0) {
+            if let Some(key_name) = sign_options.get(options::IMA_KEY_NAME) {
+                options.insert(options::KEY_TYPE.to_string(), KeyType::X509EE.to_string());
+                options.insert(options::KEY_NAME.to_string(), key_name.clone());
+                options.insert(options::SIGN_TYPE.to_string(), SignType::Ima.to_string());
+            }
+        }
+        options
+    }
 
+    //the header sections are signed with the IMA signatures of files inside
+    fn prepare_section(
+        &self,
+        section: usize,
+        content: Vec<u8>,
+        signatures: &[Vec<u8>],
+        sign_options: &HashMap<String, String>,
+    ) -> Result<Vec<u8>> {
+        let file_count = match RpmFileHandler::ima_file_count(sign_options) {
+            Some(count) if section >= count => count,
+            _ => return Ok(content),
+        };
+        let signatures = signatures.get(..file_count).ok_or_else(
+            || Error::AssembleFileError("IMA signatures of rpm files are incomplete".to_string()))?;
+        let header_size = rpm_header::header_size(&content)?;
+        let mut prepared = RpmFileHandler::add_ima_signatures(&content[..header_size], signatures)?;
+        prepared.extend_from_slice(&content[header_size..]);
+        Ok(prepared)
     }
+
     async fn assemble_data(
         &self,
         path: & PathBuf,
         data: Vec<Vec<u8>>,
         temp_dir: &PathBuf,
-        _sign_options: &HashMap<String, String>,
-    ) -> Result<(String, String)> {
+        sign_options: &HashMap<String, String>,
+    ) -> Result<Vec<(String, String)>> {
         let temp_rpm = temp_dir.join(Uuid::new_v4().to_string());
         let file = File::open(path)?;
         let mut package = RPMPackage::parse(&mut BufReader::new(file))?;
         let mut header_bytes = Vec::<u8>::with_capacity(1024);
         package.metadata.header.write(&mut header_bytes)?;
-        //calculate md5 and sha1 digest
+        let file_count = RpmFileHandler::ima_file_count(sign_options).unwrap_or(0);
+        let header_section_count = RpmFileHandler::header_section_count(sign_options);
+        if data.len() != file_count + header_section_count {
+            return Err(Error::AssembleFileError(format!(
+                "expect {} signatures for rpm, got {}", file_count + header_section_count, data.len())));
+        }
+        //the header is updated with IMA signatures in the same way as it's signed
+        if file_count > 0 {
+            header_bytes = RpmFileHandler::add_ima_signatures(&header_bytes, &data[..file_count])?;
+            package.metadata.header = Header::<IndexTag>::parse(&mut Cursor::new(&header_bytes))?;
+        }
+        let data = &data[file_count..];
+        //calculate md5, sha1 and sha256 digest
         let mut header_and_content_cursor =
             SeqCursor::new(&[header_bytes.as_slice(), package.content.as_slice()]);
         let digest_md5 = {
@@ -116,20 +325,28 @@ impl FileHandler for RpmFileHandler {
             let digest = hasher.finalize();
             hex::encode(digest)
         };
-        package.metadata.signature = Header::<IndexSignatureTag>::new_signature_header(
-            header_and_content_cursor
-                .len()
-                .try_into()
-                .expect("headers + payload can't be larger than 4gb"),
-            &digest_md5,
-            digest_sha1,
-            data[0].as_slice(),
-            data[1].as_slice(),
-        );
+        let digest_sha256 = hex::encode(openssl::sha::sha256(&header_bytes));
+        let header_only = RpmFileHandler::is_header_only(sign_options);
+        let mut original_signature = Vec::new();
+        package.metadata.signature.write(&mut original_signature)?;
+        //the uncompressed payload size is kept
+        let mut entries: Vec<HeaderEntry> = rpm_header::parse_entries(&original_signature)?.into_iter().filter(
+            |entry| entry.0 == IndexSignatureTag::RPMSIGTAG_PAYLOADSIZE as u32 ||
+                entry.0 == IndexSignatureTag::RPMSIGTAG_LONGARCHIVESIZE as u32).collect();
+        entries.push(RpmFileHandler::size_entry(header_and_content_cursor.len() as u64));
+        entries.push(rpm_header::string_entry(IndexSignatureTag::RPMSIGTAG_SHA1 as u32, &digest_sha1));
+        entries.push(rpm_header::string_entry(IndexSignatureTag::RPMSIGTAG_SHA256 as u32, &digest_sha256));
+        entries.push(rpm_header::bin_entry(IndexSignatureTag::RPMSIGTAG_RSA as u32, &data[0]));
+        if !header_only {
+            entries.push(rpm_header::bin_entry(IndexSignatureTag::RPMSIGTAG_MD5 as u32, &digest_md5));
+            entries.push(rpm_header::bin_entry(IndexSignatureTag::RPMSIGTAG_PGP as u32, &data[1]));
+        }
+        package.metadata.signature = Header::<IndexSignatureTag>::parse_signature(
+            &mut Cursor::new(rpm_header::build_signature_header(entries)))?;
         //save data into temp file
         let mut output = File::create(temp_rpm.clone())?;
         package.write(&mut output)?;
-        Ok((temp_rpm.as_path().display().to_string(), format!("{}", path.display())))
+        Ok(vec![(temp_rpm.as_path().display().to_string(), format!("{}", path.display()))])
     }
 }
 
@@ -199,6 +416,16 @@ mod test {
         assert_eq!(raw_content.len(), 2);
         assert_eq!(raw_content[0].len(), 4325);
         assert_eq!(raw_content[1].len(), 67757);
+        let package = RPMPackage::parse(&mut BufReader::new(File::open(&path).unwrap())).expect("parse rpm successfully");
+        let mut header_bytes = Vec::new();
+        package.metadata.header.write(&mut header_bytes).expect("write header successfully");
+        assert_eq!(raw_content[0], header_bytes);
+        let entries: HashMap<u32, HeaderEntry> = rpm_header::parse_entries(&raw_content[0]).expect("parse header successfully")
+            .into_iter().map(|entry| (entry.0, entry)).collect();
+        assert_eq!(entries[&(IndexTag::RPMTAG_NAME as u32)].3, b"Imath\0");
+        //header is followed by the payload
+        assert_eq!(raw_content[1][..header_bytes.len()], header_bytes);
+        assert_eq!(raw_content[1][header_bytes.len()..], package.content);
     }
 
     #[tokio::test]
@@ -218,7 +445,215 @@ mod test {
         let _raw_content = file_handler.assemble_data(&path, fake_signature, &env::temp_dir(), &mut sign_options).await.expect("assemble data failed");
     }
 
-}
+    fn get_signature_entries(path: &str) -> HashMap<u32, HeaderEntry> {
+        let package = RPMPackage::parse(&mut BufReader::new(File::open(path).unwrap())).expect("parse signed rpm successfully");
+        let mut signature_header = Vec::new();
+        package.metadata.signature.write(&mut signature_header).expect("write signature header successfully");
+        rpm_header::parse_entries(&signature_header).expect("parse signature header successfully").into_iter().map(
+            |entry| (entry.0, entry)).collect()
+    }
 
+    #[tokio::test]
+    async fn test_assemble_data_with_sha256_digest() {
+        let file_handler = RpmFileHandler::new();
+        let path = generate_signed_rpm().expect("generate signed rpm failed");
+        let mut sign_options = HashMap::new();
+        let raw_content = file_handler.split_data(&path, &mut sign_options).await.expect("get raw content failed");
+        let (temp_file, _) = file_handler.assemble_data(
+            &path, vec![vec![1, 2, 3, 4], vec![5, 6, 7, 8]], &env::temp_dir(), &sign_options).await.expect("assemble data failed").remove(0);
+        let entries = get_signature_entries(&temp_file);
+        let digest = format!("{}\0", hex::encode(openssl::sha::sha256(&raw_content[0])));
+        assert_eq!(entries[&(IndexSignatureTag::RPMSIGTAG_SHA256 as u32)].3, digest.as_bytes());
+        assert_eq!(entries[&(IndexSignatureTag::RPMSIGTAG_RSA as u32)].3, vec![1, 2, 3, 4]);
+        assert_eq!(entries[&(IndexSignatureTag::RPMSIGTAG_PGP as u32)].3, vec![5, 6, 7, 8]);
+        assert_eq!(entries[&(IndexSignatureTag::RPMSIGTAG_SIZE as u32)].3, (raw_content[1].len() as u32).to_be_bytes());
+        assert!(entries.contains_key(&(IndexSignatureTag::RPMSIGTAG_MD5 as u32)));
+        assert!(entries.contains_key(&(IndexSignatureTag::RPMSIGTAG_SHA1 as u32)));
+        assert!(entries.contains_key(&(IndexSignatureTag::RPMSIGTAG_PAYLOADSIZE as u32)));
+        let _ = std::fs::remove_file(temp_file);
+    }
+
+    #[tokio::test]
+    async fn test_assemble_data_with_header_only() {
+        let file_handler = RpmFileHandler::new();
+        let path = generate_signed_rpm().expect("generate signed rpm failed");
+        let mut sign_options = HashMap::from([(options::RPM_HEADER_ONLY.to_string(), "true".to_string())]);
+        let raw_content = file_handler.split_data(&path, &mut sign_options).await.expect("get raw content failed");
+        assert_eq!(raw_content.len(), 1);
+        assert_eq!(rpm_header::header_size(&raw_content[0]).expect("get header size successfully"), raw_content[0].len());
+        file_handler.assemble_data(&path, vec![], &env::temp_dir(), &sign_options).await.expect_err("header signature is missing");
+        let (temp_file, _) = file_handler.assemble_data(
+            &path, vec![vec![1, 2, 3, 4]], &env::temp_dir(), &sign_options).await.expect("assemble data failed").remove(0);
+        let entries = get_signature_entries(&temp_file);
+        assert_eq!(entries[&(IndexSignatureTag::RPMSIGTAG_RSA as u32)].3, vec![1, 2, 3, 4]);
+        assert!(entries.contains_key(&(IndexSignatureTag::RPMSIGTAG_SHA256 as u32)));
+        assert!(!entries.contains_key(&(IndexSignatureTag::RPMSIGTAG_PGP as u32)));
+        assert!(!entries.contains_key(&(IndexSignatureTag::RPMSIGTAG_MD5 as u32)));
+
+        sign_options.insert(options::IMA_KEY_NAME.to_string(), "ima".to_string());
+        sign_options.insert(options::IMA_FILE_COUNT.to_string(), "1".to_string());
+        let options = file_handler.section_options(0, &sign_options);
+        assert_eq!(options.get(options::KEY_NAME), Some(&"ima".to_string()));
+        let options = file_handler.section_options(1, &sign_options);
+        assert_eq!(options.get(options::KEY_NAME), None);
+        let _ = std::fs::remove_file(temp_file);
+    }
+
+    #[tokio::test]
+    async fn test_get_signatures() {
+        let file_handler = RpmFileHandler::new();
+        //the package is signed in header only mode
+        let path = get_signed_rpm().expect("get signed rpm failed");
+        let mut sign_options = HashMap::new();
+        let signatures = file_handler.get_signatures(&path, &mut sign_options).await.expect("get signatures successfully");
+        assert_eq!(signatures.len(), 1);
+        assert_eq!(sign_options.get(options::RPM_HEADER_ONLY), Some(&"true".to_string()));
+
+        let path = generate_signed_rpm().expect("generate signed rpm failed");
+        let (temp_file, _) = file_handler.assemble_data(
+            &path, vec![vec![1, 2, 3, 4], vec![5, 6, 7, 8]], &env::temp_dir(), &HashMap::new()).await.expect("assemble data failed").remove(0);
+        let mut sign_options = HashMap::new();
+        let signatures = file_handler.get_signatures(&PathBuf::from(&temp_file), &mut sign_options).await.expect("get signatures successfully");
+        assert_eq!(signatures, vec![vec![1, 2, 3, 4], vec![5, 6, 7, 8]]);
+        assert_eq!(sign_options.get(options::RPM_HEADER_ONLY), None);
+        let _ = std::fs::remove_file(temp_file);
+
+        //DSA signatures are rejected instead of being taken as unsigned
+        let mut package = RPMPackage::parse(&mut BufReader::new(File::open(&path).unwrap())).expect("parse rpm successfully");
+        package.metadata.signature = Header::<IndexSignatureTag>::parse_signature(&mut Cursor::new(rpm_header::build_signature_header(
+            vec![rpm_header::bin_entry(IndexSignatureTag::RPMSIGTAG_DSA as u32, &[1, 2, 3, 4])]))).expect("parse signature header successfully");
+        let temp_file = env::temp_dir().join(Uuid::new_v4().to_string());
+        package.write(&mut File::create(&temp_file).unwrap()).expect("write rpm successfully");
+        let result = file_handler.get_signatures(&temp_file, &mut HashMap::new()).await;
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "failed to parse rpm file: DSA signature (RPMSIGTAG_DSA/RPMSIGTAG_GPG) of rpm package is not supported"
+        );
+        let _ = std::fs::remove_file(temp_file);
+    }
+
+    #[tokio::test]
+    async fn test_get_signatures_with_mismatched_digests() {
+        let file_handler = RpmFileHandler::new();
+        let path = get_signed_rpm().expect("get signed rpm failed");
+        //payload of the header only signed package is swapped
+        let mut package = RPMPackage::parse(&mut BufReader::new(File::open(&path).unwrap())).expect("parse rpm successfully");
+        package.content = vec![1, 2, 3, 4];
+        let temp_file = env::temp_dir().join(Uuid::new_v4().to_string());
+        package.write(&mut File::create(&temp_file).unwrap()).expect("write rpm successfully");
+        let result = file_handler.get_signatures(&temp_file, &mut HashMap::new()).await;
+        assert_eq!(result.unwrap_err().to_string(), Error::VerifyError("payload digest of rpm package mismatches".to_string()).to_string());
+        let _ = std::fs::remove_file(temp_file);
+
+        //SHA-256 digest of header is modified
+        let mut package = RPMPackage::parse(&mut BufReader::new(File::open(&path).unwrap())).expect("parse rpm successfully");
+        let mut signature_header = Vec::new();
+        package.metadata.signature.write(&mut signature_header).unwrap();
+        let entries = rpm_header::parse_entries(&signature_header).unwrap().into_iter().map(
+            |entry| if entry.0 == IndexSignatureTag::RPMSIGTAG_SHA256 as u32 {
+                rpm_header::string_entry(entry.0, &hex::encode([0u8; 32]))
+            } else { entry }).collect();
+        package.metadata.signature = Header::<IndexSignatureTag>::parse_signature(
+            &mut Cursor::new(rpm_header::build_signature_header(entries))).expect("parse signature header successfully");
+        let temp_file = env::temp_dir().join(Uuid::new_v4().to_string());
+        package.write(&mut File::create(&temp_file).unwrap()).expect("write rpm successfully");
+        let result = file_handler.get_signatures(&temp_file, &mut HashMap::new()).await;
+        assert_eq!(result.unwrap_err().to_string(), Error::VerifyError("SHA-256 digest of rpm header mismatches".to_string()).to_string());
+        let _ = std::fs::remove_file(temp_file);
+    }
+
+    #[test]
+    fn test_size_entry() {
+        assert_eq!(RpmFileHandler::size_entry(1024), rpm_header::int32_entry(IndexSignatureTag::RPMSIGTAG_SIZE as u32, 1024));
+        let size = 5u64 << 30;
+        assert_eq!(RpmFileHandler::size_entry(size), rpm_header::int64_entry(IndexSignatureTag::RPMSIGTAG_LONGSIGSIZE as u32, size));
+    }
+
+    #[tokio::test]
+    async fn test_split_data_with_ima_files() {
+        let mut sign_options = HashMap::from([(options::IMA_KEY_NAME.to_string(), "ima".to_string())]);
+        let file_handler = RpmFileHandler::new();
+        let path = get_signed_rpm().expect("get signed rpm failed");
+        let raw_content = file_handler.split_data(&path, &mut sign_options).await.expect("get raw content failed");
+        let package = RPMPackage::parse(&mut BufReader::new(File::open(&path).unwrap())).expect("parse rpm successfully");
+        let mut header_bytes = Vec::new();
+        package.metadata.header.write(&mut header_bytes).expect("write header successfully");
+        let (file_count, indexes) = RpmFileHandler::get_ima_files(&header_bytes).expect("get ima files successfully");
+        assert!(!indexes.is_empty());
+        assert!(indexes.len() <= file_count);
+        assert_eq!(raw_content.len(), indexes.len() + 2);
+        assert_eq!(sign_options.get(options::IMA_FILE_COUNT), Some(&indexes.len().to_string()));
+        //file content must match the file digests in header, and the header sections are placed after files
+        let digests = package.metadata.get_file_checksums().expect("get file digests successfully");
+        for (index, content) in indexes.iter().zip(raw_content.iter()) {
+            assert_eq!(hex::encode(openssl::sha::sha256(content)), digests[*index]);
+        }
+        assert_eq!(raw_content[indexes.len()], header_bytes);
 
+        let options = file_handler.section_options(indexes.len() - 1, &sign_options);
+        assert_eq!(options.get(options::KEY_NAME), Some(&"ima".to_string()));
+        assert_eq!(options.get(options::KEY_TYPE), Some(&KeyType::X509EE.to_string()));
+        assert_eq!(options.get(options::SIGN_TYPE), Some(&SignType::Ima.to_string()));
+        let options = file_handler.section_options(indexes.len(), &sign_options);
+        assert_eq!(options.get(options::KEY_NAME), None);
 
+        //header sections are updated with the IMA signatures before signing
+        let signatures: Vec<Vec<u8>> = (0..indexes.len()).map(|index| vec![3, 2, 4, index as u8]).collect();
+        assert_eq!(file_handler.prepare_section(0, raw_content[0].clone(), &[], &sign_options).unwrap(), raw_content[0]);
+        file_handler.prepare_section(indexes.len(), header_bytes.clone(), &[], &sign_options).expect_err("IMA signatures are missing");
+        let header = file_handler.prepare_section(
+            indexes.len(), header_bytes.clone(), &signatures, &sign_options).expect("prepare header successfully");
+        assert_ne!(header, header_bytes);
+        let header_and_content = file_handler.prepare_section(
+            indexes.len() + 1, raw_content[indexes.len() + 1].clone(), &signatures, &sign_options).expect("prepare header and content successfully");
+        assert_eq!(header_and_content[..header.len()], header);
+        assert_eq!(header_and_content[header.len()..], package.content);
+    }
+
+    #[tokio::test]
+    async fn test_assemble_data_with_ima_signatures() {
+        let mut sign_options = HashMap::from([(options::IMA_KEY_NAME.to_string(), "ima".to_string())]);
+        let file_handler = RpmFileHandler::new();
+        let path = generate_signed_rpm().expect("generate signed rpm failed");
+        let raw_content = file_handler.split_data(&path, &mut sign_options).await.expect("get raw content failed");
+        let file_count = raw_content.len() - 2;
+        let mut fake_signature: Vec<Vec<u8>> = (0..file_count).map(|index| vec![3, 2, 4, index as u8]).collect();
+        fake_signature.extend([vec![1, 2, 3, 4], vec![5, 6, 7, 8]]);
+        let result = file_handler.assemble_data(&path, fake_signature[file_count..].to_vec(), &env::temp_dir(), &sign_options).await;
+        assert!(result.is_err());
+
+        let (temp_file, _) = file_handler.assemble_data(
+            &path, fake_signature.clone(), &env::temp_dir(), &sign_options).await.expect("assemble data failed").remove(0);
+        let package = RPMPackage::parse(&mut BufReader::new(File::open(&temp_file).unwrap())).expect("parse signed rpm successfully");
+        let mut header_bytes = Vec::new();
+        package.metadata.header.write(&mut header_bytes).expect("write header successfully");
+        //the header is identical to the one signed
+        let signed_header = file_handler.prepare_section(
+            file_count, raw_content[file_count].clone(), &fake_signature[..file_count], &sign_options).expect("prepare header successfully");
+        assert_eq!(header_bytes, signed_header);
+
+        let entries: HashMap<u32, HeaderEntry> = rpm_header::parse_entries(&header_bytes).expect("parse header successfully").into_iter().map(
+            |entry| (entry.0, entry)).collect();
+        let (all_files, indexes) = RpmFileHandler::get_ima_files(&header_bytes).expect("get ima files successfully");
+        let signatures = rpm_header::string_array_values(&entries[&(IndexTag::RPMTAG_FILESIGNATURES as u32)]);
+        assert_eq!(signatures.len(), all_files);
+        assert_eq!(signatures.iter().filter(|signature| !signature.is_empty()).count(), indexes.len());
+        for (index, signature) in indexes.iter().zip(fake_signature.iter()) {
+            assert_eq!(signatures[*index], hex::encode(signature));
+        }
+        assert_eq!(rpm_header::integer_values(&entries[&(IndexTag::RPMTAG_FILESIGNATURELENGTH as u32)]), vec![4]);
+        //other tags of main header are kept
+        for entry in rpm_header::parse_entries(&raw_content[file_count]).expect("parse original header successfully") {
+            assert_eq!(entries[&entry.0], entry);
+        }
+        //header signature and digest cover the IMA signatures
+        let signature_entries = get_signature_entries(&temp_file);
+        let digest = format!("{}\0", hex::encode(openssl::sha::sha256(&header_bytes)));
+        assert_eq!(signature_entries[&(IndexSignatureTag::RPMSIGTAG_SHA256 as u32)].3, digest.as_bytes());
+        assert_eq!(signature_entries[&(IndexSignatureTag::RPMSIGTAG_RSA as u32)].3, vec![1, 2, 3, 4]);
+        assert_eq!(signature_entries[&(IndexSignatureTag::RPMSIGTAG_PGP as u32)].3, vec![5, 6, 7, 8]);
+        assert!(!signature_entries.contains_key(&(IndexSignatureTag::RPMSIGTAG_FILESIGNATURES as u32)));
+        let _ = std::fs::remove_file(temp_file);
+    }
+
+}
@@ -0,0 +1,237 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+//helpers for writing the rpm headers directly, since entries can't be added into `rpm::Header`, the serialized
+//header is rebuilt and parsed again with `Header::parse_signature` or `Header::parse`.
+use crate::util::error::{Error, Result};
+
+const HEADER_MAGIC: [u8; 8] = [0x8e, 0xad, 0xe8, 0x01, 0x00, 0x00, 0x00, 0x00];
+//region tags of header image, signature header and main header
+const HEADER_IMAGE: u32 = 61;
+const HEADER_SIGNATURES: u32 = 62;
+const HEADER_IMMUTABLE: u32 = 63;
+const INDEX_ENTRY_SIZE: usize = 16;
+const RPM_CHAR_TYPE: u32 = 1;
+const RPM_INT8_TYPE: u32 = 2;
+const RPM_INT16_TYPE: u32 = 3;
+const RPM_INT32_TYPE: u32 = 4;
+const RPM_INT64_TYPE: u32 = 5;
+const RPM_STRING_TYPE: u32 = 6;
+const RPM_BIN_TYPE: u32 = 7;
+const RPM_STRING_ARRAY_TYPE: u32 = 8;
+const RPM_I18NSTRING_TYPE: u32 = 9;
+//signature header is aligned to 8 bytes in package
+const SIGNATURE_HEADER_ALIGNMENT: usize = 8;
+
+//(tag, type, count, data) of a header index entry
+pub type HeaderEntry = (u32, u32, u32, Vec<u8>);
+
+fn get_data_length(data_type: u32, count: usize, store: &[u8]) -> Result<usize> {
+    let length = match data_type {
+        RPM_CHAR_TYPE | RPM_INT8_TYPE | RPM_BIN_TYPE => count,
+        RPM_INT16_TYPE => count * 2,
+        RPM_INT32_TYPE => count * 4,
+        RPM_INT64_TYPE => count * 8,
+        RPM_STRING_TYPE | RPM_STRING_ARRAY_TYPE | RPM_I18NSTRING_TYPE => {
+            let mut length = 0;
+            for _ in 0..count {
+                length += store.get(length..).and_then(|remain| remain.iter().position(|c| *c == 0)).ok_or(
+                    Error::RpmParseError("unterminated string in rpm header".to_string()))? + 1;
+            }
+            length
+        }
+        _ => 0,
+    };
+    if length > store.len() {
+        return Err(Error::RpmParseError("rpm header entry exceeds the header store".to_string()));
+    }
+    Ok(length)
+}
+
+fn get_data_alignment(data_type: u32) -> usize {
+    match data_type {
+        RPM_INT16_TYPE => 2,
+        RPM_INT32_TYPE => 4,
+        RPM_INT64_TYPE => 8,
+        _ => 1,
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+pub fn int32_entry(tag: u32, value: u32) -> HeaderEntry {
+    (tag, RPM_INT32_TYPE, 1, value.to_be_bytes().to_vec())
+}
+
+pub fn int64_entry(tag: u32, value: u64) -> HeaderEntry {
+    (tag, RPM_INT64_TYPE, 1, value.to_be_bytes().to_vec())
+}
+
+pub fn bin_entry(tag: u32, value: &[u8]) -> HeaderEntry {
+    (tag, RPM_BIN_TYPE, value.len() as u32, value.to_vec())
+}
+
+pub fn string_entry(tag: u32, value: &str) -> HeaderEntry {
+    let mut data = value.as_bytes().to_vec();
+    data.push(0);
+    (tag, RPM_STRING_TYPE, 1, data)
+}
+
+pub fn string_array_entry(tag: u32, values: &[String]) -> HeaderEntry {
+    let mut data = Vec::new();
+    for value in values {
+        data.extend_from_slice(value.as_bytes());
+        data.push(0);
+    }
+    (tag, RPM_STRING_ARRAY_TYPE, values.len() as u32, data)
+}
+
+pub fn string_array_values(entry: &HeaderEntry) -> Vec<String> {
+    entry.3.split(|c| *c == 0).take(entry.2 as usize).map(|value| String::from_utf8_lossy(value).to_string()).collect()
+}
+
+//values of int16, int32 and int64 array entries
+pub fn integer_values(entry: &HeaderEntry) -> Vec<u64> {
+    let size = match entry.1 {
+        RPM_INT16_TYPE => 2,
+        RPM_INT32_TYPE => 4,
+        RPM_INT64_TYPE => 8,
+        _ => return vec![],
+    };
+    entry.3.chunks_exact(size).map(|value| value.iter().fold(0u64, |result, byte| (result << 8) | *byte as u64)).collect()
+}
+
+//size of the serialized header at the beginning of data, the padding of signature header is excluded
+pub fn header_size(data: &[u8]) -> Result<usize> {
+    if data.len() < INDEX_ENTRY_SIZE || data[0..8] != HEADER_MAGIC {
+        return Err(Error::RpmParseError("invalid rpm header".to_string()));
+    }
+    let size = INDEX_ENTRY_SIZE * (read_u32(data, 8) as usize + 1) + read_u32(data, 12) as usize;
+    if size > data.len() {
+        return Err(Error::RpmParseError("invalid rpm header".to_string()));
+    }
+    Ok(size)
+}
+
+//all the entries in serialized header except the region tag
+pub fn parse_entries(header: &[u8]) -> Result<Vec<HeaderEntry>> {
+    header_size(header)?;
+    let index_count = read_u32(header, 8) as usize;
+    let store_size = read_u32(header, 12) as usize;
+    let store_start = INDEX_ENTRY_SIZE * (index_count + 1);
+    let store = &header[store_start..store_start + store_size];
+    let mut entries: Vec<HeaderEntry> = Vec::new();
+    for index in 0..index_count {
+        let entry = &header[INDEX_ENTRY_SIZE * (index + 1)..INDEX_ENTRY_SIZE * (index + 2)];
+        let (tag, data_type, offset, count) = (read_u32(entry, 0), read_u32(entry, 4), read_u32(entry, 8) as usize, read_u32(entry, 12));
+        if (HEADER_IMAGE..=HEADER_IMMUTABLE).contains(&tag) {
+            continue;
+        }
+        let data = store.get(offset..).ok_or(Error::RpmParseError("invalid rpm header".to_string()))?;
+        let length = get_data_length(data_type, count as usize, data)?;
+        entries.push((tag, data_type, count, data[..length].to_vec()));
+    }
+    Ok(entries)
+}
+
+//serialize the signature header, the result is padded to the alignment of signature header.
+pub fn build_signature_header(entries: Vec<HeaderEntry>) -> Vec<u8> {
+    let mut result = build_header(HEADER_SIGNATURES, entries);
+    result.resize(result.len().div_ceil(SIGNATURE_HEADER_ALIGNMENT) * SIGNATURE_HEADER_ALIGNMENT, 0);
+    result
+}
+
+//serialize the main header which is covered by the immutable region
+pub fn build_main_header(entries: Vec<HeaderEntry>) -> Vec<u8> {
+    build_header(HEADER_IMMUTABLE, entries)
+}
+
+//all the entries are arranged in the order of tag and covered by the region as rpm does
+fn build_header(region_tag: u32, mut entries: Vec<HeaderEntry>) -> Vec<u8> {
+    entries.sort_by_key(|entry| entry.0);
+    let mut index = Vec::new();
+    let mut store = Vec::new();
+    for (tag, data_type, count, data) in entries.iter() {
+        let alignment = get_data_alignment(*data_type);
+        store.resize(store.len().div_ceil(alignment) * alignment, 0);
+        index.extend_from_slice(&tag.to_be_bytes());
+        index.extend_from_slice(&data_type.to_be_bytes());
+        index.extend_from_slice(&(store.len() as u32).to_be_bytes());
+        index.extend_from_slice(&count.to_be_bytes());
+        store.extend_from_slice(data);
+    }
+    //region trailer is placed at the end of store, its offset is the negative size of all index entries.
+    let region_offset = store.len() as u32;
+    store.extend_from_slice(&region_tag.to_be_bytes());
+    store.extend_from_slice(&RPM_BIN_TYPE.to_be_bytes());
+    store.extend_from_slice(&(-(((entries.len() + 1) * INDEX_ENTRY_SIZE) as i32)).to_be_bytes());
+    store.extend_from_slice(&(INDEX_ENTRY_SIZE as u32).to_be_bytes());
+
+    let mut result = Vec::with_capacity(INDEX_ENTRY_SIZE * (entries.len() + 2) + store.len());
+    result.extend_from_slice(&HEADER_MAGIC);
+    result.extend_from_slice(&((entries.len() + 1) as u32).to_be_bytes());
+    result.extend_from_slice(&(store.len() as u32).to_be_bytes());
+    result.extend_from_slice(&region_tag.to_be_bytes());
+    result.extend_from_slice(&RPM_BIN_TYPE.to_be_bytes());
+    result.extend_from_slice(&region_offset.to_be_bytes());
+    result.extend_from_slice(&(INDEX_ENTRY_SIZE as u32).to_be_bytes());
+    result.extend(index);
+    result.extend(store);
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_build_and_parse_signature_header() {
+        let entries = vec![
+            string_entry(273, "sha256"),
+            int64_entry(270, 1 << 33),
+            bin_entry(268, &[1, 2, 3]),
+            int32_entry(1007, 10),
+            string_array_entry(274, &["a".to_string(), String::new()]),
+        ];
+        let header = build_signature_header(entries.clone());
+        assert_eq!(0, header.len() % SIGNATURE_HEADER_ALIGNMENT);
+        let mut expected = entries;
+        expected.sort_by_key(|entry| entry.0);
+        assert_eq!(expected, parse_entries(&header).expect("parse signature header successfully"));
+        parse_entries(&header[8..]).expect_err("invalid header magic");
+        parse_entries(&header[..header.len() - 16]).expect_err("truncated header");
+    }
+
+    #[test]
+    fn test_build_main_header() {
+        let entries = vec![
+            string_array_entry(1035, &["abc".to_string(), String::new(), "def".to_string()]),
+            (1028, RPM_INT32_TYPE, 2, [7u32.to_be_bytes(), 0u32.to_be_bytes()].concat()),
+            string_entry(1000, "name"),
+        ];
+        let mut header = build_main_header(entries.clone());
+        assert_eq!(header_size(&header).expect("get header size successfully"), header.len());
+        header.extend_from_slice(b"payload");
+        let parsed = parse_entries(&header).expect("parse main header successfully");
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[0], entries[2]);
+        assert_eq!(string_array_values(&parsed[2]), vec!["abc".to_string(), String::new(), "def".to_string()]);
+        assert_eq!(integer_values(&parsed[1]), vec![7, 0]);
+        assert_eq!(integer_values(&parsed[0]), Vec::<u64>::new());
+    }
+}
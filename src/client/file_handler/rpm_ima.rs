@@ -0,0 +1,129 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+//helpers for generating rpm file signatures which are used by IMA appraisal, it's equivalent to `rpmsign --signfiles`:
+//1. the content of files is extracted from the (compressed) cpio payload.
+//2. the IMA signatures are saved into the `RPMSIGTAG_FILESIGNATURES` tag of signature header as hex strings.
+use std::collections::HashMap;
+use crate::util::error::{Error, Result};
+use super::rpm_header::{self, HeaderEntry};
+
+const CPIO_NEWC_MAGIC: &[u8] = b"070701";
+const CPIO_HEADER_SIZE: usize = 110;
+const CPIO_TRAILER: &str = "TRAILER!!!";
+
+fn parse_hex_field(field: &[u8]) -> Result<usize> {
+    let value = std::str::from_utf8(field).map_err(|_| Error::RpmParseError("invalid cpio header".to_string()))?;
+    usize::from_str_radix(value, 16).map_err(|_| Error::RpmParseError("invalid cpio header".to_string()))
+}
+
+fn align_to_four(value: usize) -> usize {
+    (value + 3) & !3
+}
+
+//read the regular files in cpio archive of 'newc' format, the key is the absolute path of file.
+//for hard linked files, the content is only stored in the last link, it's shared with other links here.
+pub fn read_cpio_files(archive: &[u8]) -> Result<HashMap<String, &[u8]>> {
+    let mut files = HashMap::new();
+    let mut links: HashMap<usize, (Vec<String>, &[u8])> = HashMap::new();
+    let mut offset = 0;
+    loop {
+        let header = archive.get(offset..offset + CPIO_HEADER_SIZE).ok_or(
+            Error::RpmParseError("unexpected end of cpio archive".to_string()))?;
+        if &header[0..6] != CPIO_NEWC_MAGIC {
+            return Err(Error::RpmParseError("only cpio archive of 'newc' format is supported".to_string()));
+        }
+        let inode = parse_hex_field(&header[6..14])?;
+        let mode = parse_hex_field(&header[14..22])?;
+        let nlink = parse_hex_field(&header[38..46])?;
+        let file_size = parse_hex_field(&header[54..62])?;
+        let name_size = parse_hex_field(&header[94..102])?;
+        let name_start = offset + CPIO_HEADER_SIZE;
+        let name = archive.get(name_start..name_start + name_size).ok_or(
+            Error::RpmParseError("unexpected end of cpio archive".to_string()))?;
+        let name = String::from_utf8_lossy(name.strip_suffix(&[0]).unwrap_or(name)).to_string();
+        let data_start = align_to_four(name_start + name_size);
+        let data = archive.get(data_start..data_start + file_size).ok_or(
+            Error::RpmParseError("unexpected end of cpio archive".to_string()))?;
+        offset = align_to_four(data_start + file_size);
+        if name == CPIO_TRAILER {
+            break;
+        }
+        if mode & 0o170000 != 0o100000 {
+            continue;
+        }
+        let path = format!("/{}", name.trim_start_matches('.').trim_start_matches('/'));
+        if nlink > 1 {
+            let link = links.entry(inode).or_insert((vec![], data));
+            link.0.push(path);
+            if !data.is_empty() {
+                link.1 = data;
+            }
+        } else {
+            files.insert(path, data);
+        }
+    }
+    for (paths, data) in links.into_values() {
+        for path in paths {
+            files.insert(path, data);
+        }
+    }
+    Ok(files)
+}
+
+//entries of the file signatures and the maximum length of binary signatures, which are added into signature header
+pub fn file_signature_entries(signatures: &[String], signature_tag: u32, length_tag: u32) -> Vec<HeaderEntry> {
+    let max_length = signatures.iter().map(|signature| signature.len() / 2).max().unwrap_or(0) as u32;
+    vec![
+        rpm_header::string_array_entry(signature_tag, signatures),
+        rpm_header::int32_entry(length_tag, max_length),
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cpio_entry(name: &str, inode: usize, mode: usize, nlink: usize, data: &[u8]) -> Vec<u8> {
+        let mut entry = format!("070701{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}",
+                                inode, mode, 0, 0, nlink, 0, data.len(), 0, 0, 0, 0, name.len() + 1, 0).into_bytes();
+        entry.extend_from_slice(name.as_bytes());
+        entry.push(0);
+        entry.resize(align_to_four(entry.len()), 0);
+        entry.extend_from_slice(data);
+        entry.resize(align_to_four(entry.len()), 0);
+        entry
+    }
+
+    #[test]
+    fn test_read_cpio_files() {
+        let mut archive = Vec::new();
+        archive.extend(cpio_entry("./usr/bin", 1, 0o40755, 2, b""));
+        archive.extend(cpio_entry("./usr/bin/hello", 2, 0o100755, 1, b"hello"));
+        archive.extend(cpio_entry("./usr/bin/link1", 3, 0o100644, 2, b""));
+        archive.extend(cpio_entry("./usr/bin/link2", 3, 0o100644, 2, b"linked"));
+        archive.extend(cpio_entry("./usr/bin/symlink", 4, 0o120777, 1, b"hello"));
+        archive.extend(cpio_entry(CPIO_TRAILER, 0, 0, 1, b""));
+        let files = read_cpio_files(&archive).expect("read cpio archive successfully");
+        assert_eq!(files.len(), 3);
+        assert_eq!(files.get("/usr/bin/hello"), Some(&b"hello".as_slice()));
+        assert_eq!(files.get("/usr/bin/link1"), Some(&b"linked".as_slice()));
+        assert_eq!(files.get("/usr/bin/link2"), Some(&b"linked".as_slice()));
+
+        let result = read_cpio_files(&archive[..archive.len() - 20]);
+        assert!(result.is_err());
+    }
+}
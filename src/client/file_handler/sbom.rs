@@ -0,0 +1,256 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use super::traits::FileHandler;
+use crate::util::payload;
+use crate::util::sign::{KeyType, SignType};
+use crate::util::error::{Error, Result};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use uuid::Uuid;
+
+use crate::util::options;
+
+//Stands for ASCII Armored file
+const PGP_EXTENSION: &str = "asc";
+const CMS_EXTENSION: &str = "p7s";
+const SPDX_EXTENSION: &str = "spdx";
+
+#[derive(Debug, PartialEq)]
+enum SbomFormat {
+    CycloneDxJson,
+    CycloneDxXml,
+    Spdx,
+}
+
+#[derive(Clone)]
+pub struct SbomFileHandler {}
+
+impl SbomFileHandler {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    fn detect_format(path: &Path, content: &[u8]) -> Result<SbomFormat> {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+        match extension {
+            "json" => {
+                let value: Value = serde_json::from_slice(content)?;
+                if value.get("bomFormat").and_then(|f| f.as_str()) == Some("CycloneDX") {
+                    return Ok(SbomFormat::CycloneDxJson)
+                } else if value.get("spdxVersion").is_some() {
+                    return Ok(SbomFormat::Spdx)
+                }
+            }
+            "xml" => {
+                let text = std::str::from_utf8(content).map_err(|e| Error::SplitFileError(e.to_string()))?;
+                let (root, _) = payload::get_root_element(text).map_err(|e| Error::SplitFileError(e.to_string()))?;
+                let root = root.to_lowercase();
+                if root.contains("cyclonedx") || root.starts_with("<bom") {
+                    return Ok(SbomFormat::CycloneDxXml)
+                } else if root.contains("spdx") {
+                    return Ok(SbomFormat::Spdx)
+                }
+            }
+            SPDX_EXTENSION => return Ok(SbomFormat::Spdx),
+            _ => {}
+        }
+        Err(Error::SplitFileError(format!("file {} is neither a CycloneDX nor a SPDX document", path.display())))
+    }
+
+    fn is_pgp(sign_options: &HashMap<String, String>) -> bool {
+        matches!(sign_options.get(options::KEY_TYPE), Some(key_type) if key_type == KeyType::Pgp.to_string().as_str())
+    }
+}
+
+/* CycloneDX documents are signed with embedded signatures, JSF for json and enveloped XML-DSig for xml, the whole
+ * document is sent and the server builds the signature payload and returns the signed document. SPDX documents are
+ * signed with detached signatures, armored openpgp signature or cms signature */
+#[async_trait]
+impl FileHandler for SbomFileHandler {
+    fn validate_options(&self, _sign_options: &HashMap<String, String>) -> Result<()> {
+        Ok(())
+    }
+
+    async fn split_data(
+        &self,
+        path: &PathBuf,
+        sign_options: &mut HashMap<String, String>,
+    ) -> Result<Vec<Vec<u8>>> {
+        let content = fs::read(path).await?;
+        match SbomFileHandler::detect_format(path, &content)? {
+            SbomFormat::CycloneDxJson => {
+                sign_options.insert(options::SIGN_TYPE.to_string(), SignType::Jsf.to_string());
+            }
+            SbomFormat::CycloneDxXml => {
+                sign_options.insert(options::SIGN_TYPE.to_string(), SignType::XmlDsig.to_string());
+            }
+            SbomFormat::Spdx => {
+                sign_options.insert(options::DETACHED.to_string(), "true".to_string());
+                if !SbomFileHandler::is_pgp(sign_options) {
+                    sign_options.insert(options::SIGN_TYPE.to_string(), SignType::Cms.to_string());
+                }
+            }
+        }
+        Ok(vec![content])
+    }
+
+    async fn assemble_data(
+        &self,
+        path: &PathBuf,
+        data: Vec<Vec<u8>>,
+        temp_dir: &PathBuf,
+        sign_options: &HashMap<String, String>,
+    ) -> Result<Vec<(String, String)>> {
+        let temp_file = temp_dir.join(Uuid::new_v4().to_string());
+        let content = fs::read(path).await?;
+        //signed CycloneDX document replaces the original one
+        fs::write(temp_file.clone(), &data[0]).await?;
+        let file_name = match SbomFileHandler::detect_format(path, &content)? {
+            SbomFormat::CycloneDxJson | SbomFormat::CycloneDxXml => path.display().to_string(),
+            SbomFormat::Spdx => {
+                let extension = if SbomFileHandler::is_pgp(sign_options) {
+                    PGP_EXTENSION
+                } else {
+                    CMS_EXTENSION
+                };
+                format!("{}.{}", path.display(), extension)
+            }
+        };
+        Ok(vec![(temp_file.as_path().display().to_string(), file_name)])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::canonicalization::{canonicalize_json, canonicalize_xml};
+    use crate::util::payload::RawAlgorithm;
+    use data_encoding::{BASE64, BASE64URL_NOPAD};
+    use openssl::hash::{hash, MessageDigest};
+    use openssl::pkey::{PKey, Private};
+    use openssl::rsa::Rsa;
+    use openssl::sign::{Signer, Verifier};
+    use std::env;
+
+    fn get_sbom_path(name: &str) -> PathBuf {
+        env::current_dir().expect("get current dir failed").join("test_assets").join("sbom").join(name)
+    }
+
+    fn sign_raw(key: &PKey<Private>, content: &[u8]) -> Result<Vec<u8>> {
+        let mut signer = Signer::new(MessageDigest::sha256(), key)?;
+        Ok(signer.sign_oneshot_to_vec(content)?)
+    }
+
+    fn verify_raw(key: &PKey<Private>, signature: &[u8], content: &[u8]) -> bool {
+        let mut verifier = Verifier::new(MessageDigest::sha256(), key).unwrap();
+        verifier.verify_oneshot(signature, content).unwrap()
+    }
+
+    #[test]
+    fn test_detect_format() {
+        for (name, format) in [
+            ("cyclonedx-sbom.json", SbomFormat::CycloneDxJson),
+            ("cyclonedx-sbom.xml", SbomFormat::CycloneDxXml),
+            ("spdx-sbom.json", SbomFormat::Spdx),
+            ("spdx-sbom.xml", SbomFormat::Spdx)] {
+            let path = get_sbom_path(name);
+            assert_eq!(SbomFileHandler::detect_format(&path, &std::fs::read(&path).unwrap()).unwrap(), format);
+        }
+        let _ = SbomFileHandler::detect_format(Path::new("fake.json"), b"{}").expect_err("unknown document should fail");
+        let _ = SbomFileHandler::detect_format(Path::new("fake.xml"), b"<project></project>").expect_err("unknown document should fail");
+    }
+
+    #[tokio::test]
+    async fn test_sign_cyclonedx_json() {
+        let handler = SbomFileHandler::new();
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut options = HashMap::from([(options::KEY_TYPE.to_string(), KeyType::X509EE.to_string())]);
+        let path = get_sbom_path("cyclonedx-sbom.json");
+        let data = handler.split_data(&path, &mut options).await.expect("split cyclonedx json should work");
+        assert_eq!(options.get(options::SIGN_TYPE), Some(&SignType::Jsf.to_string()));
+        assert_eq!(data[0], std::fs::read(&path).unwrap());
+        //the signed document is built by server
+        let signed = payload::sign_jsf(&data[0], RawAlgorithm::Rsa, |content| sign_raw(&key, content)).unwrap();
+        let (temp_file, file_name) = handler.assemble_data(
+            &path, vec![signed], &env::temp_dir(), &options).await.expect("assemble cyclonedx json should work").remove(0);
+        assert_eq!(file_name, path.display().to_string());
+
+        let mut signed: Value = serde_json::from_slice(&std::fs::read(&temp_file).unwrap()).unwrap();
+        let jsf = signed[payload::JSF_SIGNATURE].as_object_mut().unwrap();
+        assert_eq!(jsf["algorithm"], "RS256");
+        let value = BASE64URL_NOPAD.decode(jsf.remove("value").unwrap().as_str().unwrap().as_bytes()).unwrap();
+        assert!(verify_raw(&key, &value, canonicalize_json(&signed).unwrap().as_bytes()));
+    }
+
+    #[tokio::test]
+    async fn test_sign_cyclonedx_xml() {
+        let handler = SbomFileHandler::new();
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut options = HashMap::from([(options::KEY_TYPE.to_string(), KeyType::Pgp.to_string())]);
+        let path = get_sbom_path("cyclonedx-sbom.xml");
+        let data = handler.split_data(&path, &mut options).await.expect("split cyclonedx xml should work");
+        assert_eq!(options.get(options::SIGN_TYPE), Some(&SignType::XmlDsig.to_string()));
+        let mut signed_info = vec![];
+        let signed = payload::sign_xmldsig(&data[0], RawAlgorithm::Rsa, |content| {
+            signed_info = content.to_vec();
+            sign_raw(&key, content)
+        }).unwrap();
+        assert!(String::from_utf8_lossy(&signed_info).starts_with("<SignedInfo xmlns=\"http://www.w3.org/2000/09/xmldsig#\"><CanonicalizationMethod"));
+        let (temp_file, _) = handler.assemble_data(
+            &path, vec![signed], &env::temp_dir(), &options).await.expect("assemble cyclonedx xml should work").remove(0);
+
+        let signed = std::fs::read_to_string(&temp_file).unwrap();
+        assert!(signed.trim_end().ends_with("</SignatureValue></Signature></CycloneDXDocument>"));
+        //digest is calculated on the document without signature element
+        let document = payload::remove_xml_signature(&signed);
+        assert_eq!(document, std::fs::read_to_string(&path).unwrap());
+        let digest = BASE64.encode(&hash(MessageDigest::sha256(), canonicalize_xml(&document).unwrap().as_bytes()).unwrap());
+        assert!(signed.contains(&format!("<DigestValue>{}</DigestValue>", digest)));
+        let start = signed.find("<SignatureValue>").unwrap() + "<SignatureValue>".len();
+        let end = signed.find("</SignatureValue>").unwrap();
+        assert!(verify_raw(&key, &BASE64.decode(&signed.as_bytes()[start..end]).unwrap(), &signed_info));
+
+        //existing signature is replaced when signing again
+        let resigned = payload::sign_xmldsig(signed.as_bytes(), RawAlgorithm::Rsa, |content| {
+            assert_eq!(content, signed_info);
+            sign_raw(&key, content)
+        }).unwrap();
+        assert_eq!(resigned, signed.into_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_sign_spdx() {
+        let handler = SbomFileHandler::new();
+        for (name, key_type, extension, sign_type) in [
+            ("spdx-sbom.json", KeyType::Pgp, "asc", None),
+            ("spdx-sbom.xml", KeyType::X509EE, "p7s", Some(SignType::Cms.to_string()))] {
+            let mut options = HashMap::from([(options::KEY_TYPE.to_string(), key_type.to_string())]);
+            let path = get_sbom_path(name);
+            let data = handler.split_data(&path, &mut options).await.expect("split spdx should work");
+            assert_eq!(data[0], std::fs::read(&path).unwrap());
+            assert_eq!(options.get(options::DETACHED), Some(&"true".to_string()));
+            assert_eq!(options.get(options::SIGN_TYPE), sign_type.as_ref());
+            let (temp_file, file_name) = handler.assemble_data(
+                &path, vec![vec![1, 2, 3]], &env::temp_dir(), &options).await.expect("assemble spdx should work").remove(0);
+            assert_eq!(file_name, format!("{}.{}", path.display(), extension));
+            assert_eq!(std::fs::read(temp_file).unwrap(), vec![1, 2, 3]);
+        }
+    }
+}
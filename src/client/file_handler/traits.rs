@@ -14,7 +14,7 @@
  *
  */
 
-use crate::util::error::Result;
+use crate::util::error::{Error, Result};
 use async_trait::async_trait;
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -31,12 +31,36 @@ pub trait FileHandler: Send + Sync {
         let content = fs::read(path).await?;
         Ok(vec![content])
     }
-    //return the temporary file path and signature file name
+    //sign options used for the specified section of split data, all sections share the same options by default
+    fn section_options(&self, _section: usize, sign_options: &HashMap<String, String>) -> HashMap<String, String> {
+        sign_options.clone()
+    }
+    //update the section with the signatures of preceding sections right before it's signed, for instance, the rpm
+    //header which carries the IMA signatures of files
+    fn prepare_section(
+        &self,
+        _section: usize,
+        content: Vec<u8>,
+        _signatures: &[Vec<u8>],
+        _sign_options: &HashMap<String, String>,
+    ) -> Result<Vec<u8>> {
+        Ok(content)
+    }
+    //existing signatures which are embedded in or placed next to the file, the signature of index N is verified against
+    //the section N returned by `split_data`, and the options are updated so that the file is split as it was signed.
+    async fn get_signatures(
+        &self,
+        _path: &PathBuf,
+        _sign_options: &mut HashMap<String, String>,
+    ) -> Result<Vec<Vec<u8>>> {
+        Err(Error::UnsupportedTypeError("verifying signatures of the file type".to_string()))
+    }
+    //return the temporary file paths and signature file names, one pair for each generated file
     async fn assemble_data(
         &self,
         path: &PathBuf,
         data: Vec<Vec<u8>>,
         temp_dir: &PathBuf,
         sign_options: &HashMap<String, String>,
-    ) -> Result<(String, String)>;
+    ) -> Result<Vec<(String, String)>>;
 }
@@ -0,0 +1,215 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::util::error::{Error, Result};
+use crate::util::key::get_content_digest;
+
+pub const MANIFEST_FILE_NAME: &str = "signatrust-manifest.json";
+
+/// Signed artifacts are written to the output directory with the same layout as the input path.
+#[derive(Debug, Clone)]
+pub struct OutputLayout {
+    input_root: PathBuf,
+    output_dir: PathBuf,
+}
+
+impl OutputLayout {
+    pub fn new(input: &Path, output_dir: &Path) -> Self {
+        let input_root = if input.is_dir() {
+            input.to_path_buf()
+        } else {
+            input.parent().map(|parent| parent.to_path_buf()).unwrap_or_default()
+        };
+        Self {
+            input_root,
+            output_dir: output_dir.to_path_buf(),
+        }
+    }
+
+    /// Map the path generated next to the input onto the output directory.
+    pub fn get_output_path(&self, path: &Path) -> Result<PathBuf> {
+        let relative = path.strip_prefix(&self.input_root).map_err(|_| Error::AssembleFileError(
+            format!("file {} is outside of input path {}", path.display(), self.input_root.display())))?;
+        if relative.components().any(|component| !matches!(component, Component::Normal(_))) {
+            return Err(Error::AssembleFileError(format!("file {} can't be mapped onto output directory", path.display())))
+        }
+        Ok(self.output_dir.join(relative))
+    }
+
+    /// Output directory must not be the input directory, otherwise the inputs are overwritten.
+    pub fn is_in_place(&self) -> bool {
+        match (self.input_root.canonicalize(), self.output_dir.canonicalize()) {
+            (Ok(input_root), Ok(output_dir)) => input_root == output_dir,
+            _ => false,
+        }
+    }
+
+    pub fn get_output_dir(&self) -> &Path {
+        &self.output_dir
+    }
+
+    pub fn get_manifest_path(&self) -> PathBuf {
+        self.output_dir.join(MANIFEST_FILE_NAME)
+    }
+
+    /// Copy the files of input path which are not written by signing into the output directory, so that the output is
+    /// a complete copy of input, for instance, the blobs of OCI image layout. Files of failed inputs are not copied.
+    pub fn copy_unchanged(&self, input: &Path, written: &HashSet<PathBuf>, failed: &[PathBuf]) -> Result<usize> {
+        let output_dir = self.output_dir.canonicalize()?;
+        let mut copied = 0;
+        for entry in walkdir::WalkDir::new(input) {
+            let entry = entry.map_err(|err| Error::WalkDirectoryError(err.to_string()))?;
+            let path = entry.path();
+            //output directory can be placed inside of the input directory
+            if !entry.file_type().is_file() || path.canonicalize()?.starts_with(&output_dir) ||
+                failed.iter().any(|failed| path.starts_with(failed)) {
+                continue
+            }
+            let output = self.get_output_path(path)?;
+            if written.contains(&output) {
+                continue
+            }
+            if let Some(parent) = output.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(path, &output)?;
+            copied += 1;
+        }
+        Ok(copied)
+    }
+}
+
+/// Key which produces the signatures of file, the file can be signed with several keys, for instance, the IMA key of rpm.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SigningKey {
+    pub name: String,
+    pub fingerprint: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// The original file replaced by the output, or the signed file if output is a new file such as detached signature
+    pub input: String,
+    pub output: String,
+    /// The specified key comes first, followed by other keys in the order of signing
+    pub keys: Vec<SigningKey>,
+    /// SHA-256 digests in hex, input digest is absent if input is a directory
+    pub input_digest: Option<String>,
+    pub output_digest: String,
+}
+
+impl ManifestEntry {
+    pub fn new(original: &Path, signed_file: &Path, output: &Path, keys: &[SigningKey]) -> Result<Self> {
+        let input = if original.is_file() { original } else { signed_file };
+        let input_digest = if input.is_file() { Some(get_content_digest(&fs::read(input)?)) } else { None };
+        Ok(Self {
+            input: input.display().to_string(),
+            output: output.display().to_string(),
+            keys: keys.to_vec(),
+            input_digest,
+            output_digest: get_content_digest(&fs::read(output)?),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    /// Entries are sorted by output path so that the manifest is stable across runs.
+    pub fn save(mut self, path: &Path) -> Result<()> {
+        self.entries.sort_by(|a, b| a.output.cmp(&b.output));
+        fs::write(path, serde_json::to_string_pretty(&self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_get_output_path() {
+        let input = env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(input.join("x86_64")).unwrap();
+        let layout = OutputLayout::new(&input, Path::new("/output"));
+        assert_eq!(layout.get_output_path(&input.join("x86_64/a.rpm")).unwrap(), PathBuf::from("/output/x86_64/a.rpm"));
+        assert_eq!(layout.get_manifest_path(), PathBuf::from("/output").join(MANIFEST_FILE_NAME));
+        assert!(!layout.is_in_place());
+        assert!(OutputLayout::new(&input, &input.join("x86_64/..")).is_in_place());
+        layout.get_output_path(Path::new("/other/a.rpm")).expect_err("file outside of input path");
+        layout.get_output_path(&input.join("../a.rpm")).expect_err("file can't be mapped onto output directory");
+
+        let layout = OutputLayout::new(&input.join("x86_64/a.rpm"), Path::new("/output"));
+        assert_eq!(layout.get_output_path(&input.join("x86_64/a.rpm.asc")).unwrap(), PathBuf::from("/output/a.rpm.asc"));
+        let layout = OutputLayout::new(Path::new("a.rpm"), Path::new("/output"));
+        assert_eq!(layout.get_output_path(Path::new("a.rpm")).unwrap(), PathBuf::from("/output/a.rpm"));
+        fs::remove_dir_all(input).unwrap();
+    }
+
+    #[test]
+    fn test_copy_unchanged() {
+        let input = env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(input.join("blobs/sha256")).unwrap();
+        fs::create_dir_all(input.join("failed")).unwrap();
+        fs::write(input.join("index.json"), "index").unwrap();
+        fs::write(input.join("blobs/sha256/abcd"), "blob").unwrap();
+        fs::write(input.join("failed/a.rpm"), "rpm").unwrap();
+        //output directory inside of input directory is skipped
+        let layout = OutputLayout::new(&input, &input.join("output"));
+        fs::create_dir_all(layout.get_output_dir()).unwrap();
+        fs::write(input.join("output/index.json"), "signed index").unwrap();
+        let written = HashSet::from([input.join("output/index.json")]);
+        let copied = layout.copy_unchanged(&input, &written, &[input.join("failed")]).expect("copy should work");
+        assert_eq!(copied, 1);
+        assert_eq!(fs::read_to_string(input.join("output/blobs/sha256/abcd")).unwrap(), "blob");
+        assert_eq!(fs::read_to_string(input.join("output/index.json")).unwrap(), "signed index");
+        assert!(!input.join("output/failed").exists());
+        fs::remove_dir_all(input).unwrap();
+    }
+
+    #[test]
+    fn test_manifest_entry() {
+        let dir = env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&dir).unwrap();
+        let original = dir.join("file.bin");
+        let output = dir.join("file.bin.asc");
+        fs::write(&original, "content").unwrap();
+        fs::write(&output, "signature").unwrap();
+        let keys = vec![SigningKey { name: "default-pgp".to_string(), fingerprint: "ABCD".to_string() }];
+        //detached signature doesn't exist in input path
+        let entry = ManifestEntry::new(&dir.join("file.bin.sig"), &original, &output, &keys).expect("create entry should work");
+        assert_eq!(entry.input, original.display().to_string());
+        assert_eq!(entry.input_digest, Some(get_content_digest("content".as_bytes())));
+        assert_eq!(entry.output_digest, get_content_digest("signature".as_bytes()));
+        assert_eq!(entry.keys, keys);
+
+        let entry = ManifestEntry::new(&original, &dir, &output, &keys).expect("create entry should work");
+        assert_eq!(entry.input, original.display().to_string());
+        let entry = ManifestEntry::new(&dir.join("blob"), &dir, &output, &keys).expect("create entry should work");
+        assert_eq!(entry.input_digest, None);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
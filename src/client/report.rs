@@ -0,0 +1,180 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::util::xml;
+use crate::client::sign_identity::SignIdentity;
+use crate::util::error::Result;
+
+const STATUS_SUCCEEDED: &str = "succeeded";
+const STATUS_FAILED: &str = "failed";
+
+#[derive(clap::ValueEnum, Clone, Debug, PartialEq)]
+pub enum ReportFormat {
+    Json,
+    Junit,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportEntry {
+    pub file: String,
+    pub file_type: String,
+    pub key_type: String,
+    pub key_name: String,
+    pub status: String,
+    pub error: Option<String>,
+    pub duration_ms: u128,
+}
+
+impl ReportEntry {
+    pub fn new(identity: &SignIdentity) -> Self {
+        let error = identity.error.borrow().as_ref().err().map(|err| err.to_string());
+        Self {
+            file: identity.file_path.display().to_string(),
+            file_type: identity.file_type.to_string(),
+            key_type: identity.key_type.to_string(),
+            key_name: identity.key_id.clone(),
+            status: if error.is_none() { STATUS_SUCCEEDED } else { STATUS_FAILED }.to_string(),
+            error,
+            duration_ms: identity.start_time.borrow().map(|start| start.elapsed().as_millis()).unwrap_or_default(),
+        }
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.status == STATUS_FAILED
+    }
+}
+
+/// Result of each file in signing process, it's used by CI system to display the failures per file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Report {
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub duration_ms: u128,
+    pub entries: Vec<ReportEntry>,
+}
+
+impl Report {
+    pub fn new(mut entries: Vec<ReportEntry>, duration: Duration) -> Self {
+        entries.sort_by(|a, b| a.file.cmp(&b.file));
+        let failed = entries.iter().filter(|entry| entry.is_failed()).count();
+        Self {
+            total: entries.len(),
+            succeeded: entries.len() - failed,
+            failed,
+            duration_ms: duration.as_millis(),
+            entries,
+        }
+    }
+
+    //each file is a test case, test cases are grouped by file type and key name in class name
+    fn to_junit(&self) -> String {
+        let mut lines = vec![
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string(),
+            format!(r#"<testsuites name="signatrust" tests="{}" failures="{}" time="{}">"#, self.total, self.failed, seconds(self.duration_ms)),
+            format!(r#"  <testsuite name="signatrust-client" tests="{}" failures="{}" time="{}">"#, self.total, self.failed, seconds(self.duration_ms)),
+        ];
+        for entry in self.entries.iter() {
+            let testcase = format!(r#"    <testcase classname="{}" name="{}" time="{}""#,
+                                   xml::escape(&format!("{}.{}", entry.file_type, entry.key_name)),
+                                   xml::escape(&entry.file), seconds(entry.duration_ms));
+            match &entry.error {
+                Some(error) => {
+                    lines.push(format!("{}>", testcase));
+                    lines.push(format!(r#"      <failure message="{}">{}</failure>"#, xml::escape(error), xml::escape(error)));
+                    lines.push("    </testcase>".to_string());
+                }
+                None => lines.push(format!("{}/>", testcase)),
+            }
+        }
+        lines.push("  </testsuite>".to_string());
+        lines.push("</testsuites>".to_string());
+        lines.join("\n")
+    }
+
+    pub fn save(&self, path: &Path, format: &ReportFormat) -> Result<()> {
+        let content = match format {
+            ReportFormat::Json => serde_json::to_string_pretty(self)?,
+            ReportFormat::Junit => self.to_junit(),
+        };
+        fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+fn seconds(milliseconds: u128) -> String {
+    format!("{:.3}", milliseconds as f64 / 1000.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::error::Error;
+    use crate::util::sign::{FileType, KeyType};
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::time::Instant;
+
+    fn get_report() -> Report {
+        let succeeded = SignIdentity::new(FileType::Rpm, PathBuf::from("/rpms/b.rpm"), KeyType::Pgp, "default-pgp".to_string(), HashMap::new());
+        *succeeded.start_time.borrow_mut() = Some(Instant::now());
+        let failed = SignIdentity::new(FileType::Rpm, PathBuf::from("/rpms/a&<\"c\">.rpm"), KeyType::Pgp, "default-pgp".to_string(), HashMap::new());
+        *failed.error.borrow_mut() = Err(Error::RemoteSignError("key \"default-pgp\" not found".to_string()));
+        Report::new(vec![ReportEntry::new(&succeeded), ReportEntry::new(&failed)], Duration::from_millis(1500))
+    }
+
+    #[test]
+    fn test_json_report() {
+        let report = get_report();
+        assert_eq!((report.total, report.succeeded, report.failed, report.duration_ms), (2, 1, 1, 1500));
+        assert_eq!(report.entries[0].file, "/rpms/a&<\"c\">.rpm");
+        assert_eq!(report.entries[0].status, STATUS_FAILED);
+        assert_eq!(report.entries[0].duration_ms, 0);
+        assert_eq!(report.entries[1].status, STATUS_SUCCEEDED);
+        assert_eq!(report.entries[1].error, None);
+        let value: serde_json::Value = serde_json::from_str(&serde_json::to_string(&report).unwrap()).unwrap();
+        assert_eq!(value["entries"][0]["file_type"], "rpm");
+        assert_eq!(value["entries"][0]["key_type"], "pgp");
+        assert_eq!(value["entries"][0]["key_name"], "default-pgp");
+        assert!(value["entries"][0]["error"].as_str().unwrap().contains("not found"));
+    }
+
+    #[test]
+    fn test_junit_report() {
+        let junit = get_report().to_junit();
+        let lines: Vec<&str> = junit.lines().collect();
+        assert_eq!(lines[1], r#"<testsuites name="signatrust" tests="2" failures="1" time="1.500">"#);
+        assert_eq!(lines[3], r#"    <testcase classname="rpm.default-pgp" name="/rpms/a&amp;&lt;&quot;c&quot;&gt;.rpm" time="0.000">"#);
+        assert_eq!(lines[4], r#"      <failure message="failed to remote sign file: key &quot;default-pgp&quot; not found">failed to remote sign file: key &quot;default-pgp&quot; not found</failure>"#);
+        assert!(lines[6].starts_with(r#"    <testcase classname="rpm.default-pgp" name="/rpms/b.rpm""#));
+        assert!(lines[6].ends_with("/>"));
+        assert_eq!(lines.last(), Some(&"</testsuites>"));
+    }
+
+    #[test]
+    fn test_junit_report_with_control_characters() {
+        let failed = SignIdentity::new(FileType::Rpm, PathBuf::from("/rpms/a.rpm"), KeyType::Pgp, "default-pgp".to_string(), HashMap::new());
+        *failed.error.borrow_mut() = Err(Error::RemoteSignError("\u{1b}[31mkey not found\u{1b}[0m\n".to_string()));
+        let junit = Report::new(vec![ReportEntry::new(&failed)], Duration::from_millis(0)).to_junit();
+        let lines: Vec<&str> = junit.lines().collect();
+        assert_eq!(lines[4], "      <failure message=\"failed to remote sign file: \u{FFFD}[31mkey not found\u{FFFD}[0m&#xA;\">failed to remote sign file: \u{FFFD}[31mkey not found\u{FFFD}[0m&#xA;</failure>");
+    }
+}
@@ -19,7 +19,10 @@ use std::path::PathBuf;
 use std::cell::{RefCell};
 use crate::util::error::{Result};
 use crate::util::sign::{FileType, KeyType};
+use crate::client::transparency::TransparencyProof;
+use crate::client::manifest::{ManifestEntry, SigningKey};
 use std::collections::HashMap;
+use std::time::Instant;
 
 
 pub struct SignIdentity {
@@ -31,6 +34,14 @@ pub struct SignIdentity {
     pub raw_content: Box<RefCell<Vec<Vec<u8>>>>,
     pub signature: Box<RefCell<Vec<Vec<u8>>>>,
     pub sign_options: RefCell<HashMap<String, String>>,
+    //transparency log proofs of signatures, empty if transparency log is disabled on server
+    pub transparency_proofs: RefCell<Vec<TransparencyProof>>,
+    //keys which produce the signatures with fingerprints returned by server, the specified key comes first
+    pub signing_keys: RefCell<Vec<SigningKey>>,
+    //artifacts written to output directory, empty if output directory is not specified
+    pub manifest_entries: RefCell<Vec<ManifestEntry>>,
+    //time when the file starts to be split, used for measuring the duration of signing
+    pub start_time: RefCell<Option<Instant>>,
     pub error: RefCell<Result<()>>,
 }
 
@@ -44,7 +55,25 @@ impl SignIdentity {
             raw_content: Box::new(RefCell::new(vec![])),
             signature: Box::new(RefCell::new(vec![])),
             sign_options: RefCell::new(sign_options),
+            transparency_proofs: RefCell::new(vec![]),
+            signing_keys: RefCell::new(vec![]),
+            manifest_entries: RefCell::new(vec![]),
+            start_time: RefCell::new(None),
             error: RefCell::new(Ok(())),
         }
     }
+
+    //record the key which produces signature, the specified key is always placed first
+    pub(crate) fn add_signing_key(&self, name: &str, fingerprint: String) {
+        let mut keys = self.signing_keys.borrow_mut();
+        if keys.iter().any(|key| key.name == name) {
+            return
+        }
+        let key = SigningKey { name: name.to_string(), fingerprint };
+        if name == self.key_id {
+            keys.insert(0, key);
+        } else {
+            keys.push(key);
+        }
+    }
 }
@@ -0,0 +1,86 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::client::worker::signer::signatrust::TransparencyLogEntry;
+use crate::domain::transparency::entity::LogLeaf;
+use crate::domain::transparency::merkle::{leaf_hash, verify_inclusion};
+use crate::util::error::{Error, Result};
+use crate::util::key::get_content_digest;
+
+pub const PROOF_FILE_EXTENSION: &str = "tlog.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeHeadProof {
+    pub tree_size: u64,
+    pub root_hash: String,
+    pub timestamp: i64,
+    pub key_name: String,
+    pub signature: String,
+}
+
+/// Inclusion proof of one signature in the transparency log, binary fields are hex encoded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransparencyProof {
+    pub leaf_index: u64,
+    pub key_name: String,
+    pub content_digest: String,
+    pub signature_digest: String,
+    pub leaf_hash: String,
+    pub inclusion_proof: Vec<String>,
+    pub tree_head: TreeHeadProof,
+}
+
+impl TransparencyProof {
+    /// Ensure the log entry returned by server covers the signature and is consistent with the tree head.
+    pub fn new(entry: TransparencyLogEntry, signature: &[u8]) -> Result<Self> {
+        let tree_head = entry.tree_head.ok_or_else(|| Error::RemoteSignError("tree head is absent in log entry".to_string()))?;
+        let expected = leaf_hash(&LogLeaf::leaf_data(&entry.key_name, &entry.content_digest, &get_content_digest(signature)));
+        if expected != entry.leaf_hash {
+            return Err(Error::RemoteSignError("transparency log entry does not match the signature".to_string()))
+        }
+        if !verify_inclusion(entry.leaf_index, tree_head.tree_size, &entry.leaf_hash, &entry.inclusion_proof, &tree_head.root_hash) {
+            return Err(Error::RemoteSignError("invalid inclusion proof of transparency log entry".to_string()))
+        }
+        Ok(Self {
+            leaf_index: entry.leaf_index,
+            key_name: entry.key_name,
+            content_digest: entry.content_digest,
+            signature_digest: entry.signature_digest,
+            leaf_hash: hex::encode(entry.leaf_hash),
+            inclusion_proof: entry.inclusion_proof.iter().map(hex::encode).collect(),
+            tree_head: TreeHeadProof {
+                tree_size: tree_head.tree_size,
+                root_hash: hex::encode(tree_head.root_hash),
+                timestamp: tree_head.timestamp,
+                key_name: tree_head.key_name,
+                signature: hex::encode(tree_head.signature),
+            },
+        })
+    }
+
+    /// Proofs are saved next to the artifact, one entry for each of the signatures in file.
+    pub fn save(proofs: &[TransparencyProof], artifact: &Path) -> Result<PathBuf> {
+        let mut path = artifact.as_os_str().to_owned();
+        path.push(format!(".{}", PROOF_FILE_EXTENSION));
+        let path = PathBuf::from(path);
+        fs::write(&path, serde_json::to_string_pretty(proofs)?)?;
+        Ok(path)
+    }
+}
@@ -23,23 +23,58 @@ use async_trait::async_trait;
 use std::path::{Path, PathBuf};
 use std::fs::copy;
 use crate::util::error::Error;
+use crate::client::transparency::TransparencyProof;
+use crate::client::manifest::{ManifestEntry, OutputLayout};
 
 
 use std::fs;
 
 pub struct Assembler {
-    temp_dir: PathBuf
+    temp_dir: PathBuf,
+    save_transparency_proof: bool,
+    //signed files are written to output directory instead of the input path if specified
+    output_layout: Option<OutputLayout>,
 }
 
 
 impl Assembler {
 
-    pub fn new(temp_dir: String) -> Self {
+    pub fn new(temp_dir: String, save_transparency_proof: bool, output_layout: Option<OutputLayout>) -> Self {
         Self {
-            temp_dir: PathBuf::from(temp_dir)
+            temp_dir: PathBuf::from(temp_dir),
+            save_transparency_proof,
+            output_layout,
         }
     }
 
+    fn save_file(&self, item: &SignIdentity, temp_file: &Path, file_name: &Path) -> Result<PathBuf, Error> {
+        let layout = match &self.output_layout {
+            Some(layout) => layout,
+            None => {
+                copy(temp_file, file_name)?;
+                return Ok(file_name.to_path_buf())
+            }
+        };
+        let output = layout.get_output_path(file_name)?;
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        copy(temp_file, &output)?;
+        item.manifest_entries.borrow_mut().push(ManifestEntry::new(
+            file_name, &item.file_path, &output, &item.signing_keys.borrow())?);
+        Ok(output)
+    }
+
+    fn save_proofs(&self, item: &SignIdentity, artifact: &Path) -> Result<(), Error> {
+        let proofs = item.transparency_proofs.borrow();
+        if proofs.is_empty() {
+            return Err(Error::AssembleFileError(
+                "transparency log proof is absent, please check whether transparency log is enabled on server".to_string()))
+        }
+        let path = TransparencyProof::save(&proofs, artifact)?;
+        debug!("successfully saved transparency log proof {}", path.display());
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -49,19 +84,31 @@ impl SignHandler for Assembler {
         let signatures: Vec<Vec<u8>> = (*item.signature).borrow().clone();
         let sign_options = item.sign_options.borrow().clone();
         match handler.assemble_data(&item.file_path,  signatures, &self.temp_dir, &sign_options).await {
-            Ok(content) => {
+            Ok(contents) => {
                 debug!("successfully assemble file {}", item.file_path.as_path().display());
-                let temp_file = Path::new(&content.0);
-                match copy(temp_file, Path::new(&content.1)) {
-                    Ok(_) => {
-                        debug!("successfully saved file {}", item.file_path.as_path().display());
+                let mut outputs = Vec::new();
+                for (temp_file, file_name) in contents.iter() {
+                    let temp_file = Path::new(temp_file);
+                    match self.save_file(&item, temp_file, Path::new(file_name)) {
+                        Ok(output) => {
+                            debug!("successfully saved file {}", output.display());
+                            outputs.push(output);
+                        }
+                        Err(err) => {
+                            *item.error.borrow_mut() = Err(err);
+                        }
                     }
-                    Err(err) => {
-                        *item.error.borrow_mut() = Err(Error::AssembleFileError(format!("{:?}", err)));
+                    //remove temp file when finished
+                    let _ = fs::remove_file(temp_file);
+                }
+                //proofs of all signatures are saved next to the first generated file
+                if self.save_transparency_proof && item.error.borrow().is_ok() {
+                    if let Some(output) = outputs.first() {
+                        if let Err(err) = self.save_proofs(&item, output) {
+                            *item.error.borrow_mut() = Err(err);
+                        }
                     }
                 }
-                //remove temp file when finished
-                let _ = fs::remove_file(temp_file);
             }
             Err(err) => {
                 *item.error.borrow_mut() = Err(Error::AssembleFileError(format!("{:?}", err)));
@@ -15,6 +15,7 @@
  */
 
 use crate::client::{sign_identity::SignIdentity};
+use crate::client::transparency::TransparencyProof;
 use crate::client::worker::traits::SignHandler;
 use crate::client::file_handler::traits::FileHandler;
 use async_trait::async_trait;
@@ -24,67 +25,152 @@ pub mod signatrust {
 }
 
 use tonic::transport::Channel;
+use tonic::metadata::AsciiMetadataValue;
+use tonic::Request;
 use self::signatrust::{
-    signatrust_client::SignatrustClient, SignStreamRequest,
+    signatrust_client::SignatrustClient, SignStreamRequest, SignStreamResponse, SignDigestRequest, HashState,
 };
 
 use crate::util::error::Error;
+use crate::util::digest::ContentDigest;
+use crate::util::options;
+use crate::util::sign::SignType;
+use std::collections::HashMap;
 use std::io::{Cursor, Read};
 
 pub struct RemoteSigner {
     client: SignatrustClient<Channel>,
     buffer_size: usize,
+    token: Option<AsciiMetadataValue>,
+    digest_only: bool,
 }
 
 
 impl RemoteSigner {
 
-    pub fn new(channel: Channel, buffer_size: usize) -> Self {
+    pub fn new(channel: Channel, buffer_size: usize, token: Option<AsciiMetadataValue>, digest_only: bool) -> Self {
         Self {
             client: SignatrustClient::new(channel),
             buffer_size,
+            token,
+            digest_only,
         }
     }
+
+    fn with_token<T>(&self, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        if let Some(token) = self.token.clone() {
+            request.metadata_mut().insert(options::AUTH_METADATA_KEY, token);
+        }
+        request
+    }
+
+    //sign single content with specified key, it's used by commands which don't go through the file handlers
+    pub async fn sign(&mut self, key_type: String, key_id: String, sign_options: HashMap<String, String>, sign_content: Vec<u8>) -> Result<Vec<u8>, Error> {
+        let response = if self.digest_only {
+            self.sign_digest(key_type, key_id, sign_options, sign_content).await?
+        } else {
+            self.sign_stream(key_type, key_id, sign_options, sign_content).await?
+        };
+        if !response.error.is_empty() {
+            return Err(Error::RemoteSignError(response.error))
+        }
+        Ok(response.signature)
+    }
+
+    async fn sign_stream(&mut self, key_type: String, key_id: String, sign_options: HashMap<String, String>, sign_content: Vec<u8>) -> Result<SignStreamResponse, Error> {
+        let mut sign_segments: Vec<SignStreamRequest> = Vec::new();
+        let mut buffer = vec![0; self.buffer_size];
+        let mut cursor = Cursor::new(sign_content);
+        while let Ok(length) = cursor.read(&mut buffer) {
+            if length == 0 {
+                break
+            }
+            let content = buffer[0..length].to_vec();
+            sign_segments.push(SignStreamRequest{
+                data: content,
+                options: sign_options.clone(),
+                key_type: key_type.clone(),
+                key_id: key_id.clone(),
+            });
+        }
+        if sign_segments.is_empty() {
+            return Err(Error::FileContentEmpty)
+        }
+        let request = self.with_token(tokio_stream::iter(sign_segments));
+        Ok(self.client.sign_stream(request).await.map_err(|err| Error::RemoteSignError(format!("{:?}", err)))?.into_inner())
+    }
+
+    //only the digest of content is sent, authenticode content is the digest of image already and it's sent as is
+    //since the digest algorithm of image may not be SHA-256.
+    async fn sign_digest(&mut self, key_type: String, key_id: String, sign_options: HashMap<String, String>, sign_content: Vec<u8>) -> Result<SignStreamResponse, Error> {
+        if sign_content.is_empty() {
+            return Err(Error::FileContentEmpty)
+        }
+        if sign_options.get(options::SIGN_TYPE) == Some(&SignType::Authenticode.to_string()) {
+            return self.sign_stream(key_type, key_id, sign_options, sign_content).await
+        }
+        let digest = ContentDigest::from_content(&sign_content);
+        let request = self.with_token(SignDigestRequest {
+            key_type,
+            key_id,
+            options: sign_options,
+            digest_algorithm: digest.algorithm,
+            digest: digest.digest,
+            hash_state: digest.state.map(|state| HashState {
+                state: state.state(),
+                length: state.length(),
+                tail: state.tail().to_vec(),
+            }),
+        });
+        Ok(self.client.sign_digest(request).await.map_err(|err| Error::RemoteSignError(format!("{:?}", err)))?.into_inner())
+    }
 }
 
 #[async_trait]
 impl SignHandler for RemoteSigner {
-    async fn process(&mut self, _handler: Box<dyn FileHandler>, item: SignIdentity) -> SignIdentity {
+    async fn process(&mut self, handler: Box<dyn FileHandler>, item: SignIdentity) -> SignIdentity {
         let mut signed_content = Vec::new();
         let read_data = item.raw_content.borrow().clone();
-        for sign_content in read_data.into_iter() {
-            let mut sign_segments: Vec<SignStreamRequest> = Vec::new();
-            let mut buffer = vec![0; self.buffer_size];
-            let mut cursor = Cursor::new(sign_content);
-            while let Ok(length) = cursor.read(&mut buffer) {
-                if length == 0 {
-                    break
+        for (section, sign_content) in read_data.into_iter().enumerate() {
+            let sign_options = handler.section_options(section, &item.sign_options.borrow());
+            let prepared = handler.prepare_section(section, sign_content, &signed_content, &item.sign_options.borrow());
+            let sign_content = match prepared {
+                Ok(content) => content,
+                Err(err) => {
+                    *item.error.borrow_mut() = Err(err);
+                    return item
                 }
-                let content = buffer[0..length].to_vec();
-                sign_segments.push(SignStreamRequest{
-                    data: content,
-                    options: item.sign_options.borrow().clone(),
-                    key_type: format!("{}", item.key_type),
-                    key_id: item.key_id.clone(),
-                });
-            }
-            if sign_segments.is_empty() {
-                *item.error.borrow_mut() = Err(Error::FileContentEmpty);
-                return item
-            }
-            let result = self.client.sign_stream(
-                tokio_stream::iter(sign_segments)).await;
+            };
+            //section can be signed with a different key, for instance, IMA signatures of files inside rpm package
+            let key_type = sign_options.get(options::KEY_TYPE).cloned().unwrap_or(format!("{}", item.key_type));
+            let key_id = sign_options.get(options::KEY_NAME).cloned().unwrap_or(item.key_id.clone());
+            let result = if self.digest_only {
+                self.sign_digest(key_type, key_id.clone(), sign_options, sign_content).await
+            } else {
+                self.sign_stream(key_type, key_id.clone(), sign_options, sign_content).await
+            };
             match result {
-                Ok(result) => {
-                    let data = result.into_inner();
+                Ok(data) => {
                     if data.error.is_empty() {
+                        if let Some(entry) = data.log_entry {
+                            match TransparencyProof::new(entry, &data.signature) {
+                                Ok(proof) => item.transparency_proofs.borrow_mut().push(proof),
+                                Err(err) => *item.error.borrow_mut() = Err(err),
+                            }
+                        }
+                        item.add_signing_key(&key_id, data.key_fingerprint);
                         signed_content.push(data.signature);
                     } else {
                         *item.error.borrow_mut() = Err(Error::RemoteSignError(data.error))
                     }
                 }
+                Err(Error::FileContentEmpty) => {
+                    *item.error.borrow_mut() = Err(Error::FileContentEmpty);
+                    return item
+                }
                 Err(err) => {
-                    *item.error.borrow_mut() = Err(Error::RemoteSignError(format!("{:?}", err)))
+                    *item.error.borrow_mut() = Err(err)
                 }
             }
         }
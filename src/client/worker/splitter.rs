@@ -21,6 +21,7 @@ use crate::client::worker::traits::SignHandler;
 use crate::client::file_handler::traits::FileHandler;
 use async_trait::async_trait;
 use crate::util::error;
+use std::time::Instant;
 
 pub struct Splitter {
 }
@@ -37,6 +38,7 @@ impl Splitter {
 #[async_trait]
 impl SignHandler for Splitter {
     async fn process(&mut self, handler: Box<dyn FileHandler>, item: SignIdentity) -> SignIdentity {
+        *item.start_time.borrow_mut() = Some(Instant::now());
         let mut sign_options = item.sign_options.borrow().clone();
         match handler.split_data(&item.file_path, &mut sign_options).await {
             Ok(content) => {
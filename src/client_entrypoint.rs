@@ -18,7 +18,7 @@
 use std::env;
 use crate::util::error::{Result, Error};
 use clap::{Parser, Subcommand};
-use crate::client::cmd::add;
+use crate::client::cmd::{add, keys, verify};
 use config::{Config, File};
 use std::sync::{Arc, atomic::AtomicBool, RwLock};
 use crate::client::cmd::traits::SignCommand;
@@ -51,7 +51,24 @@ pub struct App {
 #[derive(Subcommand)]
 enum Commands {
     #[command(about = "Create new signature for single file or all of the files in directory", long_about = None)]
-    Add(add::CommandAdd),
+    Add(Box<add::CommandAdd>),
+    #[command(about = "Verify signatures of single file or all of the files in directory with public key or certificate", long_about = None)]
+    Verify(verify::CommandVerify),
+    #[command(about = "List, show and export the keys managed by control server", long_about = None)]
+    Keys(keys::CommandKeys),
+}
+
+fn run<T: SignCommand>(handler: T) -> Result<()> {
+    if let Err(err) = handler.validate() {
+        error!("failed to validate command: {}", err);
+        return Err(err);
+    }
+
+    if let Err(err) = handler.handle() {
+        error!("failed to handle command: {}", err);
+        return Err(Error::PartialSuccessError)
+    }
+    Ok(())
 }
 
 fn main() -> Result<()> {
@@ -65,24 +82,12 @@ fn main() -> Result<()> {
     let signal = Arc::new(AtomicBool::new(false));
     signal_hook::flag::register(signal_hook::consts::SIGTERM, Arc::clone(&signal)).expect("failed to register sigterm signal");
     signal_hook::flag::register(signal_hook::consts::SIGINT, Arc::clone(&signal)).expect("failed to register sigint signal");
-    //construct handler
-    let command = match app.command {
-        Some(Commands::Add(add_command)) => {
-            Some(add::CommandAddHandler::new(signal, Arc::new(RwLock::new(client)), add_command)?)
-        }
-        None => {None}
-    };
-    //handler and quit
-    if let Some(handler) = command {
-        if let Err(err) = handler.validate() {
-            error!("failed to validate command: {}", err);
-            return Err(err);
-        }
-
-        if let Err(err) = handler.handle() {
-            error!("failed to handle command: {}", err);
-            return Err(Error::PartialSuccessError)
-        }
+    //construct handler and quit
+    let config = Arc::new(RwLock::new(client));
+    match app.command {
+        Some(Commands::Add(add_command)) => run(add::CommandAddHandler::new(signal, config, *add_command)?),
+        Some(Commands::Verify(verify_command)) => run(verify::CommandVerifyHandler::new(signal, config, verify_command)?),
+        Some(Commands::Keys(keys_command)) => run(keys::CommandKeysHandler::new(signal, config, keys_command)?),
+        None => Ok(()),
     }
-    Ok(())
 }
@@ -0,0 +1,212 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use crate::util::error::{Error, Result};
+use crate::domain::token::entity::Token;
+use crate::util::key::get_content_digest;
+use crate::util::options;
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignAuditResult {
+    Success,
+    Failure,
+}
+
+impl Display for SignAuditResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignAuditResult::Success => write!(f, "success"),
+            SignAuditResult::Failure => write!(f, "failure"),
+        }
+    }
+}
+
+impl FromStr for SignAuditResult {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "success" => Ok(SignAuditResult::Success),
+            "failure" => Ok(SignAuditResult::Failure),
+            _ => Err(Error::UnsupportedTypeError(format!("unsupported sign audit result {}", s))),
+        }
+    }
+}
+
+//the content digest is computed by server from the content, or supplied by client in prehashed signing
+#[derive(Debug, Clone, PartialEq)]
+pub enum DigestSource {
+    Server,
+    Client,
+}
+
+impl Display for DigestSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DigestSource::Server => write!(f, "server"),
+            DigestSource::Client => write!(f, "client"),
+        }
+    }
+}
+
+impl FromStr for DigestSource {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "server" => Ok(DigestSource::Server),
+            "client" => Ok(DigestSource::Client),
+            _ => Err(Error::UnsupportedTypeError(format!("unsupported digest source {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SignAudit {
+    pub id: i64,
+    //key id is absent when the request is rejected before the key is loaded
+    pub key_id: Option<i32>,
+    pub key_name: String,
+    pub key_type: String,
+    pub user_id: i32,
+    pub user_email: Option<String>,
+    pub token_id: i32,
+    pub client_address: String,
+    pub sign_options: HashMap<String, String>,
+    pub content_digest: String,
+    pub digest_source: DigestSource,
+    pub result: SignAuditResult,
+    pub error: Option<String>,
+    pub create_at: DateTime<Utc>,
+}
+
+impl Display for SignAudit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "id: {}, key_name: {}, user_id: {}, token_id: {}, client_address: {}, result: {}",
+            self.id, self.key_name, self.user_id, self.token_id, self.client_address, self.result
+        )
+    }
+}
+
+impl SignAudit {
+    pub fn new(token: &Token, client_address: String, key_type: &str, key_name: &str, sign_options: &HashMap<String, String>, content: &[u8]) -> Result<Self> {
+        Ok(SignAudit {
+            id: 0,
+            key_id: None,
+            key_name: key_name.to_string(),
+            key_type: key_type.to_string(),
+            user_id: token.user_id,
+            user_email: None,
+            token_id: token.id,
+            client_address,
+            sign_options: sign_options.iter()
+                .filter(|(key, _)| !options::SECRET_OPTIONS.contains(&key.as_str()))
+                .map(|(key, value)| (key.clone(), value.clone())).collect(),
+            content_digest: get_content_digest(content),
+            digest_source: DigestSource::Server,
+            result: SignAuditResult::Success,
+            error: None,
+            create_at: Utc::now(),
+        })
+    }
+
+    /// Record the digest supplied by client, the content is never seen by server in prehashed signing.
+    pub fn set_client_digest(&mut self, digest: &[u8]) {
+        self.content_digest = hex::encode(digest);
+        self.digest_source = DigestSource::Client;
+    }
+
+    pub fn set_result<T>(&mut self, result: &Result<T>) {
+        match result {
+            Ok(_) => {
+                self.result = SignAuditResult::Success;
+                self.error = None;
+            }
+            Err(err) => {
+                self.result = SignAuditResult::Failure;
+                self.error = Some(err.to_string());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SignAuditFilter {
+    pub key_name: Option<String>,
+    pub user_email: Option<String>,
+    pub result: Option<SignAuditResult>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    //only the records signed by this user or signed with the keys owned by this user are returned
+    pub visible_to: Option<i32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PagedSignAudit {
+    pub total: i64,
+    pub audits: Vec<SignAudit>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sign_audit_result() {
+        let token = Token::new(1, "test token".to_string(), "fake token".to_string()).unwrap();
+        let options = HashMap::from([("detached".to_string(), "true".to_string())]);
+        let mut audit = SignAudit::new(&token, "127.0.0.1:8080".to_string(), "pgp", "default-pgp", &options, "content".as_bytes()).unwrap();
+        assert_eq!(audit.content_digest, "ed7002b439e9ac845f22357d822bac1444730fbdb6016d3ec9432297b9ec9f73");
+        assert_eq!(audit.result, SignAuditResult::Success);
+
+        audit.set_result::<()>(&Err(Error::NotFoundError));
+        assert_eq!(audit.result, SignAuditResult::Failure);
+        assert_eq!(audit.error, Some("record not found error".to_string()));
+
+        audit.set_result(&Ok(()));
+        assert_eq!(audit.result, SignAuditResult::Success);
+        assert_eq!(audit.error, None);
+    }
+
+    #[test]
+    fn test_sign_audit_without_secret_options() {
+        let token = Token::new(1, "test token".to_string(), "fake token".to_string()).unwrap();
+        let options = HashMap::from([
+            ("detached".to_string(), "true".to_string()),
+            ("passphrase".to_string(), "123456".to_string())]);
+        let audit = SignAudit::new(&token, "127.0.0.1:8080".to_string(), "pgp", "default-pgp", &options, "content".as_bytes()).unwrap();
+        assert_eq!(audit.sign_options, HashMap::from([("detached".to_string(), "true".to_string())]));
+    }
+
+    #[test]
+    fn test_sign_audit_digest_source() {
+        let token = Token::new(1, "test token".to_string(), "fake token".to_string()).unwrap();
+        let mut audit = SignAudit::new(&token, "127.0.0.1:8080".to_string(), "pgp", "default-pgp", &HashMap::new(), "content".as_bytes()).unwrap();
+        assert_eq!(audit.digest_source, DigestSource::Server);
+        audit.set_client_digest(&[0xab; 32]);
+        assert_eq!(audit.digest_source, DigestSource::Client);
+        assert_eq!(audit.content_digest, "ab".repeat(32));
+        assert_eq!(DigestSource::from_str(&audit.digest_source.to_string()).unwrap(), DigestSource::Client);
+        let _ = DigestSource::from_str("unknown").expect_err("unknown digest source should fail");
+    }
+}
@@ -0,0 +1,2 @@
+pub mod entity;
+pub mod repository;
\ No newline at end of file
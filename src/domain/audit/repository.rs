@@ -0,0 +1,25 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use super::entity::{PagedSignAudit, SignAudit, SignAuditFilter};
+use crate::util::error::Result;
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait Repository: Send + Sync {
+    async fn create(&self, audit: SignAudit) -> Result<()>;
+    async fn get_by_filter(&self, filter: &SignAuditFilter, page: u32, page_size: u32) -> Result<PagedSignAudit>;
+}
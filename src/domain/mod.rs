@@ -2,6 +2,9 @@ pub mod clusterkey;
 pub mod datakey;
 pub mod user;
 pub mod token;
+pub mod permission;
+pub mod audit;
+pub mod transparency;
 pub mod encryption_engine;
 pub mod encryptor;
 pub mod kms_provider;
@@ -0,0 +1,100 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use crate::util::error::Result;
+
+use chrono::{DateTime, Utc};
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone)]
+pub struct KeyPermission {
+    pub id: i32,
+    pub key_id: i32,
+    pub user_id: i32,
+    pub user_email: String,
+    //none stands for all of the tokens owned by this user
+    pub token_id: Option<i32>,
+    pub create_at: DateTime<Utc>,
+}
+
+impl Display for KeyPermission {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "id: {}, key_id: {}, user_id: {}, token_id: {:?}",
+            self.id, self.key_id, self.user_id, self.token_id
+        )
+    }
+}
+
+impl KeyPermission {
+    pub fn new(key_id: i32, user_id: i32, user_email: String, token_id: Option<i32>) -> Result<Self> {
+        Ok(KeyPermission {
+            id: 0,
+            key_id,
+            user_id,
+            user_email,
+            token_id,
+            create_at: Utc::now(),
+        })
+    }
+
+    pub fn is_granted(&self, user_id: i32, token_id: i32) -> bool {
+        if self.user_id != user_id {
+            return false
+        }
+        match self.token_id {
+            None => true,
+            Some(id) => id == token_id
+        }
+    }
+
+    //keys without any permission records are denied for the users other than the owner
+    pub fn is_sign_allowed(permissions: &[KeyPermission], user_id: i32, token_id: i32) -> bool {
+        permissions.iter().any(|p| p.is_granted(user_id, token_id))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_key_permission_granted() {
+        let user_permission = KeyPermission::new(1, 2, "test@openeuler.org".to_string(), None).unwrap();
+        assert!(user_permission.is_granted(2, 10));
+        assert!(user_permission.is_granted(2, 11));
+        assert!(!user_permission.is_granted(3, 10));
+
+        let token_permission = KeyPermission::new(1, 2, "test@openeuler.org".to_string(), Some(10)).unwrap();
+        assert!(token_permission.is_granted(2, 10));
+        assert!(!token_permission.is_granted(2, 11));
+        assert!(!token_permission.is_granted(3, 10));
+    }
+
+    #[test]
+    fn test_key_permission_sign_allowed() {
+        assert!(!KeyPermission::is_sign_allowed(&[], 2, 10));
+        let permissions = vec![
+            KeyPermission::new(1, 2, "test@openeuler.org".to_string(), Some(10)).unwrap(),
+            KeyPermission::new(1, 3, "other@openeuler.org".to_string(), None).unwrap(),
+        ];
+        assert!(KeyPermission::is_sign_allowed(&permissions, 2, 10));
+        assert!(!KeyPermission::is_sign_allowed(&permissions, 2, 11));
+        assert!(KeyPermission::is_sign_allowed(&permissions, 3, 11));
+        assert!(!KeyPermission::is_sign_allowed(&permissions, 4, 10));
+    }
+}
@@ -15,6 +15,7 @@
  */
 
 use crate::util::error::Result;
+use crate::util::digest::ContentDigest;
 use std::collections::HashMap;
 use chrono::{DateTime, Utc};
 use crate::domain::datakey::entity::{DataKey, DataKeyContent, KeyType, RevokedKey, SecDataKey};
@@ -35,5 +36,7 @@ pub trait SignPlugins: Send + Sync {
             Self: Sized;
     fn generate_keys(&self, key_type: &KeyType, infra_configs: &HashMap<String, String>) -> Result<DataKeyContent>;
     fn sign(&self, content: Vec<u8>, options: HashMap<String, String>) -> Result<Vec<u8>>;
+    //sign with the digest which is computed by client, the result is identical to the one signed with whole content
+    fn sign_prehashed(&self, digest: ContentDigest, options: HashMap<String, String>) -> Result<Vec<u8>>;
     fn generate_crl_content(&self, revoked_keys: Vec<RevokedKey>, last_update: DateTime<Utc>, next_update: DateTime<Utc>) -> Result<Vec<u8>>;
 }
@@ -19,6 +19,7 @@ use std::collections::HashMap;
 use std::str::FromStr;
 
 use crate::domain::datakey::entity::{DataKey, RevokedKey};
+use crate::util::digest::ContentDigest;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 
@@ -44,6 +45,7 @@ pub trait SignBackend: Send + Sync{
     async fn generate_keys(&self, data_key: &mut DataKey) -> Result<()>;
     async fn rotate_key(&mut self) -> Result<bool>;
     async fn sign(&self, data_key: &DataKey, content: Vec<u8>, options: HashMap<String, String>) -> Result<Vec<u8>>;
+    async fn sign_prehashed(&self, data_key: &DataKey, digest: ContentDigest, options: HashMap<String, String>) -> Result<Vec<u8>>;
     async fn decode_public_keys(&self, data_key: &mut DataKey) -> Result<()>;
     async fn generate_crl_content(&self, data_key: &DataKey, revoked_keys: Vec<RevokedKey>, last_update: DateTime<Utc>, next_update: DateTime<Utc>) -> Result<Vec<u8>>;
 }
@@ -0,0 +1,108 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use super::merkle::leaf_hash;
+use crate::util::key::get_content_digest;
+
+use chrono::{DateTime, Utc};
+use std::fmt::{Display, Formatter};
+
+const LEAF_VERSION: &str = "signatrust-log-v1";
+const TREE_HEAD_VERSION: &str = "signatrust-tree-head-v1";
+
+#[derive(Debug, Clone)]
+pub struct LogLeaf {
+    pub leaf_index: u64,
+    pub key_id: i32,
+    pub key_name: String,
+    pub content_digest: String,
+    pub signature_digest: String,
+    pub leaf_hash: Vec<u8>,
+    pub create_at: DateTime<Utc>,
+}
+
+impl Display for LogLeaf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "leaf_index: {}, key_name: {}, content_digest: {}, signature_digest: {}",
+            self.leaf_index, self.key_name, self.content_digest, self.signature_digest
+        )
+    }
+}
+
+impl LogLeaf {
+    pub fn new(key_id: i32, key_name: String, content_digest: String, signature: &[u8]) -> Self {
+        let signature_digest = get_content_digest(signature);
+        LogLeaf {
+            leaf_index: 0,
+            key_id,
+            leaf_hash: leaf_hash(&LogLeaf::leaf_data(&key_name, &content_digest, &signature_digest)),
+            key_name,
+            content_digest,
+            signature_digest,
+            create_at: Utc::now(),
+        }
+    }
+
+    /// The content of leaf which is hashed into the tree, verifiers are able to rebuild it
+    /// from the artifact and its signature.
+    pub fn leaf_data(key_name: &str, content_digest: &str, signature_digest: &str) -> Vec<u8> {
+        format!("{}\n{}\n{}\n{}", LEAF_VERSION, key_name, content_digest, signature_digest).into_bytes()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SignedTreeHead {
+    pub tree_size: u64,
+    pub root_hash: Vec<u8>,
+    pub timestamp: i64,
+    pub key_name: String,
+    pub signature: Vec<u8>,
+}
+
+impl SignedTreeHead {
+    /// The content signed by the log key.
+    pub fn payload(tree_size: u64, root_hash: &[u8], timestamp: i64) -> Vec<u8> {
+        format!("{}\n{}\n{}\n{}", TREE_HEAD_VERSION, tree_size, hex::encode(root_hash), timestamp).into_bytes()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub leaf: LogLeaf,
+    pub inclusion_proof: Vec<Vec<u8>>,
+    pub tree_head: SignedTreeHead,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::transparency::merkle::verify_inclusion;
+
+    #[test]
+    fn test_log_leaf_hash() {
+        let leaf = LogLeaf::new(1, "fake_key".to_string(), get_content_digest(b"content"), b"signature");
+        assert_eq!(leaf.signature_digest, get_content_digest(b"signature"));
+        let data = LogLeaf::leaf_data("fake_key", &leaf.content_digest, &leaf.signature_digest);
+        assert_eq!(leaf.leaf_hash, leaf_hash(&data));
+        assert!(verify_inclusion(0, 1, &leaf.leaf_hash, &[], &leaf_hash(&data)));
+        assert_eq!(
+            SignedTreeHead::payload(1, &[0xab, 0xcd], 100),
+            b"signatrust-tree-head-v1\n1\nabcd\n100".to_vec()
+        );
+    }
+}
@@ -0,0 +1,278 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+//! Merkle tree hashing follows RFC 6962, the tree is persisted as the complete subtrees which are
+//! identified by (level, index) and covers the leaves in range [index * 2^level, (index + 1) * 2^level).
+use crate::util::error::{Error, Result};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+pub type NodeId = (u32, u64);
+
+pub fn leaf_hash(data: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::default();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(data);
+    hasher.finalize().to_vec()
+}
+
+pub fn node_hash(left: &[u8], right: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::default();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().to_vec()
+}
+
+//the largest power of two smaller than n, n should be greater than 1
+fn split_point(n: u64) -> u64 {
+    let mut k = 1;
+    while k << 1 < n {
+        k <<= 1;
+    }
+    k
+}
+
+/// Nodes which will be completed when leaf with `index` is appended, in bottom-up order, each item
+/// contains the node id and the id of its left child, the right child is always the previous node.
+pub fn completed_nodes(index: u64) -> Vec<(NodeId, NodeId)> {
+    //the subtree of level l is completed when the lowest l bits of index are all ones
+    (1..=index.trailing_ones()).map(|level| {
+        let node_index = index >> level;
+        ((level, node_index), (level - 1, node_index * 2))
+    }).collect()
+}
+
+/// Split leaves in range [start, end) into complete subtrees from left to right.
+pub fn complete_subtrees(start: u64, end: u64) -> Vec<NodeId> {
+    let mut nodes = vec![];
+    let mut current = start;
+    while current < end {
+        let mut level = if current == 0 { 63 } else { current.trailing_zeros() };
+        while (1u64 << level) > end - current {
+            level -= 1;
+        }
+        nodes.push((level, current >> level));
+        current += 1 << level;
+    }
+    nodes
+}
+
+/// Leaf ranges whose tree hashes make up the audit path of leaf `index` in tree of `size`.
+pub fn inclusion_proof_ranges(index: u64, size: u64) -> Vec<(u64, u64)> {
+    fn path(index: u64, start: u64, end: u64, ranges: &mut Vec<(u64, u64)>) {
+        if end - start <= 1 {
+            return;
+        }
+        let k = split_point(end - start);
+        if index < k {
+            path(index, start, start + k, ranges);
+            ranges.push((start + k, end));
+        } else {
+            path(index - k, start + k, end, ranges);
+            ranges.push((start, start + k));
+        }
+    }
+    let mut ranges = vec![];
+    path(index, 0, size, &mut ranges);
+    ranges
+}
+
+/// Leaf ranges whose tree hashes make up the consistency proof between tree of `first` and `second`, see RFC 6962 section 2.1.2.
+pub fn consistency_proof_ranges(first: u64, second: u64) -> Vec<(u64, u64)> {
+    fn subproof(m: u64, start: u64, end: u64, complete: bool, ranges: &mut Vec<(u64, u64)>) {
+        let n = end - start;
+        if m == n {
+            if !complete {
+                ranges.push((start, end));
+            }
+            return;
+        }
+        let k = split_point(n);
+        if m <= k {
+            subproof(m, start, start + k, complete, ranges);
+            ranges.push((start + k, end));
+        } else {
+            subproof(m - k, start + k, end, false, ranges);
+            ranges.push((start, start + k));
+        }
+    }
+    let mut ranges = vec![];
+    if 0 < first && first < second {
+        subproof(first, 0, second, true, &mut ranges);
+    }
+    ranges
+}
+
+/// Compute the tree hash of leaves in range [start, end) with the hashes of complete subtrees.
+pub fn range_hash(start: u64, end: u64, nodes: &HashMap<NodeId, Vec<u8>>) -> Result<Vec<u8>> {
+    let mut result: Option<Vec<u8>> = None;
+    for id in complete_subtrees(start, end).iter().rev() {
+        let hash = nodes.get(id).ok_or(Error::NotFoundError)?;
+        result = match result {
+            None => Some(hash.clone()),
+            Some(right) => Some(node_hash(hash, &right)),
+        }
+    }
+    result.ok_or_else(|| Error::ParameterError("empty range of merkle tree".to_string()))
+}
+
+/// Verify the audit path of leaf against the root hash, see RFC 9162 section 2.1.3.2.
+pub fn verify_inclusion(index: u64, size: u64, leaf: &[u8], proof: &[Vec<u8>], root: &[u8]) -> bool {
+    if index >= size {
+        return false;
+    }
+    let (mut fn_, mut sn) = (index, size - 1);
+    let mut result = leaf.to_vec();
+    for p in proof {
+        if sn == 0 {
+            return false;
+        }
+        if fn_ & 1 == 1 || fn_ == sn {
+            result = node_hash(p, &result);
+            while fn_ & 1 == 0 && fn_ != 0 {
+                fn_ >>= 1;
+                sn >>= 1;
+            }
+        } else {
+            result = node_hash(&result, p);
+        }
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+    sn == 0 && result == root
+}
+
+/// Verify the consistency proof between two tree heads, see RFC 9162 section 2.1.4.2.
+pub fn verify_consistency(first: u64, second: u64, first_root: &[u8], second_root: &[u8], proof: &[Vec<u8>]) -> bool {
+    if first == 0 || first > second {
+        return false;
+    }
+    if first == second {
+        return proof.is_empty() && first_root == second_root;
+    }
+    //the first tree is a complete subtree of the second one and its root is omitted from the proof
+    let mut path = proof.to_vec();
+    if first.is_power_of_two() {
+        path.insert(0, first_root.to_vec());
+    }
+    if path.is_empty() {
+        return false;
+    }
+    let (mut fn_, mut sn) = (first - 1, second - 1);
+    while fn_ & 1 == 1 {
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+    let (mut fr, mut sr) = (path[0].clone(), path[0].clone());
+    for c in path.iter().skip(1) {
+        if sn == 0 {
+            return false;
+        }
+        if fn_ & 1 == 1 || fn_ == sn {
+            fr = node_hash(c, &fr);
+            sr = node_hash(c, &sr);
+            while fn_ & 1 == 0 && fn_ != 0 {
+                fn_ >>= 1;
+                sn >>= 1;
+            }
+        } else {
+            sr = node_hash(&sr, c);
+        }
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+    sn == 0 && fr == first_root && sr == second_root
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tree_hash(leaves: &[Vec<u8>]) -> Vec<u8> {
+        if leaves.len() == 1 {
+            return leaves[0].clone();
+        }
+        let k = split_point(leaves.len() as u64) as usize;
+        node_hash(&tree_hash(&leaves[..k]), &tree_hash(&leaves[k..]))
+    }
+
+    fn append(nodes: &mut HashMap<NodeId, Vec<u8>>, index: u64, data: &[u8]) -> Vec<u8> {
+        let mut current = leaf_hash(data);
+        nodes.insert((0, index), current.clone());
+        for (id, left) in completed_nodes(index) {
+            current = node_hash(nodes.get(&left).unwrap(), &current);
+            nodes.insert(id, current.clone());
+        }
+        leaf_hash(data)
+    }
+
+    #[test]
+    fn test_complete_subtrees() {
+        assert_eq!(complete_subtrees(0, 1), vec![(0, 0)]);
+        assert_eq!(complete_subtrees(0, 4), vec![(2, 0)]);
+        assert_eq!(complete_subtrees(0, 7), vec![(2, 0), (1, 2), (0, 6)]);
+        assert_eq!(complete_subtrees(4, 7), vec![(1, 2), (0, 6)]);
+        assert_eq!(completed_nodes(0), vec![]);
+        assert_eq!(completed_nodes(3), vec![((1, 1), (0, 2)), ((2, 0), (1, 0))]);
+    }
+
+    #[test]
+    fn test_inclusion_proof_with_persisted_nodes() {
+        let mut nodes = HashMap::new();
+        let mut leaves = vec![];
+        for size in 1..=33u64 {
+            leaves.push(append(&mut nodes, size - 1, format!("leaf {}", size).as_bytes()));
+            let root = range_hash(0, size, &nodes).expect("root hash should be computed");
+            assert_eq!(root, tree_hash(&leaves));
+            for index in 0..size {
+                let proof: Vec<Vec<u8>> = inclusion_proof_ranges(index, size).into_iter().map(
+                    |(start, end)| range_hash(start, end, &nodes).unwrap()).collect();
+                assert!(verify_inclusion(index, size, &leaves[index as usize], &proof, &root));
+                assert!(!verify_inclusion(index, size, &leaf_hash("fake".as_bytes()), &proof, &root));
+            }
+        }
+    }
+
+    #[test]
+    fn test_consistency_proof_with_persisted_nodes() {
+        let mut nodes = HashMap::new();
+        let mut roots = vec![];
+        for size in 1..=33u64 {
+            append(&mut nodes, size - 1, format!("leaf {}", size).as_bytes());
+            roots.push(range_hash(0, size, &nodes).expect("root hash should be computed"));
+        }
+        //proof of RFC 6962 section 2.1.3 example between tree of size 3 and 7
+        assert_eq!(consistency_proof_ranges(3, 7), vec![(2, 3), (3, 4), (0, 2), (4, 7)]);
+        assert_eq!(consistency_proof_ranges(4, 7), vec![(4, 7)]);
+        for second in 1..=33u64 {
+            for first in 1..=second {
+                let proof: Vec<Vec<u8>> = consistency_proof_ranges(first, second).into_iter().map(
+                    |(start, end)| range_hash(start, end, &nodes).unwrap()).collect();
+                let (first_root, second_root) = (&roots[first as usize - 1], &roots[second as usize - 1]);
+                assert!(verify_consistency(first, second, first_root, second_root, &proof), "{} {}", first, second);
+                if first != second {
+                    assert!(!verify_consistency(first, second, &leaf_hash("fake".as_bytes()), second_root, &proof));
+                    assert!(!verify_consistency(first, second, first_root, &leaf_hash("fake".as_bytes()), &proof));
+                }
+            }
+        }
+        assert!(!verify_consistency(0, 1, &roots[0], &roots[0], &[]));
+    }
+}
@@ -0,0 +1,3 @@
+pub mod entity;
+pub mod merkle;
+pub mod repository;
\ No newline at end of file
@@ -0,0 +1,32 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use super::entity::{LogLeaf, SignedTreeHead};
+use super::merkle::NodeId;
+use crate::util::error::Result;
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+#[async_trait]
+pub trait Repository: Send + Sync {
+    /// Append the leaf at the end of log and persist the completed subtrees, returns the leaf with index.
+    async fn append(&self, leaf: LogLeaf) -> Result<LogLeaf>;
+    async fn get_nodes(&self, ids: &[NodeId]) -> Result<HashMap<NodeId, Vec<u8>>>;
+    async fn get_leaf(&self, leaf_index: u64) -> Result<LogLeaf>;
+    async fn create_tree_head(&self, tree_head: &SignedTreeHead) -> Result<()>;
+    /// Get the signed tree head of specified size, the latest one is returned if size is absent.
+    async fn get_tree_head(&self, tree_size: Option<u64>) -> Result<SignedTreeHead>;
+}
@@ -0,0 +1,74 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+#![allow(dead_code)]
+use std::env;
+use crate::util::error::Result;
+use crate::client::cmd::gpg;
+use config::{Config, File};
+use std::sync::{Arc, atomic::AtomicBool, RwLock};
+use crate::client::cmd::traits::SignCommand;
+
+mod infra;
+mod util;
+mod client;
+mod domain;
+
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate lazy_static;
+
+//the shim is invoked by tools like git and rpmsign with gpg arguments, hence the configuration is specified by environment
+const CONFIG_ENV: &str = "SIGNATRUST_CLIENT_CONFIG";
+
+fn main() -> Result<()> {
+    //prepare config and logger, logs are written into stderr and won't mix with signature in stdout
+    env_logger::init();
+    //gpg exits with status 2 on errors, messages are printed on stderr since the logger may be disabled
+    let path = match env::var(CONFIG_ENV) {
+        Ok(path) => path,
+        Err(_) => match env::current_dir() {
+            Ok(dir) => format!("{}/{}", dir.display(), "client.toml"),
+            Err(err) => {
+                eprintln!("gpg-shim: current dir not found: {}", err);
+                std::process::exit(2);
+            }
+        },
+    };
+    let client = match Config::builder().add_source(File::with_name(path.as_str())).build() {
+        Ok(client) => client,
+        Err(err) => {
+            eprintln!("gpg-shim: failed to load client configuration file {}, set it with {}: {}", path, CONFIG_ENV, err);
+            std::process::exit(2);
+        }
+    };
+    let arguments = gpg::GpgArguments::parse(&env::args().skip(1).collect::<Vec<String>>())?;
+    let handler = gpg::CommandGpgHandler::new(Arc::new(AtomicBool::new(false)), Arc::new(RwLock::new(client)), arguments)?;
+    if let Err(err) = handler.validate() {
+        error!("failed to validate command: {}", err);
+        return Err(err);
+    }
+    match handler.handle() {
+        Ok(true) => Ok(()),
+        //gpg exits with status 1 when signature is bad
+        Ok(false) => std::process::exit(1),
+        Err(err) => {
+            error!("failed to handle command: {}", err);
+            Err(err)
+        }
+    }
+}
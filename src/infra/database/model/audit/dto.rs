@@ -0,0 +1,90 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use std::str::FromStr;
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+
+use crate::domain::audit::entity::{DigestSource, SignAudit, SignAuditResult};
+use crate::util::error::Error;
+
+//keep in accordance with the column length of sign_audit table
+const MAX_ERROR_LENGTH: usize = 1000;
+
+#[derive(Debug, FromRow)]
+pub(super) struct SignAuditDTO {
+    pub id: i64,
+    pub key_id: Option<i32>,
+    pub key_name: String,
+    pub key_type: String,
+    pub user_id: i32,
+    #[sqlx(default)]
+    pub user_email: Option<String>,
+    pub token_id: i32,
+    pub client_address: String,
+    pub sign_options: String,
+    pub content_digest: String,
+    pub digest_source: String,
+    pub result: String,
+    pub error: Option<String>,
+    pub create_at: DateTime<Utc>,
+}
+
+impl TryFrom<SignAudit> for SignAuditDTO {
+    type Error = Error;
+
+    fn try_from(audit: SignAudit) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: audit.id,
+            key_id: audit.key_id,
+            key_name: audit.key_name.clone(),
+            key_type: audit.key_type.clone(),
+            user_id: audit.user_id,
+            user_email: audit.user_email.clone(),
+            token_id: audit.token_id,
+            client_address: audit.client_address.clone(),
+            sign_options: serde_json::to_string(&audit.sign_options)?,
+            content_digest: audit.content_digest.clone(),
+            digest_source: audit.digest_source.to_string(),
+            result: audit.result.to_string(),
+            error: audit.error.map(|e| e.chars().take(MAX_ERROR_LENGTH).collect()),
+            create_at: audit.create_at,
+        })
+    }
+}
+
+impl TryFrom<SignAuditDTO> for SignAudit {
+    type Error = Error;
+
+    fn try_from(dto: SignAuditDTO) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: dto.id,
+            key_id: dto.key_id,
+            key_name: dto.key_name.clone(),
+            key_type: dto.key_type.clone(),
+            user_id: dto.user_id,
+            user_email: dto.user_email.clone(),
+            token_id: dto.token_id,
+            client_address: dto.client_address.clone(),
+            sign_options: serde_json::from_str(&dto.sign_options)?,
+            content_digest: dto.content_digest.clone(),
+            digest_source: DigestSource::from_str(&dto.digest_source)?,
+            result: SignAuditResult::from_str(&dto.result)?,
+            error: dto.error.clone(),
+            create_at: dto.create_at,
+        })
+    }
+}
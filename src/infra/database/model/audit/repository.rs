@@ -0,0 +1,103 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use crate::infra::database::pool::DbPool;
+use crate::domain::audit::entity::{PagedSignAudit, SignAudit, SignAuditFilter};
+use crate::domain::audit::repository::Repository;
+use crate::util::error::Result;
+use async_trait::async_trait;
+use std::boxed::Box;
+
+use crate::infra::database::model::audit::dto::SignAuditDTO;
+
+const FILTER_CONDITION: &str = "WHERE (? IS NULL OR A.key_name = ?) \
+            AND (? IS NULL OR U.email = ?) \
+            AND (? IS NULL OR A.result = ?) \
+            AND (? IS NULL OR A.create_at >= ?) \
+            AND (? IS NULL OR A.create_at <= ?) \
+            AND (? IS NULL OR A.user_id = ? OR A.key_id IN (SELECT D.id FROM data_key D WHERE D.user = ?))";
+
+#[derive(Clone)]
+pub struct SignAuditRepository {
+    db_pool: DbPool,
+}
+
+impl SignAuditRepository {
+    pub fn new(db_pool: DbPool) -> Self {
+        Self {
+            db_pool,
+        }
+    }
+}
+
+#[async_trait]
+impl Repository for SignAuditRepository {
+
+    async fn create(&self, audit: SignAudit) -> Result<()> {
+        let dto = SignAuditDTO::try_from(audit)?;
+        let _ = sqlx::query("INSERT INTO sign_audit(key_id, key_name, key_type, user_id, token_id, client_address, sign_options, content_digest, digest_source, result, error, create_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
+            .bind(dto.key_id)
+            .bind(&dto.key_name)
+            .bind(&dto.key_type)
+            .bind(dto.user_id)
+            .bind(dto.token_id)
+            .bind(&dto.client_address)
+            .bind(&dto.sign_options)
+            .bind(&dto.content_digest)
+            .bind(&dto.digest_source)
+            .bind(&dto.result)
+            .bind(&dto.error)
+            .bind(dto.create_at)
+            .execute(&self.db_pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_by_filter(&self, filter: &SignAuditFilter, page: u32, page_size: u32) -> Result<PagedSignAudit> {
+        let result = filter.result.as_ref().map(|r| r.to_string());
+        let total: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM sign_audit A LEFT JOIN user U ON A.user_id = U.id {}", FILTER_CONDITION))
+            .bind(&filter.key_name).bind(&filter.key_name)
+            .bind(&filter.user_email).bind(&filter.user_email)
+            .bind(&result).bind(&result)
+            .bind(filter.start_time).bind(filter.start_time)
+            .bind(filter.end_time).bind(filter.end_time)
+            .bind(filter.visible_to).bind(filter.visible_to).bind(filter.visible_to)
+            .fetch_one(&self.db_pool)
+            .await?;
+        let dtos: Vec<SignAuditDTO> = sqlx::query_as(&format!(
+            "SELECT A.*, U.email AS user_email FROM sign_audit A LEFT JOIN user U ON A.user_id = U.id {} \
+            ORDER BY A.id DESC LIMIT ? OFFSET ?", FILTER_CONDITION))
+            .bind(&filter.key_name).bind(&filter.key_name)
+            .bind(&filter.user_email).bind(&filter.user_email)
+            .bind(&result).bind(&result)
+            .bind(filter.start_time).bind(filter.start_time)
+            .bind(filter.end_time).bind(filter.end_time)
+            .bind(filter.visible_to).bind(filter.visible_to).bind(filter.visible_to)
+            .bind(page_size)
+            .bind((page - 1) * page_size)
+            .fetch_all(&self.db_pool)
+            .await?;
+        let mut audits = vec![];
+        for dto in dtos.into_iter() {
+            audits.push(SignAudit::try_from(dto)?);
+        }
+        Ok(PagedSignAudit {
+            total,
+            audits,
+        })
+    }
+}
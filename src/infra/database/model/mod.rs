@@ -2,4 +2,7 @@ pub mod clusterkey;
 pub mod datakey;
 pub mod user;
 pub mod token;
-pub mod request_delete;
\ No newline at end of file
+pub mod request_delete;
+pub mod permission;
+pub mod audit;
+pub mod transparency;
\ No newline at end of file
@@ -0,0 +1,56 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+
+use crate::domain::permission::entity::KeyPermission;
+
+#[derive(Debug, FromRow)]
+pub(super) struct KeyPermissionDTO {
+    pub id: i32,
+    pub key_id: i32,
+    pub user_id: i32,
+    pub user_email: String,
+    pub token_id: Option<i32>,
+    pub create_at: DateTime<Utc>,
+}
+
+impl From<KeyPermission> for KeyPermissionDTO {
+    fn from(permission: KeyPermission) -> Self {
+        Self {
+            id: permission.id,
+            key_id: permission.key_id,
+            user_id: permission.user_id,
+            user_email: permission.user_email.clone(),
+            token_id: permission.token_id,
+            create_at: permission.create_at,
+        }
+    }
+}
+
+impl From<KeyPermissionDTO> for KeyPermission {
+    fn from(dto: KeyPermissionDTO) -> Self {
+        Self {
+            id: dto.id,
+            key_id: dto.key_id,
+            user_id: dto.user_id,
+            user_email: dto.user_email.clone(),
+            token_id: dto.token_id,
+            create_at: dto.create_at,
+        }
+    }
+}
@@ -0,0 +1,84 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use crate::infra::database::pool::DbPool;
+use crate::domain::permission::entity::KeyPermission;
+use crate::domain::permission::repository::Repository;
+use crate::util::error::Result;
+use async_trait::async_trait;
+use std::boxed::Box;
+
+use crate::infra::database::model::permission::dto::KeyPermissionDTO;
+
+
+#[derive(Clone)]
+pub struct KeyPermissionRepository {
+    db_pool: DbPool,
+}
+
+impl KeyPermissionRepository {
+    pub fn new(db_pool: DbPool) -> Self {
+        Self {
+            db_pool,
+        }
+    }
+}
+
+#[async_trait]
+impl Repository for KeyPermissionRepository {
+
+    async fn create(&self, permission: KeyPermission) -> Result<KeyPermission> {
+        let dto = KeyPermissionDTO::from(permission);
+        let record : u64 = sqlx::query("INSERT INTO datakey_permission(key_id, user_id, user_email, token_id, create_at) VALUES (?, ?, ?, ?, ?)")
+            .bind(dto.key_id)
+            .bind(dto.user_id)
+            .bind(&dto.user_email)
+            .bind(dto.token_id)
+            .bind(dto.create_at)
+            .execute(&self.db_pool)
+            .await?.last_insert_id();
+        self.get_by_id(record as i32).await
+    }
+
+    async fn get_by_id(&self, id: i32) -> Result<KeyPermission> {
+        let selected: KeyPermissionDTO = sqlx::query_as("SELECT * FROM datakey_permission WHERE id = ?")
+            .bind(id)
+            .fetch_one(&self.db_pool)
+            .await?;
+        Ok(KeyPermission::from(selected))
+    }
+
+    async fn get_by_key_id(&self, key_id: i32) -> Result<Vec<KeyPermission>> {
+        let dtos: Vec<KeyPermissionDTO> = sqlx::query_as("SELECT * FROM datakey_permission WHERE key_id = ?")
+            .bind(key_id)
+            .fetch_all(&self.db_pool)
+            .await?;
+        let mut results = vec![];
+        for dto in dtos.into_iter() {
+            results.push(KeyPermission::from(dto));
+        }
+        Ok(results)
+    }
+
+    async fn delete_by_key_and_id(&self, key_id: i32, id: i32) -> Result<()> {
+        let _: Option<KeyPermissionDTO> = sqlx::query_as("DELETE FROM datakey_permission WHERE id = ? AND key_id = ?")
+            .bind(id)
+            .bind(key_id)
+            .fetch_optional(&self.db_pool)
+            .await?;
+        Ok(())
+    }
+}
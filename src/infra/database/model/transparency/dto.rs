@@ -0,0 +1,104 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+
+use crate::domain::transparency::entity::{LogLeaf, SignedTreeHead};
+use crate::util::error::Error;
+
+#[derive(Debug, FromRow)]
+pub(super) struct LogLeafDTO {
+    pub leaf_index: i64,
+    pub key_id: i32,
+    pub key_name: String,
+    pub content_digest: String,
+    pub signature_digest: String,
+    pub leaf_hash: String,
+    pub create_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+pub(super) struct LogNodeDTO {
+    pub node_level: i32,
+    pub node_index: i64,
+    pub hash: String,
+}
+
+#[derive(Debug, FromRow)]
+pub(super) struct TreeHeadDTO {
+    pub tree_size: i64,
+    pub root_hash: String,
+    pub signed_at: i64,
+    pub key_name: String,
+    pub signature: String,
+}
+
+impl From<&SignedTreeHead> for TreeHeadDTO {
+    fn from(head: &SignedTreeHead) -> Self {
+        Self {
+            tree_size: head.tree_size as i64,
+            root_hash: hex::encode(&head.root_hash),
+            signed_at: head.timestamp,
+            key_name: head.key_name.clone(),
+            signature: hex::encode(&head.signature),
+        }
+    }
+}
+
+impl TryFrom<TreeHeadDTO> for SignedTreeHead {
+    type Error = Error;
+
+    fn try_from(dto: TreeHeadDTO) -> Result<Self, Self::Error> {
+        Ok(Self {
+            tree_size: dto.tree_size as u64,
+            root_hash: hex::decode(dto.root_hash)?,
+            timestamp: dto.signed_at,
+            key_name: dto.key_name,
+            signature: hex::decode(dto.signature)?,
+        })
+    }
+}
+
+impl From<LogLeaf> for LogLeafDTO {
+    fn from(leaf: LogLeaf) -> Self {
+        Self {
+            leaf_index: leaf.leaf_index as i64,
+            key_id: leaf.key_id,
+            key_name: leaf.key_name,
+            content_digest: leaf.content_digest,
+            signature_digest: leaf.signature_digest,
+            leaf_hash: hex::encode(leaf.leaf_hash),
+            create_at: leaf.create_at,
+        }
+    }
+}
+
+impl TryFrom<LogLeafDTO> for LogLeaf {
+    type Error = Error;
+
+    fn try_from(dto: LogLeafDTO) -> Result<Self, Self::Error> {
+        Ok(Self {
+            leaf_index: dto.leaf_index as u64,
+            key_id: dto.key_id,
+            key_name: dto.key_name,
+            content_digest: dto.content_digest,
+            signature_digest: dto.signature_digest,
+            leaf_hash: hex::decode(dto.leaf_hash)?,
+            create_at: dto.create_at,
+        })
+    }
+}
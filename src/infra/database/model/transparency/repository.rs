@@ -0,0 +1,158 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use crate::infra::database::pool::DbPool;
+use crate::domain::transparency::entity::{LogLeaf, SignedTreeHead};
+use crate::domain::transparency::merkle::{completed_nodes, node_hash, NodeId};
+use crate::domain::transparency::repository::Repository;
+use crate::util::error::{Error, Result};
+use async_trait::async_trait;
+use sqlx::{MySql, Transaction};
+use std::boxed::Box;
+use std::collections::HashMap;
+
+use crate::infra::database::model::transparency::dto::{LogLeafDTO, LogNodeDTO, TreeHeadDTO};
+
+//the log only has one head row which is locked when appending to serialize the writers
+const LOG_HEAD_ID: i32 = 1;
+
+#[derive(Clone)]
+pub struct TransparencyLogRepository {
+    db_pool: DbPool,
+}
+
+impl TransparencyLogRepository {
+    pub fn new(db_pool: DbPool) -> Self {
+        Self {
+            db_pool,
+        }
+    }
+
+    async fn create_node(&self, id: NodeId, hash: &[u8], tx: &mut Transaction<'_, MySql>) -> Result<()> {
+        let _ = sqlx::query("INSERT INTO transparency_log_node(node_level, node_index, hash) VALUES (?, ?, ?)")
+            .bind(id.0)
+            .bind(id.1 as i64)
+            .bind(hex::encode(hash))
+            .execute(tx)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_node(&self, id: NodeId, tx: &mut Transaction<'_, MySql>) -> Result<Vec<u8>> {
+        let dto: LogNodeDTO = sqlx::query_as("SELECT * FROM transparency_log_node WHERE node_level = ? AND node_index = ?")
+            .bind(id.0)
+            .bind(id.1 as i64)
+            .fetch_one(tx)
+            .await?;
+        Ok(hex::decode(dto.hash)?)
+    }
+}
+
+#[async_trait]
+impl Repository for TransparencyLogRepository {
+
+    async fn append(&self, leaf: LogLeaf) -> Result<LogLeaf> {
+        let mut tx = self.db_pool.begin().await?;
+        //1. lock the log head and take the next leaf index
+        let tree_size: i64 = sqlx::query_scalar("SELECT tree_size FROM transparency_log_head WHERE id = ? FOR UPDATE")
+            .bind(LOG_HEAD_ID)
+            .fetch_one(&mut tx)
+            .await?;
+        let mut dto = LogLeafDTO::from(leaf);
+        dto.leaf_index = tree_size;
+        let _ = sqlx::query("INSERT INTO transparency_log_leaf(leaf_index, key_id, key_name, content_digest, signature_digest, leaf_hash, create_at) VALUES (?, ?, ?, ?, ?, ?, ?)")
+            .bind(dto.leaf_index)
+            .bind(dto.key_id)
+            .bind(&dto.key_name)
+            .bind(&dto.content_digest)
+            .bind(&dto.signature_digest)
+            .bind(&dto.leaf_hash)
+            .bind(dto.create_at)
+            .execute(&mut tx)
+            .await?;
+        //2. persist the leaf node and all of the subtrees completed by it
+        let mut current = hex::decode(&dto.leaf_hash)?;
+        self.create_node((0, tree_size as u64), &current, &mut tx).await?;
+        for (id, left) in completed_nodes(tree_size as u64) {
+            current = node_hash(&self.get_node(left, &mut tx).await?, &current);
+            self.create_node(id, &current, &mut tx).await?;
+        }
+        //3. move the log head forward
+        let _ = sqlx::query("UPDATE transparency_log_head SET tree_size = ?, update_at = ? WHERE id = ?")
+            .bind(tree_size + 1)
+            .bind(dto.create_at)
+            .bind(LOG_HEAD_ID)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        LogLeaf::try_from(dto)
+    }
+
+    async fn get_nodes(&self, ids: &[NodeId]) -> Result<HashMap<NodeId, Vec<u8>>> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let condition = vec!["(node_level = ? AND node_index = ?)"; ids.len()].join(" OR ");
+        let query = format!("SELECT * FROM transparency_log_node WHERE {}", condition);
+        let mut query = sqlx::query_as(&query);
+        for id in ids {
+            query = query.bind(id.0).bind(id.1 as i64);
+        }
+        let dtos: Vec<LogNodeDTO> = query.fetch_all(&self.db_pool).await?;
+        let mut nodes = HashMap::new();
+        for dto in dtos.into_iter() {
+            nodes.insert((dto.node_level as u32, dto.node_index as u64), hex::decode(dto.hash)?);
+        }
+        if nodes.len() != ids.len() {
+            return Err(Error::DatabaseError("transparency log nodes are missing".to_string()));
+        }
+        Ok(nodes)
+    }
+
+    async fn get_leaf(&self, leaf_index: u64) -> Result<LogLeaf> {
+        let dto: LogLeafDTO = sqlx::query_as("SELECT * FROM transparency_log_leaf WHERE leaf_index = ?")
+            .bind(leaf_index as i64)
+            .fetch_one(&self.db_pool)
+            .await?;
+        LogLeaf::try_from(dto)
+    }
+
+    async fn create_tree_head(&self, tree_head: &SignedTreeHead) -> Result<()> {
+        let dto = TreeHeadDTO::from(tree_head);
+        let _ = sqlx::query("INSERT INTO transparency_log_tree_head(tree_size, root_hash, signed_at, key_name, signature) VALUES (?, ?, ?, ?, ?)")
+            .bind(dto.tree_size)
+            .bind(&dto.root_hash)
+            .bind(dto.signed_at)
+            .bind(&dto.key_name)
+            .bind(&dto.signature)
+            .execute(&self.db_pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_tree_head(&self, tree_size: Option<u64>) -> Result<SignedTreeHead> {
+        let dto: TreeHeadDTO = match tree_size {
+            Some(tree_size) => sqlx::query_as("SELECT * FROM transparency_log_tree_head WHERE tree_size = ?")
+                .bind(tree_size as i64)
+                .fetch_one(&self.db_pool)
+                .await?,
+            None => sqlx::query_as("SELECT * FROM transparency_log_tree_head ORDER BY tree_size DESC LIMIT 1")
+                .fetch_one(&self.db_pool)
+                .await?,
+        };
+        SignedTreeHead::try_from(dto)
+    }
+}
@@ -32,6 +32,7 @@ use crate::domain::datakey::entity::{INFRA_CONFIG_DOMAIN_NAME, RevokedKey, SecDa
 use crate::infra::sign_plugin::signers::Signers;
 use crate::domain::datakey::entity::DataKey;
 use crate::util::error::{Error, Result};
+use crate::util::digest::ContentDigest;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use crate::infra::encryption::algorithm::factory::AlgorithmFactory;
@@ -117,6 +118,11 @@ impl SignBackend for MemorySignBackend {
         Signers::load_from_data_key(&data_key.key_type, sec_key)?.sign(content, options)
     }
 
+    async fn sign_prehashed(&self, data_key: &DataKey, digest: ContentDigest, options: HashMap<String, String>) -> Result<Vec<u8>> {
+        let sec_key = SecDataKey::load(data_key, &self.engine).await?;
+        Signers::load_from_data_key(&data_key.key_type, sec_key)?.sign_prehashed(digest, options)
+    }
+
     async fn decode_public_keys(&self, data_key: &mut DataKey) -> Result<()> {
         data_key.public_key = self.engine.decode(data_key.public_key.clone()).await?;
         data_key.certificate = self.engine.decode(data_key.certificate.clone()).await?;
@@ -18,14 +18,16 @@ use crate::domain::sign_plugin::SignPlugins;
 
 use crate::util::error::{Error, Result};
 use crate::util::options;
+use crate::util::sign::SignType;
+use crate::util::payload::{self, RawAlgorithm};
 use chrono::{DateTime, Utc};
 use pgp::composed::signed_key::{SignedSecretKey, SignedPublicKey};
 use pgp::composed::{key::SecretKeyParamsBuilder, KeyType};
-use pgp::crypto::{hash::HashAlgorithm, sym::SymmetricKeyAlgorithm};
+use pgp::crypto::{hash::{HashAlgorithm, Hasher}, sym::SymmetricKeyAlgorithm};
 use pgp::packet::SignatureConfig;
 use pgp::packet::*;
 
-use pgp::types::KeyTrait;
+use pgp::types::{KeyTrait, PublicParams};
 use pgp::types::{CompressionAlgorithm, SecretKeyTrait};
 use pgp::Deserializable;
 use serde::Deserialize;
@@ -33,10 +35,12 @@ use smallvec::*;
 use std::collections::HashMap;
 use std::io::{Cursor};
 use std::str::from_utf8;
+use std::str::FromStr;
 use validator::{Validate, ValidationError};
 use pgp::composed::StandaloneSignature;
 use crate::domain::datakey::entity::{DataKey, DataKeyContent, SecDataKey, KeyType as EntityKeyType, RevokedKey};
 use crate::util::key::encode_u8_to_hex_string;
+use crate::util::digest::ContentDigest;
 use super::util::{validate_utc_time_not_expire, validate_utc_time, attributes_validate};
 
 // NOTE: `eddsa` will be supported only when it's supported in rpm library, check https://github.com/rpm-rs/rpm/pull/146
@@ -112,6 +116,22 @@ pub fn get_digest_algorithm(hash_digest: &str) -> Result<HashAlgorithm> {
     }
 }
 
+//hash armor header used in cleartext signature framework, check https://www.rfc-editor.org/rfc/rfc4880#section-7
+fn get_armor_hash_name(hash_digest: HashAlgorithm) -> Result<&'static str> {
+    match hash_digest {
+        HashAlgorithm::MD5 => Ok("MD5"),
+        HashAlgorithm::SHA1 => Ok("SHA1"),
+        HashAlgorithm::RIPEMD160 => Ok("RIPEMD160"),
+        HashAlgorithm::SHA2_256 => Ok("SHA256"),
+        HashAlgorithm::SHA2_384 => Ok("SHA384"),
+        HashAlgorithm::SHA2_512 => Ok("SHA512"),
+        HashAlgorithm::SHA2_224 => Ok("SHA224"),
+        HashAlgorithm::SHA3_256 => Ok("SHA3-256"),
+        HashAlgorithm::SHA3_512 => Ok("SHA3-512"),
+        _ => Err(Error::UnsupportedTypeError(format!("{:?} in cleartext signature", hash_digest))),
+    }
+}
+
 fn validate_key_type(key_type: &str) -> std::result::Result<(), ValidationError> {
     if !VALID_KEY_TYPE.contains(&key_type) {
         return Err(ValidationError::new("invalid key type, possible values are rsa/ecdh/eddsa"));
@@ -142,6 +162,115 @@ pub struct OpenPGPPlugin {
 }
 
 impl OpenPGPPlugin {
+    fn get_passphrase_fn(options: &HashMap<String, String>) -> impl FnOnce() -> String + '_ {
+        || match options.get(options::PASSPHRASE) {
+            None => {
+                String::new()
+            }
+            Some(password) => {
+                password.to_string()
+            }
+        }
+    }
+
+    fn get_signature_config(&self, digest: HashAlgorithm, typ: SignatureType) -> SignatureConfig {
+        let now = Utc::now();
+        let secret_key_id = self.secret_key.clone().unwrap().key_id();
+        SignatureConfig {
+            version: SignatureVersion::V4,
+            typ,
+            pub_alg: self.public_key.clone().unwrap().primary_key.algorithm(),
+            hash_alg: digest,
+            issuer: Some(secret_key_id.clone()),
+            created: Some(now),
+            unhashed_subpackets: vec![],
+            hashed_subpackets: vec![
+                Subpacket::SignatureCreationTime(now),
+                Subpacket::Issuer(secret_key_id),
+            ],
+        }
+    }
+
+    fn encode_signature(&self, signature_packet: Signature, options: &HashMap<String, String>) -> Result<Vec<u8>> {
+        //detached signature, it's armored unless binary format is required
+        if let Some(detached) = options.get(options::DETACHED) {
+            if detached == "true" && !OpenPGPPlugin::is_signature_format(options, options::SIGNATURE_FORMAT_BINARY) {
+                let standard_signature = StandaloneSignature::new(signature_packet);
+                return Ok(standard_signature.to_armored_bytes(None)?)
+            }
+        }
+        let mut signature_bytes = Vec::with_capacity(1024);
+        let mut cursor = Cursor::new(&mut signature_bytes);
+        write_packet(&mut cursor, &signature_packet)
+            .map_err(|e| Error::SignError(self.identity.clone(), e.to_string()))?;
+        Ok(signature_bytes)
+    }
+
+    fn is_cleartext(options: &HashMap<String, String>) -> bool {
+        OpenPGPPlugin::is_signature_format(options, options::SIGNATURE_FORMAT_CLEARTEXT)
+    }
+
+    fn is_signature_format(options: &HashMap<String, String>, format: &str) -> bool {
+        options.get(options::SIGNATURE_FORMAT).map(|value| value == format).unwrap_or(false)
+    }
+
+    fn get_sign_type(options: &HashMap<String, String>) -> Result<Option<SignType>> {
+        options.get(options::SIGN_TYPE).map(|value| SignType::from_str(value)).transpose()
+    }
+
+    //raw signatures whose payloads are built from the content, only rsa key is supported. raw signature of arbitrary
+    //content is rejected, otherwise openpgp key can be used to sign the crafted key binding or certification.
+    fn sign_embedded(&self, sign_type: SignType, content: &[u8], options: &HashMap<String, String>) -> Result<Option<Vec<u8>>> {
+        let sign = |data: &[u8]| self.sign_raw(&ContentDigest::from_content(data).digest, options);
+        match sign_type {
+            SignType::Raw => Err(Error::UnsupportedTypeError("raw signature with openpgp key".to_string())),
+            SignType::Jsf => Ok(Some(payload::sign_jsf(content, RawAlgorithm::Rsa, sign)?)),
+            SignType::XmlDsig => Ok(Some(payload::sign_xmldsig(content, RawAlgorithm::Rsa, sign)?)),
+            SignType::Cosign => Ok(Some(payload::sign_cosign(content, sign)?)),
+            _ => Ok(None),
+        }
+    }
+
+    //raw RSASSA-PKCS1-v1_5 signature of sha256 digest, which is padded to the length of modulus
+    fn sign_raw(&self, digest: &[u8], options: &HashMap<String, String>) -> Result<Vec<u8>> {
+        let secret_key = self.secret_key.clone().unwrap();
+        let modulus_length = match secret_key.primary_key.public_params() {
+            PublicParams::RSA { n, .. } => n.as_bytes().len(),
+            _ => return Err(Error::UnsupportedTypeError("raw signature with non rsa openpgp key".to_string())),
+        };
+        let mpis = secret_key.create_signature(OpenPGPPlugin::get_passphrase_fn(options), HashAlgorithm::SHA2_256, digest)
+            .map_err(|e| Error::SignError(self.identity.clone(), e.to_string()))?;
+        let signature = mpis.first().ok_or_else(
+            || Error::SignError(self.identity.clone(), "empty rsa signature".to_string()))?.as_bytes();
+        let mut raw_signature = vec![0; modulus_length.saturating_sub(signature.len())];
+        raw_signature.extend_from_slice(signature);
+        Ok(raw_signature)
+    }
+
+    /* cleartext signed message, the signature is calculated over the canonical text where line endings are
+     * converted to <CR><LF> and trailing whitespaces are removed, the line ending of last line is excluded */
+    fn sign_cleartext(&self, content: &[u8], digest: HashAlgorithm, options: &HashMap<String, String>) -> Result<Vec<u8>> {
+        let text = from_utf8(content).map_err(|e| Error::SignError(self.identity.clone(), e.to_string()))?;
+        let lines: Vec<&str> = text.strip_suffix('\n').unwrap_or(text).split('\n').map(
+            |line| line.strip_suffix('\r').unwrap_or(line)).collect();
+        let canonical_text = lines.iter().map(
+            |line| line.trim_end_matches([' ', '\t'])).collect::<Vec<&str>>().join("\r\n");
+        let signature_packet = self.get_signature_config(digest, SignatureType::Text)
+            .sign(&self.secret_key.clone().unwrap(), OpenPGPPlugin::get_passphrase_fn(options), Cursor::new(canonical_text))
+            .map_err(|e| Error::SignError(self.identity.clone(), e.to_string()))?;
+        let mut message = format!("{}\nHash: {}\n\n", payload::PGP_CLEARTEXT_HEADER, get_armor_hash_name(digest)?).into_bytes();
+        for line in lines {
+            //dash-escaped text
+            if line.starts_with('-') {
+                message.extend_from_slice(b"- ");
+            }
+            message.extend_from_slice(line.as_bytes());
+            message.push(b'\n');
+        }
+        message.extend(StandaloneSignature::new(signature_packet).to_armored_bytes(None)?);
+        Ok(message)
+    }
+
     pub fn attributes_validate(attr: &HashMap<String, String>) -> Result<PgpKeyGenerationParameter> {
         let parameter: PgpKeyGenerationParameter =
             serde_json::from_str(serde_json::to_string(&attr)?.as_str())?;
@@ -254,47 +383,47 @@ impl SignPlugins for OpenPGPPlugin {
         if let Some(digest_str) = options.get("digest_algorithm") {
                 digest = get_digest_algorithm(digest_str)?
         }
-        let passwd_fn = || return match options.get("passphrase") {
-            None => {
-                String::new()
-            }
-            Some(password) => {
-                password.to_string()
+        if OpenPGPPlugin::is_cleartext(&options) {
+            return self.sign_cleartext(&content, digest, &options)
+        }
+        if let Some(sign_type) = OpenPGPPlugin::get_sign_type(&options)? {
+            if let Some(signature) = self.sign_embedded(sign_type, &content, &options)? {
+                return Ok(signature)
             }
-        };
-        let now = Utc::now();
-        let secret_key_id = self.secret_key.clone().unwrap().key_id();
-        let sig_cfg = SignatureConfig {
-            version: SignatureVersion::V4,
-            typ: SignatureType::Binary,
-            pub_alg: self.public_key.clone().unwrap().primary_key.algorithm(),
-            hash_alg: digest,
-            issuer: Some(secret_key_id.clone()),
-            created: Some(now),
-            unhashed_subpackets: vec![],
-            hashed_subpackets: vec![
-                Subpacket::SignatureCreationTime(now),
-                Subpacket::Issuer(secret_key_id),
-            ],
-        };
+        }
         let read_cursor = Cursor::new(content);
-        let signature_packet = sig_cfg
-            .sign(&self.secret_key.clone().unwrap(), passwd_fn, read_cursor)
+        let signature_packet = self.get_signature_config(digest, SignatureType::Binary)
+            .sign(&self.secret_key.clone().unwrap(), OpenPGPPlugin::get_passphrase_fn(&options), read_cursor)
             .map_err(|e| Error::SignError(self.identity.clone(), e.to_string()))?;
+        self.encode_signature(signature_packet, &options)
+    }
 
-
-        //detached signature
-        if let Some(detached) = options.get(options::DETACHED) {
-            if detached == "true" {
-                let standard_signature = StandaloneSignature::new(signature_packet);
-                return Ok(standard_signature.to_armored_bytes(None)?)
+    fn sign_prehashed(&self, digest: ContentDigest, options: HashMap<String, String>) -> Result<Vec<u8>> {
+        if let Some(digest_str) = options.get("digest_algorithm") {
+            if get_digest_algorithm(digest_str)? != HashAlgorithm::SHA2_256 {
+                return Err(Error::ParameterError(format!("digest algorithm {} is not supported in prehashed signing", digest_str)))
             }
         }
-        let mut signature_bytes = Vec::with_capacity(1024);
-        let mut cursor = Cursor::new(&mut signature_bytes);
-        write_packet(&mut cursor, &signature_packet)
+        if OpenPGPPlugin::is_cleartext(&options) {
+            return Err(Error::ParameterError("cleartext signature is not supported in prehashed signing".to_string()))
+        }
+        //the payloads of raw signatures must be built from the content, which is absent in prehashed signing
+        if let Some(sign_type @ (SignType::Raw | SignType::Jsf | SignType::XmlDsig | SignType::Cosign)) = OpenPGPPlugin::get_sign_type(&options)? {
+            return Err(Error::UnsupportedTypeError(format!("{} signature in prehashed signing", sign_type)))
+        }
+        //the signature trailer needs to be appended into the hash state of content before finalizing
+        let state = digest.state.ok_or_else(|| Error::ParameterError(
+            "hash state is required for openpgp prehashed signing".to_string()))?;
+        let sig_cfg = self.get_signature_config(HashAlgorithm::SHA2_256, SignatureType::Binary);
+        let mut hasher: Box<dyn Hasher> = Box::new(state);
+        let len = sig_cfg.hash_signature_data(&mut *hasher)
             .map_err(|e| Error::SignError(self.identity.clone(), e.to_string()))?;
-        Ok(signature_bytes)
+        hasher.update(&sig_cfg.trailer(len));
+        let hash = hasher.finish();
+        let signature = self.secret_key.clone().unwrap()
+            .create_signature(OpenPGPPlugin::get_passphrase_fn(&options), HashAlgorithm::SHA2_256, &hash)
+            .map_err(|e| Error::SignError(self.identity.clone(), e.to_string()))?;
+        self.encode_signature(Signature::from_config(sig_cfg, [hash[0], hash[1]], signature), &options)
     }
 
     fn generate_crl_content(&self, _revoked_keys: Vec<RevokedKey>, _last_update: DateTime<Utc>, _next_update: DateTime<Utc>) -> Result<Vec<u8>> {
@@ -313,6 +442,7 @@ mod test {
     use crate::domain::encryption_engine::EncryptionEngine;
     use crate::infra::encryption::dummy_engine::DummyEngine;
     use crate::util::options::DETACHED;
+    use pgp::types::{Mpi, PublicKeyTrait};
 
     fn get_encryption_engine() -> Box<dyn EncryptionEngine> {
         Box::new(DummyEngine::default())
@@ -576,34 +706,122 @@ j0AZp6WE
         assert_eq!("60780E80350801A395B1B08302A5B5FB87CD058E", datakey.fingerprint);
     }
 
-    #[tokio::test]
-    async fn test_sign_with_armored_text() {
-        let content = "hello world".as_bytes();
-        let mut parameter = get_default_parameter();
-        parameter.insert(DETACHED.to_string(), "true".to_string());
-        let dummy_engine = get_encryption_engine();
-        let sec_datakey = SecDataKey::load(
-            &get_default_datakey(
-                None, Some(parameter.clone())), &dummy_engine).await.expect("load sec datakey successfully");
-        let plugin = OpenPGPPlugin::new(sec_datakey).expect("create openpgp plugin successfully");
-        let keys = plugin.generate_keys(&KeyType::OpenPGP, &HashMap::new()).expect(format!("generate key successfully").as_str());
-        let sec_keys = SecDataKey {
+    //generate a new key pair with the default parameter, the private key is protected by the default passphrase
+    fn test_key() -> SecDataKey {
+        let plugin = OpenPGPPlugin::new(SecDataKey {
             name: "".to_string(),
-            private_key: SecVec::new(keys.private_key.clone()),
-            public_key: SecVec::new(keys.public_key.clone()),
-            certificate: SecVec::new(keys.certificate.clone()),
+            private_key: SecVec::new(vec![]),
+            public_key: SecVec::new(vec![]),
+            certificate: SecVec::new(vec![]),
+            identity: "".to_string(),
+            attributes: get_default_parameter(),
+            parent: None,
+        }).expect("create openpgp plugin successfully");
+        let keys = plugin.generate_keys(&KeyType::OpenPGP, &HashMap::new()).expect("generate key successfully");
+        SecDataKey {
+            name: "".to_string(),
+            private_key: SecVec::new(keys.private_key),
+            public_key: SecVec::new(keys.public_key),
+            certificate: SecVec::new(keys.certificate),
             identity: "".to_string(),
             attributes: Default::default(),
             parent: None,
-        };
-        let instance = OpenPGPPlugin::new(sec_keys).expect("create openpgp instance successfully");
+        }
+    }
+
+    #[test]
+    fn test_sign_with_armored_text() {
+        let content = "hello world".as_bytes();
+        let mut parameter = get_default_parameter();
+        parameter.insert(DETACHED.to_string(), "true".to_string());
+        let instance = OpenPGPPlugin::new(test_key()).expect("create openpgp instance successfully");
+        let public_key = instance.public_key.clone().expect("public key exists");
         let signature = instance.sign(content.to_vec(), parameter).expect("sign successfully");
         let signature_text = from_utf8(&signature).expect("signature bytes to string should work");
         assert_eq!(true, signature_text.contains("-----BEGIN PGP SIGNATURE-----"));
         assert_eq!(true, signature_text.contains("-----END PGP SIGNATURE-----"));
         let (standalone, _) = StandaloneSignature::from_string(signature_text).expect("parse signature successfully");
-        let public = from_utf8(&keys.public_key).expect("parse public key should work");
-        let (public_key, _) = SignedPublicKey::from_string(public).expect("parse signed public key should work");
         standalone.verify(&public_key, content).expect("signature matches");
     }
+
+    #[test]
+    fn test_sign_prehashed_verified_with_content() {
+        let content = "hello world".as_bytes();
+        let mut parameter = get_default_parameter();
+        parameter.insert(DETACHED.to_string(), "true".to_string());
+        let instance = OpenPGPPlugin::new(test_key()).expect("create openpgp instance successfully");
+        let public_key = instance.public_key.clone().expect("public key exists");
+        let mut digest = ContentDigest::from_content(content);
+        let signature = instance.sign_prehashed(digest.clone(), parameter.clone()).expect("sign with digest successfully");
+        let signature_text = from_utf8(&signature).expect("signature bytes to string should work");
+        let (standalone, _) = StandaloneSignature::from_string(signature_text).expect("parse signature successfully");
+        standalone.verify(&public_key, content).expect("signature matches");
+        let _ = standalone.verify(&public_key, "fake content".as_bytes()).expect_err("signature mismatches fake content");
+        digest.state = None;
+        let _ = instance.sign_prehashed(digest, parameter).expect_err("sign without hash state should fail");
+    }
+
+    #[test]
+    fn test_sign_with_cleartext() {
+        let content = "Origin: openEuler\n-----dash line\ntrailing whitespace \t\n".as_bytes();
+        let mut parameter = get_default_parameter();
+        parameter.insert(options::SIGNATURE_FORMAT.to_string(), options::SIGNATURE_FORMAT_CLEARTEXT.to_string());
+        let instance = OpenPGPPlugin::new(test_key()).expect("create openpgp instance successfully");
+        let public_key = instance.public_key.clone().expect("public key exists");
+        let message = instance.sign(content.to_vec(), parameter.clone()).expect("sign successfully");
+        let message_text = from_utf8(&message).expect("message bytes to string should work");
+        let (text, signature_text) = message_text.split_once("-----BEGIN PGP SIGNATURE-----").expect("signature block exists");
+        assert_eq!(text, "-----BEGIN PGP SIGNED MESSAGE-----\nHash: SHA256\n\nOrigin: openEuler\n- -----dash line\ntrailing whitespace \t\n");
+        let (standalone, _) = StandaloneSignature::from_string(
+            &format!("-----BEGIN PGP SIGNATURE-----{}", signature_text)).expect("parse signature successfully");
+        assert_eq!(standalone.signature.typ(), SignatureType::Text);
+        standalone.verify(&public_key, "Origin: openEuler\r\n-----dash line\r\ntrailing whitespace".as_bytes()).expect("signature matches");
+        let _ = instance.sign_prehashed(ContentDigest::from_content(content), parameter).expect_err("prehashed cleartext signing should fail");
+    }
+
+    #[test]
+    fn test_sign_with_signature_format() {
+        let content = "SHA256 (file.iso) = 0123456789abcdef\n".as_bytes();
+        let mut parameter = get_default_parameter();
+        parameter.insert(DETACHED.to_string(), "true".to_string());
+        parameter.insert(options::SIGNATURE_FORMAT.to_string(), options::SIGNATURE_FORMAT_BINARY.to_string());
+        let instance = OpenPGPPlugin::new(test_key()).expect("create openpgp instance successfully");
+        let public_key = instance.public_key.clone().expect("public key exists");
+        for signature in [
+            instance.sign(content.to_vec(), parameter.clone()).expect("sign binary successfully"),
+            instance.sign_prehashed(ContentDigest::from_content(content), parameter.clone()).expect("sign binary with digest successfully")] {
+            assert!(from_utf8(&signature).map(|text| !text.contains("-----BEGIN PGP SIGNATURE-----")).unwrap_or(true));
+            let standalone = StandaloneSignature::from_bytes(Cursor::new(signature)).expect("parse binary signature successfully");
+            assert_eq!(standalone.signature.typ(), SignatureType::Binary);
+            standalone.verify(&public_key, content).expect("signature matches");
+        }
+
+        parameter.insert(options::SIGNATURE_FORMAT.to_string(), options::SIGNATURE_FORMAT_CLEARTEXT.to_string());
+        let message = instance.sign(content.to_vec(), parameter.clone()).expect("sign cleartext successfully");
+        let message_text = from_utf8(&message).expect("message bytes to string should work");
+        assert!(message_text.starts_with("-----BEGIN PGP SIGNED MESSAGE-----\nHash: SHA256\n\nSHA256 (file.iso) = 0123456789abcdef\n-----BEGIN PGP SIGNATURE-----"));
+    }
+
+    #[test]
+    fn test_sign_embedded_payload_and_reject_raw() {
+        let content = "hello world".as_bytes();
+        let mut parameter = get_default_parameter();
+        parameter.insert(options::SIGN_TYPE.to_string(), SignType::Raw.to_string());
+        let instance = OpenPGPPlugin::new(test_key()).expect("create openpgp instance successfully");
+        let _ = instance.sign(content.to_vec(), parameter.clone()).expect_err("raw signature with openpgp key should fail");
+        let _ = instance.sign_prehashed(ContentDigest::from_content(content), parameter.clone()).expect_err("raw signature with digest should fail");
+
+        //the jsf payload is built from the document by plugin
+        let public_key = instance.public_key.clone().expect("public key exists");
+        parameter.insert(options::SIGN_TYPE.to_string(), SignType::Jsf.to_string());
+        let signed = instance.sign(br#"{"bomFormat":"CycloneDX"}"#.to_vec(), parameter.clone()).expect("sign jsf successfully");
+        let mut value: serde_json::Value = serde_json::from_slice(&signed).expect("parse signed document successfully");
+        assert_eq!(value["signature"]["algorithm"], "RS256");
+        let signature = data_encoding::BASE64URL_NOPAD.decode(
+            value["signature"].as_object_mut().unwrap().remove("value").unwrap().as_str().unwrap().as_bytes()).unwrap();
+        let payload = crate::util::canonicalization::canonicalize_json(&value).unwrap();
+        public_key.verify_signature(HashAlgorithm::SHA2_256, &ContentDigest::from_content(payload.as_bytes()).digest,
+                                    &[Mpi::from_raw_slice(&signature)]).expect("jsf signature matches");
+        let _ = instance.sign_prehashed(ContentDigest::from_content(&signed), parameter).expect_err("jsf signature with digest should fail");
+    }
 }
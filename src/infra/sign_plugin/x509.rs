@@ -21,14 +21,18 @@ use std::time::{SystemTime, Duration};
 use chrono::{DateTime, Utc};
 use openssl::asn1::{Asn1Integer, Asn1Time};
 use openssl::bn::{BigNum, MsbOption};
-use openssl::cms::{CmsContentInfo, CMSOptions};
 use openssl::dsa::Dsa;
-use openssl::hash::MessageDigest;
+use openssl::cms::{CmsContentInfo, CMSOptions};
+use openssl::hash::{hash, MessageDigest};
 use openssl::nid::Nid;
 use openssl::pkcs7::{Pkcs7, Pkcs7Flags};
-use openssl::pkey::{PKey, Private};
+use openssl::pkey::{Id, PKey, Private};
+use openssl::pkey_ctx::PkeyCtx;
+use openssl::sign::Signer;
+use openssl::md::Md;
+use openssl::rsa::Padding;
 use openssl::rsa::Rsa;
-use openssl::stack::Stack;
+use openssl::stack::{Stack, StackRef};
 use openssl::x509;
 use openssl::x509::extension::{AuthorityKeyIdentifier, BasicConstraints, KeyUsage, SubjectKeyIdentifier};
 use openssl::x509::{X509Crl, X509Extension};
@@ -36,19 +40,127 @@ use secstr::SecVec;
 use serde::Deserialize;
 use foreign_types_shared::{ForeignType, ForeignTypeRef};
 use openssl_sys::{X509_CRL_new, X509_CRL_set_issuer_name, X509_CRL_set1_lastUpdate, X509_CRL_add0_revoked, X509_CRL_sign, X509_CRL_set1_nextUpdate, X509_REVOKED_new, X509_REVOKED_set_serialNumber, X509_REVOKED_set_revocationDate};
+use cms::builder::{create_signing_time_attribute, SignedDataBuilder, SignerInfoBuilder};
+use cms::cert::{CertificateChoices, IssuerAndSerialNumber};
+use cms::signed_data::{EncapsulatedContentInfo, SignerIdentifier};
+use const_oid::db::{rfc5911, rfc5912, rfc8410};
+use const_oid::ObjectIdentifier;
+use der::asn1::{BitString, Null};
+use der::{Any, Decode, Encode};
+use spki::{AlgorithmIdentifierOwned, DynSignatureAlgorithmIdentifier, SignatureBitStringEncoding};
 
 use validator::{Validate, ValidationError};
 use crate::util::options;
 use crate::util::sign::SignType;
+use crate::util::authenticode;
+use crate::util::payload::{self, RawAlgorithm};
 use crate::domain::datakey::entity::{DataKey, DataKeyContent, INFRA_CONFIG_DOMAIN_NAME, KeyType, RevokedKey, SecDataKey, SecParentDateKey};
 use crate::util::error::{Error, Result};
 use crate::domain::sign_plugin::SignPlugins;
 use crate::util::key::{decode_hex_string_to_u8, encode_u8_to_hex_string};
+use crate::util::digest::{ContentDigest, SHA2_256};
 use super::util::{validate_utc_time_not_expire, validate_utc_time, attributes_validate};
 
-const VALID_KEY_TYPE: [&str; 2] = ["rsa", "dsa"];
+//key length is ignored for ed25519 key
+const VALID_KEY_TYPE: [&str; 3] = ["rsa", "dsa", "ed25519"];
 const VALID_KEY_SIZE: [&str; 3] = ["2048", "3072", "4096"];
 const VALID_DIGEST_ALGORITHM: [&str; 6] = ["md5", "sha1", "sha2_256","sha2_384","sha2_512","sha2_224"];
+const IMA_XATTR_DIGSIG: u8 = 0x03;
+const IMA_SIGNATURE_VERSION: u8 = 0x02;
+const IMA_HASH_ALGO_SHA256: u8 = 0x04;
+
+const ID_DSA_WITH_SHA_384: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.3.3");
+const ID_DSA_WITH_SHA_512: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.3.4");
+
+fn get_message_digest(digest_algorithm: &str) -> Result<MessageDigest> {
+    match digest_algorithm {
+        "md5" => Ok(MessageDigest::md5()),
+        "sha1" => Ok(MessageDigest::sha1()),
+        "sha2_224" => Ok(MessageDigest::sha224()),
+        "sha2_256" => Ok(MessageDigest::sha256()),
+        "sha2_384" => Ok(MessageDigest::sha384()),
+        "sha2_512" => Ok(MessageDigest::sha512()),
+        _ => Err(Error::ParameterError(
+            "invalid digest algorithm for x509".to_string(),
+        )),
+    }
+}
+
+//hash algorithm of IMA signature header, reference: include/uapi/linux/hash_info.h
+fn ima_hash_algorithm(digest: MessageDigest) -> Result<u8> {
+    match digest.type_() {
+        Nid::MD5 => Ok(1),
+        Nid::SHA1 => Ok(2),
+        Nid::SHA256 => Ok(IMA_HASH_ALGO_SHA256),
+        Nid::SHA384 => Ok(5),
+        Nid::SHA512 => Ok(6),
+        Nid::SHA224 => Ok(7),
+        nid => Err(Error::UnsupportedTypeError(format!("digest algorithm {:?} in IMA signature", nid))),
+    }
+}
+
+fn digest_algorithm_identifier(digest: MessageDigest) -> Result<AlgorithmIdentifierOwned> {
+    let oid = match digest.type_() {
+        Nid::MD5 => rfc5912::ID_MD_5,
+        Nid::SHA1 => rfc5912::ID_SHA_1,
+        Nid::SHA224 => rfc5912::ID_SHA_224,
+        Nid::SHA256 => rfc5912::ID_SHA_256,
+        Nid::SHA384 => rfc5912::ID_SHA_384,
+        Nid::SHA512 => rfc5912::ID_SHA_512,
+        nid => return Err(Error::UnsupportedTypeError(format!("digest algorithm {:?} in signed data", nid))),
+    };
+    Ok(AlgorithmIdentifierOwned { oid, parameters: None })
+}
+
+/// Signer used by the cms builder, the signed attributes are signed with openssl.
+struct OpensslSigner<'a> {
+    private_key: &'a PKey<Private>,
+    digest: MessageDigest,
+}
+
+struct OpensslSignature(Vec<u8>);
+
+impl SignatureBitStringEncoding for OpensslSignature {
+    fn to_bitstring(&self) -> der::Result<BitString> {
+        BitString::from_bytes(&self.0)
+    }
+}
+
+impl<'a> signature::Keypair for OpensslSigner<'a> {
+    type VerifyingKey = Vec<u8>;
+
+    fn verifying_key(&self) -> Self::VerifyingKey {
+        self.private_key.public_key_to_der().unwrap_or_default()
+    }
+}
+
+//identical to the signature algorithms used by openssl in cms signer info
+impl<'a> DynSignatureAlgorithmIdentifier for OpensslSigner<'a> {
+    fn signature_algorithm_identifier(&self) -> spki::Result<AlgorithmIdentifierOwned> {
+        let (oid, parameters) = match (self.private_key.id(), self.digest.type_()) {
+            (Id::RSA, _) => (rfc5912::RSA_ENCRYPTION, Some(Any::from(Null))),
+            (Id::ED25519, _) => (rfc8410::ID_ED_25519, None),
+            (Id::DSA, Nid::SHA1) => (rfc5912::DSA_WITH_SHA_1, None),
+            (Id::DSA, Nid::SHA224) => (rfc5912::DSA_WITH_SHA_224, None),
+            (Id::DSA, Nid::SHA256) => (rfc5912::DSA_WITH_SHA_256, None),
+            (Id::DSA, Nid::SHA384) => (ID_DSA_WITH_SHA_384, None),
+            (Id::DSA, Nid::SHA512) => (ID_DSA_WITH_SHA_512, None),
+            _ => return Err(spki::Error::AlgorithmParametersMissing),
+        };
+        Ok(AlgorithmIdentifierOwned { oid, parameters })
+    }
+}
+
+impl<'a> signature::Signer<OpensslSignature> for OpensslSigner<'a> {
+    fn try_sign(&self, message: &[u8]) -> std::result::Result<OpensslSignature, signature::Error> {
+        let mut signer = if self.private_key.id() == Id::ED25519 {
+            Signer::new_without_digest(self.private_key)
+        } else {
+            Signer::new(self.digest, self.private_key)
+        }.map_err(signature::Error::from_source)?;
+        Ok(OpensslSignature(signer.sign_oneshot_to_vec(message).map_err(signature::Error::from_source)?))
+    }
+}
 
 #[derive(Debug, Validate, Deserialize)]
 pub struct X509KeyGenerationParameter {
@@ -96,6 +208,7 @@ impl X509KeyGenerationParameter {
         return match self.key_type.as_str() {
             "rsa" => Ok(PKey::from_rsa(Rsa::generate(self.key_length.parse()?)?)?),
             "dsa" => Ok(PKey::from_dsa(Dsa::generate(self.key_length.parse()?)?)?),
+            "ed25519" => Ok(PKey::generate_ed25519()?),
             _ => Err(Error::ParameterError(
                 "invalid key type for x509".to_string(),
             )),
@@ -103,17 +216,15 @@ impl X509KeyGenerationParameter {
     }
 
     pub fn get_digest_algorithm(&self) -> Result<MessageDigest> {
-        return match self.digest_algorithm.as_str() {
-            "md5" => Ok(MessageDigest::md5()),
-            "sha1" => Ok(MessageDigest::sha1()),
-            "sha2_256" => Ok(MessageDigest::sha224()),
-            "sha2_384" => Ok(MessageDigest::sha256()),
-            "sha2_512" => Ok(MessageDigest::sha384()),
-            "sha2_224" => Ok(MessageDigest::sha512()),
-            _ => Err(Error::ParameterError(
-                "invalid digest algorithm for x509".to_string(),
-            )),
-        };
+        get_message_digest(&self.digest_algorithm)
+    }
+
+    //ed25519 signs the certificate without message digest
+    pub fn get_signing_digest(&self, signing_key: &PKey<Private>) -> Result<MessageDigest> {
+        if signing_key.id() == Id::ED25519 {
+            return Ok(MessageDigest::null())
+        }
+        self.get_digest_algorithm()
     }
 
     pub fn get_subject_name(&self) -> Result<x509::X509Name> {
@@ -130,7 +241,7 @@ impl X509KeyGenerationParameter {
 
 fn validate_x509_key_type(key_type: &str) -> std::result::Result<(), ValidationError> {
     if !VALID_KEY_TYPE.contains(&key_type) {
-        return Err(ValidationError::new("invalid key type, possible values are rsa/dsa"));
+        return Err(ValidationError::new("invalid key type, possible values are rsa/dsa/ed25519"));
     }
     Ok(())
 }
@@ -166,6 +277,140 @@ pub struct X509Plugin {
 }
 
 impl X509Plugin {
+    //the signed attributes are identical to the ones generated in `CmsContentInfo::sign` and `Pkcs7::sign`, except the
+    //message digest attribute is filled with the prehashed digest instead of computing from content, it's used for both
+    //cms and pkcs7 signature in prehashed signing and for the keys whose digest algorithm is not sha256.
+    fn sign_signed_data_prehashed(certificate: &x509::X509, private_key: &PKey<Private>, chain: Option<&StackRef<x509::X509>>,
+                                  message_digest: MessageDigest, digest: &[u8]) -> Result<Vec<u8>> {
+        let certificate = x509_cert::Certificate::from_der(&certificate.to_der()?)?;
+        let signer = OpensslSigner {
+            private_key,
+            digest: message_digest,
+        };
+        let digest_algorithm = digest_algorithm_identifier(signer.digest)?;
+        let content = EncapsulatedContentInfo {
+            econtent_type: rfc5911::ID_DATA,
+            econtent: None,
+        };
+        let mut signer_info = SignerInfoBuilder::new(
+            &signer,
+            SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+                issuer: certificate.tbs_certificate.issuer.clone(),
+                serial_number: certificate.tbs_certificate.serial_number.clone(),
+            }),
+            digest_algorithm.clone(),
+            &content,
+            Some(digest))?;
+        signer_info.add_signed_attribute(create_signing_time_attribute()?)?;
+        let mut builder = SignedDataBuilder::new(&content);
+        builder.add_digest_algorithm(digest_algorithm)?;
+        if let Some(chain) = chain {
+            builder.add_certificate(CertificateChoices::Certificate(certificate))?;
+            for cert in chain {
+                builder.add_certificate(CertificateChoices::Certificate(x509_cert::Certificate::from_der(&cert.to_der()?)?))?;
+            }
+        }
+        builder.add_signer_info(signer_info)?;
+        Ok(builder.build()?.to_der()?)
+    }
+
+    fn sign_raw(private_key: &PKey<Private>, message_digest: MessageDigest, digest: &[u8]) -> Result<Vec<u8>> {
+        let mut ctx = PkeyCtx::new(private_key)?;
+        ctx.sign_init()?;
+        if private_key.id() == Id::RSA {
+            ctx.set_rsa_padding(Padding::PKCS1)?;
+        }
+        ctx.set_signature_md(Md::from_nid(message_digest.type_()).ok_or_else(|| Error::UnsupportedTypeError(
+            format!("digest algorithm {:?} in raw signature", message_digest.type_())))?)?;
+        let mut signature = vec![];
+        ctx.sign_to_vec(digest, &mut signature)?;
+        Ok(signature)
+    }
+
+    //pure ed25519 signature is calculated over the whole content, prehashed content is not supported
+    fn sign_ed25519(private_key: &PKey<Private>, content: &[u8]) -> Result<Vec<u8>> {
+        Ok(Signer::new_without_digest(private_key)?.sign_oneshot_to_vec(content)?)
+    }
+
+    //raw signature of the whole content, ed25519 signs the content directly
+    fn sign_content_raw(private_key: &PKey<Private>, message_digest: MessageDigest, content: &[u8]) -> Result<Vec<u8>> {
+        if private_key.id() == Id::ED25519 {
+            return X509Plugin::sign_ed25519(private_key, content)
+        }
+        X509Plugin::sign_raw(private_key, message_digest, &hash(message_digest, content)?)
+    }
+
+    //the algorithms of signatures embedded into documents are bound to sha256 for rsa key
+    fn get_raw_algorithm(private_key: &PKey<Private>, message_digest: MessageDigest) -> Result<RawAlgorithm> {
+        match private_key.id() {
+            Id::RSA if message_digest.type_() != Nid::SHA256 => Err(Error::UnsupportedTypeError(
+                format!("embedded document signature with digest algorithm {:?}", message_digest.type_()))),
+            Id::RSA => Ok(RawAlgorithm::Rsa),
+            Id::ED25519 => Ok(RawAlgorithm::Ed25519),
+            id => Err(Error::UnsupportedTypeError(format!("embedded document signature with key {:?}", id))),
+        }
+    }
+
+    //IMA signature v2 format, reference: `struct signature_v2_hdr` in linux security/integrity/integrity.h
+    fn sign_ima(certificate: &x509::X509, private_key: &PKey<Private>, message_digest: MessageDigest, digest: &[u8]) -> Result<Vec<u8>> {
+        //kernel finds the verification key in keyring with the last 4 bytes of subject key identifier
+        let key_id = certificate.subject_key_id().map(|id| id.as_slice()).filter(|id| id.len() >= 4).ok_or(
+            Error::X509InvokeError("certificate doesn't contain subject key identifier".to_string()))?;
+        let signature = X509Plugin::sign_raw(private_key, message_digest, digest)?;
+        let mut result = vec![IMA_XATTR_DIGSIG, IMA_SIGNATURE_VERSION, ima_hash_algorithm(message_digest)?];
+        result.extend_from_slice(&key_id[key_id.len() - 4..]);
+        result.extend_from_slice(&(signature.len() as u16).to_be_bytes());
+        result.extend(signature);
+        Ok(result)
+    }
+
+    //authenticode signature is generated from the digest of image, the digest algorithm of image specified by client
+    //must be the digest algorithm of key, which is used in both the SpcIndirectDataContent and the signer info.
+    fn sign_authenticode(&self, certificate: &x509::X509, private_key: &PKey<Private>, digest: Vec<u8>, options: &HashMap<String, String>) -> Result<Vec<u8>> {
+        let algorithm = options.get(options::DIGEST_ALGORITHM).map(|value| value.as_str()).unwrap_or(SHA2_256);
+        if algorithm != self.get_digest_algorithm() {
+            return Err(Error::ParameterError(format!(
+                "digest algorithm {} of image mismatches the digest algorithm {} of key", algorithm, self.get_digest_algorithm())))
+        }
+        let signer = OpensslSigner {
+            private_key,
+            digest: get_message_digest(algorithm)?,
+        };
+        let signature_algorithm = signer.signature_algorithm_identifier().map_err(|e| Error::X509InvokeError(e.to_string()))?;
+        let signature = authenticode::sign(certificate, algorithm, signature_algorithm, &digest,
+                                           |data| X509Plugin::sign_content_raw(private_key, signer.digest, data))?;
+        Ok(authenticode::encode_win_certificate(&signature))
+    }
+
+    //keys without the attribute are signed with sha2_256 which is the default of openssl as well
+    fn get_digest_algorithm(&self) -> &str {
+        self.attributes.get("digest_algorithm").map(|value| value.as_str()).unwrap_or(SHA2_256)
+    }
+
+    fn is_efi_variable(options: &HashMap<String, String>) -> bool {
+        options.get(options::EFI_VARIABLE).is_some()
+    }
+
+    fn include_certificate(options: &HashMap<String, String>) -> bool {
+        options.get(options::INCLUDE_CERTIFICATE).map(|value| value == "true").unwrap_or(false)
+    }
+
+    fn include_certificate_chain(options: &HashMap<String, String>) -> bool {
+        options.get(options::CMS_CERTIFICATE_CHAIN).map(|value| value == "true").unwrap_or(false)
+    }
+
+    //the certificates embedded into cms signature besides the signer certificate, it's the certificate of parent key
+    //since the root ca certificate is supposed to be trusted by verifier already.
+    fn get_certificate_chain(&self, options: &HashMap<String, String>) -> Result<Option<Stack<x509::X509>>> {
+        if !X509Plugin::include_certificate_chain(options) {
+            return Ok(None)
+        }
+        let mut chain = Stack::new()?;
+        if let Some(parent) = &self.parent_key {
+            chain.push(x509::X509::from_pem(parent.certificate.unsecure())?)?;
+        }
+        Ok(Some(chain))
+    }
 
     fn generate_serial_number() -> Result<BigNum> {
         let mut serial_number = BigNum::new()?;
@@ -209,7 +454,7 @@ impl X509Plugin {
         generator.append_extension(X509Extension::new_nid(None, None, Nid::NETSCAPE_COMMENT, "Signatrust Root CA")?)?;
         generator.append_extension(X509Extension::new_nid(None, None, Nid::NETSCAPE_CERT_TYPE, "objCA")?)?;
 
-        generator.sign(keys.as_ref(), parameter.get_digest_algorithm()?)?;
+        generator.sign(keys.as_ref(), parameter.get_signing_digest(&keys)?)?;
         let cert = generator.build();
         Ok(DataKeyContent{
             private_key: keys.private_key_to_pem_pkcs8()?,
@@ -258,7 +503,7 @@ impl X509Plugin {
         generator.append_extension(X509Extension::new_nid(None, None, Nid::CRL_DISTRIBUTION_POINTS, &self.generate_crl_endpoint(&self.parent_key.clone().unwrap().name, infra_config)?)?)?;
         generator.append_extension(X509Extension::new_nid(None, None, Nid::NETSCAPE_COMMENT, "Signatrust Intermediate CA")?)?;
         generator.append_extension(X509Extension::new_nid(None, None, Nid::NETSCAPE_CERT_TYPE, "objCA")?)?;
-        generator.sign(ca_key.as_ref(), parameter.get_digest_algorithm()?)?;
+        generator.sign(ca_key.as_ref(), parameter.get_signing_digest(&ca_key)?)?;
         let cert = generator.build();
         //use parent private key to sign the certificate
         Ok(DataKeyContent{
@@ -309,7 +554,7 @@ impl X509Plugin {
         generator.append_extension(X509Extension::new_nid(None, None, Nid::CRL_DISTRIBUTION_POINTS, &self.generate_crl_endpoint(&self.parent_key.clone().unwrap().name, infra_config)?)?)?;
         generator.append_extension(X509Extension::new_nid(None, None, Nid::NETSCAPE_COMMENT, "Signatrust Sign Certificate")?)?;
         generator.append_extension(X509Extension::new_nid(None, None, Nid::NETSCAPE_CERT_TYPE, "objsign")?)?;
-        generator.sign(ca_key.as_ref(), parameter.get_digest_algorithm()?)?;
+        generator.sign(ca_key.as_ref(), parameter.get_signing_digest(&ca_key)?)?;
         let cert = generator.build();
         //use parent private key to sign the certificate
         Ok(DataKeyContent{
@@ -375,17 +620,27 @@ impl SignPlugins for X509Plugin {
     fn sign(&self, content: Vec<u8>, options: HashMap<String, String>) -> Result<Vec<u8>> {
         let private_key = PKey::private_key_from_pem(self.private_key.unsecure())?;
         let certificate = x509::X509::from_pem(self.certificate.unsecure())?;
+        let message_digest = get_message_digest(self.get_digest_algorithm())?;
         match SignType::from_str(options.get(options::SIGN_TYPE).unwrap_or(&SignType::Cms.to_string()))? {
-            SignType::Authenticode => {
-                let p7b = efi_signer::EfiImage::pem_to_p7(self.certificate.unsecure())?;
-                Ok(efi_signer::EfiImage::do_sign_signature(
-                    content,
-                    p7b,
-                    private_key.private_key_to_pem_pkcs8()?,
-                    None,
-                    efi_signer::DigestAlgorithm::Sha256)?.encode()?)
+            SignType::Authenticode => self.sign_authenticode(&certificate, &private_key, content, &options),
+            //UEFI authenticated variable and jar signature require the signer certificate and no authenticated attributes,
+            //the digest algorithm of them can't be specified in openssl.
+            SignType::PKCS7 if X509Plugin::is_efi_variable(&options) || X509Plugin::include_certificate(&options) => {
+                if message_digest.type_() != Nid::SHA256 {
+                    return Err(Error::UnsupportedTypeError(format!(
+                        "digest algorithm {} for pkcs7 signature without authenticated attributes", self.get_digest_algorithm())))
+                }
+                let pkcs7 = Pkcs7::sign(
+                    &certificate,
+                    &private_key,
+                    Stack::new().as_ref()?,
+                    &content,
+                    Pkcs7Flags::DETACHED | Pkcs7Flags::BINARY | Pkcs7Flags::NOATTR,
+                )?;
+                Ok(pkcs7.to_der()?)
             }
-            SignType::PKCS7 => {
+            //openssl signs with sha256 in default, the signed data of other digest algorithms is built with cms crate
+            SignType::PKCS7 if message_digest.type_() == Nid::SHA256 => {
                 let pkcs7 = Pkcs7::sign(
                     &certificate,
                     &private_key,
@@ -398,20 +653,68 @@ impl SignPlugins for X509Plugin {
                 )?;
                 Ok(pkcs7.to_der()?)
             }
-            SignType::Cms => {
+            SignType::PKCS7 => X509Plugin::sign_signed_data_prehashed(
+                &certificate, &private_key, None, message_digest, &hash(message_digest, &content)?),
+            SignType::Cms if message_digest.type_() == Nid::SHA256 => {
                 //cms option reference: https://man.openbsd.org/CMS_sign.3
+                let chain = self.get_certificate_chain(&options)?;
+                let mut flags = CMSOptions::DETACHED | CMSOptions::BINARY | CMSOptions::NOSMIMECAP;
+                if chain.is_none() {
+                    flags |= CMSOptions::CMS_NOCERTS;
+                }
                 let cms_signature = CmsContentInfo::sign(
                     Some(&certificate),
                     Some(&private_key),
-                    None,
+                    chain.as_deref(),
                     Some(&content),
-                    CMSOptions::DETACHED
-                        | CMSOptions::CMS_NOCERTS
-                        | CMSOptions::BINARY
-                        | CMSOptions::NOSMIMECAP
+                    flags,
                 )?;
                 Ok(cms_signature.to_der()?)
             }
+            SignType::Cms => X509Plugin::sign_signed_data_prehashed(
+                &certificate, &private_key, self.get_certificate_chain(&options)?.as_deref(), message_digest,
+                &hash(message_digest, &content)?),
+            //raw signature of arbitrary content is limited to ed25519 which is required by ostree, the payloads of other
+            //raw signatures are built from content to avoid signing the crafted structures, for instance, certificate.
+            SignType::Raw if private_key.id() == Id::ED25519 => X509Plugin::sign_ed25519(&private_key, &content),
+            SignType::Raw => Err(Error::UnsupportedTypeError(format!("raw signature with key {:?}", private_key.id()))),
+            SignType::Jsf => payload::sign_jsf(&content, X509Plugin::get_raw_algorithm(&private_key, message_digest)?,
+                                               |data| X509Plugin::sign_content_raw(&private_key, message_digest, data)),
+            SignType::XmlDsig => payload::sign_xmldsig(&content, X509Plugin::get_raw_algorithm(&private_key, message_digest)?,
+                                                       |data| X509Plugin::sign_content_raw(&private_key, message_digest, data)),
+            SignType::Cosign => {
+                X509Plugin::get_raw_algorithm(&private_key, message_digest)?;
+                payload::sign_cosign(&content, |data| X509Plugin::sign_content_raw(&private_key, message_digest, data))
+            }
+            SignType::Ima => X509Plugin::sign_ima(&certificate, &private_key, message_digest, &hash(message_digest, &content)?),
+        }
+    }
+
+    fn sign_prehashed(&self, digest: ContentDigest, options: HashMap<String, String>) -> Result<Vec<u8>> {
+        let private_key = PKey::private_key_from_pem(self.private_key.unsecure())?;
+        let certificate = x509::X509::from_pem(self.certificate.unsecure())?;
+        let sign_type = SignType::from_str(options.get(options::SIGN_TYPE).unwrap_or(&SignType::Cms.to_string()))?;
+        //the prehashed digest is always sha2_256, it must be the digest algorithm of key except for authenticode signature
+        if sign_type != SignType::Authenticode && self.get_digest_algorithm() != digest.algorithm {
+            return Err(Error::ParameterError(format!(
+                "digest algorithm {} is not supported in prehashed signing", self.get_digest_algorithm())))
+        }
+        let message_digest = get_message_digest(&digest.algorithm)?;
+        match sign_type {
+            //authenticode signature is always generated from the digest of image
+            SignType::Authenticode => self.sign(digest.digest, options),
+            SignType::PKCS7 if X509Plugin::is_efi_variable(&options) => Err(Error::UnsupportedTypeError(
+                "digest signing is not supported for EFI authenticated variable".to_string())),
+            SignType::PKCS7 if X509Plugin::include_certificate(&options) => Err(Error::UnsupportedTypeError(
+                "digest signing is not supported for pkcs7 signature with certificate included".to_string())),
+            SignType::PKCS7 => X509Plugin::sign_signed_data_prehashed(
+                &certificate, &private_key, None, message_digest, &digest.digest),
+            SignType::Cms => X509Plugin::sign_signed_data_prehashed(
+                &certificate, &private_key, self.get_certificate_chain(&options)?.as_deref(), message_digest, &digest.digest),
+            //the payloads of raw signatures must be built from the content, which is absent in prehashed signing
+            sign_type @ (SignType::Raw | SignType::Jsf | SignType::XmlDsig | SignType::Cosign) => Err(Error::UnsupportedTypeError(
+                format!("{} signature in prehashed signing", sign_type))),
+            SignType::Ima => X509Plugin::sign_ima(&certificate, &private_key, message_digest, &digest.digest),
         }
     }
 
@@ -437,7 +740,7 @@ impl SignPlugins for X509Plugin {
                 unsafe {X509_CRL_add0_revoked(crl, revoked)};
             }
         }
-        unsafe {X509_CRL_sign(crl, private_key.as_ptr(), parameter.get_digest_algorithm()?.as_ptr())};
+        unsafe {X509_CRL_sign(crl, private_key.as_ptr(), parameter.get_signing_digest(&private_key)?.as_ptr())};
         let content = unsafe {X509Crl::from_ptr(crl)};
         Ok(content.to_pem()?)
     }
@@ -519,7 +822,10 @@ mod test {
 
     /// helper function to get a usable X509plugin
     async fn get_default_plugin() -> X509Plugin {
-        let parameter = get_default_parameter();
+        get_plugin_with_parameter(get_default_parameter()).await
+    }
+
+    async fn get_plugin_with_parameter(parameter: HashMap<String, String>) -> X509Plugin {
         let dummy_engine = get_encryption_engine();
         let infra_config = get_infra_config();
         // create ca
@@ -776,6 +1082,289 @@ X5BboR/QJakEK+H+EUQAiDs=
         let _signature = instance.sign(content.to_vec(), parameter).expect("sign successfully");
     }
 
+    #[tokio::test]
+    async fn test_sign_prehashed_verified_with_content() {
+        let content = "hello world".as_bytes();
+        let instance = get_default_plugin().await;
+        let certificate = x509::X509::from_pem(instance.certificate.unsecure()).expect("load certificate successfully");
+        let mut certs = Stack::new().expect("create stack successfully");
+        certs.push(certificate).expect("push certificate successfully");
+        let store = openssl::x509::store::X509StoreBuilder::new().expect("create store successfully").build();
+
+        let mut cms_options = get_default_parameter();
+        cms_options.insert(options::SIGN_TYPE.to_string(), SignType::Cms.to_string());
+        let signature = instance.sign_prehashed(ContentDigest::from_content(content), cms_options).expect("sign cms with digest successfully");
+        let mut cms = CmsContentInfo::from_der(&signature).expect("parse cms signature successfully");
+        cms.verify(Some(&certs), Some(&store), Some(content), None, CMSOptions::NOVERIFY | CMSOptions::BINARY).expect("cms signature matches content");
+        let mut cms = CmsContentInfo::from_der(&signature).expect("parse cms signature successfully");
+        let _ = cms.verify(Some(&certs), Some(&store), Some("fake content".as_bytes()), None, CMSOptions::NOVERIFY | CMSOptions::BINARY).expect_err("cms signature mismatches fake content");
+
+        let mut pkcs7_options = get_default_parameter();
+        pkcs7_options.insert(options::SIGN_TYPE.to_string(), SignType::PKCS7.to_string());
+        let signature = instance.sign_prehashed(ContentDigest::from_content(content), pkcs7_options).expect("sign pkcs7 with digest successfully");
+        let pkcs7 = Pkcs7::from_der(&signature).expect("parse pkcs7 signature successfully");
+        pkcs7.verify(&certs, &store, Some(content), None, Pkcs7Flags::NOVERIFY | Pkcs7Flags::BINARY).expect("pkcs7 signature matches content");
+        let _ = pkcs7.verify(&certs, &store, Some("fake content".as_bytes()), None, Pkcs7Flags::NOVERIFY | Pkcs7Flags::BINARY).expect_err("pkcs7 signature mismatches fake content");
+    }
+
+    #[tokio::test]
+    async fn test_sign_raw_with_rsa_key_rejected() {
+        let content = "hello world".as_bytes();
+        let instance = get_default_plugin().await;
+        let mut options = get_default_parameter();
+        options.insert(options::SIGN_TYPE.to_string(), SignType::Raw.to_string());
+        let _ = instance.sign(content.to_vec(), options.clone()).expect_err("raw signature with rsa key should fail");
+        for sign_type in [SignType::Raw, SignType::Jsf, SignType::XmlDsig, SignType::Cosign] {
+            options.insert(options::SIGN_TYPE.to_string(), sign_type.to_string());
+            let _ = instance.sign_prehashed(ContentDigest::from_content(content), options.clone())
+                .expect_err("raw signature with digest should fail");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sign_embedded_payload_built_from_content() {
+        let mut parameter = get_default_parameter();
+        parameter.insert("key_type".to_string(), "ed25519".to_string());
+        let instance = get_plugin_with_parameter(parameter).await;
+        let certificate = x509::X509::from_pem(instance.certificate.unsecure()).expect("load certificate successfully");
+        let public_key = certificate.public_key().expect("get public key successfully");
+        let mut options = get_default_parameter();
+
+        //jsf algorithm follows the key type
+        options.insert(options::SIGN_TYPE.to_string(), SignType::Jsf.to_string());
+        let signed = instance.sign(br#"{"bomFormat":"CycloneDX"}"#.to_vec(), options.clone()).expect("sign jsf successfully");
+        let mut value: serde_json::Value = serde_json::from_slice(&signed).expect("parse signed document successfully");
+        assert_eq!(value["signature"]["algorithm"], "Ed25519");
+        let signature = data_encoding::BASE64URL_NOPAD.decode(
+            value["signature"].as_object_mut().unwrap().remove("value").unwrap().as_str().unwrap().as_bytes()).unwrap();
+        let payload = crate::util::canonicalization::canonicalize_json(&value).unwrap();
+        let mut verifier = openssl::sign::Verifier::new_without_digest(&public_key).expect("create verifier successfully");
+        assert!(verifier.verify_oneshot(&signature, payload.as_bytes()).expect("verify jsf signature successfully"));
+
+        //cosign payload is built from the image reference and manifest
+        options.insert(options::SIGN_TYPE.to_string(), SignType::Cosign.to_string());
+        let manifest = br#"{"schemaVersion":2}"#;
+        let signature = instance.sign(payload::cosign_content("registry.io/app", manifest), options.clone()).expect("sign cosign successfully");
+        let payload = payload::cosign_payload("registry.io/app", &payload::manifest_digest(manifest)).unwrap();
+        let mut verifier = openssl::sign::Verifier::new_without_digest(&public_key).expect("create verifier successfully");
+        assert!(verifier.verify_oneshot(&signature, &payload).expect("verify cosign signature successfully"));
+    }
+
+    #[tokio::test]
+    async fn test_sign_signed_data_compatible_with_openssl() {
+        let content = "kernel module".as_bytes();
+        let instance = get_default_plugin().await;
+        let mut options = get_default_parameter();
+        for sign_type in [SignType::Cms, SignType::PKCS7] {
+            options.insert(options::SIGN_TYPE.to_string(), sign_type.to_string());
+            let signature = instance.sign(content.to_vec(), options.clone()).expect("sign signed data successfully");
+            let prehashed = instance.sign_prehashed(ContentDigest::from_content(content), options.clone())
+                .expect("sign prehashed signed data successfully");
+            //content signing keeps the output of openssl, the prehashed one contains the same attributes and no certificates
+            let expected = match sign_type {
+                SignType::Cms => CmsContentInfo::sign(Some(&x509::X509::from_pem(instance.certificate.unsecure()).unwrap()),
+                    Some(&PKey::private_key_from_pem(instance.private_key.unsecure()).unwrap()), None, Some(content),
+                    CMSOptions::DETACHED | CMSOptions::CMS_NOCERTS | CMSOptions::BINARY | CMSOptions::NOSMIMECAP).unwrap().to_der().unwrap(),
+                _ => Pkcs7::sign(&x509::X509::from_pem(instance.certificate.unsecure()).unwrap(),
+                    &PKey::private_key_from_pem(instance.private_key.unsecure()).unwrap(), Stack::new().as_ref().unwrap(), content,
+                    Pkcs7Flags::DETACHED | Pkcs7Flags::NOCERTS | Pkcs7Flags::BINARY | Pkcs7Flags::NOSMIMECAP).unwrap().to_der().unwrap(),
+            };
+            for der in [&signature, &prehashed, &expected] {
+                let info = cms::content_info::ContentInfo::from_der(der).expect("parse signed data successfully");
+                let signed_data = info.content.decode_as::<cms::signed_data::SignedData>().expect("decode signed data successfully");
+                assert!(signed_data.certificates.is_none());
+                let signer_info = &signed_data.signer_infos.0.as_slice()[0];
+                assert_eq!(signer_info.digest_alg.oid, rfc5912::ID_SHA_256);
+                let mut attributes: Vec<_> = signer_info.signed_attrs.as_ref().expect("signed attributes exist")
+                    .iter().map(|attribute| attribute.oid).collect();
+                attributes.sort();
+                let mut expected_attributes = vec![rfc5911::ID_CONTENT_TYPE, rfc5911::ID_MESSAGE_DIGEST, rfc5911::ID_SIGNING_TIME];
+                expected_attributes.sort();
+                assert_eq!(attributes, expected_attributes);
+            }
+            //the signatures only differ in signing time and rsa signature value
+            assert_eq!(signature.len(), expected.len());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sign_with_key_digest_algorithm() {
+        let content = "hello world".as_bytes();
+        let mut instance = get_default_plugin().await;
+        instance.attributes.insert("digest_algorithm".to_string(), "sha2_384".to_string());
+        let certificate = x509::X509::from_pem(instance.certificate.unsecure()).expect("load certificate successfully");
+        let public_key = certificate.public_key().expect("get public key successfully");
+        let mut certs = Stack::new().expect("create stack successfully");
+        certs.push(certificate).expect("push certificate successfully");
+        let store = openssl::x509::store::X509StoreBuilder::new().expect("create store successfully").build();
+        let mut options = get_default_parameter();
+
+        for sign_type in [SignType::Cms, SignType::PKCS7] {
+            options.insert(options::SIGN_TYPE.to_string(), sign_type.to_string());
+            let signature = instance.sign(content.to_vec(), options.clone()).expect("sign signed data successfully");
+            let info = cms::content_info::ContentInfo::from_der(&signature).expect("parse signed data successfully");
+            let signed_data = info.content.decode_as::<cms::signed_data::SignedData>().expect("decode signed data successfully");
+            assert_eq!(signed_data.digest_algorithms.as_slice()[0].oid, rfc5912::ID_SHA_384);
+            assert_eq!(signed_data.signer_infos.0.as_slice()[0].digest_alg.oid, rfc5912::ID_SHA_384);
+            let mut cms = CmsContentInfo::from_der(&signature).expect("parse cms signature successfully");
+            cms.verify(Some(&certs), Some(&store), Some(content), None, CMSOptions::NOVERIFY | CMSOptions::BINARY).expect("signature matches content");
+            let _ = instance.sign_prehashed(ContentDigest::from_content(content), options.clone())
+                .expect_err("prehashed sha256 digest mismatches the digest algorithm of key");
+        }
+
+        options.insert(options::SIGN_TYPE.to_string(), SignType::Ima.to_string());
+        let signature = instance.sign(content.to_vec(), options.clone()).expect("sign ima successfully");
+        assert_eq!(signature[2], 5);
+        let mut verifier = openssl::sign::Verifier::new(MessageDigest::sha384(), &public_key).expect("create verifier successfully");
+        assert!(verifier.verify_oneshot(&signature[9..], content).expect("verify ima signature successfully"));
+
+        //the algorithms of embedded signatures are bound to sha256 for rsa key
+        for sign_type in [SignType::Jsf, SignType::XmlDsig, SignType::Cosign] {
+            options.insert(options::SIGN_TYPE.to_string(), sign_type.to_string());
+            let _ = instance.sign(br#"{"bomFormat":"CycloneDX"}"#.to_vec(), options.clone())
+                .expect_err("embedded signature with sha384 rsa key should fail");
+        }
+        options.insert(options::SIGN_TYPE.to_string(), SignType::PKCS7.to_string());
+        options.insert(options::EFI_VARIABLE.to_string(), "db".to_string());
+        let _ = instance.sign(content.to_vec(), options).expect_err("efi variable with sha384 key should fail");
+    }
+
+    #[tokio::test]
+    async fn test_sign_ed25519_verified_with_content() {
+        let content = "hello world".as_bytes();
+        let mut parameter = get_default_parameter();
+        parameter.insert("key_type".to_string(), "ed25519".to_string());
+        let instance = get_plugin_with_parameter(parameter).await;
+        let certificate = x509::X509::from_pem(instance.certificate.unsecure()).expect("load certificate successfully");
+        let public_key = certificate.public_key().expect("get public key successfully");
+        assert_eq!(Id::ED25519, public_key.id());
+        let mut options = get_default_parameter();
+        options.insert(options::SIGN_TYPE.to_string(), SignType::Raw.to_string());
+        let signature = instance.sign(content.to_vec(), options.clone()).expect("sign with ed25519 successfully");
+        assert_eq!(64, signature.len());
+        let mut verifier = openssl::sign::Verifier::new_without_digest(&public_key).expect("create verifier successfully");
+        assert!(verifier.verify_oneshot(&signature, content).expect("verify ed25519 signature successfully"));
+        instance.sign_prehashed(ContentDigest::from_content(content), options).expect_err("prehashed ed25519 is unsupported");
+    }
+
+    #[tokio::test]
+    async fn test_sign_prehashed_cms_with_key_types() {
+        let content = "hello world".as_bytes();
+        for key_type in ["dsa", "ed25519"] {
+            let mut parameter = get_default_parameter();
+            parameter.insert("key_type".to_string(), key_type.to_string());
+            let instance = get_plugin_with_parameter(parameter).await;
+            let certificate = x509::X509::from_pem(instance.certificate.unsecure()).expect("load certificate successfully");
+            let mut certs = Stack::new().expect("create stack successfully");
+            certs.push(certificate).expect("push certificate successfully");
+            let store = openssl::x509::store::X509StoreBuilder::new().expect("create store successfully").build();
+            let mut options = get_default_parameter();
+            options.insert(options::SIGN_TYPE.to_string(), SignType::Cms.to_string());
+            let signature = instance.sign_prehashed(ContentDigest::from_content(content), options)
+                .unwrap_or_else(|_| panic!("sign cms with {} key and digest successfully", key_type));
+            let mut cms = CmsContentInfo::from_der(&signature).expect("parse cms signature successfully");
+            cms.verify(Some(&certs), Some(&store), Some(content), None, CMSOptions::NOVERIFY | CMSOptions::BINARY)
+                .unwrap_or_else(|_| panic!("cms signature of {} key matches content", key_type));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sign_ima_verified_with_content() {
+        let content = "hello world".as_bytes();
+        let instance = get_default_plugin().await;
+        let certificate = x509::X509::from_pem(instance.certificate.unsecure()).expect("load certificate successfully");
+        let public_key = certificate.public_key().expect("get public key successfully");
+        let key_id = certificate.subject_key_id().expect("subject key identifier exists").as_slice().to_vec();
+        let mut options = get_default_parameter();
+        options.insert(options::SIGN_TYPE.to_string(), SignType::Ima.to_string());
+        for signature in [
+            instance.sign(content.to_vec(), options.clone()).expect("sign ima successfully"),
+            instance.sign_prehashed(ContentDigest::from_content(content), options.clone()).expect("sign ima with digest successfully")] {
+            assert_eq!(signature[0..3], [IMA_XATTR_DIGSIG, IMA_SIGNATURE_VERSION, IMA_HASH_ALGO_SHA256]);
+            assert_eq!(signature[3..7], key_id[key_id.len() - 4..]);
+            assert_eq!(u16::from_be_bytes([signature[7], signature[8]]) as usize, signature.len() - 9);
+            let mut verifier = openssl::sign::Verifier::new(MessageDigest::sha256(), &public_key).expect("create verifier successfully");
+            assert!(verifier.verify_oneshot(&signature[9..], content).expect("verify ima signature successfully"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sign_efi_variable_with_certificate() {
+        let content = "efi variable".as_bytes();
+        let instance = get_default_plugin().await;
+        let certificate = x509::X509::from_pem(instance.certificate.unsecure()).expect("load certificate successfully");
+        let mut options = get_default_parameter();
+        options.insert(options::SIGN_TYPE.to_string(), SignType::PKCS7.to_string());
+        options.insert(options::EFI_VARIABLE.to_string(), "db".to_string());
+        let signature = instance.sign(content.to_vec(), options.clone()).expect("sign efi variable successfully");
+        let pkcs7 = Pkcs7::from_der(&signature).expect("load pkcs7 successfully");
+        let certs = pkcs7.signed().and_then(|signed| signed.certificates()).expect("signer certificate is included");
+        assert_eq!(certs.len(), 1);
+        let mut store = openssl::x509::store::X509StoreBuilder::new().expect("create store successfully");
+        store.add_cert(certificate).expect("add certificate successfully");
+        let store = store.build();
+        pkcs7.verify(&Stack::new().unwrap(), &store, Some(content), None, Pkcs7Flags::BINARY | Pkcs7Flags::NOVERIFY)
+            .expect("verify efi variable signature successfully");
+        assert!(instance.sign_prehashed(ContentDigest::from_content(content), options).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sign_jar_with_certificate() {
+        let content = "Signature-Version: 1.0\r\n\r\n".as_bytes();
+        let instance = get_default_plugin().await;
+        let certificate = x509::X509::from_pem(instance.certificate.unsecure()).expect("load certificate successfully");
+        let mut options = get_default_parameter();
+        options.insert(options::SIGN_TYPE.to_string(), SignType::PKCS7.to_string());
+        options.insert(options::INCLUDE_CERTIFICATE.to_string(), "true".to_string());
+        let signature = instance.sign(content.to_vec(), options.clone()).expect("sign jar signature file successfully");
+        let pkcs7 = Pkcs7::from_der(&signature).expect("load pkcs7 successfully");
+        let certs = pkcs7.signed().and_then(|signed| signed.certificates()).expect("signer certificate is included");
+        assert_eq!(certs.len(), 1);
+        let mut store = openssl::x509::store::X509StoreBuilder::new().expect("create store successfully");
+        store.add_cert(certificate).expect("add certificate successfully");
+        let store = store.build();
+        pkcs7.verify(&Stack::new().unwrap(), &store, Some(content), None, Pkcs7Flags::BINARY | Pkcs7Flags::NOVERIFY)
+            .expect("verify jar signature successfully");
+        assert!(instance.sign_prehashed(ContentDigest::from_content(content), options).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sign_cms_with_certificate_chain() {
+        let content = "firmware bundle".as_bytes();
+        let mut instance = get_default_plugin().await;
+        let parent = get_default_plugin().await;
+        instance.parent_key = Some(SecParentDateKey {
+            name: "fake ica".to_string(),
+            private_key: SecVec::new(parent.private_key.unsecure().to_vec()),
+            public_key: SecVec::new(parent.public_key.unsecure().to_vec()),
+            certificate: SecVec::new(parent.certificate.unsecure().to_vec()),
+            attributes: HashMap::new(),
+        });
+        let certificate = x509::X509::from_pem(instance.certificate.unsecure()).expect("load certificate successfully").to_der().unwrap();
+        let parent_certificate = x509::X509::from_pem(parent.certificate.unsecure()).expect("load certificate successfully").to_der().unwrap();
+        let store = openssl::x509::store::X509StoreBuilder::new().expect("create store successfully").build();
+        let mut options = get_default_parameter();
+        options.insert(options::SIGN_TYPE.to_string(), SignType::Cms.to_string());
+        options.insert(options::CMS_CERTIFICATE_CHAIN.to_string(), "true".to_string());
+        for signature in [
+            instance.sign(content.to_vec(), options.clone()).expect("sign cms with certificate successfully"),
+            instance.sign_prehashed(ContentDigest::from_content(content), options.clone()).expect("sign cms with digest successfully")] {
+            assert!(signature.windows(certificate.len()).any(|window| window == certificate.as_slice()));
+            assert!(signature.windows(parent_certificate.len()).any(|window| window == parent_certificate.as_slice()));
+            //signer certificate is loaded from the signature
+            let mut cms = CmsContentInfo::from_der(&signature).expect("parse cms signature successfully");
+            cms.verify(None, Some(&store), Some(content), None, CMSOptions::NOVERIFY | CMSOptions::BINARY).expect("cms signature matches content");
+        }
+
+        //include certificate option is only meaningful for pkcs7 signature of jar
+        options.remove(options::CMS_CERTIFICATE_CHAIN);
+        options.insert(options::INCLUDE_CERTIFICATE.to_string(), "true".to_string());
+        let signature = instance.sign(content.to_vec(), options).expect("sign cms successfully");
+        assert!(!signature.windows(certificate.len()).any(|window| window == certificate.as_slice()));
+        let mut cms = CmsContentInfo::from_der(&signature).expect("parse cms signature successfully");
+        let _ = cms.verify(None, Some(&store), Some(content), None, CMSOptions::NOVERIFY | CMSOptions::BINARY).expect_err("signer certificate is missing");
+    }
+
     #[tokio::test]
     async fn test_crl_generation() {
         let parameter = get_default_parameter();
@@ -841,4 +1430,63 @@ X5BboR/QJakEK+H+EUQAiDs=
         );
         instance.sign(file_hash, opts).unwrap();
     }
+
+    #[tokio::test]
+    async fn test_sign_authenticode_with_digest_algorithm() {
+        let mut instance = get_default_plugin().await;
+        let current_dir = env::current_dir().expect("get current dir failed");
+        let efi_file = tokio::fs::read(current_dir.join("test_assets").join("shimx64.efi"))
+            .await
+            .unwrap();
+        let image = efi_signer::EfiImage::parse(&efi_file).unwrap();
+        let digest = openssl::hash::hash(MessageDigest::sha384(), &image.raw).unwrap().to_vec();
+        let mut opts = HashMap::from([
+            (options::SIGN_TYPE.to_string(), SignType::Authenticode.to_string()),
+            (options::DIGEST_ALGORITHM.to_string(), "sha2_384".to_string()),
+        ]);
+        //digest algorithm of image must be the one of key
+        assert_eq!(instance.sign(digest.clone(), opts.clone()).unwrap_err().to_string(),
+                   Error::ParameterError("digest algorithm sha2_384 of image mismatches the digest algorithm sha2_256 of key".to_string()).to_string());
+        instance.attributes.insert("digest_algorithm".to_string(), "sha2_384".to_string());
+        let signature = instance.sign(digest.clone(), opts.clone()).expect("sign authenticode successfully");
+        let wincert = efi_signer::Signature::decode(&signature).expect("decode signature successfully");
+        let code = authenticode::AuthenticodeSignature::from_der(wincert.0.get_certificate()).expect("parse authenticode successfully");
+        assert_eq!(code.file_hash(), digest.as_slice());
+        assert_eq!(code.digest_algorithm().unwrap(), "sha2_384");
+        let certificate = x509::X509::from_pem(instance.certificate.unsecure()).expect("load certificate successfully");
+        code.verify(&certificate, &digest).expect("signature matches digest");
+        let info = cms::content_info::ContentInfo::decode(&mut der::SliceReader::new(wincert.0.get_certificate()).unwrap())
+            .expect("parse signed data successfully");
+        let signed_data = info.content.decode_as::<cms::signed_data::SignedData>().expect("decode signed data successfully");
+        assert_eq!(signed_data.version, cms::content_info::CmsVersion::V1);
+        assert_eq!(signed_data.digest_algorithms.as_slice()[0].oid, rfc5912::ID_SHA_384);
+        assert_eq!(signed_data.signer_infos.0.as_slice()[0].digest_alg.oid, rfc5912::ID_SHA_384);
+
+        //digest length must match the algorithm
+        opts.insert(options::DIGEST_ALGORITHM.to_string(), "sha2_512".to_string());
+        instance.attributes.insert("digest_algorithm".to_string(), "sha2_512".to_string());
+        assert_eq!(instance.sign(digest.clone(), opts.clone()).unwrap_err().to_string(),
+                   Error::ParameterError("invalid digest length 48 for sha2_512".to_string()).to_string());
+        opts.insert(options::DIGEST_ALGORITHM.to_string(), "md5".to_string());
+        instance.attributes.insert("digest_algorithm".to_string(), "md5".to_string());
+        assert!(instance.sign(digest, opts).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_certificate_signed_with_digest_algorithm() {
+        let mut parameter = get_default_parameter();
+        let dummy_engine = get_encryption_engine();
+        let infra_config = get_infra_config();
+        for (hash, nid) in [("sha2_256", Nid::SHA256WITHRSAENCRYPTION), ("sha2_384", Nid::SHA384WITHRSAENCRYPTION),
+                            ("sha2_512", Nid::SHA512WITHRSAENCRYPTION), ("sha2_224", Nid::SHA224WITHRSAENCRYPTION)] {
+            parameter.insert("digest_algorithm".to_string(), hash.to_string());
+            let sec_datakey = SecDataKey::load(
+                &get_default_datakey(
+                    None, Some(parameter.clone()), Some(KeyType::X509CA)), &dummy_engine).await.expect("load sec datakey successfully");
+            let plugin = X509Plugin::new(sec_datakey).expect("create plugin successfully");
+            let content = plugin.generate_keys(&KeyType::X509CA, &infra_config).expect("generate ca key successfully");
+            let certificate = x509::X509::from_pem(&content.certificate).expect("parse certificate successfully");
+            assert_eq!(certificate.signature_algorithm().object().nid(), nid);
+        }
+    }
 }
@@ -0,0 +1,63 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use actix_web::{HttpResponse, Responder, Result, web, Scope};
+use validator::Validate;
+
+use crate::util::error::Error;
+use crate::application::audit::AuditService;
+use crate::domain::audit::entity::SignAuditFilter;
+use crate::presentation::handler::control::model::audit::dto::{DEFAULT_PAGE_SIZE, ListSignAuditQuery, PagedSignAuditDTO};
+use super::model::user::dto::UserIdentity;
+
+/// Get sign audit records from database
+///
+/// Records are ordered by create time in descending order, all of the query parameters are optional.
+/// Only the records of the user's own sign requests and the requests signed with the user's keys are returned.
+/// ## Example
+/// Call the api endpoint with following curl.
+/// ```text
+/// curl https://domain:port/api/v1/audit/?key_name=default-pgp&result=failure&page=1&page_size=20
+/// ```
+#[utoipa::path(
+    get,
+    path = "/api/v1/audit/",
+    params(
+        ListSignAuditQuery
+    ),
+    security(
+        ("Authorization" = [])
+    ),
+    responses(
+        (status = 200, description = "List sign audit records", body = PagedSignAuditDTO),
+        (status = 400, description = "Bad request", body = ErrorMessage),
+        (status = 401, description = "Unauthorized", body = ErrorMessage),
+        (status = 500, description = "Server internal error", body = ErrorMessage)
+    )
+)]
+async fn list_sign_audit(user: UserIdentity, audit_service: web::Data<dyn AuditService>, query: web::Query<ListSignAuditQuery>) -> Result<impl Responder, Error> {
+    query.validate()?;
+    let page = query.page.unwrap_or(1);
+    let page_size = query.page_size.unwrap_or(DEFAULT_PAGE_SIZE);
+    let audits = audit_service.into_inner().get_sign_audits(
+        user, SignAuditFilter::try_from(&query.0)?, page, page_size).await?;
+    Ok(HttpResponse::Ok().json(PagedSignAuditDTO::new(audits, page, page_size)))
+}
+
+pub fn get_scope() -> Scope {
+    web::scope("/audit")
+        .service(web::resource("/").route(web::get().to(list_sign_audit)))
+}
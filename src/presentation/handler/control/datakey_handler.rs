@@ -24,6 +24,8 @@ use crate::presentation::handler::control::model::datakey::dto::{CertificateCont
 use crate::util::error::Error;
 use validator::Validate;
 use crate::application::datakey::KeyService;
+use crate::application::user::UserService;
+use crate::presentation::handler::control::model::permission::dto::{CreateKeyPermissionDTO, KeyPermissionDTO};
 use crate::domain::datakey::entity::{DataKey, KeyType, X509RevokeReason};
 use super::model::user::dto::UserIdentity;
 
@@ -564,6 +566,120 @@ async fn import_data_key(user: UserIdentity, key_service: web::Data<dyn KeyServi
 }
 
 
+/// Get sign permissions of specific key by id or name from database
+///
+/// Only the owner of the key is allowed to list permissions. The owner can always sign with the key, the other users
+/// or tokens are allowed to sign only when the permissions are granted.
+/// ## Example
+/// Call the api endpoint with following curl.
+/// ```text
+/// curl https://domain:port/api/v1/keys/{id_or_name}/permissions
+/// ```
+#[utoipa::path(
+    get,
+    path = "/api/v1/keys/{id_or_name}/permissions",
+    params(
+        ("id_or_name" = String, Path, description = "Key id or key name"),
+    ),
+    security(
+        ("Authorization" = [])
+    ),
+    responses(
+        (status = 200, description = "List sign permissions of key", body = [KeyPermissionDTO]),
+        (status = 401, description = "Unauthorized", body = ErrorMessage),
+        (status = 403, description = "Forbidden", body = ErrorMessage),
+        (status = 404, description = "Key not found", body = ErrorMessage),
+        (status = 500, description = "Server internal error", body = ErrorMessage)
+    )
+)]
+async fn list_key_permission(user: UserIdentity, key_service: web::Data<dyn KeyService>, id_or_name: web::Path<String>) -> Result<impl Responder, Error> {
+    let permissions = key_service.into_inner().get_permissions(user, id_or_name.into_inner()).await?;
+    let results: Vec<KeyPermissionDTO> = permissions.into_iter().map(KeyPermissionDTO::from).collect();
+    Ok(HttpResponse::Ok().json(results))
+}
+
+/// Grant sign permission of specific key to user or user's token
+///
+/// Only the owner of the key is allowed to grant permissions, and only openPGP and X509EE keys are supported.
+/// `token_id` is optional, the permission applies to all of the user's tokens if not specified.
+/// ### Request body example:
+/// ```json
+/// {
+///   "email": "test@openeuler.org",
+///   "token_id": 1
+/// }
+/// ```
+/// ## Example
+/// Call the api endpoint with following curl.
+/// ```text
+/// curl -X POST https://domain:port/api/v1/keys/{id_or_name}/permissions -d '{"email": "test@openeuler.org"}'
+/// ```
+#[utoipa::path(
+    post,
+    path = "/api/v1/keys/{id_or_name}/permissions",
+    params(
+        ("id_or_name" = String, Path, description = "Key id or key name"),
+    ),
+    request_body = CreateKeyPermissionDTO,
+    security(
+        ("Authorization" = [])
+    ),
+    responses(
+        (status = 201, description = "Permission successfully granted", body = KeyPermissionDTO),
+        (status = 400, description = "Bad request", body = ErrorMessage),
+        (status = 401, description = "Unauthorized", body = ErrorMessage),
+        (status = 403, description = "Forbidden", body = ErrorMessage),
+        (status = 404, description = "Key or user not found", body = ErrorMessage),
+        (status = 500, description = "Server internal error", body = ErrorMessage)
+    )
+)]
+async fn grant_key_permission(user: UserIdentity, key_service: web::Data<dyn KeyService>, user_service: web::Data<dyn UserService>,
+                              id_or_name: web::Path<String>, permission: web::Json<CreateKeyPermissionDTO>) -> Result<impl Responder, Error> {
+    permission.validate()?;
+    let grantee = user_service.get_by_email(&permission.email).await?;
+    if let Some(token_id) = permission.token_id {
+        let tokens = user_service.get_token(&UserIdentity::from_user(grantee.clone())).await?;
+        if !tokens.iter().any(|t| t.id == token_id) {
+            return Err(Error::ParameterError(format!("token '{}' does not belong to user '{}'", token_id, grantee.email)));
+        }
+    }
+    let created = key_service.into_inner().grant_permission(user, id_or_name.into_inner(), grantee, permission.token_id).await?;
+    Ok(HttpResponse::Created().json(KeyPermissionDTO::from(created)))
+}
+
+/// Revoke sign permission of specific key
+///
+/// Only the owner of the key is allowed to revoke permissions.
+/// ## Example
+/// Call the api endpoint with following curl.
+/// ```text
+/// curl -X DELETE https://domain:port/api/v1/keys/{id_or_name}/permissions/{permission_id}
+/// ```
+#[utoipa::path(
+    delete,
+    path = "/api/v1/keys/{id_or_name}/permissions/{permission_id}",
+    params(
+        ("id_or_name" = String, Path, description = "Key id or key name"),
+        ("permission_id" = i32, Path, description = "Permission id"),
+    ),
+    security(
+        ("Authorization" = [])
+    ),
+    responses(
+        (status = 200, description = "Permission successfully revoked"),
+        (status = 400, description = "Bad request", body = ErrorMessage),
+        (status = 401, description = "Unauthorized", body = ErrorMessage),
+        (status = 403, description = "Forbidden", body = ErrorMessage),
+        (status = 404, description = "Key not found", body = ErrorMessage),
+        (status = 500, description = "Server internal error", body = ErrorMessage)
+    )
+)]
+async fn revoke_key_permission(user: UserIdentity, key_service: web::Data<dyn KeyService>, path: web::Path<(String, i32)>) -> Result<impl Responder, Error> {
+    let (id_or_name, permission_id) = path.into_inner();
+    key_service.into_inner().revoke_permission(user, id_or_name, permission_id).await?;
+    Ok(HttpResponse::Ok())
+}
+
 pub fn get_scope() -> Scope {
     web::scope("/keys")
         .service(
@@ -576,6 +692,10 @@ pub fn get_scope() -> Scope {
         .service( web::resource("/{id_or_name}/public_key").route(web::get().to(export_public_key)))
         .service( web::resource("/{id_or_name}/certificate").route(web::get().to(export_certificate)))
         .service( web::resource("/{id_or_name}/crl").route(web::get().to(export_crl)))
+        .service( web::resource("/{id_or_name}/permissions")
+            .route(web::get().to(list_key_permission))
+            .route(web::post().to(grant_key_permission)))
+        .service( web::resource("/{id_or_name}/permissions/{permission_id}").route(web::delete().to(revoke_key_permission)))
         .service( web::resource("/{id_or_name}/actions/enable").route(web::post().to(enable_data_key)))
         .service( web::resource("/{id_or_name}/actions/disable").route(web::post().to(disable_data_key)))
         .service( web::resource("/{id_or_name}/actions/request_delete").route(web::post().to(delete_data_key)))
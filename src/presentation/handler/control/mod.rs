@@ -1,4 +1,6 @@
 pub mod datakey_handler;
 pub mod user_handler;
 pub mod health_handler;
+pub mod audit_handler;
+pub mod transparency_handler;
 pub mod model;
\ No newline at end of file
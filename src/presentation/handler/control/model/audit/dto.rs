@@ -0,0 +1,150 @@
+use crate::domain::audit::entity::{PagedSignAudit, SignAudit, SignAuditFilter, SignAuditResult};
+use crate::util::error::{Error, Result};
+use crate::util::key::sorted_map;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::{Validate, ValidationError};
+
+pub const DEFAULT_PAGE_SIZE: u32 = 20;
+
+#[derive(Deserialize, IntoParams, Validate, ToSchema)]
+pub struct ListSignAuditQuery {
+    /// Key name, optional
+    pub key_name: Option<String>,
+    /// User email, optional
+    #[validate(email)]
+    pub user_email: Option<String>,
+    /// Sign result, optional, should be one of success or failure
+    #[validate(custom = "validate_sign_result")]
+    pub result: Option<String>,
+    /// Start time in UTC, optional, for example: 2023-06-01 00:00:00+08:00
+    #[validate(custom = "validate_utc_time")]
+    pub start_time: Option<String>,
+    /// End time in UTC, optional, for example: 2023-06-30 00:00:00+08:00
+    #[validate(custom = "validate_utc_time")]
+    pub end_time: Option<String>,
+    /// Page number, optional, starts from 1
+    #[validate(range(min = 1))]
+    pub page: Option<u32>,
+    /// Page size, optional, between 1 and 100, default is 20
+    #[validate(range(min = 1, max = 100))]
+    pub page_size: Option<u32>,
+}
+
+impl TryFrom<&ListSignAuditQuery> for SignAuditFilter {
+    type Error = Error;
+
+    fn try_from(query: &ListSignAuditQuery) -> Result<Self> {
+        Ok(SignAuditFilter {
+            key_name: query.key_name.clone(),
+            user_email: query.user_email.clone(),
+            result: match query.result {
+                Some(ref r) => Some(SignAuditResult::from_str(r)?),
+                None => None,
+            },
+            start_time: match query.start_time {
+                Some(ref t) => Some(t.parse::<DateTime<Utc>>()?),
+                None => None,
+            },
+            end_time: match query.end_time {
+                Some(ref t) => Some(t.parse::<DateTime<Utc>>()?),
+                None => None,
+            },
+            visible_to: None,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SignAuditDTO {
+    /// Audit ID
+    pub id: i64,
+    /// Key ID, absent if request is rejected before key loaded
+    pub key_id: Option<i32>,
+    /// Key name
+    pub key_name: String,
+    /// Key type
+    pub key_type: String,
+    /// User ID
+    pub user_id: i32,
+    /// User email
+    pub user_email: Option<String>,
+    /// Token ID used for signing
+    pub token_id: i32,
+    /// Client address
+    pub client_address: String,
+    /// Sign options
+    #[serde(serialize_with = "sorted_map")]
+    pub sign_options: HashMap<String, String>,
+    /// SHA-256 digest of signed content in hex format
+    pub content_digest: String,
+    /// Source of content digest, server if computed from the content or client if supplied in prehashed signing
+    pub digest_source: String,
+    /// Sign result, success or failure
+    pub result: String,
+    /// Error message if failed
+    pub error: Option<String>,
+    /// Create time
+    pub create_at: String,
+}
+
+impl From<SignAudit> for SignAuditDTO {
+    fn from(audit: SignAudit) -> Self {
+        SignAuditDTO {
+            id: audit.id,
+            key_id: audit.key_id,
+            key_name: audit.key_name,
+            key_type: audit.key_type,
+            user_id: audit.user_id,
+            user_email: audit.user_email,
+            token_id: audit.token_id,
+            client_address: audit.client_address,
+            sign_options: audit.sign_options,
+            content_digest: audit.content_digest,
+            digest_source: audit.digest_source.to_string(),
+            result: audit.result.to_string(),
+            error: audit.error,
+            create_at: audit.create_at.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PagedSignAuditDTO {
+    /// Total count of records matched
+    pub total: i64,
+    /// Current page number
+    pub page: u32,
+    /// Page size
+    pub page_size: u32,
+    /// Audit records
+    pub audits: Vec<SignAuditDTO>,
+}
+
+impl PagedSignAuditDTO {
+    pub fn new(paged: PagedSignAudit, page: u32, page_size: u32) -> Self {
+        PagedSignAuditDTO {
+            total: paged.total,
+            page,
+            page_size,
+            audits: paged.audits.into_iter().map(SignAuditDTO::from).collect(),
+        }
+    }
+}
+
+fn validate_utc_time(time: &str) -> std::result::Result<(), ValidationError> {
+    if time.parse::<DateTime<Utc>>().is_err() {
+        return Err(ValidationError::new("failed to parse time string to utc"));
+    }
+    Ok(())
+}
+
+fn validate_sign_result(result: &str) -> std::result::Result<(), ValidationError> {
+    match SignAuditResult::from_str(result) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(ValidationError::new("unsupported sign result")),
+    }
+}
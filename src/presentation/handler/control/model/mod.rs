@@ -1,3 +1,6 @@
 pub mod datakey;
 pub mod user;
-pub mod token;
\ No newline at end of file
+pub mod token;
+pub mod permission;
+pub mod audit;
+pub mod transparency;
\ No newline at end of file
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use std::convert::From;
+use validator::Validate;
+
+use crate::domain::permission::entity::KeyPermission;
+use utoipa::{ToSchema};
+
+#[derive(Debug, Validate, Deserialize, ToSchema)]
+pub struct CreateKeyPermissionDTO {
+    /// Email of the user who will be granted to sign with the key
+    #[validate(email)]
+    pub email: String,
+    /// Token id, optional, the permission only applies to the specified token of the user if provided
+    pub token_id: Option<i32>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct KeyPermissionDTO {
+    pub id: i32,
+    pub key_id: i32,
+    pub user_id: i32,
+    pub user_email: String,
+    pub token_id: Option<i32>,
+    pub create_at: String,
+}
+
+impl From<KeyPermission> for KeyPermissionDTO {
+    fn from(permission: KeyPermission) -> Self {
+        KeyPermissionDTO {
+            id: permission.id,
+            key_id: permission.key_id,
+            user_id: permission.user_id,
+            user_email: permission.user_email.clone(),
+            token_id: permission.token_id,
+            create_at: permission.create_at.to_string(),
+        }
+    }
+}
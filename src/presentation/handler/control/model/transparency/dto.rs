@@ -0,0 +1,118 @@
+use crate::domain::transparency::entity::{LogLeaf, SignedTreeHead};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+#[derive(Deserialize, IntoParams, Validate, ToSchema)]
+pub struct TreeHeadQuery {
+    /// Tree size, optional, the latest tree head is used if absent
+    #[validate(range(min = 1))]
+    pub tree_size: Option<u64>,
+}
+
+#[derive(Deserialize, IntoParams, Validate, ToSchema)]
+pub struct InclusionProofQuery {
+    /// Index of the leaf, starts from 0
+    pub leaf_index: u64,
+    /// Tree size, optional, the latest tree head is used if absent
+    #[validate(range(min = 1))]
+    pub tree_size: Option<u64>,
+}
+
+#[derive(Deserialize, IntoParams, Validate, ToSchema)]
+pub struct ConsistencyProofQuery {
+    /// Size of the older tree
+    #[validate(range(min = 1))]
+    pub first: u64,
+    /// Size of the newer tree, optional, the latest tree head is used if absent
+    #[validate(range(min = 1))]
+    pub second: Option<u64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TreeHeadDTO {
+    /// Number of leaves in tree
+    pub tree_size: u64,
+    /// Root hash in hex
+    pub root_hash: String,
+    /// Unix timestamp when tree head is signed
+    pub timestamp: i64,
+    /// Name of the log key
+    pub key_name: String,
+    /// Detached signature of tree head payload in hex
+    pub signature: String,
+}
+
+impl From<SignedTreeHead> for TreeHeadDTO {
+    fn from(head: SignedTreeHead) -> Self {
+        Self {
+            tree_size: head.tree_size,
+            root_hash: hex::encode(head.root_hash),
+            timestamp: head.timestamp,
+            key_name: head.key_name,
+            signature: hex::encode(head.signature),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LogLeafDTO {
+    /// Index of the leaf
+    pub leaf_index: u64,
+    /// Name of the key which produced the signature
+    pub key_name: String,
+    /// Digest of signed content
+    pub content_digest: String,
+    /// Digest of signature
+    pub signature_digest: String,
+    /// Leaf hash in hex
+    pub leaf_hash: String,
+}
+
+impl From<LogLeaf> for LogLeafDTO {
+    fn from(leaf: LogLeaf) -> Self {
+        Self {
+            leaf_index: leaf.leaf_index,
+            key_name: leaf.key_name,
+            content_digest: leaf.content_digest,
+            signature_digest: leaf.signature_digest,
+            leaf_hash: hex::encode(leaf.leaf_hash),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InclusionProofDTO {
+    pub leaf: LogLeafDTO,
+    /// Audit path from the leaf to the root in hex
+    pub proof: Vec<String>,
+    pub tree_head: TreeHeadDTO,
+}
+
+impl From<(LogLeaf, Vec<Vec<u8>>, SignedTreeHead)> for InclusionProofDTO {
+    fn from((leaf, proof, tree_head): (LogLeaf, Vec<Vec<u8>>, SignedTreeHead)) -> Self {
+        Self {
+            leaf: LogLeafDTO::from(leaf),
+            proof: proof.into_iter().map(hex::encode).collect(),
+            tree_head: TreeHeadDTO::from(tree_head),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConsistencyProofDTO {
+    pub first: TreeHeadDTO,
+    /// Consistency proof between the two trees in hex
+    pub proof: Vec<String>,
+    pub second: TreeHeadDTO,
+}
+
+impl From<(SignedTreeHead, Vec<Vec<u8>>, SignedTreeHead)> for ConsistencyProofDTO {
+    fn from((first, proof, second): (SignedTreeHead, Vec<Vec<u8>>, SignedTreeHead)) -> Self {
+        Self {
+            first: TreeHeadDTO::from(first),
+            proof: proof.into_iter().map(hex::encode).collect(),
+            second: TreeHeadDTO::from(second),
+        }
+    }
+}
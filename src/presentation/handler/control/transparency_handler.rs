@@ -0,0 +1,112 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use actix_web::{HttpResponse, Responder, Result, web, Scope};
+use validator::Validate;
+
+use crate::util::error::Error;
+use crate::application::transparency::TransparencyService;
+use crate::presentation::handler::control::model::transparency::dto::{
+    ConsistencyProofDTO, ConsistencyProofQuery, InclusionProofDTO, InclusionProofQuery, TreeHeadDTO, TreeHeadQuery};
+
+/// Get the signed tree head of transparency log
+///
+/// The tree head is public and the api doesn't require authorization, the latest tree head is returned
+/// if tree size is absent.
+/// ## Example
+/// Call the api endpoint with following curl.
+/// ```text
+/// curl https://domain:port/api/v1/transparency/tree_head/?tree_size=10
+/// ```
+#[utoipa::path(
+    get,
+    path = "/api/v1/transparency/tree_head/",
+    params(
+        TreeHeadQuery
+    ),
+    responses(
+        (status = 200, description = "Signed tree head", body = TreeHeadDTO),
+        (status = 400, description = "Bad request", body = ErrorMessage),
+        (status = 404, description = "Tree head not found", body = ErrorMessage),
+        (status = 500, description = "Server internal error", body = ErrorMessage)
+    )
+)]
+async fn get_tree_head(transparency_service: web::Data<dyn TransparencyService>, query: web::Query<TreeHeadQuery>) -> Result<impl Responder, Error> {
+    query.validate()?;
+    let tree_head = transparency_service.into_inner().get_tree_head(query.tree_size).await?;
+    Ok(HttpResponse::Ok().json(TreeHeadDTO::from(tree_head)))
+}
+
+/// Get the inclusion proof of leaf in transparency log
+///
+/// The proof is computed upon the signed tree head of specified size, or the latest one if tree size is absent.
+/// ## Example
+/// Call the api endpoint with following curl.
+/// ```text
+/// curl https://domain:port/api/v1/transparency/proof/inclusion/?leaf_index=3&tree_size=10
+/// ```
+#[utoipa::path(
+    get,
+    path = "/api/v1/transparency/proof/inclusion/",
+    params(
+        InclusionProofQuery
+    ),
+    responses(
+        (status = 200, description = "Inclusion proof of leaf", body = InclusionProofDTO),
+        (status = 400, description = "Bad request", body = ErrorMessage),
+        (status = 404, description = "Leaf or tree head not found", body = ErrorMessage),
+        (status = 500, description = "Server internal error", body = ErrorMessage)
+    )
+)]
+async fn get_inclusion_proof(transparency_service: web::Data<dyn TransparencyService>, query: web::Query<InclusionProofQuery>) -> Result<impl Responder, Error> {
+    query.validate()?;
+    let proof = transparency_service.into_inner().get_inclusion_proof(query.leaf_index, query.tree_size).await?;
+    Ok(HttpResponse::Ok().json(InclusionProofDTO::from(proof)))
+}
+
+/// Get the consistency proof between two signed tree heads of transparency log
+///
+/// The newer tree is the latest one if second is absent.
+/// ## Example
+/// Call the api endpoint with following curl.
+/// ```text
+/// curl https://domain:port/api/v1/transparency/proof/consistency/?first=5&second=10
+/// ```
+#[utoipa::path(
+    get,
+    path = "/api/v1/transparency/proof/consistency/",
+    params(
+        ConsistencyProofQuery
+    ),
+    responses(
+        (status = 200, description = "Consistency proof between tree heads", body = ConsistencyProofDTO),
+        (status = 400, description = "Bad request", body = ErrorMessage),
+        (status = 404, description = "Tree head not found", body = ErrorMessage),
+        (status = 500, description = "Server internal error", body = ErrorMessage)
+    )
+)]
+async fn get_consistency_proof(transparency_service: web::Data<dyn TransparencyService>, query: web::Query<ConsistencyProofQuery>) -> Result<impl Responder, Error> {
+    query.validate()?;
+    let proof = transparency_service.into_inner().get_consistency_proof(query.first, query.second).await?;
+    Ok(HttpResponse::Ok().json(ConsistencyProofDTO::from(proof)))
+}
+
+pub fn get_scope() -> Scope {
+    web::scope("/transparency")
+        .service(web::resource("/tree_head/").route(web::get().to(get_tree_head)))
+        .service(web::resource("/proof/inclusion/").route(web::get().to(get_inclusion_proof)))
+        .service(web::resource("/proof/consistency/").route(web::get().to(get_consistency_proof)))
+}
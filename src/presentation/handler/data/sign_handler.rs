@@ -23,11 +23,17 @@ use tokio_stream::StreamExt;
 
 use signatrust::{
     signatrust_server::Signatrust, signatrust_server::SignatrustServer, SignStreamRequest,
-    SignStreamResponse,
+    SignStreamResponse, SignDigestRequest, TransparencyLogEntry, SignedTreeHead,
 };
 use tonic::{Request, Response, Status, Streaming};
+use tonic::metadata::MetadataMap;
 use crate::application::datakey::KeyService;
 use crate::application::user::UserService;
+use crate::domain::token::entity::Token;
+use crate::domain::transparency::entity::LogEntry;
+use crate::util::digest::{ContentDigest, Sha256State};
+use crate::util::error::Result as SignResult;
+use crate::util::options;
 
 pub struct SignHandler<K, U>
 where
@@ -49,6 +55,61 @@ where
             user_service
         }
     }
+
+    async fn validate_token(&self, metadata: &MetadataMap) -> Result<Token, Status> {
+        let token = match metadata.get(options::AUTH_METADATA_KEY) {
+            Some(value) => value.to_str().map_err(|_| Status::unauthenticated("invalid api token"))?,
+            None => return Err(Status::unauthenticated("api token is required"))
+        };
+        match self.user_service.get_valid_token(token).await {
+            Ok(token) => Ok(token),
+            Err(err) => {
+                warn!("rejected sign request with invalid api token: {}", err);
+                Err(Status::unauthenticated("invalid or expired api token"))
+            }
+        }
+    }
+}
+
+fn get_sign_response(result: SignResult<(Vec<u8>, String, Option<LogEntry>)>) -> SignStreamResponse {
+    match result {
+        Ok((content, key_fingerprint, log_entry)) => {
+            SignStreamResponse {
+                signature: content,
+                error: "".to_string(),
+                log_entry: log_entry.map(TransparencyLogEntry::from),
+                key_fingerprint,
+            }
+        }
+        Err(err) => {
+            SignStreamResponse {
+                signature: vec![],
+                error: err.to_string(),
+                log_entry: None,
+                key_fingerprint: "".to_string(),
+            }
+        }
+    }
+}
+
+impl From<LogEntry> for TransparencyLogEntry {
+    fn from(entry: LogEntry) -> Self {
+        TransparencyLogEntry {
+            leaf_index: entry.leaf.leaf_index,
+            key_name: entry.leaf.key_name,
+            content_digest: entry.leaf.content_digest,
+            signature_digest: entry.leaf.signature_digest,
+            leaf_hash: entry.leaf.leaf_hash,
+            inclusion_proof: entry.inclusion_proof,
+            tree_head: Some(SignedTreeHead {
+                tree_size: entry.tree_head.tree_size,
+                root_hash: entry.tree_head.root_hash,
+                timestamp: entry.tree_head.timestamp,
+                key_name: entry.tree_head.key_name,
+                signature: entry.tree_head.signature,
+            }),
+        }
+    }
 }
 
 #[tonic::async_trait]
@@ -61,6 +122,11 @@ where
         &self,
         request: Request<Streaming<SignStreamRequest>>,
     ) -> Result<Response<SignStreamResponse>, Status> {
+        let token = self.validate_token(request.metadata()).await?;
+        let client_address = match request.remote_addr() {
+            Some(addr) => addr.to_string(),
+            None => "unknown".to_string(),
+        };
         let mut binaries = request.into_inner();
         let mut data: Vec<u8> = vec![];
         let mut key_name: String = "".to_string();
@@ -73,21 +139,32 @@ where
             key_type = inner_result.key_type;
             options = inner_result.options;
         }
-        debug!("begin to sign key_type :{} key_name: {}", key_type, key_name);
-        match self.key_service.sign(key_type, key_name, &options, data).await {
-            Ok(content) => {
-                Ok(Response::new(SignStreamResponse {
-                    signature: content,
-                    error: "".to_string()
-                }))
-            }
-            Err(err) => {
-                Ok(Response::new(SignStreamResponse {
-                    signature: vec![],
-                    error: err.to_string(),
-                }))
-            }
-        }
+        debug!("begin to sign key_type :{} key_name: {} with token {} of user {}", key_type, key_name, token.id, token.user_id);
+        let result = self.key_service.sign(&token, client_address, key_type, key_name, &options, data).await;
+        Ok(Response::new(get_sign_response(result)))
+    }
+
+    async fn sign_digest(
+        &self,
+        request: Request<SignDigestRequest>,
+    ) -> Result<Response<SignStreamResponse>, Status> {
+        let token = self.validate_token(request.metadata()).await?;
+        let client_address = match request.remote_addr() {
+            Some(addr) => addr.to_string(),
+            None => "unknown".to_string(),
+        };
+        let request = request.into_inner();
+        debug!("begin to sign digest key_type :{} key_name: {} with token {} of user {}", request.key_type, request.key_id, token.id, token.user_id);
+        let digest = match request.hash_state {
+            Some(state) => Sha256State::from_parts(&state.state, state.length, state.tail).map(Some),
+            None => Ok(None),
+        }.and_then(|state| ContentDigest::new(request.digest_algorithm, request.digest, state));
+        let result = match digest {
+            Ok(digest) => self.key_service.sign_prehashed(
+                &token, client_address, request.key_type, request.key_id, &request.options, digest).await,
+            Err(err) => Err(err),
+        };
+        Ok(Response::new(get_sign_response(result)))
     }
 }
 
@@ -41,9 +41,14 @@ use crate::util::error::Result;
 
 use crate::application::datakey::{DBKeyService, KeyService};
 use crate::infra::database::model::token::repository::TokenRepository;
+use crate::infra::database::model::permission::repository::KeyPermissionRepository;
+use crate::infra::database::model::audit::repository::SignAuditRepository;
+use crate::infra::database::model::transparency::repository::TransparencyLogRepository;
 use crate::infra::database::model::user::repository::UserRepository;
 use crate::infra::sign_backend::factory::SignBackendFactory;
 use crate::application::user::{DBUserService, UserService};
+use crate::application::audit::{AuditService, DBAuditService};
+use crate::application::transparency::{TransparencyService, DBTransparencyService};
 use crate::domain::datakey::entity::DataKey;
 use crate::domain::token::entity::Token;
 use crate::domain::user::entity::User;
@@ -55,6 +60,8 @@ pub struct ControlServer {
     server_config: Arc<RwLock<Config>>,
     user_service: Arc<dyn UserService>,
     key_service: Arc<dyn KeyService>,
+    audit_service: Arc<dyn AuditService>,
+    transparency_service: Arc<dyn TransparencyService>,
     cancel_token: CancellationToken,
 }
 
@@ -87,6 +94,9 @@ impl Modify for SecurityAddon {
         crate::presentation::handler::control::datakey_handler::disable_data_key,
         crate::presentation::handler::control::datakey_handler::import_data_key,
         crate::presentation::handler::control::datakey_handler::key_name_identical,
+        crate::presentation::handler::control::datakey_handler::list_key_permission,
+        crate::presentation::handler::control::datakey_handler::grant_key_permission,
+        crate::presentation::handler::control::datakey_handler::revoke_key_permission,
 
         crate::presentation::handler::control::user_handler::login,
         crate::presentation::handler::control::user_handler::callback,
@@ -96,6 +106,12 @@ impl Modify for SecurityAddon {
         crate::presentation::handler::control::user_handler::list_token,
         crate::presentation::handler::control::user_handler::delete_token,
 
+        crate::presentation::handler::control::audit_handler::list_sign_audit,
+
+        crate::presentation::handler::control::transparency_handler::get_tree_head,
+        crate::presentation::handler::control::transparency_handler::get_inclusion_proof,
+        crate::presentation::handler::control::transparency_handler::get_consistency_proof,
+
         crate::presentation::handler::control::health_handler::health,
     ),
     components(
@@ -105,6 +121,18 @@ impl Modify for SecurityAddon {
                 crate::presentation::handler::control::model::datakey::dto::RevokeCertificateDTO,
                 crate::presentation::handler::control::model::datakey::dto::NameIdenticalQuery,
                 crate::presentation::handler::control::model::datakey::dto::ListKeyQuery,
+                crate::presentation::handler::control::model::permission::dto::KeyPermissionDTO,
+                crate::presentation::handler::control::model::permission::dto::CreateKeyPermissionDTO,
+                crate::presentation::handler::control::model::audit::dto::SignAuditDTO,
+                crate::presentation::handler::control::model::audit::dto::PagedSignAuditDTO,
+                crate::presentation::handler::control::model::audit::dto::ListSignAuditQuery,
+                crate::presentation::handler::control::model::transparency::dto::TreeHeadDTO,
+                crate::presentation::handler::control::model::transparency::dto::LogLeafDTO,
+                crate::presentation::handler::control::model::transparency::dto::InclusionProofDTO,
+                crate::presentation::handler::control::model::transparency::dto::ConsistencyProofDTO,
+                crate::presentation::handler::control::model::transparency::dto::TreeHeadQuery,
+                crate::presentation::handler::control::model::transparency::dto::InclusionProofQuery,
+                crate::presentation::handler::control::model::transparency::dto::ConsistencyProofQuery,
                 crate::presentation::handler::control::model::token::dto::TokenDTO,
                 crate::presentation::handler::control::model::token::dto::CreateTokenDTO,
                 crate::presentation::handler::control::model::user::dto::UserIdentity,
@@ -127,6 +155,9 @@ impl ControlServer {
         //initialize repos
         let user_repo = UserRepository::new(get_db_pool()?);
         let token_repo = TokenRepository::new(get_db_pool()?);
+        let permission_repo = KeyPermissionRepository::new(get_db_pool()?);
+        let audit_repo = SignAuditRepository::new(get_db_pool()?);
+        let log_repo = TransparencyLogRepository::new(get_db_pool()?);
 
         //initialize the service
         let user_service = Arc::new(
@@ -135,10 +166,16 @@ impl ControlServer {
                 server_config.clone())?) as Arc<dyn UserService>;
         let key_service = Arc::new(
             DBKeyService::new(
-                data_repository, sign_backend)) as Arc<dyn KeyService>;
+                data_repository, permission_repo, audit_repo.clone(), log_repo.clone(), sign_backend)) as Arc<dyn KeyService>;
+        let audit_service = Arc::new(
+            DBAuditService::new(audit_repo)) as Arc<dyn AuditService>;
+        let transparency_service = Arc::new(
+            DBTransparencyService::new(log_repo)) as Arc<dyn TransparencyService>;
         let server = ControlServer {
             user_service,
             key_service,
+            audit_service,
+            transparency_service,
             server_config,
             cancel_token,
         };
@@ -167,6 +204,10 @@ impl ControlServer {
             self.user_service.clone());
         let key_service = web::Data::from(
             self.key_service.clone());
+        let audit_service = web::Data::from(
+            self.audit_service.clone());
+        let transparency_service = web::Data::from(
+            self.transparency_service.clone());
 
         key_service.start_key_rotate_loop(self.cancel_token.clone())?;
         key_service.start_key_plugin_maintenance(
@@ -221,6 +262,8 @@ impl ControlServer {
                 .app_data(csrf_protect_key.clone())
                 .app_data(key_service.clone())
                 .app_data(user_service.clone())
+                .app_data(audit_service.clone())
+                .app_data(transparency_service.clone())
                 .app_data(limiter.clone())
                 //open api document
                 .service(
@@ -228,7 +271,9 @@ impl ControlServer {
                 )
                 .service(web::scope("/api/v1")
                     .service(user_handler::get_scope())
-                    .service(datakey_handler::get_scope()))
+                    .service(datakey_handler::get_scope())
+                    .service(audit_handler::get_scope())
+                    .service(transparency_handler::get_scope()))
                 .service(web::scope("/api")
                     .service(health_handler::get_scope()))
         });
@@ -30,6 +30,9 @@ use crate::application::user::{DBUserService, UserService};
 
 use crate::infra::database::model::datakey::repository;
 use crate::infra::database::model::token::repository::TokenRepository;
+use crate::infra::database::model::permission::repository::KeyPermissionRepository;
+use crate::infra::database::model::audit::repository::SignAuditRepository;
+use crate::infra::database::model::transparency::repository::TransparencyLogRepository;
 use crate::infra::database::model::user::repository::UserRepository;
 use crate::infra::database::pool::{create_pool, get_db_pool};
 use crate::infra::sign_backend::factory::SignBackendFactory;
@@ -116,7 +119,16 @@ impl DataServer {
             self.server_config.clone(), get_db_pool()?).await?;
         let data_repository = repository::DataKeyRepository::new(
             get_db_pool()?);
-        let key_service = DBKeyService::new(data_repository, sign_backend);
+        let permission_repository = KeyPermissionRepository::new(get_db_pool()?);
+        let audit_repository = SignAuditRepository::new(get_db_pool()?);
+        let log_repository = TransparencyLogRepository::new(get_db_pool()?);
+        let mut key_service = DBKeyService::new(data_repository, permission_repository, audit_repository, log_repository, sign_backend);
+        if self.server_config.read()?.get_bool("transparency-log.enabled").unwrap_or(false) {
+            let key_type = self.server_config.read()?.get_string("transparency-log.key_type")?;
+            let key_name = self.server_config.read()?.get_string("transparency-log.key_name")?;
+            info!("transparency log enabled, tree head will be signed with {} key {}", key_type, key_name);
+            key_service.enable_transparency_log(key_type, key_name);
+        }
         let user_repo = UserRepository::new(get_db_pool()?);
         let token_repo = TokenRepository::new(get_db_pool()?);
         let user_service = DBUserService::new(user_repo, token_repo, self.server_config.clone())?;
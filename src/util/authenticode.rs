@@ -0,0 +1,244 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+use cms::builder::{create_content_type_attribute, create_message_digest_attribute};
+use cms::cert::{CertificateChoices, IssuerAndSerialNumber};
+use cms::content_info::{CmsVersion, ContentInfo};
+use cms::signed_data::{CertificateSet, EncapsulatedContentInfo, SignedData, SignerIdentifier, SignerInfo, SignerInfos};
+use const_oid::db::{rfc5911, rfc5912};
+use const_oid::ObjectIdentifier;
+use der::asn1::{Null, OctetString, SetOfVec};
+use der::{Any, Decode, Encode, Reader, Sequence, SliceReader};
+use openssl::hash::{hash, MessageDigest};
+use openssl::pkcs7::{Pkcs7, Pkcs7Flags};
+use openssl::stack::Stack;
+use openssl::x509::store::X509StoreBuilder;
+use openssl::x509::X509;
+use spki::AlgorithmIdentifierOwned;
+use x509_cert::attr::Attribute;
+use crate::util::error::{Error, Result};
+
+const SPC_INDIRECT_DATA_OBJID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.2.1.4");
+const SPC_SP_OPUS_INFO_OBJID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.2.1.12");
+const SPC_PE_IMAGE_DATAOBJ: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.2.1.15");
+//SpcPeImageData with 'includeResources' flag and the obsolete file link, it's the same as the one generated by efi_signer
+const SPC_PE_IMAGE_DATA: [u8; 40] = [
+    0x30, 0x26, 0x03, 0x02, 0x05, 0x80, 0xa0, 0x20, 0xa2, 0x1e, 0x80, 0x1c, 0x00, 0x3c, 0x00, 0x3c, 0x00, 0x3c, 0x00,
+    0x4f, 0x00, 0x62, 0x00, 0x73, 0x00, 0x6f, 0x00, 0x6c, 0x00, 0x65, 0x00, 0x74, 0x00, 0x65, 0x00, 0x3e, 0x00, 0x3e,
+    0x00, 0x3e];
+//SpcSpOpusInfo with the obsolete file link as 'moreInfo'
+const SPC_SP_OPUS_INFO: [u8; 36] = [
+    0x30, 0x22, 0xa1, 0x20, 0xa2, 0x1e, 0x80, 0x1c, 0x00, 0x3c, 0x00, 0x3c, 0x00, 0x3c, 0x00, 0x4f, 0x00, 0x62, 0x00,
+    0x73, 0x00, 0x6f, 0x00, 0x6c, 0x00, 0x65, 0x00, 0x74, 0x00, 0x65, 0x00, 0x3e, 0x00, 0x3e, 0x00, 0x3e];
+const WIN_CERT_REVISION_2_0: u16 = 0x0200;
+const WIN_CERT_TYPE_PKCS_SIGNED_DATA: u16 = 0x0002;
+
+/// SpcIndirectDataContent, reference: Windows Authenticode Portable Executable Signature Format
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct SpcIndirectDataContent {
+    data: SpcAttributeTypeAndOptionalValue,
+    message_digest: DigestInfo,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct SpcAttributeTypeAndOptionalValue {
+    value_type: ObjectIdentifier,
+    #[asn1(optional = "true")]
+    value: Option<Any>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct DigestInfo {
+    digest_algorithm: AlgorithmIdentifierOwned,
+    digest: OctetString,
+}
+
+//the digest algorithms of authenticode are identified with the same names of client option
+fn get_digest_algorithm(algorithm: &str) -> Result<(ObjectIdentifier, MessageDigest)> {
+    match algorithm {
+        "sha2_256" => Ok((rfc5912::ID_SHA_256, MessageDigest::sha256())),
+        "sha2_384" => Ok((rfc5912::ID_SHA_384, MessageDigest::sha384())),
+        "sha2_512" => Ok((rfc5912::ID_SHA_512, MessageDigest::sha512())),
+        _ => Err(Error::UnsupportedTypeError(format!("digest algorithm {} for authenticode signature", algorithm))),
+    }
+}
+
+fn get_digest_name(oid: &ObjectIdentifier) -> Result<&'static str> {
+    ["sha2_256", "sha2_384", "sha2_512"].into_iter().find(
+        |name| matches!(get_digest_algorithm(name), Ok((id, _)) if id == *oid)).ok_or_else(
+        || Error::UnsupportedTypeError(format!("digest algorithm {} of authenticode signature", oid)))
+}
+
+/// Generate the authenticode signature (PKCS#7 signed data) of the image digest, the signed attributes are signed
+/// by `sign` with the key of `certificate` and the `signature_algorithm` is recorded in signer info.
+pub fn sign<F>(certificate: &X509, algorithm: &str, signature_algorithm: AlgorithmIdentifierOwned, digest: &[u8], sign: F) -> Result<Vec<u8>>
+    where F: FnOnce(&[u8]) -> Result<Vec<u8>> {
+    let (oid, message_digest) = get_digest_algorithm(algorithm)?;
+    if digest.len() != message_digest.size() {
+        return Err(Error::ParameterError(format!("invalid digest length {} for {}", digest.len(), algorithm)))
+    }
+    let digest_algorithm = AlgorithmIdentifierOwned { oid, parameters: Some(Any::from(Null)) };
+    let content = Any::encode_from(&SpcIndirectDataContent {
+        data: SpcAttributeTypeAndOptionalValue {
+            value_type: SPC_PE_IMAGE_DATAOBJ,
+            value: Some(Any::from_der(&SPC_PE_IMAGE_DATA)?),
+        },
+        message_digest: DigestInfo {
+            digest_algorithm: digest_algorithm.clone(),
+            digest: OctetString::new(digest)?,
+        },
+    })?;
+    //only the value of SpcIndirectDataContent is hashed, the tag and length are excluded
+    let signed_attributes = SetOfVec::try_from(vec![
+        create_content_type_attribute(SPC_INDIRECT_DATA_OBJID)?,
+        Attribute { oid: SPC_SP_OPUS_INFO_OBJID, values: SetOfVec::try_from(vec![Any::from_der(&SPC_SP_OPUS_INFO)?])? },
+        create_message_digest_attribute(&hash(message_digest, content.value())?)?,
+    ])?;
+    let signature = sign(&signed_attributes.to_der()?)?;
+    let certificate = x509_cert::Certificate::from_der(&certificate.to_der()?)?;
+    let signer_info = SignerInfo {
+        version: CmsVersion::V1,
+        sid: SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+            issuer: certificate.tbs_certificate.issuer.clone(),
+            serial_number: certificate.tbs_certificate.serial_number.clone(),
+        }),
+        digest_alg: digest_algorithm.clone(),
+        signed_attrs: Some(signed_attributes),
+        signature_algorithm,
+        signature: OctetString::new(signature)?,
+        unsigned_attrs: None,
+    };
+    //authenticode requires the version 1 of signed data which can't be generated by cms builder
+    let signed_data = SignedData {
+        version: CmsVersion::V1,
+        digest_algorithms: SetOfVec::try_from(vec![digest_algorithm])?,
+        encap_content_info: EncapsulatedContentInfo {
+            econtent_type: SPC_INDIRECT_DATA_OBJID,
+            econtent: Some(content),
+        },
+        certificates: Some(CertificateSet(SetOfVec::try_from(vec![CertificateChoices::Certificate(certificate)])?)),
+        crls: None,
+        signer_infos: SignerInfos(SetOfVec::try_from(vec![signer_info])?),
+    };
+    Ok(ContentInfo {
+        content_type: rfc5911::ID_SIGNED_DATA,
+        content: Any::encode_from(&signed_data)?,
+    }.to_der()?)
+}
+
+/// Wrap the authenticode signature into WIN_CERTIFICATE structure, which is 8 bytes aligned in the image.
+pub fn encode_win_certificate(signature: &[u8]) -> Vec<u8> {
+    let padding = (8 - signature.len() % 8) % 8;
+    let mut result = Vec::with_capacity(8 + signature.len() + padding);
+    result.extend_from_slice(&((8 + signature.len() + padding) as u32).to_le_bytes());
+    result.extend_from_slice(&WIN_CERT_REVISION_2_0.to_le_bytes());
+    result.extend_from_slice(&WIN_CERT_TYPE_PKCS_SIGNED_DATA.to_le_bytes());
+    result.extend_from_slice(signature);
+    result.resize(result.len() + padding, 0);
+    result
+}
+
+pub struct AuthenticodeSignature {
+    raw: Vec<u8>,
+    signed_data: SignedData,
+    content: SpcIndirectDataContent,
+}
+
+impl AuthenticodeSignature {
+    pub fn from_der(signature: &[u8]) -> Result<Self> {
+        //signatures in image are followed by the padding of WIN_CERTIFICATE
+        let mut reader = SliceReader::new(signature)?;
+        let content_info = ContentInfo::decode(&mut reader)?;
+        let raw = signature[..u32::from(reader.position()) as usize].to_vec();
+        if content_info.content_type != rfc5911::ID_SIGNED_DATA {
+            return Err(Error::VerifyError(format!("unexpected content type {} of authenticode signature", content_info.content_type)))
+        }
+        let signed_data: SignedData = content_info.content.decode_as()?;
+        let content = match &signed_data.encap_content_info.econtent {
+            Some(content) if signed_data.encap_content_info.econtent_type == SPC_INDIRECT_DATA_OBJID => content.decode_as()?,
+            _ => return Err(Error::VerifyError("authenticode signature doesn't contain SpcIndirectDataContent".to_string())),
+        };
+        Ok(Self { raw, signed_data, content })
+    }
+
+    /// The image digest which is signed.
+    pub fn file_hash(&self) -> &[u8] {
+        self.content.message_digest.digest.as_bytes()
+    }
+
+    /// The name of algorithm used for image digest, i.e. sha2_256, sha2_384 or sha2_512.
+    pub fn digest_algorithm(&self) -> Result<&'static str> {
+        get_digest_name(&self.content.message_digest.digest_algorithm.oid)
+    }
+
+    /// Verify that the signature is generated from the image digest with the key of certificate, the certificate
+    /// chain is not verified.
+    pub fn verify(&self, certificate: &X509, digest: &[u8]) -> Result<()> {
+        if self.file_hash() != digest {
+            return Err(Error::VerifyError("authenticode signature mismatches the image digest".to_string()))
+        }
+        let signer_info = match self.signed_data.signer_infos.0.as_slice() {
+            [signer_info] => signer_info,
+            _ => return Err(Error::VerifyError("authenticode signature must contain exactly one signer".to_string())),
+        };
+        let cert = x509_cert::Certificate::from_der(&certificate.to_der()?)?;
+        match &signer_info.sid {
+            SignerIdentifier::IssuerAndSerialNumber(sid) if sid.issuer == cert.tbs_certificate.issuer
+                && sid.serial_number == cert.tbs_certificate.serial_number => {}
+            _ => return Err(Error::VerifyError("authenticode signature is signed by another certificate".to_string())),
+        }
+        if signer_info.digest_alg.oid != self.content.message_digest.digest_algorithm.oid {
+            return Err(Error::VerifyError("digest algorithms of signer and image mismatch".to_string()))
+        }
+        let signed_attributes = signer_info.signed_attrs.as_ref().ok_or_else(
+            || Error::VerifyError("authenticode signature doesn't contain signed attributes".to_string()))?;
+        let content_type = signed_attributes.iter().find(|attribute| attribute.oid == rfc5911::ID_CONTENT_TYPE)
+            .and_then(|attribute| attribute.values.iter().next()).ok_or_else(
+            || Error::VerifyError("content type attribute not found in authenticode signature".to_string()))?;
+        if content_type.decode_as::<ObjectIdentifier>()? != SPC_INDIRECT_DATA_OBJID {
+            return Err(Error::VerifyError("content type attribute mismatches SpcIndirectDataContent".to_string()))
+        }
+        //the signed attributes are verified in their original order which is lost in decoding, openssl verifies the
+        //message digest of the value of SpcIndirectDataContent and the signature of signed attributes.
+        let content = Any::encode_from(&self.content)?;
+        let mut certificates = Stack::new()?;
+        certificates.push(certificate.clone())?;
+        Pkcs7::from_der(&self.raw)?.verify(
+            &certificates, &X509StoreBuilder::new()?.build(), Some(content.value()), None,
+            Pkcs7Flags::NOINTERN | Pkcs7Flags::NOVERIFY | Pkcs7Flags::BINARY).map_err(|e| Error::VerifyError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_signature_generated_by_efi_signer() {
+        let signature = std::fs::read(env::current_dir().unwrap().join("test_assets").join("efi.sign")).unwrap();
+        let authenticode = AuthenticodeSignature::from_der(&signature[8..]).expect("parse authenticode successfully");
+        assert_eq!(encode_win_certificate(&signature[8..]), signature);
+        assert_eq!(authenticode.digest_algorithm().unwrap(), "sha2_256");
+        assert_eq!(authenticode.content.data.value, Some(Any::from_der(&SPC_PE_IMAGE_DATA).unwrap()));
+        let certificate = match authenticode.signed_data.certificates.as_ref().unwrap().0.iter().next().unwrap() {
+            CertificateChoices::Certificate(certificate) => X509::from_der(&certificate.to_der().unwrap()).unwrap(),
+            _ => panic!("signer certificate not found"),
+        };
+        let digest = authenticode.file_hash().to_vec();
+        authenticode.verify(&certificate, &digest).expect("signature matches");
+        authenticode.verify(&certificate, &[0; 32]).expect_err("signature mismatches digest");
+    }
+}
@@ -0,0 +1,454 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+//canonicalization used in embedded signatures of structured documents, no DTD is supported in xml documents.
+use crate::util::error::{Error, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+//JSON Canonicalization Scheme, check https://www.rfc-editor.org/rfc/rfc8785
+pub fn canonicalize_json(value: &Value) -> Result<String> {
+    let mut output = String::new();
+    write_json(value, &mut output)?;
+    Ok(output)
+}
+
+fn write_json(value: &Value, output: &mut String) -> Result<()> {
+    match value {
+        Value::Number(number) => {
+            if number.is_f64() {
+                output.push_str(&format_number(number.as_f64().unwrap_or_default()));
+            } else {
+                output.push_str(&number.to_string());
+            }
+        }
+        Value::Array(array) => {
+            output.push('[');
+            for (index, item) in array.iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                write_json(item, output)?;
+            }
+            output.push(']');
+        }
+        Value::Object(object) => {
+            //members are sorted by the UTF-16 code units of keys
+            let mut keys: Vec<&String> = object.keys().collect();
+            keys.sort_by(|a, b| a.encode_utf16().cmp(b.encode_utf16()));
+            output.push('{');
+            for (index, key) in keys.into_iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                output.push_str(&serde_json::to_string(key)?);
+                output.push(':');
+                write_json(&object[key], output)?;
+            }
+            output.push('}');
+        }
+        _ => output.push_str(&serde_json::to_string(value)?),
+    }
+    Ok(())
+}
+
+//serialize number in the same way as ECMAScript Number.prototype.toString
+fn format_number(number: f64) -> String {
+    if number == 0.0 {
+        return "0".to_string()
+    }
+    //rust formats the shortest representation which round trips, such as '3.333333333333333e8'
+    let formatted = format!("{:e}", number.abs());
+    let (mantissa, exponent) = formatted.split_once('e').unwrap_or((&formatted, "0"));
+    let digits = mantissa.replace('.', "");
+    let length = digits.len() as i32;
+    let point = exponent.parse::<i32>().unwrap_or_default() + 1;
+    let sign = if number < 0.0 { "-" } else { "" };
+    if length <= point && point <= 21 {
+        format!("{}{}{}", sign, digits, "0".repeat((point - length) as usize))
+    } else if 0 < point && point <= 21 {
+        format!("{}{}.{}", sign, &digits[..point as usize], &digits[point as usize..])
+    } else if -6 < point && point <= 0 {
+        format!("{}0.{}{}", sign, "0".repeat(-point as usize), digits)
+    } else {
+        let fraction = if length > 1 { format!(".{}", &digits[1..]) } else { String::new() };
+        let exponent_sign = if point - 1 > 0 { "+" } else { "-" };
+        format!("{}{}{}e{}{}", sign, &digits[..1], fraction, exponent_sign, (point - 1).abs())
+    }
+}
+
+struct XmlAttribute {
+    name: String,
+    value: String,
+}
+
+struct XmlCanonicalizer<'a> {
+    input: &'a str,
+    position: usize,
+    output: String,
+    //namespace declarations in scope of every open element
+    namespaces: Vec<HashMap<String, String>>,
+    elements: Vec<String>,
+    root_closed: bool,
+}
+
+/* Canonical XML 1.0 without comments of the whole document,
+ * check https://www.w3.org/TR/2001/REC-xml-c14n-20010315 */
+pub fn canonicalize_xml(input: &str) -> Result<String> {
+    let normalized = input.replace("\r\n", "\n").replace('\r', "\n");
+    let mut canonicalizer = XmlCanonicalizer {
+        input: &normalized,
+        position: 0,
+        output: String::with_capacity(normalized.len()),
+        namespaces: vec![HashMap::from([(String::new(), String::new())])],
+        elements: vec![],
+        root_closed: false,
+    };
+    canonicalizer.process()?;
+    Ok(canonicalizer.output)
+}
+
+fn invalid_xml(message: &str) -> Error {
+    Error::InvalidArgumentError(format!("invalid xml document: {}", message))
+}
+
+fn is_xml_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\r')
+}
+
+fn escape_xml_text(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '\r' => output.push_str("&#xD;"),
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
+pub fn escape_xml_attribute(value: &str) -> String {
+    let mut output = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '"' => output.push_str("&quot;"),
+            '\t' => output.push_str("&#x9;"),
+            '\n' => output.push_str("&#xA;"),
+            '\r' => output.push_str("&#xD;"),
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
+//decode the entity or character reference starting with '&', return the decoded character and reference length
+fn decode_reference(text: &str) -> Result<(char, usize)> {
+    let end = text.find(';').ok_or_else(|| invalid_xml("unterminated reference"))?;
+    let name = &text[1..end];
+    let decoded = match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        _ if name.starts_with("#x") => u32::from_str_radix(&name[2..], 16).ok().and_then(char::from_u32),
+        _ if name.starts_with('#') => name[1..].parse::<u32>().ok().and_then(char::from_u32),
+        _ => None,
+    };
+    Ok((decoded.ok_or_else(|| invalid_xml(&format!("unsupported reference &{};", name)))?, end + 1))
+}
+
+fn decode_text(text: &str) -> Result<String> {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find('&') {
+        output.push_str(&rest[..index]);
+        let (c, length) = decode_reference(&rest[index..])?;
+        output.push(c);
+        rest = &rest[index + length..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+//attribute value normalization, literal whitespaces are replaced by space while referenced characters are preserved
+fn decode_attribute(value: &str) -> Result<String> {
+    let mut output = String::with_capacity(value.len());
+    let mut index = 0;
+    while let Some(c) = value[index..].chars().next() {
+        match c {
+            '&' => {
+                let (decoded, length) = decode_reference(&value[index..])?;
+                output.push(decoded);
+                index += length;
+                continue
+            }
+            '<' => return Err(invalid_xml("'<' in attribute value")),
+            '\t' | '\n' | '\r' => output.push(' '),
+            _ => output.push(c),
+        }
+        index += c.len_utf8();
+    }
+    Ok(output)
+}
+
+impl<'a> XmlCanonicalizer<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn take_until(&mut self, delimiter: &str) -> Result<&'a str> {
+        let rest = self.rest();
+        let end = rest.find(delimiter).ok_or_else(|| invalid_xml(&format!("'{}' not found", delimiter)))?;
+        self.position += end + delimiter.len();
+        Ok(&rest[..end])
+    }
+
+    fn process(&mut self) -> Result<()> {
+        while self.position < self.input.len() {
+            let rest = self.rest();
+            if rest.starts_with("<?xml") && rest[5..].starts_with(is_xml_whitespace) {
+                if self.position != 0 {
+                    return Err(invalid_xml("xml declaration is not at the beginning"))
+                }
+                self.take_until("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.position += 4;
+                self.take_until("-->")?;
+            } else if rest.starts_with("<!DOCTYPE") {
+                return Err(invalid_xml("document type declaration is not supported"))
+            } else if rest.starts_with("<![CDATA[") {
+                self.position += 9;
+                let text = self.take_until("]]>")?;
+                if self.elements.is_empty() {
+                    return Err(invalid_xml("character data outside of document element"))
+                }
+                self.output.push_str(&escape_xml_text(text));
+            } else if rest.starts_with("<?") {
+                self.position += 2;
+                let instruction = self.take_until("?>")?;
+                self.write_instruction(instruction);
+            } else if rest.starts_with("</") {
+                self.position += 2;
+                let name = self.take_until(">")?.trim_end();
+                self.end_element(name)?;
+            } else if rest.starts_with('<') {
+                self.position += 1;
+                self.start_element()?;
+            } else {
+                let end = rest.find('<').unwrap_or(rest.len());
+                self.position += end;
+                self.write_text(&rest[..end])?;
+            }
+        }
+        if !self.root_closed || !self.elements.is_empty() {
+            return Err(invalid_xml("document element is not closed"))
+        }
+        Ok(())
+    }
+
+    fn write_text(&mut self, text: &str) -> Result<()> {
+        if self.elements.is_empty() {
+            //whitespaces outside of document element are removed
+            if text.chars().all(is_xml_whitespace) {
+                return Ok(())
+            }
+            return Err(invalid_xml("text outside of document element"))
+        }
+        self.output.push_str(&escape_xml_text(&decode_text(text)?));
+        Ok(())
+    }
+
+    fn write_instruction(&mut self, instruction: &str) {
+        let (target, data) = match instruction.find(is_xml_whitespace) {
+            Some(index) => (&instruction[..index], instruction[index..].trim_start()),
+            None => (instruction, ""),
+        };
+        let mut rendered = format!("<?{}", target);
+        if !data.is_empty() {
+            rendered.push(' ');
+            rendered.push_str(data);
+        }
+        rendered.push_str("?>");
+        if self.elements.is_empty() && self.root_closed {
+            self.output.push('\n');
+        }
+        self.output.push_str(&rendered);
+        if self.elements.is_empty() && !self.root_closed {
+            self.output.push('\n');
+        }
+    }
+
+    fn parse_attributes(&mut self) -> Result<(Vec<XmlAttribute>, bool)> {
+        let mut attributes = vec![];
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start_matches(is_xml_whitespace);
+            self.position += rest.len() - trimmed.len();
+            if trimmed.starts_with("/>") {
+                self.position += 2;
+                return Ok((attributes, true))
+            }
+            if trimmed.starts_with('>') {
+                self.position += 1;
+                return Ok((attributes, false))
+            }
+            let name = self.take_until("=")?.trim_end().to_string();
+            let rest = self.rest();
+            let trimmed = rest.trim_start_matches(is_xml_whitespace);
+            self.position += rest.len() - trimmed.len();
+            let quote = match trimmed.chars().next() {
+                Some(quote) if quote == '"' || quote == '\'' => quote,
+                _ => return Err(invalid_xml(&format!("attribute {} is not quoted", name))),
+            };
+            self.position += 1;
+            let value = decode_attribute(self.take_until(&quote.to_string())?)?;
+            if name.is_empty() || attributes.iter().any(|a: &XmlAttribute| a.name == name) {
+                return Err(invalid_xml(&format!("invalid attribute '{}'", name)))
+            }
+            attributes.push(XmlAttribute { name, value });
+        }
+    }
+
+    fn start_element(&mut self) -> Result<()> {
+        if self.root_closed && self.elements.is_empty() {
+            return Err(invalid_xml("multiple document elements"))
+        }
+        let rest = self.rest();
+        let end = rest.find(|c: char| is_xml_whitespace(c) || c == '>' || c == '/').unwrap_or(rest.len());
+        let name = rest[..end].to_string();
+        if name.is_empty() {
+            return Err(invalid_xml("empty element name"))
+        }
+        self.position += end;
+        let (attributes, empty) = self.parse_attributes()?;
+
+        let parent = self.namespaces.last().cloned().unwrap_or_default();
+        let mut scope = parent.clone();
+        let mut namespaces = vec![];
+        let mut others = vec![];
+        for attribute in attributes.into_iter() {
+            let prefix = if attribute.name == "xmlns" {
+                Some(String::new())
+            } else {
+                attribute.name.strip_prefix("xmlns:").map(|prefix| prefix.to_string())
+            };
+            match prefix {
+                Some(prefix) => {
+                    scope.insert(prefix.clone(), attribute.value.clone());
+                    //superfluous namespace declarations are removed
+                    if parent.get(&prefix).map(|value| value.as_str()).unwrap_or_default() != attribute.value {
+                        namespaces.push((prefix, attribute.value));
+                    }
+                }
+                None => others.push(attribute),
+            }
+        }
+        namespaces.sort();
+        let mut sorted_attributes = vec![];
+        for attribute in others.into_iter() {
+            let (namespace, local_name) = match attribute.name.split_once(':') {
+                Some(("xml", local_name)) => (XML_NAMESPACE.to_string(), local_name.to_string()),
+                Some((prefix, local_name)) => (scope.get(prefix).cloned().ok_or_else(
+                    || invalid_xml(&format!("undeclared namespace prefix {}", prefix)))?, local_name.to_string()),
+                None => (String::new(), attribute.name.clone()),
+            };
+            sorted_attributes.push((namespace, local_name, attribute));
+        }
+        sorted_attributes.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+
+        self.output.push('<');
+        self.output.push_str(&name);
+        for (prefix, value) in namespaces.iter() {
+            if prefix.is_empty() {
+                self.output.push_str(" xmlns=\"");
+            } else {
+                self.output.push_str(&format!(" xmlns:{}=\"", prefix));
+            }
+            self.output.push_str(&escape_xml_attribute(value));
+            self.output.push('"');
+        }
+        for (_, _, attribute) in sorted_attributes.iter() {
+            self.output.push_str(&format!(" {}=\"{}\"", attribute.name, escape_xml_attribute(&attribute.value)));
+        }
+        self.output.push('>');
+        self.namespaces.push(scope);
+        self.elements.push(name.clone());
+        if empty {
+            self.end_element(&name)?;
+        }
+        Ok(())
+    }
+
+    fn end_element(&mut self, name: &str) -> Result<()> {
+        match self.elements.pop() {
+            Some(element) if element == name => {
+                self.namespaces.pop();
+                self.output.push_str(&format!("</{}>", name));
+                if self.elements.is_empty() {
+                    self.root_closed = true;
+                }
+                Ok(())
+            }
+            _ => Err(invalid_xml(&format!("unexpected end tag {}", name))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_canonicalize_json() {
+        //based on the samples in https://www.rfc-editor.org/rfc/rfc8785#section-3.2.2
+        let value: Value = serde_json::from_str(r#"{
+            "numbers": [123456789.125, 1.5e-7, 1E30, 4.50, 2e-3, 0.000000000000000000000000001, 10.0, -0.0000033, 100],
+            "string": "€$\u000F\u000aA'B\u0022\u005c\\\"\/",
+            "literals": [null, true, false],
+            "\ufb33": 1,
+            "😀": 2,
+            "\u0080": 3
+        }"#).unwrap();
+        assert_eq!(canonicalize_json(&value).unwrap(), concat!(
+            r#"{"literals":[null,true,false],"#,
+            r#""numbers":[123456789.125,1.5e-7,1e+30,4.5,0.002,1e-27,10,-0.0000033,100],"#,
+            "\"string\":\"\u{20ac}$\\u000f\\nA'B\\\"\\\\\\\\\\\"/\",\"\u{80}\":3,\"\u{1f600}\":2,\"\u{fb33}\":1}"));
+    }
+
+    #[test]
+    fn test_canonicalize_xml() {
+        let document = "<?xml version=\"1.0\"?>\r\n<?xml-stylesheet   href=\"doc.xsl\"\n   type=\"text/xsl\"   ?>\n\n<!-- comment -->\n<doc xmlns=\"http://www.ietf.org\" xmlns:w3c=\"http://www.w3.org\">\
+<e1   /><e2   ></e2 ><e3   name = \"elem3\"   id=\"elem3\"   /><e5 a:attr=\"out\" b:attr=\"sorted\" attr2=\"all\" attr=\"I'm\" xmlns:b=\"http://www.ietf.org\" xmlns:a=\"http://www.w3.org\" xmlns=\"http://example.org\"/>\
+<e6 xmlns=\"\" xmlns:w3c=\"http://www.w3.org\"><e7 xmlns=\"\"/></e6><text>First line&#x0d;&#10;Second &amp; &lt;&gt; &quot; <![CDATA[value>\"0\" && value<\"10\" ?\"valid\":\"error\"]]></text>\
+<attr attr=\"&#x9;&#10; tab\tnewline\n\"/></doc>\n<!-- comment -->\n<?pi-after?>";
+        assert_eq!(canonicalize_xml(document).unwrap(),
+                   "<?xml-stylesheet href=\"doc.xsl\"\n   type=\"text/xsl\"   ?>\n<doc xmlns=\"http://www.ietf.org\" xmlns:w3c=\"http://www.w3.org\">\
+<e1></e1><e2></e2><e3 id=\"elem3\" name=\"elem3\"></e3><e5 xmlns=\"http://example.org\" xmlns:a=\"http://www.w3.org\" xmlns:b=\"http://www.ietf.org\" attr=\"I'm\" attr2=\"all\" b:attr=\"sorted\" a:attr=\"out\"></e5>\
+<e6 xmlns=\"\"><e7></e7></e6><text>First line&#xD;\nSecond &amp; &lt;&gt; \" value&gt;\"0\" &amp;&amp; value&lt;\"10\" ?\"valid\":\"error\"</text>\
+<attr attr=\"&#x9;&#xA; tab newline \"></attr></doc>\n<?pi-after?>");
+        let _ = canonicalize_xml("<!DOCTYPE doc [<!ENTITY ent \"value\">]><doc>&ent;</doc>").expect_err("dtd is not supported");
+        let _ = canonicalize_xml("<doc><a></b></doc>").expect_err("mismatched tag should fail");
+        let _ = canonicalize_xml("<doc>&unknown;</doc>").expect_err("unknown entity should fail");
+    }
+}
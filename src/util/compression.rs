@@ -0,0 +1,111 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+use std::io::{Read, Write};
+use crate::util::error::{Error, Result};
+
+pub const GZIP: &str = "gzip";
+pub const XZ: &str = "xz";
+pub const ZSTD: &str = "zstd";
+
+//decompress the content of gzip, xz or zstd format, concatenated streams are supported as well
+pub fn decompress(format: &str, data: &[u8]) -> Result<Vec<u8>> {
+    match format {
+        XZ => {
+            let mut output = Vec::new();
+            xz2::read::XzDecoder::new_multi_decoder(data).read_to_end(&mut output)?;
+            Ok(output)
+        }
+        ZSTD => Ok(zstd::stream::decode_all(data)?),
+        GZIP => {
+            let mut output = Vec::new();
+            flate2::read::MultiGzDecoder::new(data).read_to_end(&mut output)?;
+            Ok(output)
+        }
+        _ => Err(Error::UnsupportedTypeError(format!("compression format {} is not supported", format))),
+    }
+}
+
+pub fn compress_gzip(data: &[u8], level: u32, mtime: u32, filename: Option<&[u8]>) -> Result<Vec<u8>> {
+    let mut builder = flate2::GzBuilder::new().mtime(mtime);
+    if let Some(name) = filename {
+        builder = builder.filename(name);
+    }
+    let mut encoder = builder.write(Vec::new(), flate2::Compression::new(level));
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+//check is the check id in xz stream flags, only LZMA2 filter is used as xz utils does by default
+pub fn compress_xz(data: &[u8], check: u8, dict_size: u32) -> Result<Vec<u8>> {
+    let check = match check {
+        0 => xz2::stream::Check::None,
+        1 => xz2::stream::Check::Crc32,
+        4 => xz2::stream::Check::Crc64,
+        10 => xz2::stream::Check::Sha256,
+        _ => return Err(Error::UnsupportedTypeError(format!("xz check type {} is not supported", check))),
+    };
+    let to_error = |e: xz2::stream::Error| Error::EncodeError(e.to_string());
+    let mut options = xz2::stream::LzmaOptions::new_preset(6).map_err(to_error)?;
+    options.dict_size(dict_size);
+    let mut filters = xz2::stream::Filters::new();
+    filters.lzma2(&options);
+    let stream = xz2::stream::Stream::new_stream_encoder(&filters, check).map_err(to_error)?;
+    let mut encoder = xz2::write::XzEncoder::new_stream(Vec::new(), stream);
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+//the content size is always recorded in frame header, window log is left to the level when not specified
+pub fn compress_zstd(data: &[u8], level: i32, checksum: bool, window_log: Option<u32>) -> Result<Vec<u8>> {
+    let mut encoder = zstd::stream::Encoder::new(Vec::new(), level)?;
+    encoder.include_checksum(checksum)?;
+    encoder.set_pledged_src_size(Some(data.len() as u64))?;
+    if let Some(window_log) = window_log {
+        encoder.window_log(window_log)?;
+    }
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decompress() {
+        let content = b"payload content".repeat(100);
+        let gzip = compress_gzip(&content, 6, 0, Some(b"payload")).expect("compress with gzip successfully");
+        assert_eq!(decompress(GZIP, &gzip).expect("decompress gzip successfully"), content);
+        let xz = compress_xz(&content, 4, 1024 * 1024).expect("compress with xz successfully");
+        assert_eq!(decompress(XZ, &xz).expect("decompress xz successfully"), content);
+        let zstd = zstd::stream::encode_all(content.as_slice(), 0).expect("compress with zstd successfully");
+        assert_eq!(decompress(ZSTD, &zstd).expect("decompress zstd successfully"), content);
+        assert!(decompress(XZ, &zstd).is_err());
+        assert!(decompress("bzip2", &zstd).is_err());
+        assert!(compress_xz(&content, 2, 1024 * 1024).is_err());
+    }
+
+    #[test]
+    fn test_compress_zstd_with_level() {
+        let content = b"payload content".repeat(100);
+        let compressed = compress_zstd(&content, 19, false, None).expect("compress with zstd successfully");
+        let mut encoder = zstd::stream::Encoder::new(Vec::new(), 19).expect("create zstd encoder successfully");
+        encoder.set_pledged_src_size(Some(content.len() as u64)).expect("set content size successfully");
+        encoder.write_all(&content).expect("compress with zstd successfully");
+        assert_eq!(compressed, encoder.finish().expect("compress with zstd successfully"));
+        assert_eq!(decompress(ZSTD, &compressed).expect("decompress zstd successfully"), content);
+    }
+}
@@ -0,0 +1,235 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+
+use pgp::crypto::hash::Hasher;
+use sha2::compress256;
+use sha2::digest::generic_array::GenericArray;
+use crate::util::error::{Error, Result};
+
+//only sha2_256 is supported in prehashed signing, it's the default digest of all sign plugins
+pub const SHA2_256: &str = "sha2_256";
+const SHA256_BLOCK_SIZE: usize = 64;
+const SHA256_DIGEST_SIZE: usize = 32;
+//initial hash value defined in FIPS 180-4
+const SHA256_INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// SHA-256 hash state which can be transferred and resumed, the state covers all of the complete
+/// blocks and the remaining bytes are kept in tail. The blocks are compressed by sha2 crate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sha256State {
+    state: [u32; 8],
+    length: u64,
+    tail: Vec<u8>,
+}
+
+impl Default for Sha256State {
+    fn default() -> Self {
+        Self {
+            state: SHA256_INITIAL_STATE,
+            length: 0,
+            tail: vec![],
+        }
+    }
+}
+
+impl Sha256State {
+    pub fn from_parts(state: &[u8], length: u64, tail: Vec<u8>) -> Result<Self> {
+        if state.len() != SHA256_DIGEST_SIZE || tail.len() >= SHA256_BLOCK_SIZE || length % SHA256_BLOCK_SIZE as u64 != tail.len() as u64 {
+            return Err(Error::ParameterError("invalid sha256 hash state".to_string()))
+        }
+        let mut words = [0u32; 8];
+        for (index, chunk) in state.chunks(4).enumerate() {
+            words[index] = u32::from_be_bytes(chunk.try_into()?);
+        }
+        Ok(Self {
+            state: words,
+            length,
+            tail,
+        })
+    }
+
+    pub fn state(&self) -> Vec<u8> {
+        self.state.iter().flat_map(|w| w.to_be_bytes()).collect()
+    }
+
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    pub fn tail(&self) -> &[u8] {
+        &self.tail
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.length += data.len() as u64;
+        let mut data = data;
+        if !self.tail.is_empty() {
+            let size = (SHA256_BLOCK_SIZE - self.tail.len()).min(data.len());
+            self.tail.extend_from_slice(&data[..size]);
+            data = &data[size..];
+            if self.tail.len() < SHA256_BLOCK_SIZE {
+                return
+            }
+            let block = std::mem::take(&mut self.tail);
+            self.compress(&block);
+        }
+        let size = data.len() - data.len() % SHA256_BLOCK_SIZE;
+        self.compress(&data[..size]);
+        self.tail = data[size..].to_vec();
+    }
+
+    //the message is padded with 0x80, zeros and the length in bits into complete blocks
+    pub fn finalize(&self) -> Vec<u8> {
+        let mut state = self.clone();
+        let mut padding = state.tail.clone();
+        padding.push(0x80);
+        while padding.len() % SHA256_BLOCK_SIZE != SHA256_BLOCK_SIZE - 8 {
+            padding.push(0);
+        }
+        padding.extend_from_slice(&(self.length << 3).to_be_bytes());
+        state.compress(&padding);
+        state.state()
+    }
+
+    fn compress(&mut self, blocks: &[u8]) {
+        let blocks: Vec<_> = blocks.chunks_exact(SHA256_BLOCK_SIZE).map(GenericArray::clone_from_slice).collect();
+        compress256(&mut self.state, &blocks);
+    }
+}
+
+impl std::io::Write for Sha256State {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Sha256State::update(self, buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Hasher for Sha256State {
+    fn update(&mut self, data: &[u8]) {
+        Sha256State::update(self, data)
+    }
+
+    fn finish(self: Box<Self>) -> Vec<u8> {
+        self.finalize()
+    }
+}
+
+/// Digest of the content to be signed, used when the client hashes the content locally.
+/// the hash state is required by openpgp signature since the signature trailer is hashed after the content.
+#[derive(Debug, Clone)]
+pub struct ContentDigest {
+    pub algorithm: String,
+    pub digest: Vec<u8>,
+    pub state: Option<Sha256State>,
+}
+
+impl ContentDigest {
+    pub fn new(algorithm: String, digest: Vec<u8>, state: Option<Sha256State>) -> Result<Self> {
+        if algorithm != SHA2_256 {
+            return Err(Error::UnsupportedTypeError(format!("digest algorithm {} is not supported in prehashed signing", algorithm)))
+        }
+        if digest.len() != SHA256_DIGEST_SIZE {
+            return Err(Error::ParameterError(format!("invalid digest length {} for {}", digest.len(), algorithm)))
+        }
+        if let Some(s) = &state {
+            if s.finalize() != digest {
+                return Err(Error::ParameterError("hash state does not match the digest".to_string()))
+            }
+        }
+        Ok(Self {
+            algorithm,
+            digest,
+            state,
+        })
+    }
+
+    pub fn from_content(content: &[u8]) -> Self {
+        let mut state = Sha256State::default();
+        state.update(content);
+        Self {
+            algorithm: SHA2_256.to_string(),
+            digest: state.finalize(),
+            state: Some(state),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sha2::{Digest, Sha256};
+
+    #[test]
+    fn test_sha256_state_resume_and_finalize() {
+        let content: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+        for split in [0, 1, 63, 64, 65, 128, 999, 1000] {
+            let mut state = Sha256State::default();
+            state.update(&content[..split]);
+            let resumed = Sha256State::from_parts(&state.state(), state.length(), state.tail().to_vec()).expect("resume hash state");
+            let mut resumed_state = resumed.clone();
+            resumed_state.update(&content[split..]);
+            assert_eq!(resumed_state.finalize(), Sha256::digest(&content).to_vec());
+            assert_eq!(resumed.finalize(), Sha256::digest(&content[..split]).to_vec());
+        }
+        let _ = Sha256State::from_parts(&[0u8; 31], 0, vec![]).expect_err("invalid state length should fail");
+        let _ = Sha256State::from_parts(&[0u8; 32], 65, vec![]).expect_err("inconsistent tail should fail");
+    }
+
+    #[test]
+    fn test_sha256_state_known_answer() {
+        //test vectors from FIPS 180-2, the 56 bytes message pads into two blocks
+        let vectors: Vec<(Vec<u8>, &str)> = vec![
+            (b"".to_vec(), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+            (b"abc".to_vec(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+            (b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq".to_vec(),
+             "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"),
+            (b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu".to_vec(),
+             "cf5b16a778af8380036ce59e7b0492370b249b11e8f07a51afac45037afee9d1"),
+            (vec![b'a'; 1_000_000], "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"),
+        ];
+        for (message, expected) in vectors.iter() {
+            let mut state = Sha256State::default();
+            state.update(message);
+            assert_eq!(hex::encode(state.finalize()), *expected);
+            //feed the message in chunks which are not aligned with the block size
+            for chunk_size in [1, 55, 56, 63, 64, 65, 127] {
+                let mut state = Sha256State::default();
+                for chunk in message.chunks(chunk_size) {
+                    let resumed = Sha256State::from_parts(&state.state(), state.length(), state.tail().to_vec()).expect("resume hash state");
+                    assert_eq!(resumed, state);
+                    state = resumed;
+                    state.update(chunk);
+                }
+                assert_eq!(hex::encode(state.finalize()), *expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_content_digest_validation() {
+        let digest = ContentDigest::from_content(b"hello world");
+        assert_eq!(digest.digest, Sha256::digest(b"hello world").to_vec());
+        let _ = ContentDigest::new(SHA2_256.to_string(), digest.digest.clone(), digest.state.clone()).expect("valid digest");
+        let _ = ContentDigest::new("sha1".to_string(), digest.digest.clone(), None).expect_err("unsupported algorithm should fail");
+        let _ = ContentDigest::new(SHA2_256.to_string(), vec![0; 32], digest.state).expect_err("mismatched state should fail");
+    }
+}
@@ -45,6 +45,7 @@ use anyhow::Error as AnyhowError;
 use csrf::CsrfError;
 use utoipa::{ToSchema};
 use efi_signer::error::Error as EFIError;
+use zip::result::ZipError;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -121,12 +122,16 @@ pub enum Error {
     InvalidArgumentError(String),
     #[error("failed to encode in bincode: {0}")]
     BincodeError(String),
-    #[error("failed to sign some of the files")]
+    #[error("failed to process some of the files")]
     PartialSuccessError,
     #[error("Error in sign or parse EFI image")]
     EFIError(String),
+    #[error("failed to process zip archive: {0}")]
+    ZipError(String),
     #[error("file content is empty")]
     FileContentEmpty,
+    #[error("failed to verify signature: {0}")]
+    VerifyError(String),
 }
 
 #[derive(Deserialize, Serialize, ToSchema)]
@@ -370,6 +375,12 @@ impl From<EFIError> for Error {
     }
 }
 
+impl From<ZipError> for Error {
+    fn from(error: ZipError) -> Self {
+        Error::ZipError(error.to_string())
+    }
+}
+
 impl From<CsrfError> for Error {
     fn from(error: CsrfError) -> Self {
         Error::FrameworkError(error.to_string())
@@ -384,6 +395,10 @@ impl From<data_encoding::DecodeError> for Error {
     fn from(error: data_encoding::DecodeError) -> Self { Error::FrameworkError(error.to_string()) }
 }
 
+impl From<hex::FromHexError> for Error {
+    fn from(error: hex::FromHexError) -> Self { Error::EncodeError(error.to_string()) }
+}
+
 impl From<Infallible> for Error {
     fn from(error: Infallible) -> Self { Error::FrameworkError(error.to_string()) }
 }
@@ -397,3 +412,11 @@ impl From<Vec<u8>> for Error {
 }
 
 
+
+impl From<der::Error> for Error {
+    fn from(error: der::Error) -> Self { Error::X509InvokeError(error.to_string()) }
+}
+
+impl From<cms::builder::Error> for Error {
+    fn from(error: cms::builder::Error) -> Self { Error::X509InvokeError(error.to_string()) }
+}
@@ -54,6 +54,12 @@ pub fn get_token_hash(real_token: &str) -> String {
     hex::encode(digest)
 }
 
+pub fn get_content_digest(content: &[u8]) -> String {
+    let mut hasher = Sha256::default();
+    hasher.update(content);
+    hex::encode(hasher.finalize())
+}
+
 pub fn sorted_map<S: Serializer, K: Serialize + Ord, V: Serialize>(value: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error> {
     let mut items: Vec<(_, _)> = value.iter().collect();
     items.sort_by(|a, b| a.0.cmp(b.0));
@@ -3,4 +3,10 @@ pub mod error;
 pub mod key;
 pub mod signer_container;
 pub mod options;
-pub mod sign;
\ No newline at end of file
+pub mod sign;
+pub mod digest;
+pub mod canonicalization;
+pub mod payload;
+pub mod compression;
+pub mod xml;
+pub mod authenticode;
\ No newline at end of file
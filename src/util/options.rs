@@ -14,6 +14,35 @@
  *
  */
 
+//the api token is carried in the metadata of the stream request, grpc requires the key in lowercase.
+pub const AUTH_METADATA_KEY: &str = "authorization";
 pub const DETACHED: &str = "detached";
 pub const KEY_TYPE: &str = "key_type";
-pub const SIGN_TYPE: &str = "sign_type";
\ No newline at end of file
+pub const SIGN_TYPE: &str = "sign_type";
+pub const VALIDATE_CHECKSUM: &str = "validate_checksum";
+pub const KEY_NAME: &str = "key_name";
+pub const IMA_KEY_NAME: &str = "ima_key_name";
+pub const IMA_FILE_COUNT: &str = "ima_file_count";
+pub const EFI_VARIABLE: &str = "efi_variable";
+pub const EFI_OWNER_GUID: &str = "efi_owner_guid";
+pub const EFI_TIMESTAMP: &str = "efi_timestamp";
+pub const INCLUDE_CERTIFICATE: &str = "include_certificate";
+pub const CMS_CERTIFICATE_CHAIN: &str = "cms_certificate_chain";
+pub const RPM_HEADER_ONLY: &str = "rpm_header_only";
+pub const EFI_APPEND: &str = "efi_append";
+pub const EFI_EXTRA_KEY_NAMES: &str = "efi_extra_key_names";
+pub const DIGEST_ALGORITHM: &str = "digest_algorithm";
+pub const SIGNATURE_FORMAT: &str = "signature_format";
+//values of `signature_format` option, openpgp signatures are armored and cms signatures are der encoded in default
+pub const SIGNATURE_FORMAT_ARMORED: &str = "armored";
+pub const SIGNATURE_FORMAT_BINARY: &str = "binary";
+//openpgp cleartext signed message, the text content is embedded and followed by the signature
+pub const SIGNATURE_FORMAT_CLEARTEXT: &str = "cleartext";
+pub const SIGNATURE_FORMAT_DER: &str = "der";
+pub const SIGNATURE_FORMAT_PEM: &str = "pem";
+pub const ZSTD_LEVEL: &str = "zstd_level";
+pub const EFI_SIGNATURE_ALGORITHMS: &str = "efi_signature_algorithms";
+pub const PASSPHRASE: &str = "passphrase";
+
+//options carrying secrets which must never be persisted, for instance, in sign audit
+pub const SECRET_OPTIONS: [&str; 1] = [PASSPHRASE];
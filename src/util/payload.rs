@@ -0,0 +1,213 @@
+/*
+ *
+ *  * // Copyright (c) 2023 Huawei Technologies Co.,Ltd. All rights reserved.
+ *  * //
+ *  * // signatrust is licensed under Mulan PSL v2.
+ *  * // You can use this software according to the terms and conditions of the Mulan
+ *  * // PSL v2.
+ *  * // You may obtain a copy of Mulan PSL v2 at:
+ *  * //         http://license.coscl.org.cn/MulanPSL2
+ *  * // THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ *  * // KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ *  * // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ *  * // See the Mulan PSL v2 for more details.
+ *
+ */
+//payloads of the signatures which are embedded in structured documents, they are built by the sign plugins from
+//the documents instead of being supplied by client, thus the raw signatures are always bound to the document format.
+use crate::util::canonicalization::{canonicalize_json, canonicalize_xml};
+use crate::util::error::{Error, Result};
+use data_encoding::{BASE64, BASE64URL_NOPAD};
+use openssl::sha::sha256;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+
+pub const JSF_SIGNATURE: &str = "signature";
+//openpgp cleartext signed message starts with the header, check https://www.rfc-editor.org/rfc/rfc4880#section-7
+pub const PGP_CLEARTEXT_HEADER: &str = "-----BEGIN PGP SIGNED MESSAGE-----";
+pub const XMLDSIG_NAMESPACE: &str = "http://www.w3.org/2000/09/xmldsig#";
+//the signed info is serialized in canonical form, only the namespace is absent as it's inherited from signature element
+const XMLDSIG_SIGNED_INFO: &str = "<SignedInfo>\
+<CanonicalizationMethod Algorithm=\"http://www.w3.org/2001/10/xml-exc-c14n#\"></CanonicalizationMethod>\
+<SignatureMethod Algorithm=\"{method}\"></SignatureMethod>\
+<Reference URI=\"\"><Transforms>\
+<Transform Algorithm=\"http://www.w3.org/2000/09/xmldsig#enveloped-signature\"></Transform>\
+<Transform Algorithm=\"http://www.w3.org/TR/2001/REC-xml-c14n-20010315\"></Transform>\
+</Transforms><DigestMethod Algorithm=\"http://www.w3.org/2001/04/xmlenc#sha256\"></DigestMethod>\
+<DigestValue>{digest}</DigestValue></Reference></SignedInfo>";
+//cosign signature format, check https://github.com/sigstore/cosign/blob/main/specs/SIGNATURE_SPEC.md
+const SIMPLE_SIGNING_TYPE: &str = "cosign container image signature";
+const SHA256_ALGORITHM: &str = "sha256";
+
+lazy_static! {
+    static ref ROOT_ELEMENT_REGEX: Regex = Regex::new(r"<([A-Za-z_][^\s/>]*)[^>]*>").unwrap();
+}
+
+/// Algorithm of the raw signature which is embedded in documents, the sha256 digest is always used.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RawAlgorithm {
+    Rsa,
+    Ed25519,
+}
+
+impl RawAlgorithm {
+    //check https://cyberphone.github.io/doc/security/jsf.html
+    pub fn jsf_algorithm(&self) -> &'static str {
+        match self {
+            RawAlgorithm::Rsa => "RS256",
+            RawAlgorithm::Ed25519 => "Ed25519",
+        }
+    }
+
+    //check https://www.rfc-editor.org/rfc/rfc9231
+    pub fn xmldsig_method(&self) -> &'static str {
+        match self {
+            RawAlgorithm::Rsa => "http://www.w3.org/2001/04/xmldsig-more#rsa-sha256",
+            RawAlgorithm::Ed25519 => "http://www.w3.org/2021/04/xmldsig-more#eddsa-ed25519",
+        }
+    }
+}
+
+/// JSON Signature Format of CycloneDX json document, the signature object takes part in signing except its value.
+/// The signed document is returned.
+pub fn sign_jsf<F>(document: &[u8], algorithm: RawAlgorithm, sign: F) -> Result<Vec<u8>>
+where
+    F: FnOnce(&[u8]) -> Result<Vec<u8>>,
+{
+    let mut value: Value = serde_json::from_slice(document)?;
+    let object = value.as_object_mut().ok_or_else(
+        || Error::ParameterError("CycloneDX document is not a json object".to_string()))?;
+    let mut jsf = Map::new();
+    jsf.insert("algorithm".to_string(), Value::String(algorithm.jsf_algorithm().to_string()));
+    object.insert(JSF_SIGNATURE.to_string(), Value::Object(jsf));
+    let signature = sign(canonicalize_json(&value)?.as_bytes())?;
+    value[JSF_SIGNATURE]["value"] = Value::String(BASE64URL_NOPAD.encode(&signature));
+    Ok(serde_json::to_vec_pretty(&value)?)
+}
+
+/// Return the start tag and name of the document element.
+pub fn get_root_element(text: &str) -> Result<(&str, &str)> {
+    let captures = ROOT_ELEMENT_REGEX.captures(text).ok_or_else(
+        || Error::ParameterError("document element not found in xml".to_string()))?;
+    Ok((captures.get(0).unwrap().as_str(), captures.get(1).unwrap().as_str()))
+}
+
+/// Remove the enveloped signature generated before.
+pub fn remove_xml_signature(text: &str) -> String {
+    let start_tag = format!("<Signature xmlns=\"{}\">", XMLDSIG_NAMESPACE);
+    if let Some(start) = text.find(&start_tag) {
+        if let Some(length) = text[start..].find("</Signature>") {
+            return format!("{}{}", &text[..start], &text[start + length + "</Signature>".len()..])
+        }
+    }
+    text.to_string()
+}
+
+pub fn get_signed_info(text: &str, algorithm: &RawAlgorithm) -> Result<String> {
+    let digest = sha256(canonicalize_xml(text)?.as_bytes());
+    Ok(XMLDSIG_SIGNED_INFO.replace("{method}", algorithm.xmldsig_method()).replace("{digest}", &BASE64.encode(&digest)))
+}
+
+/// Enveloped XML-DSig of CycloneDX xml document, the signature element is inserted as the last child of document
+/// element without any whitespace around. The signed document is returned.
+pub fn sign_xmldsig<F>(document: &[u8], algorithm: RawAlgorithm, sign: F) -> Result<Vec<u8>>
+where
+    F: FnOnce(&[u8]) -> Result<Vec<u8>>,
+{
+    let text = std::str::from_utf8(document).map_err(|e| Error::ParameterError(e.to_string()))?;
+    let text = remove_xml_signature(text);
+    let (_, root) = get_root_element(&text)?;
+    let position = text.rfind("</").filter(|p| text[p + 2..].starts_with(root)).ok_or_else(
+        || Error::ParameterError(format!("end tag of document element {} not found", root)))?;
+    let signed_info = get_signed_info(&text, &algorithm)?;
+    //the signed info is canonicalized with the namespace inherited from signature element
+    let signature = sign(signed_info.replacen(
+        "<SignedInfo>", &format!("<SignedInfo xmlns=\"{}\">", XMLDSIG_NAMESPACE), 1).as_bytes())?;
+    Ok(format!("{}<Signature xmlns=\"{}\">{}<SignatureValue>{}</SignatureValue></Signature>{}",
+               &text[..position], XMLDSIG_NAMESPACE, signed_info, BASE64.encode(&signature), &text[position..]).into_bytes())
+}
+
+/// Simple signing payload of cosign, i.e. the image reference and the manifest digest.
+pub fn cosign_payload(reference: &str, manifest_digest: &str) -> Result<Vec<u8>> {
+    Ok(serde_json::to_vec(&json!({
+        "critical": {
+            "identity": {"docker-reference": reference},
+            "image": {"docker-manifest-digest": manifest_digest},
+            "type": SIMPLE_SIGNING_TYPE,
+        },
+        "optional": null,
+    }))?)
+}
+
+pub fn manifest_digest(manifest: &[u8]) -> String {
+    format!("{}:{}", SHA256_ALGORITHM, hex::encode(sha256(manifest)))
+}
+
+/// The content sent to server in cosign signing, the image reference is followed by the manifest.
+pub fn cosign_content(reference: &str, manifest: &[u8]) -> Vec<u8> {
+    let mut content = format!("{}\n", reference).into_bytes();
+    content.extend_from_slice(manifest);
+    content
+}
+
+/// Sign the cosign payload which is built from the image reference and the digest of manifest.
+pub fn sign_cosign<F>(content: &[u8], sign: F) -> Result<Vec<u8>>
+where
+    F: FnOnce(&[u8]) -> Result<Vec<u8>>,
+{
+    let position = content.iter().position(|b| *b == b'\n').ok_or_else(
+        || Error::ParameterError("image reference is missing in cosign content".to_string()))?;
+    let reference = std::str::from_utf8(&content[..position]).map_err(|e| Error::ParameterError(e.to_string()))?;
+    if reference.is_empty() {
+        return Err(Error::ParameterError("image reference is empty in cosign content".to_string()))
+    }
+    let manifest = &content[position + 1..];
+    serde_json::from_slice::<Value>(manifest).map_err(|e| Error::ParameterError(format!("invalid image manifest: {}", e)))?;
+    sign(&cosign_payload(reference, &manifest_digest(manifest))?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sign_jsf() {
+        let signed = sign_jsf(br#"{"bomFormat":"CycloneDX","components":[]}"#, RawAlgorithm::Ed25519, |data| {
+            assert_eq!(data, br#"{"bomFormat":"CycloneDX","components":[],"signature":{"algorithm":"Ed25519"}}"#);
+            Ok(vec![1, 2, 3])
+        }).expect("sign jsf successfully");
+        let value: Value = serde_json::from_slice(&signed).unwrap();
+        assert_eq!(value[JSF_SIGNATURE]["algorithm"], "Ed25519");
+        assert_eq!(value[JSF_SIGNATURE]["value"], BASE64URL_NOPAD.encode(&[1, 2, 3]));
+        let _ = sign_jsf(b"[]", RawAlgorithm::Rsa, |_| Ok(vec![])).expect_err("non object document should fail");
+    }
+
+    #[test]
+    fn test_sign_xmldsig() {
+        let document = "<bom xmlns=\"http://cyclonedx.org/schema/bom/1.4\"><components/></bom>\n";
+        let signed = sign_xmldsig(document.as_bytes(), RawAlgorithm::Rsa, |data| {
+            assert!(String::from_utf8_lossy(data).starts_with(&format!("<SignedInfo xmlns=\"{}\">", XMLDSIG_NAMESPACE)));
+            Ok(vec![1, 2, 3])
+        }).expect("sign xmldsig successfully");
+        let signed = String::from_utf8(signed).unwrap();
+        assert!(signed.contains(RawAlgorithm::Rsa.xmldsig_method()));
+        assert!(signed.ends_with("<SignatureValue>AQID</SignatureValue></Signature></bom>\n"));
+        assert_eq!(remove_xml_signature(&signed), document);
+        //signature generated before is replaced
+        let resigned = sign_xmldsig(signed.as_bytes(), RawAlgorithm::Rsa, |_| Ok(vec![1, 2, 3])).expect("sign again successfully");
+        assert_eq!(String::from_utf8(resigned).unwrap(), signed);
+    }
+
+    #[test]
+    fn test_sign_cosign() {
+        let manifest = br#"{"schemaVersion":2}"#;
+        let content = cosign_content("registry.io/app", manifest);
+        let payload = sign_cosign(&content, |data| Ok(data.to_vec())).expect("sign cosign successfully");
+        assert_eq!(payload, cosign_payload("registry.io/app", &manifest_digest(manifest)).unwrap());
+        let value: Value = serde_json::from_slice(&payload).unwrap();
+        assert_eq!(value["critical"]["image"]["docker-manifest-digest"], manifest_digest(manifest));
+        let _ = sign_cosign(manifest, |data| Ok(data.to_vec())).expect_err("missing reference should fail");
+        let _ = sign_cosign(&cosign_content("registry.io/app", b"not json"), |data| Ok(data.to_vec())).expect_err("invalid manifest should fail");
+    }
+}
@@ -7,6 +7,17 @@ pub enum SignType {
     Cms,          // signed method for a CMS signed data
     Authenticode, // signed method for signing EFI image using authenticode spec
     PKCS7,        // signed method for a pkcs7 signed data
+    //the following types are selected by file handlers internally, they can't be specified in command line
+    #[value(skip)]
+    Raw,          // raw ed25519 signature of the whole content, only used for ostree commit
+    #[value(skip)]
+    Jsf,          // CycloneDX json document embedded with JSON Signature Format signature
+    #[value(skip)]
+    XmlDsig,      // CycloneDX xml document embedded with enveloped XML-DSig signature
+    #[value(skip)]
+    Cosign,       // raw signature of cosign simple signing payload built from image reference and manifest
+    #[value(skip)]
+    Ima,          // IMA v2 signature of sha256 digest, which is stored in `security.ima` extended attribute
 }
 
 impl Display for SignType {
@@ -15,6 +26,11 @@ impl Display for SignType {
             SignType::Cms => write!(f, "cms"),
             SignType::Authenticode => write!(f, "authenticode"),
             SignType::PKCS7 => write!(f, "pkcs7"),
+            SignType::Raw => write!(f, "raw"),
+            SignType::Jsf => write!(f, "jsf"),
+            SignType::XmlDsig => write!(f, "xmldsig"),
+            SignType::Cosign => write!(f, "cosign"),
+            SignType::Ima => write!(f, "ima"),
         }
     }
 }
@@ -27,6 +43,11 @@ impl FromStr for SignType {
             "cms" => Ok(SignType::Cms),
             "authenticode" => Ok(SignType::Authenticode),
             "pkcs7" => Ok(SignType::PKCS7),
+            "raw" => Ok(SignType::Raw),
+            "jsf" => Ok(SignType::Jsf),
+            "xmldsig" => Ok(SignType::XmlDsig),
+            "cosign" => Ok(SignType::Cosign),
+            "ima" => Ok(SignType::Ima),
             _ => Err(Error::ParameterError("Invalid sign_type param".to_string())),
         }
     }
@@ -38,6 +59,14 @@ pub enum FileType {
     Generic,
     KernelModule,
     EfiImage,
+    Deb,
+    AptRelease,
+    Repomd,
+    Sbom,
+    OciImage,
+    Ostree,
+    EfiVariable,
+    Jar,
 }
 
 impl Display for FileType {
@@ -47,6 +76,14 @@ impl Display for FileType {
             FileType::Generic => write!(f, "generic"),
             FileType::KernelModule => write!(f, "ko"),
             FileType::EfiImage => write!(f, "efi"),
+            FileType::Deb => write!(f, "deb"),
+            FileType::AptRelease => write!(f, "apt-release"),
+            FileType::Repomd => write!(f, "repomd"),
+            FileType::Sbom => write!(f, "sbom"),
+            FileType::OciImage => write!(f, "oci-image"),
+            FileType::Ostree => write!(f, "ostree"),
+            FileType::EfiVariable => write!(f, "efi-variable"),
+            FileType::Jar => write!(f, "jar"),
         }
     }
 }